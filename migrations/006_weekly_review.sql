@@ -0,0 +1,33 @@
+-- Weekly review
+-- Сроки задач, учёт времени и подписка на еженедельный отчёт
+
+-- Срок выполнения задачи (опционально)
+ALTER TABLE todos ADD COLUMN due_date TIMESTAMP;
+
+-- Индекс для поиска просроченных задач пользователя
+CREATE INDEX idx_todos_user_due ON todos(user_id, due_date)
+WHERE due_date IS NOT NULL;
+
+-- Учёт потраченного времени (одна запись на каждый /track)
+CREATE TABLE todo_time_entries (
+    -- Primary key
+    id SERIAL PRIMARY KEY,
+
+    -- Связь с задачей (каскадное удаление)
+    todo_id INTEGER NOT NULL REFERENCES todos(id) ON DELETE CASCADE,
+
+    -- Потраченное время в минутах
+    minutes INTEGER NOT NULL CHECK (minutes > 0),
+
+    -- Когда время было записано
+    tracked_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
+
+-- Индекс для подсчёта времени за период
+CREATE INDEX idx_time_entries_todo_tracked ON todo_time_entries(todo_id, tracked_at);
+
+-- Подписка на еженедельный отчёт (opt-in)
+ALTER TABLE users ADD COLUMN weekly_review BOOLEAN NOT NULL DEFAULT FALSE;
+
+-- Когда последний отчёт был отправлен (защита от повторной отправки)
+ALTER TABLE users ADD COLUMN last_review_at TIMESTAMP;
@@ -0,0 +1,11 @@
+-- Todo tags and estimates
+-- Метки и оценка времени для quick-add синтаксиса (#tag, ~2h)
+
+-- Метки задачи (без символа #, в нижнем регистре)
+ALTER TABLE todos ADD COLUMN tags TEXT[] NOT NULL DEFAULT '{}';
+
+-- Оценка времени выполнения в минутах
+ALTER TABLE todos ADD COLUMN estimate_minutes INTEGER;
+
+-- Индекс для фильтрации задач по меткам
+CREATE INDEX idx_todos_tags ON todos USING GIN (tags);
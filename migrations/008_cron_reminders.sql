@@ -0,0 +1,5 @@
+-- Cron reminders
+-- Cron-выражения в качестве расписания напоминаний
+
+-- Шаблон повторения может содержать cron-выражение (длиннее 50 символов)
+ALTER TABLE reminders ALTER COLUMN recurrence_pattern TYPE TEXT;
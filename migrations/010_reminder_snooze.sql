@@ -0,0 +1,9 @@
+-- Reminder snooze
+-- Откладывание напоминаний кнопками
+
+-- Сколько раз напоминание откладывали (для статистики)
+ALTER TABLE reminders ADD COLUMN snooze_count INTEGER NOT NULL DEFAULT 0;
+
+-- Очередное срабатывание отложенного повторяющегося напоминания:
+-- после отложенной доставки расписание продолжается от него
+ALTER TABLE reminders ADD COLUMN scheduled_at TIMESTAMPTZ;
@@ -0,0 +1,15 @@
+-- Nagging reminders
+-- Настойчивые напоминания: повтор до подтверждения и эскалация
+
+-- Интервал повтора в минутах; NULL - обычное напоминание
+ALTER TABLE reminders ADD COLUMN nag_interval_minutes INTEGER;
+-- Сколько повторов отправить после первого сообщения
+ALTER TABLE reminders ADD COLUMN nag_max_repeats INTEGER NOT NULL DEFAULT 0;
+-- Сколько повторов уже отправлено
+ALTER TABLE reminders ADD COLUMN nag_count INTEGER NOT NULL DEFAULT 0;
+-- Когда пользователь нажал "Подтвердить"
+ALTER TABLE reminders ADD COLUMN acknowledged_at TIMESTAMPTZ;
+-- Чат для эскалации (второй пользователь или группа)
+ALTER TABLE reminders ADD COLUMN escalate_to BIGINT;
+-- Когда эскалация была отправлена
+ALTER TABLE reminders ADD COLUMN escalated_at TIMESTAMPTZ;
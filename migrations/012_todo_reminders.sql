@@ -0,0 +1,8 @@
+-- Todo reminders
+-- Напоминания о задачах и автоматические напоминания перед сроком
+
+-- Смещения в минутах до срока задачи, например {1440} - за день
+ALTER TABLE users ADD COLUMN due_reminder_offsets INTEGER[] NOT NULL DEFAULT '{}';
+
+-- Смещение автоматического напоминания; NULL - создано пользователем
+ALTER TABLE reminders ADD COLUMN due_offset_minutes INTEGER;
@@ -0,0 +1,35 @@
+-- Reminder change notifications
+-- Уведомления планировщика об изменениях напоминаний (LISTEN/NOTIFY)
+
+-- Полезная нагрузка: {"id": 1, "remind_at": "...", "pending": true}
+-- pending = FALSE для отправленных и удалённых напоминаний
+CREATE OR REPLACE FUNCTION notify_reminder_change() RETURNS TRIGGER AS $$
+BEGIN
+    IF TG_OP = 'DELETE' THEN
+        PERFORM pg_notify('reminder_changes', json_build_object(
+            'id', OLD.id,
+            'remind_at', OLD.remind_at,
+            'pending', FALSE
+        )::text);
+        RETURN OLD;
+    END IF;
+
+    PERFORM pg_notify('reminder_changes', json_build_object(
+        'id', NEW.id,
+        'remind_at', NEW.remind_at,
+        'pending', NOT NEW.is_sent
+    )::text);
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+-- Только изменения, влияющие на расписание
+CREATE TRIGGER reminders_notify_insert_delete
+AFTER INSERT OR DELETE ON reminders
+FOR EACH ROW EXECUTE FUNCTION notify_reminder_change();
+
+CREATE TRIGGER reminders_notify_update
+AFTER UPDATE OF remind_at, is_sent ON reminders
+FOR EACH ROW
+WHEN (OLD.remind_at IS DISTINCT FROM NEW.remind_at OR OLD.is_sent IS DISTINCT FROM NEW.is_sent)
+EXECUTE FUNCTION notify_reminder_change();
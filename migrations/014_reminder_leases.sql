@@ -0,0 +1,7 @@
+-- Reminder delivery leases
+-- Аренда напоминаний: несколько экземпляров бота не отправляют одно напоминание дважды
+
+-- Экземпляр, который сейчас отправляет напоминание, и срок аренды.
+-- Аренда упавшего экземпляра истекает, и напоминание забирает другой
+ALTER TABLE reminders ADD COLUMN locked_by TEXT;
+ALTER TABLE reminders ADD COLUMN locked_until TIMESTAMPTZ;
@@ -0,0 +1,57 @@
+-- Reminder delivery retries
+-- Повторные попытки доставки, «мёртвые» напоминания и заблокировавшие бота пользователи
+
+-- Неудачные попытки доставки подряд и последняя ошибка
+ALTER TABLE reminders ADD COLUMN attempts INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE reminders ADD COLUMN last_error TEXT;
+
+-- Время следующей попытки после ошибки (remind_at не меняется,
+-- чтобы расписание повторяющихся напоминаний не сдвигалось)
+ALTER TABLE reminders ADD COLUMN retry_at TIMESTAMPTZ;
+
+-- Доставка прекращена после исчерпания попыток
+ALTER TABLE reminders ADD COLUMN dead_lettered_at TIMESTAMPTZ;
+
+-- Приостановлено: пользователь заблокировал бота
+ALTER TABLE reminders ADD COLUMN paused_at TIMESTAMPTZ;
+
+-- Пользователь заблокировал бота; снимается командой /start
+ALTER TABLE users ADD COLUMN is_blocked BOOLEAN NOT NULL DEFAULT FALSE;
+ALTER TABLE users ADD COLUMN blocked_at TIMESTAMPTZ;
+
+-- Планировщик ждёт retry_at, а приостановленные и «мёртвые» напоминания не ждёт
+CREATE OR REPLACE FUNCTION notify_reminder_change() RETURNS TRIGGER AS $$
+BEGIN
+    IF TG_OP = 'DELETE' THEN
+        PERFORM pg_notify('reminder_changes', json_build_object(
+            'id', OLD.id,
+            'remind_at', OLD.remind_at,
+            'pending', FALSE
+        )::text);
+        RETURN OLD;
+    END IF;
+
+    PERFORM pg_notify('reminder_changes', json_build_object(
+        'id', NEW.id,
+        'remind_at', COALESCE(NEW.retry_at, NEW.remind_at),
+        'pending', NOT COALESCE(NEW.is_sent, FALSE)
+            AND NEW.dead_lettered_at IS NULL
+            AND NEW.paused_at IS NULL
+    )::text);
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER reminders_notify_update ON reminders;
+
+CREATE TRIGGER reminders_notify_update
+AFTER UPDATE OF remind_at, is_sent, retry_at, dead_lettered_at, paused_at ON reminders
+FOR EACH ROW
+WHEN (
+    OLD.remind_at IS DISTINCT FROM NEW.remind_at
+    OR OLD.is_sent IS DISTINCT FROM NEW.is_sent
+    OR OLD.retry_at IS DISTINCT FROM NEW.retry_at
+    OR OLD.dead_lettered_at IS DISTINCT FROM NEW.dead_lettered_at
+    OR OLD.paused_at IS DISTINCT FROM NEW.paused_at
+)
+EXECUTE FUNCTION notify_reminder_change();
@@ -0,0 +1,19 @@
+-- Reminder target chat
+-- Напоминания в группы и каналы
+
+-- Чат Telegram, куда доставляется напоминание: личный чат владельца,
+-- группа, где его создали, или канал
+ALTER TABLE reminders ADD COLUMN chat_id BIGINT;
+
+UPDATE reminders r
+SET chat_id = u.telegram_id
+FROM users u
+WHERE u.id = r.user_id;
+
+ALTER TABLE reminders ALTER COLUMN chat_id SET NOT NULL;
+
+-- Участники группы, которых упомянуть при срабатывании (без @)
+ALTER TABLE reminders ADD COLUMN mentions TEXT[] NOT NULL DEFAULT '{}';
+
+-- Приостановка напоминаний чата, из которого удалили бота
+CREATE INDEX idx_reminders_chat_id ON reminders(chat_id) WHERE is_sent = FALSE;
@@ -0,0 +1,16 @@
+-- Quiet hours and do-not-disturb
+-- Тихие часы, режим «не беспокоить» и срочные напоминания
+
+-- Ежедневное окно тишины по местному времени пользователя
+-- (может переходить через полночь: 23:00-08:00)
+ALTER TABLE users ADD COLUMN quiet_start TIME;
+ALTER TABLE users ADD COLUMN quiet_end TIME;
+
+-- В тихие часы отправлять без звука, а не откладывать до конца окна
+ALTER TABLE users ADD COLUMN quiet_silent BOOLEAN NOT NULL DEFAULT FALSE;
+
+-- Временный режим «не беспокоить» (/dnd 2h)
+ALTER TABLE users ADD COLUMN dnd_until TIMESTAMPTZ;
+
+-- Срочные напоминания приходят и в тихие часы
+ALTER TABLE reminders ADD COLUMN is_urgent BOOLEAN NOT NULL DEFAULT FALSE;
@@ -0,0 +1,27 @@
+-- Reminder delivery log
+-- Журнал доставок напоминаний: у повторяющегося напоминания их много
+
+CREATE TABLE reminder_deliveries (
+    id SERIAL PRIMARY KEY,
+
+    -- Напоминание (NULL после отмены - история при этом сохраняется)
+    reminder_id INTEGER REFERENCES reminders(id) ON DELETE SET NULL,
+    user_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+
+    -- Текст на момент доставки
+    message TEXT,
+
+    -- На какое время было назначено (с учётом откладываний)
+    scheduled_for TIMESTAMPTZ NOT NULL,
+
+    status VARCHAR(20) NOT NULL,      -- sent, failed, skipped
+    delivered_at TIMESTAMPTZ,         -- Когда сообщение ушло в Telegram
+    attempt INTEGER NOT NULL DEFAULT 1,
+    snooze_count INTEGER NOT NULL DEFAULT 0,
+    error TEXT,
+
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- История пользователя, новые записи первыми
+CREATE INDEX idx_reminder_deliveries_user ON reminder_deliveries(user_id, created_at DESC);
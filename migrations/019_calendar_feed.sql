@@ -0,0 +1,4 @@
+-- Calendar feed
+-- Секретный токен ICS-ленты напоминаний (/calendar feed); NULL - лента выключена
+
+ALTER TABLE users ADD COLUMN calendar_token VARCHAR(64) UNIQUE;
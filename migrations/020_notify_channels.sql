@@ -0,0 +1,14 @@
+-- Notification channels
+-- Каналы доставки напоминаний: Telegram, email, HTTP-вебхук
+
+-- Каналы по умолчанию (/notify default) и адреса для email и вебхука
+ALTER TABLE users ADD COLUMN notify_channels TEXT[] NOT NULL DEFAULT '{telegram}';
+ALTER TABLE users ADD COLUMN email VARCHAR(254);
+ALTER TABLE users ADD COLUMN webhook_url TEXT;
+
+-- Каналы конкретного напоминания (/remind via ...); NULL - каналы владельца
+ALTER TABLE reminders ADD COLUMN channels TEXT[];
+
+-- Канал доставки в журнале; NULL - напоминание пропущено без отправки
+ALTER TABLE reminder_deliveries ADD COLUMN channel VARCHAR(20);
+UPDATE reminder_deliveries SET channel = 'telegram' WHERE status <> 'skipped';
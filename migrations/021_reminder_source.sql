@@ -0,0 +1,6 @@
+-- Reminder source message
+-- Сообщение с командой, из которого создано напоминание: ответ на него
+-- в том же чате или ссылка на него из канала
+
+ALTER TABLE reminders ADD COLUMN source_chat_id BIGINT;
+ALTER TABLE reminders ADD COLUMN source_message_id INTEGER;
@@ -0,0 +1,4 @@
+-- File conversion source name
+-- Имя исходного файла: результат отправляется под тем же именем с новым расширением
+
+ALTER TABLE file_conversions ADD COLUMN source_file_name TEXT;
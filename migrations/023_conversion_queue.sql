@@ -0,0 +1,14 @@
+-- Conversion job queue
+-- Очередь конвертаций: задания в file_conversions забирают воркеры, упавшие задания возвращаются в очередь
+
+-- Чат, куда отправляется результат
+ALTER TABLE file_conversions ADD COLUMN chat_id BIGINT;
+
+-- Попытки (считаются при захвате задания) и время следующей попытки после временной ошибки
+ALTER TABLE file_conversions ADD COLUMN attempts INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE file_conversions ADD COLUMN retry_at TIMESTAMPTZ;
+
+-- Воркер, который сейчас конвертирует файл, и срок аренды.
+-- Аренда упавшего экземпляра истекает, и задание забирает другой воркер
+ALTER TABLE file_conversions ADD COLUMN locked_by TEXT;
+ALTER TABLE file_conversions ADD COLUMN locked_until TIMESTAMPTZ;
@@ -0,0 +1,10 @@
+-- Todo sort keys not null
+-- Приоритет и дата создания - ключи сортировки и курсора страниц списка задач.
+-- Сравнение кортежей (priority, id) > (...) не находит строки с NULL, поэтому
+-- пустые значения заполняются значениями по умолчанию и запрещаются
+
+UPDATE todos SET priority = 3 WHERE priority IS NULL;
+UPDATE todos SET created_at = COALESCE(updated_at, CURRENT_TIMESTAMP) WHERE created_at IS NULL;
+
+ALTER TABLE todos ALTER COLUMN priority SET NOT NULL;
+ALTER TABLE todos ALTER COLUMN created_at SET NOT NULL;
@@ -0,0 +1,18 @@
+-- Email confirmation
+-- Адрес для email-напоминаний используется только после подтверждения кодом из письма
+
+-- Адрес, ожидающий подтверждения, код, время его отправки и число неверных попыток
+ALTER TABLE users ADD COLUMN pending_email VARCHAR(254);
+ALTER TABLE users ADD COLUMN email_code VARCHAR(6);
+ALTER TABLE users ADD COLUMN email_code_sent_at TIMESTAMPTZ;
+ALTER TABLE users ADD COLUMN email_code_attempts INTEGER NOT NULL DEFAULT 0;
+
+-- Заданные ранее адреса никто не подтверждал: их нужно задать заново.
+-- До этого напоминания идут в остальные каналы (или в Telegram)
+UPDATE users SET email = NULL WHERE email IS NOT NULL;
+UPDATE users
+SET notify_channels = COALESCE(NULLIF(array_remove(notify_channels, 'email'), '{}'), '{telegram}')
+WHERE 'email' = ANY(notify_channels);
+UPDATE reminders
+SET channels = NULLIF(array_remove(channels, 'email'), '{}')
+WHERE 'email' = ANY(channels);
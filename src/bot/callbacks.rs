@@ -1,30 +1,475 @@
 // Handle callback requests from inline buttons
+use sqlx::PgPool;
 use teloxide::prelude::*;
+use teloxide::types::ChatId;
+
+use crate::{
+    bot::commands::format_history,
+    bot::keyboards,
+    bot::state::{BotDialogue, State},
+    converter::queue::ConversionQueue,
+    converter::repository::ConversionRepository,
+    reminder::calendar,
+    reminder::notifier::ReminderNotifier,
+    reminder::repository::ReminderRepository,
+    reminder::service::ReminderService,
+    shared::time_parser::parse_reminder_time,
+    shared::types::TodoStatus,
+    shared::utils::format_datetime,
+    todo::repository::TodoRepository,
+    todo::service::TodoService,
+};
 
 pub type CallbackResult = Result<(), Box<dyn std::error::Error + Send + Sync>>;
 
-/// Handle callback for tasks
+/// Handle task buttons: complete_<id>, delete_<id>, remind_<id>
 pub async fn handle_todo_callback(
     bot: Bot,
     query: CallbackQuery,
+    pool: PgPool,
+    dialogue: BotDialogue,
+    data: String,
+) -> CallbackResult {
+    let (action, id) = data.split_once('_').ok_or("Malformed todo callback")?;
+    let todo_id = id.parse::<i32>()?;
+
+    let user: crate::db::models::User = sqlx::query_as(
+        "SELECT * FROM users WHERE telegram_id = $1",
+    )
+    .bind(query.from.id.0 as i64)
+    .fetch_one(&pool)
+    .await?;
+
+    let todo_service = TodoService::new(TodoRepository::new(pool.clone()));
+    let reminder_service = ReminderService::new(ReminderRepository::new(pool));
+    let todo = todo_service.get_todo(todo_id).await?;
+
+    // Check ownership
+    if todo.user_id != user.id {
+        bot.answer_callback_query(&query.id)
+            .text("❌ This is not your task!")
+            .await?;
+        return Ok(());
+    }
+
+    let answer = match action {
+        "complete" => {
+            todo_service.complete_todo(todo_id).await?;
+            reminder_service.cancel_todo_reminders(todo_id).await?;
+            format!("✅ Task #{} marked as completed!", todo_id)
+        }
+        "delete" => {
+            todo_service.delete_todo(todo_id).await?;
+            format!("🗑 Task #{} deleted!", todo_id)
+        }
+        "remind" => {
+            dialogue
+                .update(State::ReceivingTodoReminderTime { todo_id })
+                .await?;
+            bot.answer_callback_query(&query.id).await?;
+            if let Some(message) = query.message {
+                bot.send_message(
+                    message.chat.id,
+                    format!(
+                        "⏰ When should I remind you about task #{}?\n\n\
+                         Examples: 2h, tomorrow 9am, 1d before due",
+                        todo_id
+                    ),
+                )
+                .await?;
+            }
+            return Ok(());
+        }
+        _ => return Err("Unknown todo action".into()),
+    };
+
+    bot.answer_callback_query(&query.id).text(answer).await?;
+    Ok(())
+}
+
+/// Handle weekly review buttons: review_postpone_<id>, review_drop_<id>
+pub async fn handle_review_callback(
+    bot: Bot,
+    query: CallbackQuery,
+    pool: PgPool,
+    data: String,
+) -> CallbackResult {
+    let (action, id) = data
+        .strip_prefix("review_")
+        .and_then(|rest| rest.split_once('_'))
+        .ok_or("Malformed review callback")?;
+    let todo_id = id.parse::<i32>()?;
+
+    let user: crate::db::models::User = sqlx::query_as(
+        "SELECT * FROM users WHERE telegram_id = $1",
+    )
+    .bind(query.from.id.0 as i64)
+    .fetch_one(&pool)
+    .await?;
+
+    let todo_service = TodoService::new(TodoRepository::new(pool.clone()));
+    let reminder_service = ReminderService::new(ReminderRepository::new(pool));
+    let todo = todo_service.get_todo(todo_id).await?;
+
+    // Check ownership
+    if todo.user_id != user.id {
+        bot.answer_callback_query(&query.id)
+            .text("❌ This is not your task!")
+            .await?;
+        return Ok(());
+    }
+
+    let answer = match action {
+        "postpone" => {
+            let todo = todo_service.postpone_todo(todo_id, chrono::Utc::now()).await?;
+            reminder_service
+                .sync_due_reminders(&todo, &user.due_reminder_offsets)
+                .await?;
+            let due = todo
+                .due_date
+                .map(|d| d.with_timezone(&user.tz()).format("%d.%m.%Y").to_string());
+            format!("📅 Task #{} is now due {}", todo_id, due.unwrap_or_default())
+        }
+        "drop" => {
+            todo_service.change_status(todo_id, TodoStatus::Cancelled).await?;
+            reminder_service.cancel_todo_reminders(todo_id).await?;
+            format!("🗑 Task #{} dropped", todo_id)
+        }
+        _ => return Err("Unknown review action".into()),
+    };
+
+    bot.answer_callback_query(&query.id).text(answer).await?;
+    Ok(())
+}
+
+/// Page buttons of /reminderhistory: history_<page>
+pub async fn handle_history_callback(
+    bot: Bot,
+    query: CallbackQuery,
+    pool: PgPool,
     data: String,
 ) -> CallbackResult {
+    let page = data
+        .strip_prefix("history_")
+        .and_then(|page| page.parse::<i64>().ok())
+        .ok_or("Malformed history callback")?;
+
+    let user: crate::db::models::User = sqlx::query_as(
+        "SELECT * FROM users WHERE telegram_id = $1",
+    )
+    .bind(query.from.id.0 as i64)
+    .fetch_one(&pool)
+    .await?;
+
+    let reminder_service = ReminderService::new(ReminderRepository::new(pool));
+    let history = reminder_service.history(user.id, page).await?;
+
+    bot.answer_callback_query(&query.id).await?;
+    if let Some(message) = query.message {
+        bot.edit_message_text(message.chat.id, message.id, format_history(&history, user.tz()))
+            .reply_markup(keyboards::history_pages(history.page, history.pages))
+            .await?;
+    }
+    Ok(())
+}
+
+/// Preview buttons of an uploaded calendar: calendar_import, calendar_cancel
+pub async fn handle_calendar_callback(
+    bot: Bot,
+    query: CallbackQuery,
+    pool: PgPool,
+    dialogue: BotDialogue,
+    data: String,
+) -> CallbackResult {
+    let Some(State::ConfirmingCalendarImport { reminders }) = dialogue.get().await? else {
+        bot.answer_callback_query(&query.id)
+            .text("This preview has expired, send the file again")
+            .await?;
+        return Ok(());
+    };
+    dialogue.exit().await?;
+
+    let answer = if data == "calendar_import" {
+        let user: crate::db::models::User = sqlx::query_as(
+            "SELECT * FROM users WHERE telegram_id = $1",
+        )
+        .bind(query.from.id.0 as i64)
+        .fetch_one(&pool)
+        .await?;
+
+        let chat_id = query
+            .message
+            .as_ref()
+            .map_or(user.telegram_id, |message| message.chat.id.0);
+        let reminder_service = ReminderService::new(ReminderRepository::new(pool));
+        let created = calendar::import(&reminder_service, user.id, chat_id, reminders).await;
+        format!("✅ Imported {} reminder(s), see /listreminders", created)
+    } else {
+        "Import cancelled".to_string()
+    };
+
     bot.answer_callback_query(&query.id).await?;
+    if let Some(message) = query.message {
+        bot.edit_message_reply_markup(message.chat.id, message.id)
+            .await?;
+        bot.send_message(message.chat.id, answer).await?;
+    }
+    Ok(())
+}
+
+/// Targets of the conversion menu the converter supports
+const CONVERSION_TARGETS: [&str; 4] = ["png", "jpeg", "webp", "compress"];
+
+/// Parse convert_<id>_<target>
+fn parse_conversion_callback(data: &str) -> Option<(i32, &'static str)> {
+    let (id, target) = data.strip_prefix("convert_")?.split_once('_')?;
+    let target = CONVERSION_TARGETS.into_iter().find(|t| *t == target)?;
+    Some((id.parse().ok()?, target))
+}
+
+/// Conversion menu buttons: convert_<id>_<target>
+///
+/// The job is queued and a worker sends the result back as a document. The
+/// menu stays, so the same file can be converted to another format.
+pub async fn handle_conversion_callback(
+    bot: Bot,
+    query: CallbackQuery,
+    pool: PgPool,
+    queue: ConversionQueue,
+    data: String,
+) -> CallbackResult {
+    let Some((conversion_id, target)) = parse_conversion_callback(&data) else {
+        bot.answer_callback_query(&query.id)
+            .text("❌ This format is not supported")
+            .await?;
+        return Ok(());
+    };
+    let Some(chat_id) = query.message.as_ref().map(|message| message.chat.id) else {
+        bot.answer_callback_query(&query.id).await?;
+        return Ok(());
+    };
+
+    let user: crate::db::models::User = sqlx::query_as(
+        "SELECT * FROM users WHERE telegram_id = $1",
+    )
+    .bind(query.from.id.0 as i64)
+    .fetch_one(&pool)
+    .await?;
+
+    match ConversionRepository::new(pool).find_by_id(conversion_id).await {
+        Ok(conversion) if conversion.user_id == user.id => {}
+        _ => {
+            bot.answer_callback_query(&query.id)
+                .text("❌ This file is no longer available, send it again")
+                .await?;
+            return Ok(());
+        }
+    }
+
+    let answer = match queue.enqueue(conversion_id, target, chat_id).await? {
+        Some(_) => "⏳ Converting…",
+        None => "⏳ This file is still being converted",
+    };
+    bot.answer_callback_query(&query.id).text(answer).await?;
+    Ok(())
+}
+
+/// Button pressed under a delivered reminder
+#[derive(Debug, PartialEq)]
+enum ReminderAction<'a> {
+    /// Snooze by a preset understood by `parse_reminder_time` (10m, 1h, tomorrow)
+    Snooze(&'a str),
+    /// Ask the user for a snooze time
+    CustomSnooze,
+    Done,
+    /// Stop an insistent reminder
+    Acknowledge,
+    /// Ask the user for a new time (reminder list)
+    Reschedule,
+}
+
+/// Parse snooze_<id>_<when>, reminder_done_<id>, reminder_ack_<id> and
+/// reminder_reschedule_<id>
+fn parse_reminder_callback(data: &str) -> Option<(i32, ReminderAction<'_>)> {
+    if let Some(id) = data.strip_prefix("reminder_done_") {
+        return Some((id.parse().ok()?, ReminderAction::Done));
+    }
+    if let Some(id) = data.strip_prefix("reminder_ack_") {
+        return Some((id.parse().ok()?, ReminderAction::Acknowledge));
+    }
+    if let Some(id) = data.strip_prefix("reminder_reschedule_") {
+        return Some((id.parse().ok()?, ReminderAction::Reschedule));
+    }
 
-    if data.starts_with("complete_") {
-        let todo_id = data.strip_prefix("complete_").unwrap().parse::<i32>()?;
-        // Task completion logic
-        if let Some(Message { chat, .. }) = query.message {
-            bot.send_message(chat.id, format!("✅ Заyesча #{} отмечена как выполненная!", todo_id))
+    let (id, when) = data.strip_prefix("snooze_")?.split_once('_')?;
+    let action = match when {
+        "custom" => ReminderAction::CustomSnooze,
+        "10m" | "1h" | "tomorrow" => ReminderAction::Snooze(when),
+        _ => return None,
+    };
+    Some((id.parse().ok()?, action))
+}
+
+/// Handle buttons under a delivered reminder: snooze_<id>_<when>,
+/// reminder_done_<id>, reminder_ack_<id>; and under the reminder list:
+/// reminder_reschedule_<id>
+pub async fn handle_reminder_callback(
+    bot: Bot,
+    query: CallbackQuery,
+    pool: PgPool,
+    dialogue: BotDialogue,
+    data: String,
+) -> CallbackResult {
+    let (reminder_id, action) =
+        parse_reminder_callback(&data).ok_or("Malformed reminder callback")?;
+
+    // In groups anyone can press the buttons, registered or not
+    let user: Option<crate::db::models::User> = sqlx::query_as(
+        "SELECT * FROM users WHERE telegram_id = $1",
+    )
+    .bind(query.from.id.0 as i64)
+    .fetch_optional(&pool)
+    .await?;
+
+    let reminder_service = ReminderService::new(ReminderRepository::new(pool.clone()));
+    let reminder = ReminderRepository::new(pool.clone())
+        .find_by_id(reminder_id)
+        .await?;
+
+    // Check ownership
+    let Some(user) = user.filter(|user| user.id == reminder.user_id) else {
+        bot.answer_callback_query(&query.id)
+            .text("❌ This is not your reminder!")
+            .await?;
+        return Ok(());
+    };
+
+    let answer = match action {
+        ReminderAction::Snooze(when) => {
+            let until = parse_reminder_time(when, &user.now()).ok_or("Unknown snooze preset")?;
+            reminder_service
+                .snooze(reminder_id, until.with_timezone(&chrono::Utc))
                 .await?;
+            format!("😴 Snoozed until {}", format_datetime(&until))
         }
-    } else if data.starts_with("delete_") {
-        let todo_id = data.strip_prefix("delete_").unwrap().parse::<i32>()?;
-        if let Some(Message { chat, .. }) = query.message {
-            bot.send_message(chat.id, format!("🗑 Заyesча #{} уyesлена!", todo_id))
+        ReminderAction::CustomSnooze => {
+            dialogue
+                .update(State::ReceivingSnoozeTime { reminder_id })
                 .await?;
+            bot.answer_callback_query(&query.id).await?;
+            if let Some(message) = query.message {
+                bot.send_message(
+                    message.chat.id,
+                    "⏰ When should I remind you again?\n\nExamples: 20m, 1h30m, 18:00, tomorrow 9am",
+                )
+                .await?;
+            }
+            return Ok(());
         }
+        ReminderAction::Reschedule => {
+            if reminder.is_sent {
+                bot.answer_callback_query(&query.id)
+                    .text("This reminder has already fired")
+                    .await?;
+                return Ok(());
+            }
+            dialogue
+                .update(State::ReceivingRescheduleTime { reminder_id })
+                .await?;
+            bot.answer_callback_query(&query.id).await?;
+            if let Some(message) = query.message {
+                bot.send_message(
+                    message.chat.id,
+                    format!(
+                        "🕐 New time for reminder #{}?\n\nExamples: 20m, 18:00, tomorrow 9am, 20.10 14:30",
+                        reminder_id
+                    ),
+                )
+                .await?;
+            }
+            return Ok(());
+        }
+        ReminderAction::Done => {
+            reminder_service.mark_done(&reminder, user.tz()).await?;
+
+            let todo_service = TodoService::new(TodoRepository::new(pool));
+            match reminder.todo_id {
+                Some(todo_id) if todo_service.get_todo(todo_id).await?.user_id == user.id => {
+                    todo_service.complete_todo(todo_id).await?;
+                    reminder_service.cancel_todo_reminders(todo_id).await?;
+                    format!("✅ Done! Task #{} completed", todo_id)
+                }
+                _ => "✅ Done!".to_string(),
+            }
+        }
+        ReminderAction::Acknowledge => match reminder_service.acknowledge(reminder_id).await? {
+            Some(acknowledged) => {
+                // Let the escalation chat know the alarm is handled
+                if let (Some(chat_id), Some(_)) =
+                    (acknowledged.escalate_to, acknowledged.escalated_at)
+                {
+                    ReminderNotifier::new(bot.clone())
+                        .send_acknowledged(&acknowledged, &user, ChatId(chat_id))
+                        .await?;
+                }
+                "✅ Acknowledged".to_string()
+            }
+            None => "Already acknowledged".to_string(),
+        },
+    };
+
+    // Buttons of a handled reminder are no longer valid
+    if let Some(message) = &query.message {
+        bot.edit_message_reply_markup(message.chat.id, message.id)
+            .await?;
     }
 
+    bot.answer_callback_query(&query.id).text(answer).await?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_conversion_callback() {
+        assert_eq!(parse_conversion_callback("convert_7_webp"), Some((7, "webp")));
+        assert_eq!(parse_conversion_callback("convert_7_compress"), Some((7, "compress")));
+        assert_eq!(parse_conversion_callback("convert_7_pdf"), None);
+        assert_eq!(parse_conversion_callback("convert_x_png"), None);
+    }
+
+    #[test]
+    fn test_parse_reminder_callback() {
+        assert_eq!(
+            parse_reminder_callback("snooze_42_10m"),
+            Some((42, ReminderAction::Snooze("10m")))
+        );
+        assert_eq!(
+            parse_reminder_callback("snooze_7_tomorrow"),
+            Some((7, ReminderAction::Snooze("tomorrow")))
+        );
+        assert_eq!(
+            parse_reminder_callback("snooze_7_custom"),
+            Some((7, ReminderAction::CustomSnooze))
+        );
+        assert_eq!(
+            parse_reminder_callback("reminder_done_3"),
+            Some((3, ReminderAction::Done))
+        );
+        assert_eq!(
+            parse_reminder_callback("reminder_ack_5"),
+            Some((5, ReminderAction::Acknowledge))
+        );
+
+        assert_eq!(
+            parse_reminder_callback("reminder_reschedule_8"),
+            Some((8, ReminderAction::Reschedule))
+        );
+
+        assert_eq!(parse_reminder_callback("snooze_7_next year"), None);
+        assert_eq!(parse_reminder_callback("snooze_x_10m"), None);
+        assert_eq!(parse_reminder_callback("reminder_done_"), None);
+    }
+}
@@ -1,38 +1,87 @@
 // Bot command implementations
+use teloxide::net::Download;
 use teloxide::prelude::*;
-use teloxide::types::ParseMode;
+use teloxide::types::{InputFile, KeyboardRemove, Location, ParseMode, Recipient};
+use chrono_tz::Tz;
 use sqlx::PgPool;
 
 use crate::{
-    bot::keyboards,
+    bot::{callbacks, keyboards, state::{BotDialogue, State}},
+    config::{AppConfig, NotifyConfig},
+    converter::repository::ConversionRepository,
+    converter::queue::ConversionQueue,
+    converter::service::image_format,
+    db::models::NewFileConversion,
+    todo::review,
     todo::service::TodoService,
     todo::repository::TodoRepository,
+    reminder::calendar::{plan_import, ImportPlan},
+    reminder::channels::{self, missing_setup},
+    reminder::models::{HistoryPage, NagPolicy, Reminder, ReminderEdit, ReminderTarget, SourceMessage},
     reminder::service::ReminderService,
     reminder::repository::ReminderRepository,
+    shared::cron::{split_cron_command, CronSchedule},
+    shared::duration::parse_duration,
+    shared::ical::parse_calendar,
+    shared::time_parser::{
+        parse_before_due, parse_reminder_time, parse_time_range, split_reminder_time,
+    },
+    shared::timezone::{self, parse_timezone},
+    shared::types::{DeliveryStatus, NotifyChannel, RecurrencePattern},
+    shared::utils::{
+        escape_markdown, format_datetime, format_latency, parse_clock_time, parse_edit_args,
+        parse_nag_options, parse_quick_add, parse_recurring, split_channels, split_mentions,
+        split_urgent, truncate_text,
+    },
+    todo::handlers::format_minutes,
 };
 
 type HandlerResult = Result<(), Box<dyn std::error::Error + Send + Sync>>;
 
+/// Number of upcoming runs shown after /cron
+const CRON_PREVIEW_RUNS: usize = 5;
+
+/// Most automatic reminders per task (/duereminders)
+const MAX_DUE_OFFSETS: usize = 5;
+
+/// Largest .ics file accepted for import
+const MAX_CALENDAR_FILE_BYTES: u32 = 1024 * 1024;
+
 /// /start - welcome and register user
 pub async fn start(bot: Bot, msg: Message, pool: PgPool) -> HandlerResult {
     // SAFE: check user exists
     let user = msg.from().ok_or("No user in message")?;
 
     // Register user in DB (if not already registered)
-    sqlx::query(
+    let (user_id, was_blocked): (i32, bool) = sqlx::query_as(
         r#"
         INSERT INTO users (telegram_id, username, first_name)
         VALUES ($1, $2, $3)
         ON CONFLICT (telegram_id)
         DO UPDATE SET last_active_at = CURRENT_TIMESTAMP
+        RETURNING id, is_blocked
         "#,
     )
     .bind(user.id.0 as i64)
     .bind(user.username.as_deref())
     .bind(user.first_name.as_str())
-    .execute(&pool)
+    .fetch_one(&pool)
     .await?;
 
+    // Back after blocking the bot: deliver paused reminders again
+    let reminder_repo = ReminderRepository::new(pool.clone());
+    let resumed = if was_blocked {
+        reminder_repo.unblock_user(user_id).await?
+    } else {
+        0
+    };
+    // Re-added to a group
+    let resumed = if msg.chat.id.is_user() {
+        resumed
+    } else {
+        resumed + reminder_repo.resume_chat(msg.chat.id.0).await?
+    };
+
     let welcome_text = format!(
         "👋 Hello, {}\\!\n\n\
         I am a multifunctional assistant bot\\.\n\n\
@@ -43,6 +92,11 @@ pub async fn start(bot: Bot, msg: Message, pool: PgPool) -> HandlerResult {
         Use /help to see all commands\\.",
         user.first_name.replace("!", "\\!")
     );
+    let welcome_text = if resumed > 0 {
+        format!("{}\n\n▶️ {} paused reminder\\(s\\) resumed\\.", welcome_text, resumed)
+    } else {
+        welcome_text
+    };
 
     bot.send_message(msg.chat.id, welcome_text)
         .parse_mode(ParseMode::MarkdownV2)
@@ -58,6 +112,8 @@ pub async fn help(bot: Bot, msg: Message) -> HandlerResult {
 
 Task Management:
 /addtodo <text> \- add new task
+  Example: /addtodo Fix login bug \!1 \#backend @tomorrow 18:00 \~2h
+  Markers: \!1\-\!5 priority, \#tag, @date \[HH:MM\], \~estimate
 /listtodos \- show all tasks
 /completetodo <id> \- mark task as completed
 /deletetodo <id> \- delete task
@@ -65,9 +121,45 @@ Task Management:
 Reminders:
 /remind <time> <text> \- set reminder
   Example: /remind 15m Check email
-  Formats: 5m \(minutes\), 2h \(hours\), 1d \(days\)
+  Formats: 45s, 5m, 1h30m, 2d4h, 2w, in 2 weeks, at 18:00, tomorrow 9am, next monday, 20\.10 14:30
+  Russian: завтра в 9, через 2 часа, в пятницу в 7 вечера
+  Recurring: /remind every day 09:00 Standup
+  Rules: day, weekday, week, monday or mon,wed,fri, month on 15, year on 14\.03
+  Urgent: /remind urgent 22:00 Take pills \- rings through quiet hours
+  Channels: /remind via email,telegram 18:00 Pay rent \- see /notify
+/remindtodo <id> <when> \- remind about a task
+  Example: /remindtodo 12 tomorrow 9am or /remindtodo 12 1d before due
+/duereminders 1d 2h \- remind before every due date, /duereminders off to stop
 /listreminders \- show active reminders
 /cancelreminder <id> \- cancel reminder
+/reminderhistory \[page\] \- delivered, failed and skipped reminders
+/editreminder <id> time\=<when> text\=<text> repeat\=<rule\|off> \- change a reminder
+  Example: /editreminder 12 time\=tomorrow 9am repeat\=every weekday
+/calendar \- upcoming reminders and due dates as an \.ics file; send me an \.ics file to import events
+/calendar feed \- subscription link for calendar apps, /calendar feed off to revoke it
+/cron "<expr>" <text> \- cron schedule
+  Example: /cron "0 9 \* \* 1\-5" Check dashboards
+/stoprepeat <id> \- end a recurring series
+/nag \[every 5m\] \[max 6\] \[escalate @user\] <time> <text> \- repeat until acknowledged
+  Example: /nag every 10m escalate @teamlead 22:00 Hand over on\-call
+/remindchannel \[@channel\] <time> <text> \- post to a channel you administer
+  In a group, reminders fire in the group; start the text with @user to mention people
+
+Settings:
+/timezone <zone> \- set time zone, e\.g\. Europe/Moscow or UTC\+3
+/timezone \- show current zone or share location
+/quiet 23:00\-08:00 \[silent\] \- quiet hours: reminders wait until they end \(or arrive without sound\), /quiet off to stop
+/dnd 2h \- do not disturb for a while, /dnd off to end it
+/notify \- where reminders go: Telegram, email or a webhook
+/notify default telegram,email \- default channels; /notify email <address\|off> \(then /notify email confirm <code> from the email\), /notify webhook <url\|off>
+
+File Conversion:
+Send me a photo or an image file \(PNG, JPEG, GIF, WebP, BMP\) and pick a format: PNG, JPEG, WebP or a compressed JPEG
+
+Weekly Review:
+/review \- summary of the last 7 days
+/review on\|off \- automatic review every Friday
+/track <id> <time> \- record time spent on a task
 
 General:
 /start \- start bot
@@ -105,18 +197,46 @@ pub async fn add_todo(bot: Bot, msg: Message, pool: PgPool, text: String) -> Han
     .fetch_one(&pool)
     .await?;
 
+    // Parse quick-add markers: !1 #tag @tomorrow 18:00 ~2h
+    let quick = parse_quick_add(&text, user.now());
+    if quick.title.is_empty() {
+        bot.send_message(msg.chat.id, "❌ Task text cannot be empty!")
+            .await?;
+        return Ok(());
+    }
+
     // Create task
-    let todo_repo = TodoRepository::new(pool);
+    let todo_repo = TodoRepository::new(pool.clone());
     let todo_service = TodoService::new(todo_repo);
-    let todo = todo_service.create_todo(user.id, text, None, Some(3)).await?;
+    let todo = todo_service.create_quick_todo(user.id, quick).await?;
 
-    bot.send_message(
-        msg.chat.id,
-        format!("✅ Task added\\!\n\n📝 {}\n🆔 ID: {}",
-            todo.title.replace("-", "\\-").replace(".", "\\.").replace("!", "\\!"),
-            todo.id
-        ),
-    )
+    let reminder_service = ReminderService::new(ReminderRepository::new(pool));
+    let due_reminders = reminder_service
+        .sync_due_reminders(&todo, &user.due_reminder_offsets)
+        .await?;
+
+    // Echo what was recognized so typos in markers are easy to spot
+    let mut text = format!("✅ Task added\\!\n\n📝 {}\n", escape_markdown(&todo.title));
+    if todo.priority != 3 {
+        text.push_str(&format!("{} Priority: {}\n", todo.priority_emoji(), todo.priority));
+    }
+    if !todo.tags.is_empty() {
+        let tags: Vec<String> = todo.tags.iter().map(|t| format!("\\#{}", escape_markdown(t))).collect();
+        text.push_str(&format!("🏷 {}\n", tags.join(" ")));
+    }
+    if let Some(due) = todo.due_date {
+        let due = format_datetime(&due.with_timezone(&user.tz()));
+        text.push_str(&format!("📅 Due: {}\n", escape_markdown(&due)));
+    }
+    if due_reminders > 0 {
+        text.push_str(&format!("⏳ Reminders before due: {}\n", due_reminders));
+    }
+    if let Some(estimate) = todo.estimate_minutes {
+        text.push_str(&format!("⏱ Estimate: {}\n", format_minutes(estimate as i64)));
+    }
+    text.push_str(&format!("🆔 ID: {}", todo.id));
+
+    bot.send_message(msg.chat.id, text)
     .parse_mode(ParseMode::MarkdownV2)
     .reply_markup(keyboards::todo_actions(todo.id))
     .await?;
@@ -194,9 +314,13 @@ pub async fn complete_todo(bot: Bot, msg: Message, pool: PgPool, id: i32) -> Han
     }
 
     // Update status via repository directly
-    let repo2 = TodoRepository::new(pool);
+    let repo2 = TodoRepository::new(pool.clone());
     repo2.mark_completed(id).await?;
 
+    ReminderService::new(ReminderRepository::new(pool))
+        .cancel_todo_reminders(id)
+        .await?;
+
     bot.send_message(msg.chat.id, format!("✅ Task #{} marked as completed!", id))
         .await?;
 
@@ -234,20 +358,80 @@ pub async fn delete_todo(bot: Bot, msg: Message, pool: PgPool, id: i32) -> Handl
 
 /// /remind - set reminder
 pub async fn set_reminder(bot: Bot, msg: Message, pool: PgPool, text: String) -> HandlerResult {
-    // Parse format: "15m Check email" or "2h Meeting"
-    let parts: Vec<&str> = text.splitn(2, ' ').collect();
+    let chat = msg.chat.id;
+    schedule_reminder(bot, msg, pool, text, chat).await
+}
 
-    if parts.len() < 2 {
-        bot.send_message(
-            msg.chat.id,
-            "❌ Invalid format!\n\nUse: /remind <time> <text>\nExample: /remind 15m Check email",
-        )
-        .await?;
+/// Create reminder from /remind arguments, delivered to `chat`
+async fn schedule_reminder(
+    bot: Bot,
+    msg: Message,
+    pool: PgPool,
+    text: String,
+    chat: ChatId,
+) -> HandlerResult {
+    // "urgent 22:00 Take pills" rings through quiet hours
+    let (urgent, text) = split_urgent(&text);
+
+    // "via email,telegram 18:00 Pay rent" picks the delivery channels
+    let (channels, text) = match split_channels(&text) {
+        Ok(split) => split,
+        Err(e) => {
+            bot.send_message(msg.chat.id, format!("❌ {}", e.user_message()))
+                .await?;
+            return Ok(());
+        }
+    };
+
+    // Recurring format: "every day 09:00 Standup"
+    if text.to_lowercase().starts_with("every ") {
+        return set_recurring_reminder(bot, msg, pool, text, chat, urgent, channels).await;
+    }
+
+    let user_id = msg.from().ok_or("No user in message")?.id.0 as i64;
+    let user: crate::db::models::User = sqlx::query_as(
+        "SELECT * FROM users WHERE telegram_id = $1",
+    )
+    .bind(user_id)
+    .fetch_one(&pool)
+    .await?;
+
+    if let Some(problem) = channels.as_deref().and_then(|c| missing_setup(&user, c)) {
+        bot.send_message(msg.chat.id, format!("❌ {}", problem)).await?;
         return Ok(());
     }
 
-    let time_str = parts[0];
-    let reminder_text = parts[1];
+    // Parse format: "15m Check email", "tomorrow 9am Call mom", "через 2 часа Позвонить"
+    // in the user's time zone
+    let now = user.now();
+    let (remind_at, reminder_text) = match split_reminder_time(&text, &now) {
+        Some((remind_at, message)) if !message.is_empty() => (remind_at.with_timezone(&chrono::Utc), message),
+        // "/remind 5x Text": explain what is wrong with the duration
+        _ if looks_like_duration(text.split_whitespace().next().unwrap_or_default()) => {
+            let first = text.split_whitespace().next().unwrap_or_default();
+            let problem = match parse_duration(first) {
+                Err(e) => e.user_message(),
+                Ok(_) => "Reminder text cannot be empty".to_string(),
+            };
+            bot.send_message(msg.chat.id, format!("❌ {}\n\nExample: /remind 1h30m Check email", problem))
+                .await?;
+            return Ok(());
+        }
+        _ => {
+            bot.send_message(
+                msg.chat.id,
+                "❌ Invalid format!\n\n\
+                 Use: /remind <time> <text>\n\
+                 Examples:\n\
+                 /remind 15m Check email\n\
+                 /remind tomorrow 9am Call mom\n\
+                 /remind 20.10 14:30 Dentist\n\
+                 /remind через 2 часа Позвонить",
+            )
+            .await?;
+            return Ok(());
+        }
+    };
 
     // VALIDATION: limit reminder text length
     if reminder_text.len() > 500 {
@@ -256,29 +440,29 @@ pub async fn set_reminder(bot: Bot, msg: Message, pool: PgPool, text: String) ->
         return Ok(());
     }
 
-    // Parse time
-    let duration = parse_duration(time_str)?;
-    let _remind_time = chrono::Utc::now() + duration;
-
-    let user_id = msg.from().ok_or("No user in message")?.id.0 as i64;
-    let user: crate::db::models::User = sqlx::query_as(
-        "SELECT * FROM users WHERE telegram_id = $1",
-    )
-    .bind(user_id)
-    .fetch_one(&pool)
-    .await?;
+    if remind_at <= now {
+        bot.send_message(msg.chat.id, "❌ This time has already passed!")
+            .await?;
+        return Ok(());
+    }
 
+    let (mut target, reminder_text) = reminder_target(chat, &msg, reminder_text);
+    target.urgent = urgent;
+    target.channels = channels;
     let reminder_repo = ReminderRepository::new(pool);
     let reminder_service = ReminderService::new(reminder_repo);
     let reminder = reminder_service
-        .create_reminder(user.id, None, &format!("{}m", duration.num_minutes()), Some(reminder_text.to_string()))
+        .create_reminder_at(user.id, None, remind_at, Some(reminder_text.clone()), target)
         .await?;
 
     bot.send_message(
         msg.chat.id,
         format!(
-            "⏰ Reminder set!\n\n📝 {}\n🕐 Will remind in {}\n🆔 ID: {}",
-            reminder_text, time_str, reminder.id
+            "⏰ Reminder set!\n\n📝 {}\n🕐 {}{}\n🆔 ID: {}",
+            reminder_text,
+            format_datetime(&reminder.remind_at.with_timezone(&user.tz())),
+            describe_target(&reminder, msg.chat.id),
+            reminder.id
         ),
     )
     .await?;
@@ -286,10 +470,115 @@ pub async fn set_reminder(bot: Bot, msg: Message, pool: PgPool, text: String) ->
     Ok(())
 }
 
-/// /listreminders - show active reminders
-pub async fn list_reminders(bot: Bot, msg: Message, pool: PgPool) -> HandlerResult {
-    let user_id = msg.from().ok_or("No user in message")?.id.0 as i64;
+/// /remindchannel - post a reminder to a channel:
+/// /remindchannel [@channel|chat id] <time> <text>
+///
+/// Without a channel, the linked channel of the current group is used. Both
+/// the sender and the bot have to be allowed to post there.
+pub async fn remind_channel(bot: Bot, msg: Message, pool: PgPool, text: String) -> HandlerResult {
+    let from = msg.from().ok_or("No user in message")?.id;
+    let usage = "❌ Invalid format!\n\n\
+                 Use: /remindchannel [@channel] <time> <text>\n\
+                 Examples:\n\
+                 /remindchannel @mychannel tomorrow 10:00 Release notes are out\n\
+                 /remindchannel every monday 09:00 Weekly digest (in a group with a linked channel)";
+
+    let text = text.trim();
+    let (channel, rest) = match text.split_once(char::is_whitespace) {
+        Some((first, rest)) if first.starts_with('@') || first.starts_with('-') => {
+            (Some(first), rest.trim())
+        }
+        _ => (None, text),
+    };
+    if rest.is_empty() {
+        bot.send_message(msg.chat.id, usage).await?;
+        return Ok(());
+    }
+
+    let recipient = match channel {
+        Some(name) if name.starts_with('@') => Recipient::ChannelUsername(name.to_string()),
+        Some(id) => match id.parse() {
+            Ok(id) => Recipient::Id(ChatId(id)),
+            Err(_) => {
+                bot.send_message(msg.chat.id, usage).await?;
+                return Ok(());
+            }
+        },
+        None => match bot.get_chat(msg.chat.id).await?.linked_chat_id() {
+            Some(id) => Recipient::Id(ChatId(id)),
+            None => {
+                bot.send_message(
+                    msg.chat.id,
+                    format!("❌ This chat has no linked channel, name one.\n\n{}", usage),
+                )
+                .await?;
+                return Ok(());
+            }
+        },
+    };
+
+    let channel = match bot.get_chat(recipient).await {
+        Ok(chat) if chat.is_channel() => chat,
+        _ => {
+            bot.send_message(
+                msg.chat.id,
+                "❌ Channel not found. Add the bot to the channel as an administrator first.",
+            )
+            .await?;
+            return Ok(());
+        }
+    };
+    let title = channel.title().unwrap_or("the channel").to_string();
+
+    let sender = bot.get_chat_member(channel.id, from).await;
+    if !sender.is_ok_and(|member| member.kind.is_privileged()) {
+        bot.send_message(
+            msg.chat.id,
+            format!("❌ Only administrators of {} can schedule reminders there.", title),
+        )
+        .await?;
+        return Ok(());
+    }
+
+    let me = bot.get_me().await?;
+    let own = bot.get_chat_member(channel.id, me.id).await;
+    if !own.is_ok_and(|member| member.kind.can_post_messages()) {
+        bot.send_message(
+            msg.chat.id,
+            format!(
+                "❌ I cannot post in {}: make me an administrator with the right to post messages.",
+                title
+            ),
+        )
+        .await?;
+        return Ok(());
+    }
+
+    schedule_reminder(bot, msg, pool, rest.to_string(), channel.id).await
+}
+
+/// /remindtodo - reminder about a task: /remindtodo <id> <when>
+pub async fn remind_todo(bot: Bot, msg: Message, pool: PgPool, text: String) -> HandlerResult {
+    let parsed = text
+        .trim()
+        .split_once(char::is_whitespace)
+        .and_then(|(id, when)| Some((id.trim_start_matches('#').parse::<i32>().ok()?, when)));
+
+    let Some((todo_id, when)) = parsed else {
+        bot.send_message(
+            msg.chat.id,
+            "❌ Invalid format!\n\n\
+             Use: /remindtodo <id> <when>\n\
+             Examples:\n\
+             /remindtodo 12 2h\n\
+             /remindtodo 12 tomorrow 9am\n\
+             /remindtodo 12 1d before due",
+        )
+        .await?;
+        return Ok(());
+    };
 
+    let user_id = msg.from().ok_or("No user in message")?.id.0 as i64;
     let user: crate::db::models::User = sqlx::query_as(
         "SELECT * FROM users WHERE telegram_id = $1",
     )
@@ -297,37 +586,111 @@ pub async fn list_reminders(bot: Bot, msg: Message, pool: PgPool) -> HandlerResu
     .fetch_one(&pool)
     .await?;
 
-    let reminder_repo = ReminderRepository::new(pool);
-    let reminders = reminder_repo.find_by_user(user.id).await?;
+    create_todo_reminder(&bot, msg.chat.id, &pool, &user, todo_id, when).await?;
 
-    if reminders.is_empty() {
-        bot.send_message(msg.chat.id, "⏰ You have no active reminders.")
-            .await?;
-        return Ok(());
+    Ok(())
+}
+
+/// Reply to the task "Remind" button: when to remind
+pub async fn receive_todo_reminder_time(
+    bot: Bot,
+    msg: Message,
+    pool: PgPool,
+    dialogue: BotDialogue,
+    todo_id: i32,
+) -> HandlerResult {
+    let user_id = msg.from().ok_or("No user in message")?.id.0 as i64;
+    let user: crate::db::models::User = sqlx::query_as(
+        "SELECT * FROM users WHERE telegram_id = $1",
+    )
+    .bind(user_id)
+    .fetch_one(&pool)
+    .await?;
+
+    let when = msg.text().unwrap_or_default();
+    if create_todo_reminder(&bot, msg.chat.id, &pool, &user, todo_id, when).await? {
+        dialogue.exit().await?;
     }
 
-    let mut text = "⏰ *Active reminders:*\n\n".to_string();
+    Ok(())
+}
 
-    for reminder in reminders {
-        text.push_str(&format!(
-            "🆔 \\#{} \\- {}\n🕐 {}\n\n",
-            reminder.id,
-            reminder.message.as_deref().unwrap_or("No text").replace("-", "\\-").replace(".", "\\."),
-            reminder.remind_at.format("%d\\.%m\\.%Y %H:%M")
-        ));
+/// Create reminder about a task and confirm it; `false` if the input was rejected
+async fn create_todo_reminder(
+    bot: &Bot,
+    chat_id: ChatId,
+    pool: &PgPool,
+    user: &crate::db::models::User,
+    todo_id: i32,
+    when: &str,
+) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
+    let todo_service = TodoService::new(TodoRepository::new(pool.clone()));
+    let todo = todo_service.get_todo(todo_id).await?;
+
+    // Check ownership
+    if todo.user_id != user.id {
+        bot.send_message(chat_id, "❌ This is not your task!")
+            .await?;
+        return Ok(false);
     }
 
-    bot.send_message(msg.chat.id, text)
-        .parse_mode(ParseMode::MarkdownV2)
-        .await?;
+    let now = user.now();
+    let remind_at = match parse_before_due(when) {
+        Some(Ok(offset)) => match todo.due_date {
+            Some(due) => Ok((due - offset).with_timezone(&now.timezone())),
+            None => Err(format!("Task #{} has no due date", todo.id)),
+        },
+        Some(Err(e)) => Err(e.user_message()),
+        None => parse_reminder_time(when, &now).ok_or_else(|| "Could not understand the time".to_string()),
+    };
+    let remind_at = match remind_at {
+        Ok(remind_at) => remind_at,
+        Err(problem) => {
+            bot.send_message(
+                chat_id,
+                format!("❌ {}\n\nExamples: 2h, tomorrow 9am, 1d before due", problem),
+            )
+            .await?;
+            return Ok(false);
+        }
+    };
+
+    let reminder_service = ReminderService::new(ReminderRepository::new(pool.clone()));
+    let reminder = match reminder_service
+        .create_todo_reminder(
+            user.id,
+            &todo,
+            remind_at.with_timezone(&chrono::Utc),
+            ReminderTarget::chat(chat_id.0),
+        )
+        .await
+    {
+        Ok(reminder) => reminder,
+        Err(e) => {
+            bot.send_message(chat_id, format!("❌ {}", e.user_message()))
+                .await?;
+            return Ok(false);
+        }
+    };
 
-    Ok(())
+    bot.send_message(
+        chat_id,
+        format!(
+            "⏰ Reminder set!\n\n📝 Task #{}: {}\n🕐 {}\n🆔 ID: {}",
+            todo.id,
+            todo.title,
+            format_datetime(&remind_at),
+            reminder.id
+        ),
+    )
+    .await?;
+
+    Ok(true)
 }
 
-/// /cancelreminder - cancel reminder
-pub async fn cancel_reminder(bot: Bot, msg: Message, pool: PgPool, id: i32) -> HandlerResult {
+/// /duereminders - automatic reminders before due dates: /duereminders 1d 2h | off
+pub async fn due_reminders(bot: Bot, msg: Message, pool: PgPool, text: String) -> HandlerResult {
     let user_id = msg.from().ok_or("No user in message")?.id.0 as i64;
-
     let user: crate::db::models::User = sqlx::query_as(
         "SELECT * FROM users WHERE telegram_id = $1",
     )
@@ -335,66 +698,1722 @@ pub async fn cancel_reminder(bot: Bot, msg: Message, pool: PgPool, id: i32) -> H
     .fetch_one(&pool)
     .await?;
 
-    let reminder_repo = ReminderRepository::new(pool.clone());
-    let reminder = reminder_repo.find_by_id(id).await?;
-
-    if reminder.user_id != user.id {
-        bot.send_message(msg.chat.id, "❌ This is not your reminder!")
-            .await?;
+    let text = text.trim();
+    if text.is_empty() {
+        let current = if user.due_reminder_offsets.is_empty() {
+            "off".to_string()
+        } else {
+            format_offsets(&user.due_reminder_offsets)
+        };
+        bot.send_message(
+            msg.chat.id,
+            format!(
+                "⏳ Reminders before due dates: {}\n\n\
+                 Change with /duereminders 1d 2h or turn off with /duereminders off",
+                current
+            ),
+        )
+        .await?;
         return Ok(());
     }
 
-    let repo2 = ReminderRepository::new(pool);
-    repo2.delete(id).await?;
-
-    bot.send_message(msg.chat.id, format!("✅ Reminder #{} cancelled!", id))
+    let offsets = if text.eq_ignore_ascii_case("off") {
+        Vec::new()
+    } else {
+        match parse_due_offsets(text) {
+            Ok(offsets) => offsets,
+            Err(problem) => {
+                bot.send_message(
+                    msg.chat.id,
+                    format!("❌ {}\n\nExample: /duereminders 1d 2h", problem),
+                )
+                .await?;
+                return Ok(());
+            }
+        }
+    };
+
+    sqlx::query("UPDATE users SET due_reminder_offsets = $1 WHERE id = $2")
+        .bind(&offsets)
+        .bind(user.id)
+        .execute(&pool)
         .await?;
 
+    // Apply to tasks that are already scheduled
+    let todo_service = TodoService::new(TodoRepository::new(pool.clone()));
+    let reminder_service = ReminderService::new(ReminderRepository::new(pool));
+    let mut created = 0;
+    for todo in todo_service.find_upcoming(user.id, chrono::Utc::now()).await? {
+        created += reminder_service.sync_due_reminders(&todo, &offsets).await?;
+    }
+
+    let reply = if offsets.is_empty() {
+        "⏳ Automatic reminders before due dates are off".to_string()
+    } else {
+        format!(
+            "⏳ You will be reminded {} before each due date\n🔔 {} reminder(s) scheduled for open tasks",
+            format_offsets(&offsets),
+            created
+        )
+    };
+    bot.send_message(msg.chat.id, reply).await?;
+
     Ok(())
 }
 
-/// Handle arbitrary messages
-pub async fn handle_message(bot: Bot, msg: Message) -> HandlerResult {
-    bot.send_message(msg.chat.id, "Use /help to see available commands.")
+/// Whole-minute durations, largest first, without duplicates
+fn parse_due_offsets(text: &str) -> Result<Vec<i32>, String> {
+    let mut offsets = Vec::new();
+    for word in text.split_whitespace() {
+        let minutes = parse_duration(word).map_err(|e| e.user_message())?.num_minutes();
+        if minutes < 1 {
+            return Err(format!("Offset '{}' is shorter than a minute", word));
+        }
+        offsets.push(minutes as i32);
+    }
+
+    offsets.sort_unstable_by(|a, b| b.cmp(a));
+    offsets.dedup();
+    if offsets.len() > MAX_DUE_OFFSETS {
+        return Err(format!("At most {} offsets are allowed", MAX_DUE_OFFSETS));
+    }
+
+    Ok(offsets)
+}
+
+/// `1d, 2h` - whole days are shown as days
+fn format_offsets(offsets: &[i32]) -> String {
+    let parts: Vec<String> = offsets
+        .iter()
+        .map(|&m| match (m / (24 * 60), m % (24 * 60)) {
+            (days, 0) if days > 0 => format!("{}d", days),
+            _ => format_minutes(m.into()),
+        })
+        .collect();
+    parts.join(", ")
+}
+
+/// /nag - insistent reminder: /nag [every 5m] [max 6] [escalate @user] <time> <text>
+pub async fn set_nagging_reminder(bot: Bot, msg: Message, pool: PgPool, text: String) -> HandlerResult {
+    let from = msg.from().ok_or("No user in message")?.id;
+    let user_id = from.0 as i64;
+    let user: crate::db::models::User = sqlx::query_as(
+        "SELECT * FROM users WHERE telegram_id = $1",
+    )
+    .bind(user_id)
+    .fetch_one(&pool)
+    .await?;
+
+    let (urgent, text) = split_urgent(&text);
+    let options = match parse_nag_options(&text) {
+        Ok(options) => options,
+        Err(e) => {
+            bot.send_message(msg.chat.id, format!("❌ {}", e.user_message()))
+                .await?;
+            return Ok(());
+        }
+    };
+
+    let Some((remind_at, reminder_text)) = split_reminder_time(&options.rest, &user.now())
+        .filter(|(_, message)| !message.is_empty())
+    else {
+        bot.send_message(
+            msg.chat.id,
+            "❌ Invalid format!\n\n\
+             Use: /nag [every <interval>] [max <repeats>] [escalate <@user|chat id>] <time> <text>\n\
+             Examples:\n\
+             /nag 22:00 Hand over on-call\n\
+             /nag every 10m max 3 escalate @teamlead in 1h Check the deploy",
+        )
         .await?;
+        return Ok(());
+    };
+
+    let escalate_to = match options.escalate.as_deref() {
+        None => None,
+        Some(target) => match resolve_chat(&pool, target).await? {
+            Some(chat_id) => {
+                if let Err(problem) = may_escalate_to(&bot, &msg, from, chat_id).await {
+                    bot.send_message(msg.chat.id, format!("❌ Cannot escalate to {}: {}", target, problem))
+                        .await?;
+                    return Ok(());
+                }
+                Some(chat_id)
+            }
+            None => {
+                bot.send_message(
+                    msg.chat.id,
+                    format!(
+                        "❌ Cannot escalate to {}: they have to /start the bot first, \
+                         or use a group chat ID",
+                        target
+                    ),
+                )
+                .await?;
+                return Ok(());
+            }
+        },
+    };
+
+    let (mut target, reminder_text) = reminder_target(msg.chat.id, &msg, reminder_text);
+    target.urgent = urgent;
+    let nag = NagPolicy {
+        interval_minutes: options.interval.num_minutes().try_into().unwrap_or(i32::MAX),
+        max_repeats: options.max_repeats,
+        escalate_to,
+    };
+
+    let reminder_service = ReminderService::new(ReminderRepository::new(pool));
+    let reminder = match reminder_service
+        .create_nagging_reminder(
+            user.id,
+            remind_at.with_timezone(&chrono::Utc),
+            Some(reminder_text.clone()),
+            nag,
+            target,
+        )
+        .await
+    {
+        Ok(reminder) => reminder,
+        Err(e) => {
+            bot.send_message(msg.chat.id, format!("❌ {}", e.user_message()))
+                .await?;
+            return Ok(());
+        }
+    };
+
+    let mut reply = format!(
+        "🔁 Insistent reminder set!\n\n📝 {}\n🕐 {}{}\n🔁 Every {} until acknowledged, up to {} repeats",
+        reminder_text,
+        format_datetime(&reminder.remind_at.with_timezone(&user.tz())),
+        describe_target(&reminder, msg.chat.id),
+        format_minutes(nag.interval_minutes.into()),
+        nag.max_repeats
+    );
+    if let Some(target) = options.escalate.as_deref().filter(|_| escalate_to.is_some()) {
+        reply.push_str(&format!("\n🚨 Then escalates to {}", target));
+    }
+    reply.push_str(&format!("\n🆔 ID: {}", reminder.id));
+
+    bot.send_message(msg.chat.id, reply).await?;
+
     Ok(())
 }
 
-/// Handle callback buttons
-pub async fn handle_callback(bot: Bot, q: CallbackQuery) -> HandlerResult {
-    if let Some(data) = &q.data {
-        bot.answer_callback_query(&q.id).await?;
+/// Chat ID for `@username` of a registered user or a numeric chat ID
+async fn resolve_chat(pool: &PgPool, target: &str) -> Result<Option<i64>, sqlx::Error> {
+    if let Ok(chat_id) = target.parse::<i64>() {
+        return Ok(Some(chat_id));
+    }
 
-        // Process callback data
-        tracing::info!("Callback received: {}", data);
+    let Some(username) = target.strip_prefix('@') else {
+        return Ok(None);
+    };
+
+    sqlx::query_scalar("SELECT telegram_id FROM users WHERE LOWER(username) = LOWER($1)")
+        .bind(username)
+        .fetch_optional(pool)
+        .await
+}
+
+/// Check that the sender of `msg` may have a nag escalate to `chat_id`
+///
+/// A group has to have the sender as a member; a person has to be a member
+/// of the group the nag is set in, so nobody gets messages from strangers.
+async fn may_escalate_to(
+    bot: &Bot,
+    msg: &Message,
+    from: UserId,
+    chat_id: i64,
+) -> Result<(), &'static str> {
+    let is_member = |chat: ChatId, user: UserId| async move {
+        bot.get_chat_member(chat, user)
+            .await
+            .is_ok_and(|member| member.is_present())
+    };
+
+    // Positive IDs are people, negative ones groups and channels
+    if chat_id > 0 {
+        let user = UserId(chat_id as u64);
+        if user == from {
+            return Ok(());
+        }
+        if msg.chat.is_private() {
+            return Err("escalating to another person only works in a group you are both in");
+        }
+        if !is_member(msg.chat.id, user).await {
+            return Err("they are not a member of this chat");
+        }
+    } else if !is_member(ChatId(chat_id), from).await {
+        return Err("you have to be a member of that group, and so do I");
     }
     Ok(())
 }
 
-/// Parse duration from string (5m, 2h, 1d)
-fn parse_duration(s: &str) -> Result<chrono::Duration, Box<dyn std::error::Error + Send + Sync>> {
-    let len = s.len();
-    if len < 2 {
-        return Err("Invalid duration format".into());
+/// Delivery target of a reminder set for `chat` by the command in `msg`
+///
+/// In groups and channels leading `@mentions` are taken out of the text
+/// and mentioned when the reminder fires.
+fn reminder_target(chat: ChatId, msg: &Message, text: String) -> (ReminderTarget, String) {
+    let source = Some(SourceMessage {
+        chat_id: msg.chat.id.0,
+        message_id: msg.id.0,
+    });
+    if chat.is_user() {
+        let target = ReminderTarget {
+            source,
+            ..ReminderTarget::chat(chat.0)
+        };
+        return (target, text);
     }
 
-    let num: i64 = s[..len-1].parse()?;
+    let (mentions, text) = split_mentions(&text);
+    (
+        ReminderTarget {
+            chat_id: Some(chat.0),
+            mentions,
+            source,
+            ..ReminderTarget::default()
+        },
+        text,
+    )
+}
 
-    // VALIDATION: protect from negative and too large values
-    if num <= 0 {
-        return Err("Duration must be positive".into());
+/// Confirmation line about where a reminder set from `here` fires
+fn describe_target(reminder: &Reminder, here: ChatId) -> String {
+    let mut line = String::new();
+    if let Some(mentions) = reminder.mention_list() {
+        line.push_str(&format!("\n👥 {}", mentions));
+    }
+    if reminder.chat_id != here.0 {
+        line.push_str("\n📢 Posts to the channel");
+    }
+    if reminder.is_urgent {
+        line.push_str("\n🚨 Urgent: rings through quiet hours");
     }
+    if let Some(channels) = &reminder.channels {
+        line.push_str(&format!("\n📨 Via {}", channels.join(", ")));
+    }
+    line
+}
+
+/// Digits and letters only, starting with a digit ("90", "5x", "1h30m")
+fn looks_like_duration(word: &str) -> bool {
+    word.starts_with(|c: char| c.is_ascii_digit()) && word.chars().all(|c| c.is_alphanumeric())
+}
+
+/// /remind every ... - set recurring reminder
+async fn set_recurring_reminder(
+    bot: Bot,
+    msg: Message,
+    pool: PgPool,
+    text: String,
+    chat: ChatId,
+    urgent: bool,
+    channels: Option<Vec<NotifyChannel>>,
+) -> HandlerResult {
+    let user_id = msg.from().ok_or("No user in message")?.id.0 as i64;
+    let user: crate::db::models::User = sqlx::query_as(
+        "SELECT * FROM users WHERE telegram_id = $1",
+    )
+    .bind(user_id)
+    .fetch_one(&pool)
+    .await?;
 
-    if num > 365 {
-        return Err("Duration too large (max 365 units)".into());
+    if let Some(problem) = channels.as_deref().and_then(|c| missing_setup(&user, c)) {
+        bot.send_message(msg.chat.id, format!("❌ {}", problem)).await?;
+        return Ok(());
     }
 
-    let unit = &s[len-1..];
+    let mut schedule = match parse_recurring(&text, user.now()) {
+        Some(schedule) if !schedule.message.is_empty() => schedule,
+        _ => {
+            bot.send_message(
+                msg.chat.id,
+                "❌ Invalid format!\n\n\
+                 Use: /remind every <rule> [HH:MM] <text>\n\
+                 Rules: day, weekday, week, monday (or mon,wed,fri), month on 15, year on 14.03\n\
+                 Example: /remind every day 09:00 Standup",
+            )
+            .await?;
+            return Ok(());
+        }
+    };
 
-    match unit {
-        "m" => Ok(chrono::Duration::minutes(num)),
-        "h" => Ok(chrono::Duration::hours(num)),
-        "d" => Ok(chrono::Duration::days(num)),
-        _ => Err("Invalid time unit (use m, h, or d)".into()),
+    if schedule.message.len() > 500 {
+        bot.send_message(msg.chat.id, "❌ Reminder text is too long (max 500 characters)!")
+            .await?;
+        return Ok(());
     }
+
+    let (mut target, message) = reminder_target(chat, &msg, std::mem::take(&mut schedule.message));
+    target.urgent = urgent;
+    target.channels = channels;
+    schedule.message = message;
+
+    let description = schedule.pattern.describe();
+    let reminder_repo = ReminderRepository::new(pool);
+    let reminder_service = ReminderService::new(reminder_repo);
+    let reminder = reminder_service
+        .create_recurring_reminder(user.id, None, schedule, target)
+        .await?;
+
+    bot.send_message(
+        msg.chat.id,
+        format!(
+            "🔄 Recurring reminder set!\n\n📝 {}\n🔁 Repeats {} at {}\n🕐 First: {}{}\n🆔 ID: {}\n\n\
+             Use /stoprepeat {} to end the series",
+            reminder.message.as_deref().unwrap_or_default(),
+            description,
+            reminder.remind_at.with_timezone(&user.tz()).format("%H:%M %Z"),
+            format_datetime(&reminder.remind_at.with_timezone(&user.tz())),
+            describe_target(&reminder, msg.chat.id),
+            reminder.id,
+            reminder.id
+        ),
+    )
+    .await?;
+
+    Ok(())
+}
+
+/// /cron "<expression>" <text> - set reminder on a cron schedule
+pub async fn set_cron_reminder(bot: Bot, msg: Message, pool: PgPool, text: String) -> HandlerResult {
+    let (expr, message) = match split_cron_command(&text) {
+        Some((expr, message)) if !message.is_empty() => (expr, message),
+        _ => {
+            bot.send_message(
+                msg.chat.id,
+                "❌ Invalid format!\n\n\
+                 Use: /cron \"<minute> <hour> <day> <month> <weekday>\" <text>\n\
+                 Example: /cron \"0 9 * * 1-5\" Check dashboards",
+            )
+            .await?;
+            return Ok(());
+        }
+    };
+
+    let schedule = match CronSchedule::parse(&expr) {
+        Ok(schedule) => schedule,
+        Err(e) => {
+            bot.send_message(msg.chat.id, format!("❌ {}", e.user_message())).await?;
+            return Ok(());
+        }
+    };
+
+    let user_id = msg.from().ok_or("No user in message")?.id.0 as i64;
+    let user: crate::db::models::User = sqlx::query_as(
+        "SELECT * FROM users WHERE telegram_id = $1",
+    )
+    .bind(user_id)
+    .fetch_one(&pool)
+    .await?;
+
+    let (target, message) = reminder_target(msg.chat.id, &msg, message);
+    let reminder_repo = ReminderRepository::new(pool);
+    let reminder_service = ReminderService::new(reminder_repo);
+    let reminder = match reminder_service
+        .create_cron_reminder(user.id, &schedule, message, user.tz(), target)
+        .await
+    {
+        Ok(reminder) => reminder,
+        Err(e) => {
+            bot.send_message(msg.chat.id, format!("❌ {}", e.user_message())).await?;
+            return Ok(());
+        }
+    };
+
+    let upcoming: Vec<String> = schedule
+        .upcoming(&user.now(), CRON_PREVIEW_RUNS)
+        .iter()
+        .map(|at| format!("• {}", format_datetime(at)))
+        .collect();
+
+    bot.send_message(
+        msg.chat.id,
+        format!(
+            "🔄 Cron reminder set!\n\n📝 {}\n🔁 {} ({})\n\n🕐 Next runs:\n{}\n🆔 ID: {}\n\n\
+             Use /stoprepeat {} to end the series",
+            reminder.message.as_deref().unwrap_or_default(),
+            schedule.describe(),
+            schedule.expression(),
+            upcoming.join("\n"),
+            reminder.id,
+            reminder.id
+        ),
+    )
+    .await?;
+
+    Ok(())
+}
+
+/// /stoprepeat - end a recurring series
+pub async fn stop_repeat(bot: Bot, msg: Message, pool: PgPool, id: i32) -> HandlerResult {
+    let user_id = msg.from().ok_or("No user in message")?.id.0 as i64;
+
+    let user: crate::db::models::User = sqlx::query_as(
+        "SELECT * FROM users WHERE telegram_id = $1",
+    )
+    .bind(user_id)
+    .fetch_one(&pool)
+    .await?;
+
+    let reminder_repo = ReminderRepository::new(pool);
+    let reminder = reminder_repo.find_by_id(id).await?;
+
+    if reminder.user_id != user.id {
+        bot.send_message(msg.chat.id, "❌ This is not your reminder!")
+            .await?;
+        return Ok(());
+    }
+
+    if !reminder.is_recurring {
+        bot.send_message(msg.chat.id, format!("ℹ️ Reminder #{} is not recurring.", id))
+            .await?;
+        return Ok(());
+    }
+
+    let reminder_service = ReminderService::new(reminder_repo);
+    let reminder = reminder_service.stop_recurrence(id).await?;
+
+    let text = if reminder.is_sent {
+        format!("✅ Series #{} stopped.", id)
+    } else {
+        format!(
+            "✅ Series #{} stopped. The reminder at {} will be the last one.\n\
+             Use /cancelreminder {} to drop it too.",
+            id,
+            format_datetime(&reminder.remind_at),
+            id
+        )
+    };
+    bot.send_message(msg.chat.id, text).await?;
+
+    Ok(())
+}
+
+/// /listreminders - show active reminders
+pub async fn list_reminders(bot: Bot, msg: Message, pool: PgPool) -> HandlerResult {
+    let user_id = msg.from().ok_or("No user in message")?.id.0 as i64;
+
+    let user: crate::db::models::User = sqlx::query_as(
+        "SELECT * FROM users WHERE telegram_id = $1",
+    )
+    .bind(user_id)
+    .fetch_one(&pool)
+    .await?;
+
+    let reminder_repo = ReminderRepository::new(pool);
+    let reminders = reminder_repo.find_by_user(user.id).await?;
+
+    if reminders.is_empty() {
+        bot.send_message(msg.chat.id, "⏰ You have no active reminders.")
+            .await?;
+        return Ok(());
+    }
+
+    let mut text = "⏰ *Active reminders:*\n\n".to_string();
+
+    for reminder in &reminders {
+        text.push_str(&format!(
+            "🆔 \\#{} \\- {}\n🕐 {}\n",
+            reminder.id,
+            reminder.message.as_deref().unwrap_or("No text").replace("-", "\\-").replace(".", "\\."),
+            escape_markdown(&format_datetime(&reminder.remind_at.with_timezone(&user.tz())))
+        ));
+        if let Some(pattern) = reminder.recurrence() {
+            text.push_str(&format!("🔄 Repeats {}\n", escape_markdown(&pattern.describe())));
+        }
+        if reminder.snooze_count > 0 {
+            text.push_str(&format!("😴 Snoozed {} time\\(s\\)\n", reminder.snooze_count));
+        }
+        if reminder.chat_id != user.telegram_id {
+            text.push_str("📍 In a group or channel\n");
+        }
+        if reminder.dead_lettered_at.is_some() {
+            text.push_str(&format!(
+                "⚠️ Not delivered after {} attempts\n",
+                reminder.attempts
+            ));
+        } else if reminder.paused_at.is_some() {
+            text.push_str("⏸ Paused\n");
+        } else if reminder.retry_at.is_some() && reminder.last_error.is_some() {
+            text.push_str("🔁 Delivery is being retried\n");
+        } else if let Some(until) = reminder.retry_at.filter(|at| *at > chrono::Utc::now()) {
+            text.push_str(&format!(
+                "🌙 Held for quiet hours until {}\n",
+                until.with_timezone(&user.tz()).format("%H:%M")
+            ));
+        }
+        if reminder.is_urgent {
+            text.push_str("🚨 Urgent\n");
+        }
+        if let Some(channels) = &reminder.channels {
+            text.push_str(&format!("📨 Via {}\n", channels.join(", ")));
+        }
+        text.push('\n');
+    }
+
+    text.push_str("Tap 🕐 to reschedule, or use /editreminder");
+
+    bot.send_message(msg.chat.id, text)
+        .parse_mode(ParseMode::MarkdownV2)
+        .reply_markup(keyboards::reschedule_buttons(&reminders))
+        .await?;
+
+    Ok(())
+}
+
+/// /reminderhistory [page] - delivery log, newest first
+pub async fn reminder_history(bot: Bot, msg: Message, pool: PgPool, page: String) -> HandlerResult {
+    let user_id = msg.from().ok_or("No user in message")?.id.0 as i64;
+    let user: crate::db::models::User = sqlx::query_as(
+        "SELECT * FROM users WHERE telegram_id = $1",
+    )
+    .bind(user_id)
+    .fetch_one(&pool)
+    .await?;
+
+    let page = match page.trim() {
+        "" => 1,
+        page => match page.parse::<i64>() {
+            Ok(page) => page,
+            Err(_) => {
+                bot.send_message(msg.chat.id, "❌ Use: /reminderhistory [page]")
+                    .await?;
+                return Ok(());
+            }
+        },
+    };
+
+    let reminder_service = ReminderService::new(ReminderRepository::new(pool));
+    let history = reminder_service.history(user.id, page).await?;
+
+    if history.deliveries.is_empty() {
+        bot.send_message(msg.chat.id, "📜 No reminders have fired yet.")
+            .await?;
+        return Ok(());
+    }
+
+    bot.send_message(msg.chat.id, format_history(&history, user.tz()))
+        .reply_markup(keyboards::history_pages(history.page, history.pages))
+        .await?;
+
+    Ok(())
+}
+
+/// Text of a /reminderhistory page
+pub(crate) fn format_history(history: &HistoryPage, tz: Tz) -> String {
+    let mut text = format!(
+        "📜 Reminder history, page {} of {}\n",
+        history.page, history.pages
+    );
+
+    for delivery in &history.deliveries {
+        let via = match delivery.channel {
+            Some(channel) if channel != NotifyChannel::Telegram => format!(" via {}", channel),
+            _ => String::new(),
+        };
+        let id = delivery
+            .reminder_id
+            .map(|id| format!("#{}", id))
+            .unwrap_or_else(|| "cancelled".to_string());
+        text.push_str(&format!(
+            "\n{} {} {}\n🕐 Due {}\n",
+            delivery.status_emoji(),
+            id,
+            truncate_text(delivery.message.as_deref().unwrap_or("No text"), 60),
+            format_datetime(&delivery.scheduled_for.with_timezone(&tz))
+        ));
+
+        match delivery.status {
+            DeliveryStatus::Sent => {
+                if let (Some(at), Some(latency)) = (delivery.delivered_at, delivery.latency()) {
+                    text.push_str(&format!(
+                        "📬 Delivered{} {} ({} late)\n",
+                        via,
+                        at.with_timezone(&tz).format("%H:%M:%S"),
+                        format_latency(latency)
+                    ));
+                }
+            }
+            DeliveryStatus::Failed => text.push_str(&format!(
+                "⚠️ Attempt {} failed{}: {}\n",
+                delivery.attempt,
+                via,
+                truncate_text(delivery.error.as_deref().unwrap_or("unknown error"), 100)
+            )),
+            DeliveryStatus::Skipped => text.push_str("⏭ Skipped: missed while the bot was offline\n"),
+        }
+        if delivery.snooze_count > 0 {
+            text.push_str(&format!("😴 Snoozed {} time(s)\n", delivery.snooze_count));
+        }
+    }
+
+    text
+}
+
+/// /editreminder <id> time=<when> text=<text> repeat=<rule|off>
+///
+/// Any of the keys may be given; the scheduler picks up the change at once.
+pub async fn edit_reminder(bot: Bot, msg: Message, pool: PgPool, text: String) -> HandlerResult {
+    let usage = "Use: /editreminder <id> time=<when> text=<text> repeat=<rule|off>\n\
+                 Examples:\n\
+                 /editreminder 12 time=tomorrow 9am\n\
+                 /editreminder 12 text=Call mom repeat=every weekday\n\
+                 /editreminder 12 repeat=off";
+
+    let user_id = msg.from().ok_or("No user in message")?.id.0 as i64;
+    let user: crate::db::models::User = sqlx::query_as(
+        "SELECT * FROM users WHERE telegram_id = $1",
+    )
+    .bind(user_id)
+    .fetch_one(&pool)
+    .await?;
+
+    let text = text.trim();
+    let (id, rest) = text.split_once(char::is_whitespace).unwrap_or((text, ""));
+    let Ok(id) = id.parse::<i32>() else {
+        bot.send_message(msg.chat.id, format!("❌ Invalid format!\n\n{}", usage))
+            .await?;
+        return Ok(());
+    };
+    let args = match parse_edit_args(rest) {
+        Ok(args) => args,
+        Err(e) => {
+            bot.send_message(msg.chat.id, format!("❌ {}\n\n{}", e.user_message(), usage))
+                .await?;
+            return Ok(());
+        }
+    };
+
+    let reminder_repo = ReminderRepository::new(pool.clone());
+    let reminder = match reminder_repo.find_by_id(id).await {
+        Ok(reminder) if reminder.user_id == user.id && !reminder.is_sent => reminder,
+        _ => {
+            bot.send_message(msg.chat.id, format!("❌ Reminder #{} not found", id))
+                .await?;
+            return Ok(());
+        }
+    };
+
+    let now = user.now();
+    let remind_at = match args.time.as_deref() {
+        None => None,
+        Some(time) => match parse_reminder_time(time, &now) {
+            Some(at) => Some(at),
+            None => {
+                bot.send_message(
+                    msg.chat.id,
+                    format!(
+                        "❌ Could not understand the time '{}'\n\nExamples: 20m, 18:00, tomorrow 9am",
+                        time
+                    ),
+                )
+                .await?;
+                return Ok(());
+            }
+        },
+    };
+
+    let mut edit = ReminderEdit {
+        remind_at: remind_at.as_ref().map(|at| at.with_timezone(&chrono::Utc)),
+        message: args.text,
+        recurrence: None,
+    };
+    if let Some(rule) = args.repeat.as_deref() {
+        let start = remind_at.unwrap_or_else(|| reminder.remind_at.with_timezone(&user.tz()));
+        let Some((pattern, first_at)) = parse_repeat_edit(rule, start, remind_at.is_none(), &now)
+        else {
+            bot.send_message(
+                msg.chat.id,
+                format!(
+                    "❌ Unknown repeat rule '{}'\n\n\
+                     Rules: day, weekday, week, monday or mon,wed,fri, month on 15, \
+                     year on 14.03 (optionally with HH:MM), or off",
+                    rule
+                ),
+            )
+            .await?;
+            return Ok(());
+        };
+        edit.recurrence = Some(pattern);
+        edit.remind_at = first_at.or(edit.remind_at);
+    }
+
+    let reminder_service = ReminderService::new(reminder_repo);
+    let reminder = match reminder_service.edit_reminder(id, edit).await {
+        Ok(reminder) => reminder,
+        Err(e) => {
+            bot.send_message(msg.chat.id, format!("❌ {}", e.user_message()))
+                .await?;
+            return Ok(());
+        }
+    };
+
+    bot.send_message(msg.chat.id, describe_edited(&reminder, &user))
+        .await?;
+
+    Ok(())
+}
+
+/// Recurrence change of /editreminder: `off` or an `every ...` rule
+///
+/// Returns the new pattern and the first occurrence. That is at the
+/// rule's own time if it has one and `rule_time_wins`, otherwise at
+/// `start`'s time of day on the first matching day.
+fn parse_repeat_edit(
+    rule: &str,
+    start: chrono::DateTime<Tz>,
+    rule_time_wins: bool,
+    now: &chrono::DateTime<Tz>,
+) -> Option<(Option<RecurrencePattern>, Option<chrono::DateTime<chrono::Utc>>)> {
+    let lowered = rule.trim().to_lowercase();
+    if matches!(lowered.as_str(), "off" | "no" | "none") {
+        return Some((None, None));
+    }
+
+    let rule = if lowered.starts_with("every ") {
+        lowered
+    } else {
+        format!("every {}", lowered)
+    };
+    let schedule = parse_recurring(&rule, *now).filter(|s| s.message.is_empty())?;
+
+    let has_time = rule.split_whitespace().any(|word| parse_clock_time(word).is_some());
+    let first_at = if has_time && rule_time_wins {
+        schedule.first_at
+    } else {
+        schedule
+            .pattern
+            .first_after(start, now)?
+            .with_timezone(&chrono::Utc)
+    };
+
+    Some((Some(schedule.pattern), Some(first_at)))
+}
+
+/// Confirmation after a reminder was edited or rescheduled
+fn describe_edited(reminder: &Reminder, user: &crate::db::models::User) -> String {
+    let mut text = format!(
+        "✏️ Reminder #{} updated\n\n📝 {}\n🕐 {}",
+        reminder.id,
+        reminder.message.as_deref().unwrap_or("No text"),
+        format_datetime(&reminder.remind_at.with_timezone(&user.tz()))
+    );
+    if let Some(pattern) = reminder.recurrence() {
+        text.push_str(&format!("\n🔄 Repeats {}", pattern.describe()));
+    }
+    text
+}
+
+/// New time for a reminder after its 🕐 button in /listreminders
+pub async fn receive_reschedule_time(
+    bot: Bot,
+    msg: Message,
+    pool: PgPool,
+    dialogue: BotDialogue,
+    reminder_id: i32,
+) -> HandlerResult {
+    let user_id = msg.from().ok_or("No user in message")?.id.0 as i64;
+    let user: crate::db::models::User = sqlx::query_as(
+        "SELECT * FROM users WHERE telegram_id = $1",
+    )
+    .bind(user_id)
+    .fetch_one(&pool)
+    .await?;
+
+    let Some(at) = msg.text().and_then(|text| parse_reminder_time(text, &user.now())) else {
+        bot.send_message(
+            msg.chat.id,
+            "❌ Could not understand the time!\n\nExamples: 20m, 18:00, tomorrow 9am, 20.10 14:30",
+        )
+        .await?;
+        return Ok(());
+    };
+
+    let edit = ReminderEdit {
+        remind_at: Some(at.with_timezone(&chrono::Utc)),
+        ..ReminderEdit::default()
+    };
+    let reminder_service = ReminderService::new(ReminderRepository::new(pool));
+    match reminder_service.edit_reminder(reminder_id, edit).await {
+        Ok(reminder) => {
+            dialogue.exit().await?;
+            bot.send_message(msg.chat.id, describe_edited(&reminder, &user))
+                .await?;
+        }
+        Err(e) => {
+            bot.send_message(msg.chat.id, format!("❌ {}", e.user_message()))
+                .await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// /calendar - reminders and due dates as an .ics file,
+/// /calendar feed [off] - subscription URL for calendar apps
+pub async fn calendar(
+    bot: Bot,
+    msg: Message,
+    pool: PgPool,
+    app: AppConfig,
+    arg: String,
+) -> HandlerResult {
+    let user_id = msg.from().ok_or("No user in message")?.id.0 as i64;
+    let user: crate::db::models::User = sqlx::query_as(
+        "SELECT * FROM users WHERE telegram_id = $1",
+    )
+    .bind(user_id)
+    .fetch_one(&pool)
+    .await?;
+
+    match arg.trim().to_lowercase().as_str() {
+        "" => {
+            let ics = crate::reminder::calendar::export_calendar(&pool, &user).await?;
+            bot.send_document(
+                msg.chat.id,
+                InputFile::memory(ics.into_bytes()).file_name("reminders.ics"),
+            )
+            .caption(
+                "📅 Your upcoming reminders and due dates. Open the file to add them to your calendar.\n\n\
+                 To import events, send me an .ics file.",
+            )
+            .await?;
+        }
+        "feed" => {
+            // Served by the health server, so both need to be configured
+            let Some(base_url) = app.public_url.as_deref().filter(|_| app.port.is_some()) else {
+                bot.send_message(
+                    msg.chat.id,
+                    "❌ The calendar feed is not available on this bot. Use /calendar to get a file.",
+                )
+                .await?;
+                return Ok(());
+            };
+            let token = crate::reminder::calendar::enable_feed(&pool, user.id).await?;
+            bot.send_message(
+                msg.chat.id,
+                format!(
+                    "📅 Subscribe to this address in your calendar app:\n\n{}\n\n\
+                     It updates automatically. Keep it secret: anyone with the link sees your reminders. \
+                     /calendar feed off revokes it.",
+                    crate::reminder::calendar::feed_url(base_url, &token)
+                ),
+            )
+            .await?;
+        }
+        "feed off" => {
+            crate::reminder::calendar::disable_feed(&pool, user.id).await?;
+            bot.send_message(msg.chat.id, "📅 Calendar feed turned off, the old link no longer works.")
+                .await?;
+        }
+        _ => {
+            bot.send_message(msg.chat.id, "❌ Use: /calendar, /calendar feed or /calendar feed off")
+                .await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Check if a message is an .ics file sent in a private chat
+pub fn is_calendar_file(msg: Message) -> bool {
+    let Some(document) = msg.document() else {
+        return false;
+    };
+    let by_name = document
+        .file_name
+        .as_deref()
+        .is_some_and(|name| name.to_lowercase().ends_with(".ics"));
+    let by_type = document
+        .mime_type
+        .as_ref()
+        .is_some_and(|mime| mime.essence_str() == "text/calendar");
+
+    msg.chat.is_private() && (by_name || by_type)
+}
+
+/// Uploaded .ics file: preview the reminders it would create
+pub async fn receive_calendar_file(
+    bot: Bot,
+    msg: Message,
+    pool: PgPool,
+    dialogue: BotDialogue,
+) -> HandlerResult {
+    let document = msg.document().ok_or("No document in message")?;
+    let user_id = msg.from().ok_or("No user in message")?.id.0 as i64;
+    let user: crate::db::models::User = sqlx::query_as(
+        "SELECT * FROM users WHERE telegram_id = $1",
+    )
+    .bind(user_id)
+    .fetch_one(&pool)
+    .await?;
+
+    if document.file.size > MAX_CALENDAR_FILE_BYTES {
+        bot.send_message(msg.chat.id, "❌ The calendar file is too large (max 1 MB)")
+            .await?;
+        return Ok(());
+    }
+
+    let file = bot.get_file(&document.file.id).await?;
+    let mut data = Vec::new();
+    bot.download_file(&file.path, &mut data).await?;
+
+    let parsed = match parse_calendar(&String::from_utf8_lossy(&data), user.tz()) {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            bot.send_message(msg.chat.id, format!("❌ {}", e.user_message()))
+                .await?;
+            return Ok(());
+        }
+    };
+    let plan = plan_import(parsed, user.tz(), chrono::Utc::now());
+    let preview = format_import_preview(&plan, user.tz());
+
+    if plan.reminders.is_empty() {
+        bot.send_message(msg.chat.id, preview).await?;
+        return Ok(());
+    }
+
+    let count = plan.reminders.len();
+    dialogue
+        .update(State::ConfirmingCalendarImport {
+            reminders: plan.reminders,
+        })
+        .await?;
+    bot.send_message(msg.chat.id, preview)
+        .reply_markup(keyboards::calendar_import(count))
+        .await?;
+
+    Ok(())
+}
+
+/// Preview of an import: what will be created and what is left out
+fn format_import_preview(plan: &ImportPlan, tz: Tz) -> String {
+    const SHOWN: usize = 15;
+
+    let mut text = if plan.reminders.is_empty() {
+        "📅 Nothing to import from this calendar.\n".to_string()
+    } else {
+        format!("📅 Import {} reminder(s) from this calendar?\n\n", plan.reminders.len())
+    };
+
+    for reminder in plan.reminders.iter().take(SHOWN) {
+        text.push_str(&format!(
+            "• {} — {}",
+            reminder.remind_at.with_timezone(&tz).format("%d.%m %H:%M"),
+            reminder.text
+        ));
+        if let Some(pattern) = &reminder.recurrence {
+            text.push_str(&format!(" 🔄 {}", pattern.describe()));
+        }
+        text.push('\n');
+    }
+    if plan.reminders.len() > SHOWN {
+        text.push_str(&format!("…and {} more\n", plan.reminders.len() - SHOWN));
+    }
+
+    if !plan.skipped.is_empty() {
+        let names: Vec<String> = plan
+            .skipped
+            .iter()
+            .take(SHOWN)
+            .map(|(summary, reason)| format!("{} ({})", truncate_text(summary, 40), reason))
+            .collect();
+        text.push_str(&format!(
+            "\nSkipped {}: {}",
+            plan.skipped.len(),
+            names.join(", ")
+        ));
+        if plan.skipped.len() > SHOWN {
+            text.push_str(", …");
+        }
+        text.push('\n');
+    }
+
+    text
+}
+
+/// Check if a message is a photo or an image file sent in a private chat
+pub fn is_image_file(msg: Message) -> bool {
+    let is_image = msg.photo().is_some()
+        || msg.document().is_some_and(|document| {
+            image_format(
+                document.mime_type.as_ref().map(|mime| mime.essence_str()),
+                document.file_name.as_deref(),
+            )
+            .is_some()
+        });
+
+    msg.chat.is_private() && is_image
+}
+
+/// Uploaded photo or image: record it and offer the conversion menu
+pub async fn receive_image(
+    bot: Bot,
+    msg: Message,
+    pool: PgPool,
+    config: AppConfig,
+) -> HandlerResult {
+    let user_id = msg.from().ok_or("No user in message")?.id.0 as i64;
+    let user: crate::db::models::User = sqlx::query_as(
+        "SELECT * FROM users WHERE telegram_id = $1",
+    )
+    .bind(user_id)
+    .fetch_one(&pool)
+    .await?;
+
+    // Photos come in several sizes: convert the largest
+    let (file, format, file_name) = if let Some(document) = msg.document() {
+        let format = image_format(
+            document.mime_type.as_ref().map(|mime| mime.essence_str()),
+            document.file_name.as_deref(),
+        )
+        .ok_or("Not an image")?;
+        (&document.file, format, document.file_name.clone())
+    } else {
+        let photo = msg
+            .photo()
+            .and_then(|sizes| sizes.iter().max_by_key(|size| size.width * size.height))
+            .ok_or("No photo in message")?;
+        (&photo.file, "jpeg", None)
+    };
+
+    if file.size as usize > config.max_file_size {
+        bot.send_message(
+            msg.chat.id,
+            format!(
+                "❌ The file is too large (max {} MB)",
+                config.max_file_size / (1024 * 1024)
+            ),
+        )
+        .await?;
+        return Ok(());
+    }
+
+    let conversion = ConversionRepository::new(pool)
+        .create(NewFileConversion {
+            user_id: user.id,
+            source_file_id: file.id.clone(),
+            source_format: format.to_string(),
+            source_file_name: file_name,
+        })
+        .await?;
+
+    bot.send_message(
+        msg.chat.id,
+        format!("📄 {} image received. Convert it to:", format.to_uppercase()),
+    )
+    .reply_to_message_id(msg.id)
+    .reply_markup(keyboards::conversion_menu(conversion.id, "image"))
+    .await?;
+
+    Ok(())
+}
+
+/// /cancelreminder - cancel reminder
+pub async fn cancel_reminder(bot: Bot, msg: Message, pool: PgPool, id: i32) -> HandlerResult {
+    let user_id = msg.from().ok_or("No user in message")?.id.0 as i64;
+
+    let user: crate::db::models::User = sqlx::query_as(
+        "SELECT * FROM users WHERE telegram_id = $1",
+    )
+    .bind(user_id)
+    .fetch_one(&pool)
+    .await?;
+
+    let reminder_repo = ReminderRepository::new(pool.clone());
+    let reminder = reminder_repo.find_by_id(id).await?;
+
+    if reminder.user_id != user.id {
+        bot.send_message(msg.chat.id, "❌ This is not your reminder!")
+            .await?;
+        return Ok(());
+    }
+
+    let repo2 = ReminderRepository::new(pool);
+    repo2.delete(id).await?;
+
+    bot.send_message(msg.chat.id, format!("✅ Reminder #{} cancelled!", id))
+        .await?;
+
+    Ok(())
+}
+
+/// Reply to the "Custom" snooze button: the new reminder time
+pub async fn receive_snooze_time(
+    bot: Bot,
+    msg: Message,
+    pool: PgPool,
+    dialogue: BotDialogue,
+    reminder_id: i32,
+) -> HandlerResult {
+    let user_id = msg.from().ok_or("No user in message")?.id.0 as i64;
+
+    let user: Option<crate::db::models::User> = sqlx::query_as(
+        "SELECT * FROM users WHERE telegram_id = $1",
+    )
+    .bind(user_id)
+    .fetch_optional(&pool)
+    .await?;
+
+    // In a group, only the owner's reply is the snooze time
+    let reminder = ReminderRepository::new(pool.clone()).find_by_id(reminder_id).await?;
+    let Some(user) = user.filter(|user| user.id == reminder.user_id) else {
+        return Ok(());
+    };
+
+    let Some(until) = msg.text().and_then(|text| parse_reminder_time(text, &user.now())) else {
+        bot.send_message(
+            msg.chat.id,
+            "❌ Could not understand the time!\n\nExamples: 20m, 1h30m, 18:00, tomorrow 9am",
+        )
+        .await?;
+        return Ok(());
+    };
+
+    let reminder_service = ReminderService::new(ReminderRepository::new(pool));
+    match reminder_service
+        .snooze(reminder_id, until.with_timezone(&chrono::Utc))
+        .await
+    {
+        Ok(_) => {
+            dialogue.exit().await?;
+            bot.send_message(
+                msg.chat.id,
+                format!("😴 Snoozed until {}", format_datetime(&until)),
+            )
+            .await?;
+        }
+        Err(e) => {
+            bot.send_message(msg.chat.id, format!("❌ {}", e.user_message()))
+                .await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Handle arbitrary messages
+pub async fn handle_message(bot: Bot, msg: Message) -> HandlerResult {
+    bot.send_message(msg.chat.id, "Use /help to see available commands.")
+        .await?;
+    Ok(())
+}
+
+/// /review - weekly review on demand, /review on|off - automatic Friday review
+pub async fn review(bot: Bot, msg: Message, pool: PgPool, arg: String) -> HandlerResult {
+    let user_id = msg.from().ok_or("No user in message")?.id.0 as i64;
+
+    let user: crate::db::models::User = sqlx::query_as(
+        "SELECT * FROM users WHERE telegram_id = $1",
+    )
+    .bind(user_id)
+    .fetch_one(&pool)
+    .await?;
+
+    match arg.trim().to_lowercase().as_str() {
+        "" => {
+            review::send_review(&bot, &pool, &user, chrono::Utc::now()).await?;
+        }
+        "on" => {
+            review::set_subscription(&pool, user.id, true).await?;
+            bot.send_message(
+                msg.chat.id,
+                "🗓 Weekly review enabled! You will get a summary every Friday evening.",
+            )
+            .await?;
+        }
+        "off" => {
+            review::set_subscription(&pool, user.id, false).await?;
+            bot.send_message(msg.chat.id, "🗓 Weekly review disabled.")
+                .await?;
+        }
+        _ => {
+            bot.send_message(msg.chat.id, "❌ Use: /review, /review on or /review off")
+                .await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// /timezone - show or set the user's time zone
+pub async fn set_timezone(bot: Bot, msg: Message, pool: PgPool, name: String) -> HandlerResult {
+    let user_id = msg.from().ok_or("No user in message")?.id.0 as i64;
+
+    let user: crate::db::models::User = sqlx::query_as(
+        "SELECT * FROM users WHERE telegram_id = $1",
+    )
+    .bind(user_id)
+    .fetch_one(&pool)
+    .await?;
+
+    if name.trim().is_empty() {
+        bot.send_message(
+            msg.chat.id,
+            format!(
+                "🌍 Your time zone: {} (now {})\n\n\
+                 Change it with /timezone Europe/Moscow or /timezone UTC+3,\n\
+                 or share your location to detect it.",
+                user.tz(),
+                format_datetime(&user.now())
+            ),
+        )
+        .reply_markup(keyboards::share_location())
+        .await?;
+        return Ok(());
+    }
+
+    let Some(tz) = parse_timezone(&name) else {
+        bot.send_message(
+            msg.chat.id,
+            "❌ Unknown time zone!\n\nUse an IANA name like Europe/Moscow or America/New_York, \
+             or an offset like UTC+3",
+        )
+        .await?;
+        return Ok(());
+    };
+
+    save_timezone(&pool, user.id, tz).await?;
+
+    bot.send_message(
+        msg.chat.id,
+        format!(
+            "🌍 Time zone set to {}\n🕐 Local time: {}",
+            tz,
+            format_datetime(&chrono::Utc::now().with_timezone(&tz))
+        ),
+    )
+    .await?;
+
+    Ok(())
+}
+
+/// /quiet - daily quiet hours: /quiet 23:00-08:00 [silent], /quiet off
+///
+/// Private reminders due in the window wait until it ends, or arrive
+/// without sound with `silent`. Urgent reminders are not affected.
+pub async fn quiet_hours(bot: Bot, msg: Message, pool: PgPool, text: String) -> HandlerResult {
+    let user_id = msg.from().ok_or("No user in message")?.id.0 as i64;
+    let user: crate::db::models::User = sqlx::query_as(
+        "SELECT * FROM users WHERE telegram_id = $1",
+    )
+    .bind(user_id)
+    .fetch_one(&pool)
+    .await?;
+
+    let text = text.trim();
+    if text.is_empty() {
+        let reply = match (user.quiet_start, user.quiet_end) {
+            (Some(start), Some(end)) => format!(
+                "🌙 Quiet hours: {}-{}, {}\n\n\
+                 Change with /quiet 23:00-08:00 [silent] or turn off with /quiet off",
+                start.format("%H:%M"),
+                end.format("%H:%M"),
+                quiet_behaviour(user.quiet_silent)
+            ),
+            _ => "🌙 Quiet hours are off\n\n\
+                  Set them with /quiet 23:00-08:00, or /quiet 23:00-08:00 silent \
+                  to get reminders without sound instead"
+                .to_string(),
+        };
+        bot.send_message(msg.chat.id, reply).await?;
+        return Ok(());
+    }
+
+    let (hours, silent) = if text.eq_ignore_ascii_case("off") {
+        (None, false)
+    } else {
+        let lowered = text.to_lowercase();
+        let (range, silent) = match lowered.strip_suffix("silent") {
+            Some(range) => (range.trim(), true),
+            None => (lowered.as_str(), false),
+        };
+        match parse_time_range(range) {
+            Some(hours) => (Some(hours), silent),
+            None => {
+                bot.send_message(
+                    msg.chat.id,
+                    "❌ Invalid quiet hours!\n\nExamples: /quiet 23:00-08:00, /quiet 22-7 silent",
+                )
+                .await?;
+                return Ok(());
+            }
+        }
+    };
+
+    sqlx::query(
+        "UPDATE users SET quiet_start = $1, quiet_end = $2, quiet_silent = $3 WHERE id = $4",
+    )
+    .bind(hours.map(|(start, _)| start))
+    .bind(hours.map(|(_, end)| end))
+    .bind(silent)
+    .bind(user.id)
+    .execute(&pool)
+    .await?;
+
+    // Held reminders are checked against the new hours right away
+    let released = ReminderRepository::new(pool).release_held(user.id).await?;
+
+    let mut reply = match hours {
+        Some((start, end)) => format!(
+            "🌙 Quiet hours set: {}-{} ({}), {}\n🚨 Reminders set with /remind urgent still ring",
+            start.format("%H:%M"),
+            end.format("%H:%M"),
+            user.tz(),
+            quiet_behaviour(silent)
+        ),
+        None => "🔔 Quiet hours are off".to_string(),
+    };
+    if released > 0 {
+        reply.push_str(&format!("\n📬 {} held reminder(s) rechecked", released));
+    }
+    bot.send_message(msg.chat.id, reply).await?;
+
+    Ok(())
+}
+
+/// /dnd - do not disturb for a while: /dnd 2h, /dnd off
+pub async fn do_not_disturb(bot: Bot, msg: Message, pool: PgPool, text: String) -> HandlerResult {
+    let user_id = msg.from().ok_or("No user in message")?.id.0 as i64;
+    let user: crate::db::models::User = sqlx::query_as(
+        "SELECT * FROM users WHERE telegram_id = $1",
+    )
+    .bind(user_id)
+    .fetch_one(&pool)
+    .await?;
+
+    let now = chrono::Utc::now();
+    let text = text.trim();
+    if text.is_empty() {
+        let reply = match user.dnd_until.filter(|until| *until > now) {
+            Some(until) => format!(
+                "🔕 Do not disturb until {}, {}\n\nEnd it with /dnd off",
+                format_datetime(&until.with_timezone(&user.tz())),
+                quiet_behaviour(user.quiet_silent)
+            ),
+            None => "🔔 Do not disturb is off\n\nUse /dnd 2h to hold reminders for a while".to_string(),
+        };
+        bot.send_message(msg.chat.id, reply).await?;
+        return Ok(());
+    }
+
+    let until = if text.eq_ignore_ascii_case("off") {
+        None
+    } else {
+        match parse_duration(text) {
+            Ok(duration) => Some(now + duration),
+            Err(e) => {
+                bot.send_message(
+                    msg.chat.id,
+                    format!("❌ {}\n\nExample: /dnd 2h or /dnd off", e.user_message()),
+                )
+                .await?;
+                return Ok(());
+            }
+        }
+    };
+
+    sqlx::query("UPDATE users SET dnd_until = $1 WHERE id = $2")
+        .bind(until)
+        .bind(user.id)
+        .execute(&pool)
+        .await?;
+
+    let reply = match until {
+        Some(until) => format!(
+            "🔕 Do not disturb until {}, {}\n🚨 Urgent reminders still ring",
+            format_datetime(&until.with_timezone(&user.tz())),
+            quiet_behaviour(user.quiet_silent)
+        ),
+        None => {
+            let released = ReminderRepository::new(pool).release_held(user.id).await?;
+            let mut reply = "🔔 Do not disturb is off".to_string();
+            if released > 0 {
+                reply.push_str(&format!("\n📬 {} held reminder(s) are on their way", released));
+            }
+            reply
+        }
+    };
+    bot.send_message(msg.chat.id, reply).await?;
+
+    Ok(())
+}
+
+/// What happens to reminders during quiet hours
+fn quiet_behaviour(silent: bool) -> &'static str {
+    if silent {
+        "reminders arrive without sound"
+    } else {
+        "reminders wait until it ends"
+    }
+}
+
+/// /notify - notification channels: /notify default telegram,email,
+/// /notify email <address|off>, /notify email confirm <code>,
+/// /notify webhook <url|off>
+pub async fn notify_settings(
+    bot: Bot,
+    msg: Message,
+    pool: PgPool,
+    notify: NotifyConfig,
+    text: String,
+) -> HandlerResult {
+    let user_id = msg.from().ok_or("No user in message")?.id.0 as i64;
+    let user: crate::db::models::User = sqlx::query_as(
+        "SELECT * FROM users WHERE telegram_id = $1",
+    )
+    .bind(user_id)
+    .fetch_one(&pool)
+    .await?;
+
+    let text = text.trim();
+    let (setting, value) = match text.split_once(char::is_whitespace) {
+        Some((setting, value)) => (setting.to_lowercase(), value.trim()),
+        None => (text.to_lowercase(), ""),
+    };
+
+    let reply = match (setting.as_str(), value) {
+        ("", _) => format_notify_settings(&user, &notify),
+        ("default", list) => match NotifyChannel::parse_list(list) {
+            Ok(chosen) => match missing_setup(&user, &chosen) {
+                Some(problem) => format!("❌ {}", problem),
+                None => {
+                    channels::set_default_channels(&pool, user.id, &chosen).await?;
+                    format!(
+                        "📨 Reminders now go to {}",
+                        NotifyChannel::names(&chosen).join(", ")
+                    )
+                }
+            },
+            Err(e) => format!("❌ {}", e.user_message()),
+        },
+        ("email", "off") => {
+            channels::remove_email(&pool, user.id).await?;
+            "📧 Email address removed".to_string()
+        }
+        ("email", _) if notify.smtp.is_none() => {
+            "❌ Email reminders are not available on this bot".to_string()
+        }
+        ("email", value) if value.split_whitespace().next().is_some_and(|word| word.eq_ignore_ascii_case("confirm")) => {
+            let code = value.split_whitespace().nth(1).unwrap_or_default();
+            match channels::confirm_email(&pool, user.id, code).await? {
+                Some(address) => format!(
+                    "📧 Email set to {}\n\n\
+                     Use it for all reminders with /notify default telegram,email \
+                     or for one with /remind via email <time> <text>",
+                    address
+                ),
+                None => "❌ Wrong or expired code. Get a new one with /notify email <address>"
+                    .to_string(),
+            }
+        }
+        ("email", address) if !address.is_empty() => {
+            let smtp = notify.smtp.as_ref().ok_or("No SMTP relay configured")?;
+            let sent = match channels::validate_email(address) {
+                Ok(address) => channels::request_email(&pool, smtp, user.id, &address)
+                    .await
+                    .map(|()| address),
+                Err(e) => Err(e),
+            };
+            match sent {
+                Ok(address) => format!(
+                    "📧 A code was sent to {}\n\n\
+                     Confirm the address with /notify email confirm <code>",
+                    address
+                ),
+                Err(e) => format!("❌ {}", e.user_message()),
+            }
+        }
+        ("webhook", "off") => {
+            channels::set_webhook(&pool, user.id, None).await?;
+            "🔗 Webhook removed".to_string()
+        }
+        ("webhook", url) if !url.is_empty() => {
+            match channels::validate_webhook_url(url, notify.webhook_allow_private) {
+                Ok(url) => {
+                    channels::set_webhook(&pool, user.id, Some(&url)).await?;
+                    format!(
+                        "🔗 Webhook set to {}\n\n\
+                         Each reminder is POSTed there as JSON. Use it for all reminders with \
+                         /notify default telegram,webhook or for one with /remind via webhook <time> <text>",
+                        url
+                    )
+                }
+                Err(e) => format!("❌ {}", e.user_message()),
+            }
+        }
+        _ => "❌ Use: /notify default telegram,email | /notify email <address|off> | \
+              /notify email confirm <code> | /notify webhook <url|off>"
+            .to_string(),
+    };
+
+    bot.send_message(msg.chat.id, reply).await?;
+    Ok(())
+}
+
+/// Current channels and addresses for /notify
+fn format_notify_settings(user: &crate::db::models::User, notify: &NotifyConfig) -> String {
+    let email = match (&user.email, &user.pending_email, &notify.smtp) {
+        (_, _, None) => "not available on this bot".to_string(),
+        (Some(email), _, Some(_)) => email.clone(),
+        (None, Some(pending), Some(_)) => format!("{} (waiting for confirmation)", pending),
+        (None, None, Some(_)) => "not set".to_string(),
+    };
+    format!(
+        "📨 Reminders go to: {}\n📧 Email: {}\n🔗 Webhook: {}\n\n\
+         Change with:\n\
+         /notify default telegram,email,webhook\n\
+         /notify email you@example.com (or off)\n\
+         /notify webhook https://example.com/hook (or off)\n\
+         One reminder only: /remind via email 18:00 Pay rent",
+        NotifyChannel::names(&user.default_channels()).join(", "),
+        email,
+        user.webhook_url.as_deref().unwrap_or("not set")
+    )
+}
+
+/// Shared location - detect time zone from longitude
+pub async fn timezone_from_location(
+    bot: Bot,
+    msg: Message,
+    pool: PgPool,
+    location: Location,
+) -> HandlerResult {
+    let user_id = msg.from().ok_or("No user in message")?.id.0 as i64;
+
+    let user: crate::db::models::User = sqlx::query_as(
+        "SELECT * FROM users WHERE telegram_id = $1",
+    )
+    .bind(user_id)
+    .fetch_one(&pool)
+    .await?;
+
+    let tz = timezone::timezone_from_location(location.longitude);
+    save_timezone(&pool, user.id, tz).await?;
+
+    bot.send_message(
+        msg.chat.id,
+        format!(
+            "🌍 Time zone set to {} from your location\n🕐 Local time: {}\n\n\
+             This offset ignores daylight saving time; use /timezone Europe/Berlin \
+             (your region) for exact rules.",
+            tz,
+            format_datetime(&chrono::Utc::now().with_timezone(&tz))
+        ),
+    )
+    .reply_markup(KeyboardRemove::new())
+    .await?;
+
+    Ok(())
+}
+
+async fn save_timezone(pool: &PgPool, user_id: i32, tz: Tz) -> HandlerResult {
+    sqlx::query("UPDATE users SET timezone = $1 WHERE id = $2")
+        .bind(tz.name())
+        .bind(user_id)
+        .execute(pool)
+        .await?;
+
+    tracing::debug!("Time zone for user {} set to {}", user_id, tz);
+    Ok(())
+}
+
+/// /track - record time spent on a task: /track <id> <duration>
+pub async fn track_time(bot: Bot, msg: Message, pool: PgPool, text: String) -> HandlerResult {
+    let parts: Vec<&str> = text.split_whitespace().collect();
+
+    let (id, duration) = match parts.as_slice() {
+        [id, duration] => match id.trim_start_matches('#').parse::<i32>() {
+            Ok(id) => (id, *duration),
+            Err(_) => {
+                bot.send_message(msg.chat.id, "❌ Invalid task ID!")
+                    .await?;
+                return Ok(());
+            }
+        },
+        _ => {
+            bot.send_message(
+                msg.chat.id,
+                "❌ Invalid format!\n\nUse: /track <id> <time>\nExample: /track 12 45m",
+            )
+            .await?;
+            return Ok(());
+        }
+    };
+
+    let duration = match parse_duration(duration) {
+        Ok(duration) => duration,
+        Err(e) => {
+            bot.send_message(msg.chat.id, format!("❌ {}", e.user_message()))
+                .await?;
+            return Ok(());
+        }
+    };
+
+    let user_id = msg.from().ok_or("No user in message")?.id.0 as i64;
+    let user: crate::db::models::User = sqlx::query_as(
+        "SELECT * FROM users WHERE telegram_id = $1",
+    )
+    .bind(user_id)
+    .fetch_one(&pool)
+    .await?;
+
+    let todo_repo = TodoRepository::new(pool);
+    let todo_service = TodoService::new(todo_repo);
+    let todo = todo_service.get_todo(id).await?;
+
+    if todo.user_id != user.id {
+        bot.send_message(msg.chat.id, "❌ This is not your task!")
+            .await?;
+        return Ok(());
+    }
+
+    todo_service.track_time(id, duration.num_minutes()).await?;
+
+    bot.send_message(
+        msg.chat.id,
+        format!("⏱ Tracked {} on task #{}", format_minutes(duration.num_minutes()), id),
+    )
+    .await?;
+
+    Ok(())
+}
+
+/// Handle callback buttons
+pub async fn handle_callback(
+    bot: Bot,
+    q: CallbackQuery,
+    pool: PgPool,
+    dialogue: BotDialogue,
+    conversion_queue: ConversionQueue,
+) -> HandlerResult {
+    if let Some(data) = q.data.clone() {
+        tracing::info!("Callback received: {}", data);
+
+        if data.starts_with("review_") {
+            return callbacks::handle_review_callback(bot, q, pool, data).await;
+        }
+        if data.starts_with("calendar_") {
+            return callbacks::handle_calendar_callback(bot, q, pool, dialogue, data).await;
+        }
+        if data.starts_with("convert_") {
+            return callbacks::handle_conversion_callback(bot, q, pool, conversion_queue, data).await;
+        }
+        if data.starts_with("history_") {
+            return callbacks::handle_history_callback(bot, q, pool, data).await;
+        }
+        if data.starts_with("snooze_") || data.starts_with("reminder_") {
+            return callbacks::handle_reminder_callback(bot, q, pool, dialogue, data).await;
+        }
+        if ["complete_", "delete_", "remind_"].iter().any(|p| data.starts_with(p)) {
+            return callbacks::handle_todo_callback(bot, q, pool, dialogue, data).await;
+        }
+
+        bot.answer_callback_query(&q.id).await?;
+    }
+    Ok(())
 }
@@ -20,10 +20,40 @@ pub fn schema() -> UpdateHandler<Box<dyn std::error::Error + Send + Sync + 'stat
         .branch(case![Command::DeleteTodo(id)].endpoint(commands::delete_todo))
         .branch(case![Command::Remind(text)].endpoint(commands::set_reminder))
         .branch(case![Command::ListReminders].endpoint(commands::list_reminders))
-        .branch(case![Command::CancelReminder(id)].endpoint(commands::cancel_reminder));
+        .branch(case![Command::CancelReminder(id)].endpoint(commands::cancel_reminder))
+        .branch(case![Command::EditReminder(text)].endpoint(commands::edit_reminder))
+        .branch(case![Command::ReminderHistory(page)].endpoint(commands::reminder_history))
+        .branch(case![Command::Calendar(arg)].endpoint(commands::calendar))
+        .branch(case![Command::Cron(text)].endpoint(commands::set_cron_reminder))
+        .branch(case![Command::Nag(text)].endpoint(commands::set_nagging_reminder))
+        .branch(case![Command::RemindTodo(text)].endpoint(commands::remind_todo))
+        .branch(case![Command::RemindChannel(text)].endpoint(commands::remind_channel))
+        .branch(case![Command::DueReminders(text)].endpoint(commands::due_reminders))
+        .branch(case![Command::StopRepeat(id)].endpoint(commands::stop_repeat))
+        .branch(case![Command::Review(arg)].endpoint(commands::review))
+        .branch(case![Command::Track(text)].endpoint(commands::track_time))
+        .branch(case![Command::Timezone(name)].endpoint(commands::set_timezone))
+        .branch(case![Command::Quiet(text)].endpoint(commands::quiet_hours))
+        .branch(case![Command::Dnd(text)].endpoint(commands::do_not_disturb))
+        .branch(case![Command::Notify(text)].endpoint(commands::notify_settings));
 
     let message_handler = Update::filter_message()
         .branch(command_handler)
+        .branch(
+            case![State::ReceivingSnoozeTime { reminder_id }]
+                .endpoint(commands::receive_snooze_time),
+        )
+        .branch(
+            case![State::ReceivingRescheduleTime { reminder_id }]
+                .endpoint(commands::receive_reschedule_time),
+        )
+        .branch(
+            case![State::ReceivingTodoReminderTime { todo_id }]
+                .endpoint(commands::receive_todo_reminder_time),
+        )
+        .branch(dptree::filter(commands::is_calendar_file).endpoint(commands::receive_calendar_file))
+        .branch(dptree::filter(commands::is_image_file).endpoint(commands::receive_image))
+        .branch(Message::filter_location().endpoint(commands::timezone_from_location))
         .branch(dptree::endpoint(commands::handle_message));
 
     let callback_query_handler = Update::filter_callback_query()
@@ -64,4 +94,49 @@ pub enum Command {
 
     #[command(description = "Отменить напоминание: /cancelreminder <id>")]
     CancelReminder(i32),
+
+    #[command(description = "Изменить напоминание: /editreminder <id> time=<когда> text=<текст> repeat=<правило>")]
+    EditReminder(String),
+
+    #[command(description = "История напоминаний: /reminderhistory [страница]")]
+    ReminderHistory(String),
+
+    #[command(description = "Календарь .ics: /calendar, /calendar feed [off]")]
+    Calendar(String),
+
+    #[command(description = "Напоминание по cron: /cron \"<выражение>\" <текст>")]
+    Cron(String),
+
+    #[command(description = "Настойчивое напоминание до подтверждения: /nag <время> <текст>")]
+    Nag(String),
+
+    #[command(description = "Напомнить о задаче: /remindtodo <id> <когда>")]
+    RemindTodo(String),
+
+    #[command(description = "Напоминание в канал: /remindchannel [@канал] <время> <текст>")]
+    RemindChannel(String),
+
+    #[command(description = "Напоминания до срока задач: /duereminders 1d 2h")]
+    DueReminders(String),
+
+    #[command(description = "Остановить повторение: /stoprepeat <id>")]
+    StopRepeat(i32),
+
+    #[command(description = "Недельный отчёт: /review [on|off]")]
+    Review(String),
+
+    #[command(description = "Учесть время по задаче: /track <id> <время>")]
+    Track(String),
+
+    #[command(description = "Часовой пояс: /timezone Europe/Moscow")]
+    Timezone(String),
+
+    #[command(description = "Тихие часы: /quiet 23:00-08:00 [silent] | off")]
+    Quiet(String),
+
+    #[command(description = "Не беспокоить: /dnd 2h | off")]
+    Dnd(String),
+
+    #[command(description = "Каналы уведомлений: /notify [default <каналы> | email <адрес> | webhook <url>]")]
+    Notify(String),
 }
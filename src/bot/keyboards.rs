@@ -1,5 +1,9 @@
 // Keyboards and inline buttons for bot
-use teloxide::types::{InlineKeyboardButton, InlineKeyboardMarkup, KeyboardButton, KeyboardMarkup};
+use crate::db::models::{Reminder, Todo};
+use crate::shared::utils::truncate_text;
+use teloxide::types::{
+    ButtonRequest, InlineKeyboardButton, InlineKeyboardMarkup, KeyboardButton, KeyboardMarkup,
+};
 
 /// Main bot menu
 pub fn main_menu() -> KeyboardMarkup {
@@ -16,6 +20,15 @@ pub fn main_menu() -> KeyboardMarkup {
     .resize_keyboard(true)
 }
 
+/// One-time button that shares the user's location (time zone detection)
+pub fn share_location() -> KeyboardMarkup {
+    KeyboardMarkup::new(vec![vec![
+        KeyboardButton::new("📍 Share location").request(ButtonRequest::Location),
+    ]])
+    .resize_keyboard(true)
+    .one_time_keyboard(true)
+}
+
 /// Actions for specific task
 pub fn todo_actions(todo_id: i32) -> InlineKeyboardMarkup {
     InlineKeyboardMarkup::new(vec![
@@ -29,24 +42,110 @@ pub fn todo_actions(todo_id: i32) -> InlineKeyboardMarkup {
     ])
 }
 
-/// File conversion menu
-pub fn conversion_menu(file_type: &str) -> InlineKeyboardMarkup {
+/// Delivered reminder: snooze options and done
+///
+/// For a reminder about a task, done completes the task.
+pub fn reminder_actions(reminder_id: i32, todo_id: Option<i32>) -> InlineKeyboardMarkup {
+    let done = match todo_id {
+        Some(todo_id) => format!("✅ Complete task #{}", todo_id),
+        None => "✅ Done".to_string(),
+    };
+
+    InlineKeyboardMarkup::new(vec![
+        vec![
+            InlineKeyboardButton::callback("😴 10m", format!("snooze_{}_10m", reminder_id)),
+            InlineKeyboardButton::callback("😴 1h", format!("snooze_{}_1h", reminder_id)),
+            InlineKeyboardButton::callback(
+                "🌅 Tomorrow morning",
+                format!("snooze_{}_tomorrow", reminder_id),
+            ),
+        ],
+        vec![
+            InlineKeyboardButton::callback("✏️ Custom", format!("snooze_{}_custom", reminder_id)),
+            InlineKeyboardButton::callback(done, format!("reminder_done_{}", reminder_id)),
+        ],
+    ])
+}
+
+/// Insistent reminder: repeats until acknowledged
+pub fn reminder_ack(reminder_id: i32) -> InlineKeyboardMarkup {
+    InlineKeyboardMarkup::new(vec![vec![InlineKeyboardButton::callback(
+        "✅ Acknowledge",
+        format!("reminder_ack_{}", reminder_id),
+    )]])
+}
+
+/// Reminder list: a reschedule button per reminder
+pub fn reschedule_buttons(reminders: &[Reminder]) -> InlineKeyboardMarkup {
+    // Telegram allows at most 100 buttons per message
+    let buttons: Vec<InlineKeyboardButton> = reminders
+        .iter()
+        .take(96)
+        .map(|reminder| {
+            InlineKeyboardButton::callback(
+                format!("🕐 #{}", reminder.id),
+                format!("reminder_reschedule_{}", reminder.id),
+            )
+        })
+        .collect();
+
+    InlineKeyboardMarkup::new(buttons.chunks(4).map(|row| row.to_vec()))
+}
+
+/// Reminder history: newer and older pages
+pub fn history_pages(page: i64, pages: i64) -> InlineKeyboardMarkup {
+    let mut row = Vec::new();
+    if page > 1 {
+        row.push(InlineKeyboardButton::callback(
+            "◀️ Newer",
+            format!("history_{}", page - 1),
+        ));
+    }
+    if page < pages {
+        row.push(InlineKeyboardButton::callback(
+            "Older ▶️",
+            format!("history_{}", page + 1),
+        ));
+    }
+
+    // An empty keyboard removes the buttons of a single page
+    let rows = if row.is_empty() { vec![] } else { vec![row] };
+    InlineKeyboardMarkup::new(rows)
+}
+
+/// Confirm or cancel importing an uploaded calendar
+pub fn calendar_import(count: usize) -> InlineKeyboardMarkup {
+    InlineKeyboardMarkup::new(vec![vec![
+        InlineKeyboardButton::callback(format!("✅ Import {}", count), "calendar_import"),
+        InlineKeyboardButton::callback("✖️ Cancel", "calendar_cancel"),
+    ]])
+}
+
+/// Weekly review: reschedule or drop each stale task
+pub fn review_actions(todos: &[Todo]) -> InlineKeyboardMarkup {
+    InlineKeyboardMarkup::new(todos.iter().map(|todo| {
+        vec![
+            InlineKeyboardButton::callback(
+                format!("📅 +1w #{} {}", todo.id, truncate_text(&todo.title, 20)),
+                format!("review_postpone_{}", todo.id),
+            ),
+            InlineKeyboardButton::callback("🗑 Drop", format!("review_drop_{}", todo.id)),
+        ]
+    }))
+}
+
+/// File conversion menu: convert_<id>_<target>
+pub fn conversion_menu(conversion_id: i32, file_type: &str) -> InlineKeyboardMarkup {
+    let button = |label: &str, target: &str| {
+        InlineKeyboardButton::callback(label, format!("convert_{}_{}", conversion_id, target))
+    };
+
     match file_type {
         "image" => InlineKeyboardMarkup::new(vec![
-            vec![
-                InlineKeyboardButton::callback("→ PNG", "convert_png"),
-                InlineKeyboardButton::callback("→ JPEG", "convert_jpeg"),
-            ],
-            vec![
-                InlineKeyboardButton::callback("→ WebP", "convert_webp"),
-                InlineKeyboardButton::callback("🔄 Compress", "compress"),
-            ],
-        ]),
-        "document" => InlineKeyboardMarkup::new(vec![
-            vec![
-                InlineKeyboardButton::callback("→ PDF", "convert_pdf"),
-            ],
+            vec![button("→ PNG", "png"), button("→ JPEG", "jpeg")],
+            vec![button("→ WebP", "webp"), button("🔄 Compress", "compress")],
         ]),
+        "document" => InlineKeyboardMarkup::new(vec![vec![button("→ PDF", "pdf")]]),
         _ => InlineKeyboardMarkup::new(Vec::<Vec<InlineKeyboardButton>>::new()),
     }
 }
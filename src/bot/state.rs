@@ -1,5 +1,10 @@
 // Dialog states for FSM (Finite State Machine)
+use crate::reminder::calendar::PlannedReminder;
 use serde::{Deserialize, Serialize};
+use teloxide::dispatching::dialogue::{Dialogue, InMemStorage};
+
+/// Dialogue handle injected into handlers
+pub type BotDialogue = Dialogue<State, InMemStorage<State>>;
 
 /// Bot states for dialogs
 #[derive(Clone, Default, Debug, Serialize, Deserialize)]
@@ -11,4 +16,9 @@ pub enum State {
     ReceivingReminderTime,
     ReceivingReminderText { time: String },
     ConvertingFile { file_id: String, file_type: String },
+    ReceivingSnoozeTime { reminder_id: i32 },
+    ReceivingRescheduleTime { reminder_id: i32 },
+    ReceivingTodoReminderTime { todo_id: i32 },
+    /// Uploaded calendar waiting for the user to confirm the import
+    ConfirmingCalendarImport { reminders: Vec<PlannedReminder> },
 }
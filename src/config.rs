@@ -6,6 +6,7 @@
 // 3. System environment variables (priority)
 
 use crate::error::{AppError, Result};
+use crate::shared::duration::parse_duration;
 use serde::{Deserialize, Serialize};
 use std::env;
 
@@ -23,6 +24,12 @@ pub struct Config {
 
     /// Logging configuration
     pub logging: LoggingConfig,
+
+    /// Reminder delivery configuration
+    pub reminders: ReminderConfig,
+
+    /// Email and webhook notification channels
+    pub notify: NotifyConfig,
 }
 
 /// Telegram bot configuration
@@ -70,6 +77,14 @@ pub struct AppConfig {
     #[serde(default)]
     pub port: Option<u16>,
 
+    /// Public address of `port`, e.g. https://bot.example.com (calendar feed links)
+    #[serde(default)]
+    pub public_url: Option<String>,
+
+    /// Bearer token for GET /metrics; without it only local connections get metrics
+    #[serde(default)]
+    pub metrics_token: Option<String>,
+
     /// Directory for temporary files
     #[serde(default = "default_temp_dir")]
     pub temp_dir: String,
@@ -81,6 +96,18 @@ pub struct AppConfig {
     /// Maximum uploaded file size (in bytes)
     #[serde(default = "default_max_file_size")]
     pub max_file_size: usize,
+
+    /// Files converted in parallel
+    #[serde(default = "default_conversion_workers")]
+    pub conversion_workers: usize,
+
+    /// Converted files are deleted after this long (minutes)
+    #[serde(default = "default_conversion_file_retention_minutes")]
+    pub conversion_file_retention_minutes: i64,
+
+    /// Conversion history is deleted after this long (minutes)
+    #[serde(default = "default_conversion_history_retention_minutes")]
+    pub conversion_history_retention_minutes: i64,
 }
 
 /// Logging configuration
@@ -95,6 +122,108 @@ pub struct LoggingConfig {
     pub format: String,
 }
 
+/// Reminder delivery configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReminderConfig {
+    /// What to do with reminders that became due while the bot was down
+    #[serde(default)]
+    pub catch_up: CatchUpPolicy,
+
+    /// With `skip`: reminders late by more than this are not sent (minutes)
+    #[serde(default = "default_catch_up_max_age_minutes")]
+    pub catch_up_max_age_minutes: i64,
+}
+
+/// Email and webhook notification channels
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct NotifyConfig {
+    /// Mail relay for the email channel; `None` - email is off
+    #[serde(default)]
+    pub smtp: Option<SmtpConfig>,
+
+    /// Accept plain http and private addresses as webhook URLs (development)
+    #[serde(default)]
+    pub webhook_allow_private: bool,
+}
+
+/// SMTP relay (mail submission server)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SmtpConfig {
+    pub host: String,
+
+    #[serde(default = "default_smtp_port")]
+    pub port: u16,
+
+    /// How the connection is encrypted
+    #[serde(default)]
+    pub security: SmtpSecurity,
+
+    /// Sender address, e.g. reminders@example.com
+    pub from: String,
+
+    /// Credentials for AUTH PLAIN, if the relay requires them
+    #[serde(default)]
+    pub username: Option<String>,
+    #[serde(default)]
+    pub password: Option<String>,
+}
+
+/// Encryption of the SMTP connection
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SmtpSecurity {
+    /// Upgrade with STARTTLS when the server offers it (port 25 or 587);
+    /// without it credentials are only sent to a relay on this machine
+    #[default]
+    StartTls,
+    /// TLS from the start (port 465)
+    Tls,
+}
+
+impl std::str::FromStr for SmtpSecurity {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "starttls" => Ok(Self::StartTls),
+            "tls" => Ok(Self::Tls),
+            other => Err(AppError::Config(format!(
+                "Unknown SMTP_SECURITY '{}': use starttls or tls",
+                other
+            ))),
+        }
+    }
+}
+
+/// Delivery of reminders missed during downtime
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CatchUpPolicy {
+    /// Send each one with a "late by" note
+    #[default]
+    Individual,
+    /// One summary message per chat
+    Summary,
+    /// Drop the ones older than the maximum age, send the rest individually
+    Skip,
+}
+
+impl std::str::FromStr for CatchUpPolicy {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "individual" => Ok(Self::Individual),
+            "summary" => Ok(Self::Summary),
+            "skip" => Ok(Self::Skip),
+            other => Err(AppError::Config(format!(
+                "Unknown REMINDER_CATCH_UP '{}': use individual, summary or skip",
+                other
+            ))),
+        }
+    }
+}
+
 // Default values
 fn default_max_handlers() -> usize { 100 }
 fn default_max_connections() -> u32 { 10 }
@@ -105,8 +234,13 @@ fn default_environment() -> String { "development".to_string() }
 fn default_temp_dir() -> String { "./tmp".to_string() }
 fn default_output_dir() -> String { "./converted".to_string() }
 fn default_max_file_size() -> usize { 20 * 1024 * 1024 } // 20 MB
+fn default_conversion_workers() -> usize { 2 }
+fn default_conversion_file_retention_minutes() -> i64 { 24 * 60 } // 1 day
+fn default_conversion_history_retention_minutes() -> i64 { 30 * 24 * 60 } // 30 days
 fn default_log_level() -> String { "info".to_string() }
 fn default_log_format() -> String { "pretty".to_string() }
+fn default_catch_up_max_age_minutes() -> i64 { 60 }
+fn default_smtp_port() -> u16 { 25 }
 
 impl Config {
     /// Load configuration from environment variables
@@ -144,12 +278,40 @@ impl Config {
             name: env::var("APP_NAME").unwrap_or_else(|_| default_app_name()),
             environment: env::var("ENVIRONMENT").unwrap_or_else(|_| default_environment()),
             port: env::var("PORT").ok().and_then(|v| v.parse().ok()),
+            public_url: env::var("PUBLIC_URL")
+                .ok()
+                .map(|url| url.trim().trim_end_matches('/').to_string())
+                .filter(|url| !url.is_empty()),
+            metrics_token: env::var("METRICS_TOKEN")
+                .ok()
+                .map(|token| token.trim().to_string())
+                .filter(|token| !token.is_empty()),
             temp_dir: env::var("TEMP_DIR").unwrap_or_else(|_| default_temp_dir()),
             output_dir: env::var("OUTPUT_DIR").unwrap_or_else(|_| default_output_dir()),
             max_file_size: env::var("MAX_FILE_SIZE")
                 .ok()
                 .and_then(|v| v.parse().ok())
                 .unwrap_or_else(default_max_file_size),
+            conversion_workers: env::var("CONVERSION_WORKERS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or_else(default_conversion_workers),
+            conversion_file_retention_minutes: match env::var("CONVERSION_FILE_RETENTION") {
+                Ok(value) => parse_duration(&value)
+                    .map_err(|e| {
+                        AppError::Config(format!("Invalid CONVERSION_FILE_RETENTION: {}", e))
+                    })?
+                    .num_minutes(),
+                Err(_) => default_conversion_file_retention_minutes(),
+            },
+            conversion_history_retention_minutes: match env::var("CONVERSION_HISTORY_RETENTION") {
+                Ok(value) => parse_duration(&value)
+                    .map_err(|e| {
+                        AppError::Config(format!("Invalid CONVERSION_HISTORY_RETENTION: {}", e))
+                    })?
+                    .num_minutes(),
+                Err(_) => default_conversion_history_retention_minutes(),
+            },
         };
 
         let logging = LoggingConfig {
@@ -157,11 +319,56 @@ impl Config {
             format: env::var("LOG_FORMAT").unwrap_or_else(|_| default_log_format()),
         };
 
+        let reminders = ReminderConfig {
+            catch_up: match env::var("REMINDER_CATCH_UP") {
+                Ok(value) => value.parse()?,
+                Err(_) => CatchUpPolicy::default(),
+            },
+            catch_up_max_age_minutes: match env::var("REMINDER_CATCH_UP_MAX_AGE") {
+                Ok(value) => parse_duration(&value)
+                    .map_err(|e| {
+                        AppError::Config(format!("Invalid REMINDER_CATCH_UP_MAX_AGE: {}", e))
+                    })?
+                    .num_minutes(),
+                Err(_) => default_catch_up_max_age_minutes(),
+            },
+        };
+
+        let smtp = match env::var("SMTP_HOST") {
+            Ok(host) if !host.trim().is_empty() => Some(SmtpConfig {
+                host: host.trim().to_string(),
+                port: env::var("SMTP_PORT")
+                    .ok()
+                    .and_then(|v| v.parse().ok())
+                    .unwrap_or_else(default_smtp_port),
+                security: match env::var("SMTP_SECURITY") {
+                    Ok(value) => value.parse()?,
+                    Err(_) => SmtpSecurity::default(),
+                },
+                from: env::var("SMTP_FROM").map_err(|_| {
+                    AppError::Config("SMTP_FROM not set (required with SMTP_HOST)".to_string())
+                })?,
+                username: env::var("SMTP_USERNAME").ok().filter(|v| !v.is_empty()),
+                password: env::var("SMTP_PASSWORD").ok().filter(|v| !v.is_empty()),
+            }),
+            _ => None,
+        };
+
+        let notify = NotifyConfig {
+            smtp,
+            webhook_allow_private: env::var("WEBHOOK_ALLOW_PRIVATE")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(false),
+        };
+
         Ok(Config {
             telegram,
             database,
             app,
             logging,
+            reminders,
+            notify,
         })
     }
 
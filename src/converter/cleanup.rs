@@ -0,0 +1,266 @@
+// Conversion cleanup - retention of converted files and conversion history
+//
+// A background task runs at startup and then every CLEANUP_INTERVAL:
+// 1. Downloads left in TEMP_DIR (a conversion normally deletes its own) are
+//    deleted once older than ORPHAN_GRACE
+// 2. Results in OUTPUT_DIR older than CONVERSION_FILE_RETENTION are deleted
+//    and their conversions forget the path
+// 3. Younger results no conversion refers to (a worker died between
+//    converting and recording) are deleted after ORPHAN_GRACE as orphans
+// 4. Conversions created more than CONVERSION_HISTORY_RETENTION ago are
+//    pruned in batches along idx_conversions_created_at; jobs still queued
+//    or being converted are kept
+//
+// Each run is logged and counted in ConversionMetrics (GET /metrics).
+
+use crate::config::AppConfig;
+use crate::converter::metrics::ConversionMetrics;
+use crate::converter::repository::ConversionRepository;
+use crate::converter::service::{ConverterService, StoredFile};
+use crate::error::Result;
+use chrono::Utc;
+use sqlx::PgPool;
+use std::io::ErrorKind;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::time::{sleep, Duration};
+
+/// Time between cleanup runs
+const CLEANUP_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Age before an unreferenced file counts as an orphan; well above the
+/// job lease, so files of running conversions are never touched
+const ORPHAN_GRACE: Duration = Duration::from_secs(60 * 60);
+
+/// Rows deleted per statement when pruning history
+const PRUNE_BATCH: i64 = 1000;
+
+/// What a cleanup run did
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct CleanupStats {
+    pub expired_files: u64,
+    pub orphan_files: u64,
+    pub freed_bytes: u64,
+    pub pruned_rows: u64,
+}
+
+/// What to do with a stored file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Disposal {
+    Keep,
+    /// Result past the retention period
+    Expire,
+    /// Leftover download
+    Orphan,
+    /// Result to delete unless a conversion refers to it
+    OrphanUnlessReferenced,
+}
+
+/// Periodic cleanup of converter files and history
+pub struct ConversionCleanup {
+    converter: ConverterService,
+    repo: ConversionRepository,
+    file_retention: Duration,
+    history_retention: chrono::Duration,
+    metrics: Arc<ConversionMetrics>,
+}
+
+impl ConversionCleanup {
+    pub fn new(pool: PgPool, converter: ConverterService, config: &AppConfig) -> Self {
+        let minutes = |minutes: i64| Duration::from_secs(minutes.max(0) as u64 * 60);
+        Self {
+            converter,
+            repo: ConversionRepository::new(pool),
+            file_retention: minutes(config.conversion_file_retention_minutes),
+            history_retention: chrono::Duration::minutes(
+                config.conversion_history_retention_minutes,
+            ),
+            metrics: Arc::new(ConversionMetrics::default()),
+        }
+    }
+
+    /// Shared metrics, updated after each run
+    pub fn metrics(&self) -> Arc<ConversionMetrics> {
+        Arc::clone(&self.metrics)
+    }
+
+    /// Clean up now and every CLEANUP_INTERVAL until the process exits
+    pub async fn run(self) -> Result<()> {
+        tracing::info!(
+            "Starting conversion cleanup (files kept {} min, history {} days)...",
+            self.file_retention.as_secs() / 60,
+            self.history_retention.num_days()
+        );
+
+        loop {
+            ConversionMetrics::inc(&self.metrics.cleanup_runs_total);
+            match self.run_once(SystemTime::now()).await {
+                Ok(stats) => {
+                    self.record(&stats);
+                    tracing::info!(
+                        "Conversion cleanup: {} expired and {} orphan files deleted ({} KB), {} history rows pruned",
+                        stats.expired_files,
+                        stats.orphan_files,
+                        stats.freed_bytes / 1024,
+                        stats.pruned_rows
+                    );
+                }
+                Err(e) => {
+                    ConversionMetrics::inc(&self.metrics.cleanup_failures_total);
+                    tracing::error!("Conversion cleanup error: {}", e);
+                }
+            }
+
+            sleep(CLEANUP_INTERVAL).await;
+        }
+    }
+
+    /// Delete old and orphaned files, then prune the history
+    pub async fn run_once(&self, now: SystemTime) -> Result<CleanupStats> {
+        let mut stats = CleanupStats::default();
+        let mut expired = Vec::new();
+        let mut orphans = Vec::new();
+        let mut candidates = Vec::new();
+        for file in self.converter.stored_files().await? {
+            match disposal(&file, now, self.file_retention) {
+                Disposal::Keep => {}
+                Disposal::Expire => expired.push(file),
+                Disposal::Orphan => orphans.push(file),
+                Disposal::OrphanUnlessReferenced => candidates.push(file),
+            }
+        }
+
+        if !candidates.is_empty() {
+            let paths: Vec<String> = candidates.iter().map(path_string).collect();
+            let referenced = self.repo.referenced_results(&paths).await?;
+            orphans.extend(
+                candidates
+                    .into_iter()
+                    .filter(|file| !referenced.contains(&path_string(file))),
+            );
+        }
+
+        let mut deleted = Vec::new();
+        for file in &expired {
+            if remove(file).await {
+                stats.expired_files += 1;
+                stats.freed_bytes += file.size;
+                deleted.push(path_string(file));
+            }
+        }
+        for file in &orphans {
+            if remove(file).await {
+                stats.orphan_files += 1;
+                stats.freed_bytes += file.size;
+            }
+        }
+        if !deleted.is_empty() {
+            self.repo.forget_results(&deleted).await?;
+        }
+
+        let before = Utc::now() - self.history_retention;
+        stats.pruned_rows = self.repo.prune(before, PRUNE_BATCH).await?;
+        Ok(stats)
+    }
+
+    fn record(&self, stats: &CleanupStats) {
+        let metrics = &self.metrics;
+        ConversionMetrics::add(&metrics.cleanup_expired_files_total, stats.expired_files);
+        ConversionMetrics::add(&metrics.cleanup_orphan_files_total, stats.orphan_files);
+        ConversionMetrics::add(&metrics.cleanup_freed_bytes_total, stats.freed_bytes);
+        ConversionMetrics::add(&metrics.cleanup_pruned_rows_total, stats.pruned_rows);
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        ConversionMetrics::set(&metrics.cleanup_last_run_seconds, now.as_secs());
+    }
+}
+
+/// Decide on a file by its age and directory
+fn disposal(file: &StoredFile, now: SystemTime, file_retention: Duration) -> Disposal {
+    // Modified "in the future" (clock changes) counts as new
+    let age = now.duration_since(file.modified).unwrap_or_default();
+    if file.temporary {
+        if age >= ORPHAN_GRACE {
+            Disposal::Orphan
+        } else {
+            Disposal::Keep
+        }
+    } else if age >= file_retention {
+        Disposal::Expire
+    } else if age >= ORPHAN_GRACE {
+        Disposal::OrphanUnlessReferenced
+    } else {
+        Disposal::Keep
+    }
+}
+
+fn path_string(file: &StoredFile) -> String {
+    file.path.to_string_lossy().into_owned()
+}
+
+/// Delete a file; false if it could not be deleted
+async fn remove(file: &StoredFile) -> bool {
+    match tokio::fs::remove_file(&file.path).await {
+        Ok(()) => true,
+        // Already gone, e.g. removed by another instance
+        Err(e) if e.kind() == ErrorKind::NotFound => false,
+        Err(e) => {
+            tracing::warn!("Failed to remove {:?}: {}", file.path, e);
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_disposal() {
+        let now = SystemTime::now();
+        let hours = |h: u64| Duration::from_secs(h * 60 * 60);
+        let file = |age: Duration, temporary| StoredFile {
+            path: PathBuf::from("converted/0b1c.webp"),
+            size: 1024,
+            modified: now - age,
+            temporary,
+        };
+        let retention = hours(24);
+
+        assert_eq!(
+            disposal(&file(hours(0), false), now, retention),
+            Disposal::Keep
+        );
+        assert_eq!(
+            disposal(&file(hours(0), true), now, retention),
+            Disposal::Keep
+        );
+        assert_eq!(
+            disposal(&file(hours(2), true), now, retention),
+            Disposal::Orphan
+        );
+        assert_eq!(
+            disposal(&file(hours(2), false), now, retention),
+            Disposal::OrphanUnlessReferenced
+        );
+        assert_eq!(
+            disposal(&file(hours(25), false), now, retention),
+            Disposal::Expire
+        );
+
+        // A retention shorter than the grace period applies to results only
+        let short = Duration::from_secs(30 * 60);
+        assert_eq!(disposal(&file(hours(0), false), now, short), Disposal::Keep);
+        assert_eq!(disposal(&file(short, false), now, short), Disposal::Expire);
+        assert_eq!(disposal(&file(short, true), now, short), Disposal::Keep);
+
+        // Clock went back
+        let future = StoredFile {
+            modified: now + hours(1),
+            ..file(hours(0), false)
+        };
+        assert_eq!(disposal(&future, now, retention), Disposal::Keep);
+    }
+}
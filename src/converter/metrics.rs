@@ -0,0 +1,86 @@
+// Conversion metrics - counters of the converter's cleanup task
+//
+// Updated by converter::cleanup after each run; rendered in Prometheus text
+// format next to the scheduler metrics (GET /metrics).
+
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// File converter metrics
+#[derive(Debug, Default)]
+pub struct ConversionMetrics {
+    pub cleanup_runs_total: AtomicU64,
+    /// Cleanup runs that stopped on an error
+    pub cleanup_failures_total: AtomicU64,
+    /// Converted files deleted after the retention period
+    pub cleanup_expired_files_total: AtomicU64,
+    /// Files deleted because no conversion refers to them
+    pub cleanup_orphan_files_total: AtomicU64,
+    pub cleanup_freed_bytes_total: AtomicU64,
+    /// Conversion history rows deleted after the retention period
+    pub cleanup_pruned_rows_total: AtomicU64,
+    /// Unix time of the last successful cleanup
+    pub cleanup_last_run_seconds: AtomicU64,
+}
+
+impl ConversionMetrics {
+    pub fn inc(counter: &AtomicU64) {
+        Self::add(counter, 1);
+    }
+
+    pub fn add(counter: &AtomicU64, value: u64) {
+        counter.fetch_add(value, Ordering::Relaxed);
+    }
+
+    pub fn set(gauge: &AtomicU64, value: u64) {
+        gauge.store(value, Ordering::Relaxed);
+    }
+
+    /// Prometheus text exposition format
+    pub fn render(&self) -> String {
+        let metrics = [
+            (
+                "conversion_cleanup_runs_total",
+                "counter",
+                &self.cleanup_runs_total,
+            ),
+            (
+                "conversion_cleanup_failures_total",
+                "counter",
+                &self.cleanup_failures_total,
+            ),
+            (
+                "conversion_cleanup_expired_files_total",
+                "counter",
+                &self.cleanup_expired_files_total,
+            ),
+            (
+                "conversion_cleanup_orphan_files_total",
+                "counter",
+                &self.cleanup_orphan_files_total,
+            ),
+            (
+                "conversion_cleanup_freed_bytes_total",
+                "counter",
+                &self.cleanup_freed_bytes_total,
+            ),
+            (
+                "conversion_cleanup_pruned_rows_total",
+                "counter",
+                &self.cleanup_pruned_rows_total,
+            ),
+            (
+                "conversion_cleanup_last_run_seconds",
+                "gauge",
+                &self.cleanup_last_run_seconds,
+            ),
+        ];
+
+        let mut output = String::new();
+        for (name, kind, value) in metrics {
+            let _ = writeln!(output, "# TYPE {} {}", name, kind);
+            let _ = writeln!(output, "{} {}", name, value.load(Ordering::Relaxed));
+        }
+        output
+    }
+}
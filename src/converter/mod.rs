@@ -1,5 +1,8 @@
 // Converter module - конвертация файлов
 pub mod service;
+pub mod cleanup;
+pub mod metrics;
 pub mod processors;
 pub mod queue;
+pub mod repository;
 pub mod storage;
@@ -1,17 +1,401 @@
-// Conversion queue - очередь для фоновой обработки файлов
-// (Упрощённая заглушка, можно расширить в будущем)
+// Conversion queue - durable background processing of file conversions
+//
+// Architecture:
+// 1. Jobs are rows of `file_conversions` (migration 023): choosing a format
+//    queues the upload (pending with target_format and chat_id set) and
+//    wakes a worker of this instance
+// 2. CONVERSION_WORKERS workers claim the oldest due job with
+//    FOR UPDATE SKIP LOCKED and a lease in `locked_by`/`locked_until`, so
+//    several instances can share the table without waiting on each other;
+//    the lease is renewed every LEASE_RENEWAL while the job runs
+// 3. A claimed job (processing) is downloaded, converted and sent back as a
+//    document, then marked completed, or failed with `error_message`; only
+//    the worker holding the lease can record the outcome
+// 4. Transient errors (network, Telegram, flood control, I/O) put the job
+//    back with `retry_at` and a growing delay, up to MAX_ATTEMPTS
+// 5. Jobs of a crashed instance are claimed again by any worker once their
+//    lease has run out; jobs that died on their last attempt are failed on
+//    startup and then every JOB_LEASE
+//
+// Workers also poll every POLL_INTERVAL for jobs queued by other instances
+// and retries that became due.
 
-use crate::error::Result;
+use crate::converter::repository::ConversionRepository;
+use crate::converter::service::ConverterService;
+use crate::db::models::FileConversion;
+use crate::error::{AppError, Result};
+use crate::shared::types::DbId;
+use chrono::Utc;
+use sqlx::PgPool;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use teloxide::net::Download;
+use teloxide::prelude::*;
+use teloxide::types::{ChatId, InputFile};
+use tokio::io::AsyncWriteExt;
+use tokio::sync::Notify;
+use tokio::time::{sleep, Duration};
+use uuid::Uuid;
 
-pub struct ConversionQueue;
+/// How long a claimed job is reserved for one worker without renewal
+const JOB_LEASE: chrono::Duration = chrono::Duration::minutes(2);
+
+/// Renewal period of the lease of a running job, well within JOB_LEASE
+const LEASE_RENEWAL: Duration = Duration::from_secs(30);
+
+/// Attempts before a job fails for good
+pub const MAX_ATTEMPTS: i32 = 3;
+
+/// First backoff after a transient error; doubles with each attempt
+const FIRST_RETRY_DELAY: chrono::Duration = chrono::Duration::seconds(30);
+
+/// Check for jobs this instance was not woken for
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Queue of conversion jobs, shared by the bot handlers and the workers
+#[derive(Clone)]
+pub struct ConversionQueue {
+    repo: ConversionRepository,
+    wake: Arc<Notify>,
+}
 
 impl ConversionQueue {
-    pub fn new() -> Self {
-        Self
+    pub fn new(pool: PgPool) -> Self {
+        Self {
+            repo: ConversionRepository::new(pool),
+            wake: Arc::new(Notify::new()),
+        }
+    }
+
+    /// Queue conversion `id` to `target`, with the result sent to `chat_id`
+    ///
+    /// `None` while the file is already queued or being converted.
+    pub async fn enqueue(
+        &self,
+        id: DbId,
+        target: &str,
+        chat_id: ChatId,
+    ) -> Result<Option<FileConversion>> {
+        let job = self.repo.enqueue(id, target, chat_id.0).await?;
+        if let Some(job) = &job {
+            tracing::debug!("Conversion job {} queued ({})", job.id, target);
+            self.wake.notify_one();
+        }
+        Ok(job)
     }
+}
+
+/// Worker pool converting queued files
+pub struct ConversionWorkers {
+    bot: Bot,
+    converter: ConverterService,
+    queue: ConversionQueue,
+    workers: usize,
+    /// Lease owner name of this instance
+    worker_id: Arc<str>,
+}
 
-    pub async fn enqueue(&self, _job_id: i32) -> Result<()> {
-        // TODO: Implement queue logic
+impl ConversionWorkers {
+    pub fn new(bot: Bot, converter: ConverterService, queue: ConversionQueue) -> Self {
+        let host = std::env::var("HOSTNAME").unwrap_or_else(|_| "bot".to_string());
+        Self {
+            bot,
+            converter,
+            queue,
+            workers: 1,
+            worker_id: format!("{}-{}", host, Uuid::new_v4()).into(),
+        }
+    }
+
+    /// Number of jobs converted in parallel
+    pub fn with_workers(mut self, workers: usize) -> Self {
+        self.workers = workers.max(1);
+        self
+    }
+
+    /// Recover interrupted jobs, then run the workers until the process exits
+    pub async fn run(self) -> Result<()> {
+        let failed = self.fail_abandoned().await?;
+        let requeued = self.queue.repo.requeue_abandoned().await?;
+        if requeued + failed > 0 {
+            tracing::warn!(
+                "Recovered interrupted conversions: {} requeued, {} failed",
+                requeued,
+                failed
+            );
+        }
+        tracing::info!(
+            "Starting {} conversion workers {}...",
+            self.workers,
+            self.worker_id
+        );
+
+        let this = Arc::new(self);
+        let mut handles: Vec<_> = (0..this.workers)
+            .map(|_| {
+                let this = Arc::clone(&this);
+                tokio::spawn(async move { this.work().await })
+            })
+            .collect();
+        handles.push(tokio::spawn(async move { this.reap().await }));
+        for handle in handles {
+            handle
+                .await
+                .map_err(|e| AppError::Internal(format!("Conversion worker died: {}", e)))?;
+        }
         Ok(())
     }
+
+    async fn work(&self) {
+        loop {
+            match self
+                .queue
+                .repo
+                .claim(&self.worker_id, JOB_LEASE, MAX_ATTEMPTS)
+                .await
+            {
+                Ok(Some(job)) => self.process(job).await,
+                Ok(None) => {
+                    tokio::select! {
+                        _ = self.queue.wake.notified() => {}
+                        _ = sleep(POLL_INTERVAL) => {}
+                    }
+                }
+                Err(e) => {
+                    tracing::error!("Failed to claim a conversion job: {}", e);
+                    sleep(POLL_INTERVAL).await;
+                }
+            }
+        }
+    }
+
+    /// Fail jobs that died on their last attempt, every JOB_LEASE
+    async fn reap(&self) {
+        let period = JOB_LEASE.to_std().unwrap_or(POLL_INTERVAL);
+        loop {
+            sleep(period).await;
+            if let Err(e) = self.fail_abandoned().await {
+                tracing::error!("Failed to fail abandoned conversion jobs: {}", e);
+            }
+        }
+    }
+
+    /// Fail jobs that died on their last attempt and tell their users;
+    /// returns how many
+    async fn fail_abandoned(&self) -> Result<u64> {
+        let failed = self.queue.repo.fail_abandoned(MAX_ATTEMPTS).await?;
+        for job in &failed {
+            tracing::warn!("Conversion job {} was interrupted too many times", job.id);
+            let error = AppError::Internal("Interrupted too many times".to_string());
+            self.report_failure(job, &error).await;
+        }
+        Ok(failed.len() as u64)
+    }
+
+    /// Convert a claimed job and record the outcome
+    async fn process(&self, job: FileConversion) {
+        let converted = tokio::select! {
+            converted = convert_file(&self.bot, &self.converter, &job) => converted,
+            _ = self.keep_lease(job.id) => {
+                tracing::warn!("Conversion job {} was taken over by another worker", job.id);
+                return;
+            }
+        };
+
+        let repo = &self.queue.repo;
+        let worker_id = &*self.worker_id;
+        let outcome = match converted {
+            Ok(output_path) => {
+                repo.complete(job.id, worker_id, &output_path.to_string_lossy())
+                    .await
+            }
+            Err(error) if is_transient(&error) && job.attempts < MAX_ATTEMPTS => {
+                let at = Utc::now() + retry_delay(&error, job.attempts);
+                tracing::warn!(
+                    "Conversion job {} failed (attempt {}), retrying at {}: {}",
+                    job.id,
+                    job.attempts,
+                    at,
+                    error
+                );
+                repo.schedule_retry(job.id, worker_id, at, &error.to_string())
+                    .await
+            }
+            Err(error) => {
+                tracing::warn!("Conversion job {} failed: {}", job.id, error);
+                let result = repo.fail(job.id, worker_id, &error.to_string()).await;
+                if matches!(result, Ok(true)) {
+                    self.report_failure(&job, &error).await;
+                }
+                result
+            }
+        };
+
+        match outcome {
+            Ok(true) => {}
+            Ok(false) => tracing::warn!(
+                "Conversion job {} lost its lease before the outcome was recorded",
+                job.id
+            ),
+            Err(e) => tracing::error!("Failed to record conversion job {}: {}", job.id, e),
+        }
+    }
+
+    /// Renew the lease of job `id` every LEASE_RENEWAL; returns once the
+    /// lease is lost
+    async fn keep_lease(&self, id: DbId) {
+        loop {
+            sleep(LEASE_RENEWAL).await;
+            match self.queue.repo.renew(id, &self.worker_id, JOB_LEASE).await {
+                Ok(true) => {}
+                Ok(false) => return,
+                // Retried on the next tick, the lease outlasts a few failures
+                Err(e) => tracing::warn!("Failed to renew conversion job {}: {}", id, e),
+            }
+        }
+    }
+
+    /// Tell the user a conversion gave up
+    async fn report_failure(&self, job: &FileConversion, error: &AppError) {
+        let Some(chat_id) = job.chat_id else {
+            return;
+        };
+        let text = match error {
+            AppError::FileProcessing(_) => {
+                "❌ Could not convert this file, is it a valid image?".to_string()
+            }
+            error => format!("❌ {}", error.user_message()),
+        };
+        if let Err(e) = self.bot.send_message(ChatId(chat_id), text).await {
+            tracing::debug!("Failed to report conversion job {}: {}", job.id, e);
+        }
+    }
+}
+
+/// Errors worth another attempt: the file itself may still be fine
+fn is_transient(error: &AppError) -> bool {
+    matches!(
+        error,
+        AppError::Telegram(_) | AppError::RateLimited(_) | AppError::Io(_) | AppError::Database(_)
+    )
+}
+
+/// Telegram's flood-control wait, otherwise 30s, 1m, 2m, ...
+fn retry_delay(error: &AppError, attempts: i32) -> chrono::Duration {
+    if let AppError::RateLimited(delay) = error {
+        return chrono::Duration::from_std(*delay).unwrap_or(FIRST_RETRY_DELAY);
+    }
+    let exponent = attempts.clamp(1, 10) as u32 - 1;
+    FIRST_RETRY_DELAY * 2i32.pow(exponent)
+}
+
+/// Download the source file, convert it and send the result back;
+/// returns where the result is stored
+async fn convert_file(
+    bot: &Bot,
+    converter: &ConverterService,
+    job: &FileConversion,
+) -> Result<PathBuf> {
+    let (Some(source_file_id), Some(target), Some(chat_id)) = (
+        job.source_file_id.as_deref(),
+        job.target_format.as_deref(),
+        job.chat_id,
+    ) else {
+        return Err(AppError::Internal(format!(
+            "Conversion job {} is incomplete",
+            job.id
+        )));
+    };
+    let file = bot.get_file(source_file_id).await?;
+
+    let input_path = converter.temp_path(job.source_format.as_deref().unwrap_or("bin"));
+    let converted = match download(bot, &file.path, &input_path).await {
+        Ok(()) => converter.convert(&input_path, target).await,
+        Err(e) => Err(e),
+    };
+    if let Err(e) = tokio::fs::remove_file(&input_path).await {
+        tracing::warn!("Failed to remove {:?}: {}", input_path, e);
+    }
+    let output_path = converted?;
+
+    let file_name = result_file_name(job, &output_path);
+    bot.send_document(
+        ChatId(chat_id),
+        InputFile::file(&output_path).file_name(file_name),
+    )
+    .await?;
+    Ok(output_path)
+}
+
+/// Save a Telegram file to `path`
+async fn download(bot: &Bot, file_path: &str, path: &Path) -> Result<()> {
+    let mut file = tokio::fs::File::create(path).await?;
+    bot.download_file(file_path, &mut file).await?;
+    // tokio writes in the background: wait before the file is read
+    file.flush().await?;
+    Ok(())
+}
+
+/// Name of a converted file: the uploaded name with the new extension
+fn result_file_name(job: &FileConversion, output_path: &Path) -> String {
+    let stem = job
+        .source_file_name
+        .as_deref()
+        .map(|name| name.rsplit_once('.').map_or(name, |(stem, _)| stem))
+        .filter(|stem| !stem.is_empty())
+        .map_or_else(|| format!("image_{}", job.id), str::to_string);
+    match output_path.extension() {
+        Some(extension) => format!("{}.{}", stem, extension.to_string_lossy()),
+        None => stem,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_retry_delay() {
+        let network = AppError::Telegram("timeout".to_string());
+        assert!(is_transient(&network));
+        assert_eq!(retry_delay(&network, 1), chrono::Duration::seconds(30));
+        assert_eq!(retry_delay(&network, 3), chrono::Duration::minutes(2));
+
+        let flood = AppError::RateLimited(Duration::from_secs(7));
+        assert_eq!(retry_delay(&flood, 2), chrono::Duration::seconds(7));
+
+        assert!(!is_transient(&AppError::FileProcessing(
+            "corrupt".to_string()
+        )));
+        assert!(!is_transient(&AppError::ChatUnavailable(
+            "blocked".to_string()
+        )));
+    }
+
+    #[test]
+    fn test_result_file_name() {
+        let mut job = FileConversion {
+            id: 7,
+            user_id: 1,
+            source_file_id: Some("file".to_string()),
+            source_format: Some("png".to_string()),
+            target_format: Some("webp".to_string()),
+            status: Some("processing".to_string()),
+            result_file_path: None,
+            error_message: None,
+            created_at: Utc::now(),
+            completed_at: None,
+            source_file_name: Some("holiday.photo.PNG".to_string()),
+            chat_id: Some(100),
+            attempts: 1,
+            retry_at: None,
+            locked_by: None,
+            locked_until: None,
+        };
+        let output = Path::new("converted/0b1c.webp");
+
+        assert_eq!(result_file_name(&job, output), "holiday.photo.webp");
+        job.source_file_name = None;
+        assert_eq!(result_file_name(&job, output), "image_7.webp");
+    }
 }
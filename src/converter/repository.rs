@@ -0,0 +1,341 @@
+// Conversion repository - history of file conversions
+//
+// A row is created when a file is uploaded (pending), queued when a format
+// is chosen, claimed by a worker (processing) and closed as completed or
+// failed. See converter::queue for the worker side.
+
+use crate::db::models::{FileConversion, NewFileConversion};
+use crate::error::{not_found, Result};
+use crate::shared::types::{ConversionStatus, DbId, Timestamp};
+use chrono::Duration;
+use sqlx::PgPool;
+
+#[derive(Clone)]
+pub struct ConversionRepository {
+    pool: PgPool,
+}
+
+impl ConversionRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Record an uploaded file waiting for a target format
+    pub async fn create(&self, new_conversion: NewFileConversion) -> Result<FileConversion> {
+        let conversion = sqlx::query_as::<_, FileConversion>(
+            r#"
+            INSERT INTO file_conversions (
+                user_id, source_file_id, source_format, source_file_name, status
+            )
+            VALUES ($1, $2, $3, $4, $5)
+            RETURNING *
+            "#,
+        )
+        .bind(new_conversion.user_id)
+        .bind(&new_conversion.source_file_id)
+        .bind(&new_conversion.source_format)
+        .bind(&new_conversion.source_file_name)
+        .bind(ConversionStatus::Pending.to_string())
+        .fetch_one(&self.pool)
+        .await?;
+
+        tracing::debug!("File conversion {} created", conversion.id);
+        Ok(conversion)
+    }
+
+    pub async fn find_by_id(&self, id: DbId) -> Result<FileConversion> {
+        sqlx::query_as::<_, FileConversion>("SELECT * FROM file_conversions WHERE id = $1")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?
+            .ok_or_else(|| not_found(format!("File conversion {} not found", id)))
+    }
+
+    /// Queue a conversion to `target_format`, with the result sent to `chat_id`
+    ///
+    /// An upload waiting for a format is queued as is; a finished one is
+    /// converted again as a new row, so each format chosen has its own
+    /// history entry. `None` while the file is queued or being converted.
+    pub async fn enqueue(
+        &self,
+        id: DbId,
+        target_format: &str,
+        chat_id: i64,
+    ) -> Result<Option<FileConversion>> {
+        let queued = sqlx::query_as::<_, FileConversion>(
+            r#"
+            UPDATE file_conversions SET target_format = $2, chat_id = $3
+            WHERE id = $1 AND status = $4 AND target_format IS NULL
+            RETURNING *
+            "#,
+        )
+        .bind(id)
+        .bind(target_format)
+        .bind(chat_id)
+        .bind(ConversionStatus::Pending.to_string())
+        .fetch_optional(&self.pool)
+        .await?;
+        if queued.is_some() {
+            return Ok(queued);
+        }
+
+        let again = sqlx::query_as::<_, FileConversion>(
+            r#"
+            INSERT INTO file_conversions (
+                user_id, source_file_id, source_format, source_file_name,
+                target_format, chat_id, status
+            )
+            SELECT user_id, source_file_id, source_format, source_file_name, $2, $3, $4
+            FROM file_conversions
+            WHERE id = $1 AND status IN ($5, $6)
+            RETURNING *
+            "#,
+        )
+        .bind(id)
+        .bind(target_format)
+        .bind(chat_id)
+        .bind(ConversionStatus::Pending.to_string())
+        .bind(ConversionStatus::Completed.to_string())
+        .bind(ConversionStatus::Failed.to_string())
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(again)
+    }
+
+    /// Claim the oldest due job for `worker_id`
+    ///
+    /// Queued jobs and jobs whose worker's lease ran out are taken; rows
+    /// locked by another worker are skipped rather than waited for.
+    pub async fn claim(
+        &self,
+        worker_id: &str,
+        lease: Duration,
+        max_attempts: i32,
+    ) -> Result<Option<FileConversion>> {
+        let job = sqlx::query_as::<_, FileConversion>(
+            r#"
+            UPDATE file_conversions
+            SET status = $4, attempts = attempts + 1,
+                locked_by = $1, locked_until = NOW() + make_interval(secs => $2)
+            WHERE id = (
+                SELECT id FROM file_conversions
+                WHERE attempts < $3
+                  AND (
+                      (status = $5 AND target_format IS NOT NULL
+                       AND (retry_at IS NULL OR retry_at <= NOW()))
+                      OR (status = $4 AND locked_until < NOW())
+                  )
+                ORDER BY created_at
+                LIMIT 1
+                FOR UPDATE SKIP LOCKED
+            )
+            RETURNING *
+            "#,
+        )
+        .bind(worker_id)
+        .bind(lease.num_seconds() as f64)
+        .bind(max_attempts)
+        .bind(ConversionStatus::Processing.to_string())
+        .bind(ConversionStatus::Pending.to_string())
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(job)
+    }
+
+    /// Keep job `id` reserved for `worker_id` for another `lease`;
+    /// false if the worker no longer holds it
+    pub async fn renew(&self, id: DbId, worker_id: &str, lease: Duration) -> Result<bool> {
+        let renewed = sqlx::query(
+            r#"
+            UPDATE file_conversions SET locked_until = NOW() + make_interval(secs => $3)
+            WHERE id = $1 AND locked_by = $2 AND status = $4
+            "#,
+        )
+        .bind(id)
+        .bind(worker_id)
+        .bind(lease.num_seconds() as f64)
+        .bind(ConversionStatus::Processing.to_string())
+        .execute(&self.pool)
+        .await?
+        .rows_affected();
+
+        Ok(renewed == 1)
+    }
+
+    /// Fail jobs whose worker died on their last attempt
+    pub async fn fail_abandoned(&self, max_attempts: i32) -> Result<Vec<FileConversion>> {
+        let failed = sqlx::query_as::<_, FileConversion>(
+            r#"
+            UPDATE file_conversions
+            SET status = $2, error_message = 'Interrupted too many times', completed_at = NOW(),
+                locked_by = NULL, locked_until = NULL
+            WHERE status = $3 AND locked_until < NOW() AND attempts >= $1
+            RETURNING *
+            "#,
+        )
+        .bind(max_attempts)
+        .bind(ConversionStatus::Failed.to_string())
+        .bind(ConversionStatus::Processing.to_string())
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(failed)
+    }
+
+    /// Put jobs of a crashed instance back in the queue; returns how many
+    pub async fn requeue_abandoned(&self) -> Result<u64> {
+        let requeued = sqlx::query(
+            r#"
+            UPDATE file_conversions
+            SET status = $1, locked_by = NULL, locked_until = NULL
+            WHERE status = $2 AND locked_until < NOW()
+            "#,
+        )
+        .bind(ConversionStatus::Pending.to_string())
+        .bind(ConversionStatus::Processing.to_string())
+        .execute(&self.pool)
+        .await?
+        .rows_affected();
+
+        Ok(requeued)
+    }
+
+    // The outcome of a job is only recorded by the worker holding it: false
+    // if the lease ran out and another worker took the job over
+
+    /// Return a job to the queue after a transient error
+    pub async fn schedule_retry(
+        &self,
+        id: DbId,
+        worker_id: &str,
+        at: Timestamp,
+        error: &str,
+    ) -> Result<bool> {
+        let updated = sqlx::query(
+            r#"
+            UPDATE file_conversions
+            SET status = $3, retry_at = $4, error_message = $5,
+                locked_by = NULL, locked_until = NULL
+            WHERE id = $1 AND locked_by = $2
+            "#,
+        )
+        .bind(id)
+        .bind(worker_id)
+        .bind(ConversionStatus::Pending.to_string())
+        .bind(at)
+        .bind(error)
+        .execute(&self.pool)
+        .await?
+        .rows_affected();
+
+        Ok(updated == 1)
+    }
+
+    pub async fn complete(
+        &self,
+        id: DbId,
+        worker_id: &str,
+        result_file_path: &str,
+    ) -> Result<bool> {
+        let updated = sqlx::query(
+            r#"
+            UPDATE file_conversions
+            SET status = $3, result_file_path = $4, error_message = NULL, completed_at = NOW(),
+                retry_at = NULL, locked_by = NULL, locked_until = NULL
+            WHERE id = $1 AND locked_by = $2
+            "#,
+        )
+        .bind(id)
+        .bind(worker_id)
+        .bind(ConversionStatus::Completed.to_string())
+        .bind(result_file_path)
+        .execute(&self.pool)
+        .await?
+        .rows_affected();
+
+        Ok(updated == 1)
+    }
+
+    pub async fn fail(&self, id: DbId, worker_id: &str, error: &str) -> Result<bool> {
+        let updated = sqlx::query(
+            r#"
+            UPDATE file_conversions
+            SET status = $3, error_message = $4, completed_at = NOW(),
+                retry_at = NULL, locked_by = NULL, locked_until = NULL
+            WHERE id = $1 AND locked_by = $2
+            "#,
+        )
+        .bind(id)
+        .bind(worker_id)
+        .bind(ConversionStatus::Failed.to_string())
+        .bind(error)
+        .execute(&self.pool)
+        .await?
+        .rows_affected();
+
+        Ok(updated == 1)
+    }
+
+    /// Those of `paths` that a conversion still records as its result
+    pub async fn referenced_results(&self, paths: &[String]) -> Result<Vec<String>> {
+        let referenced = sqlx::query_scalar::<_, String>(
+            "SELECT result_file_path FROM file_conversions WHERE result_file_path = ANY($1)",
+        )
+        .bind(paths)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(referenced)
+    }
+
+    /// Drop `paths` from the conversions they belong to, once the files are deleted
+    pub async fn forget_results(&self, paths: &[String]) -> Result<u64> {
+        let forgotten = sqlx::query(
+            "UPDATE file_conversions SET result_file_path = NULL WHERE result_file_path = ANY($1)",
+        )
+        .bind(paths)
+        .execute(&self.pool)
+        .await?
+        .rows_affected();
+
+        Ok(forgotten)
+    }
+
+    /// Delete history created before `before`, `batch` rows at a time
+    ///
+    /// Finished conversions and uploads no format was chosen for go; jobs
+    /// still queued or being converted stay. Returns the rows deleted.
+    pub async fn prune(&self, before: Timestamp, batch: i64) -> Result<u64> {
+        let mut pruned = 0;
+        loop {
+            // Walks idx_conversions_created_at from the oldest row
+            let deleted = sqlx::query(
+                r#"
+                DELETE FROM file_conversions
+                WHERE id IN (
+                    SELECT id FROM file_conversions
+                    WHERE created_at < $1
+                      AND (status IN ($3, $4) OR (status = $5 AND target_format IS NULL))
+                    ORDER BY created_at
+                    LIMIT $2
+                )
+                "#,
+            )
+            .bind(before)
+            .bind(batch)
+            .bind(ConversionStatus::Completed.to_string())
+            .bind(ConversionStatus::Failed.to_string())
+            .bind(ConversionStatus::Pending.to_string())
+            .execute(&self.pool)
+            .await?
+            .rows_affected();
+
+            pruned += deleted;
+            if deleted < batch as u64 {
+                return Ok(pruned);
+            }
+        }
+    }
+}
@@ -1,10 +1,55 @@
 // Converter service - simplified version for image processing
+//
+// Decoding and encoding are CPU-bound and run on tokio's blocking pool, so
+// a large image does not stall the other tasks of the runtime.
 
 use crate::error::{AppError, Result};
+use image::codecs::jpeg::JpegEncoder;
 use image::ImageFormat;
 use std::path::{Path, PathBuf};
+use std::time::SystemTime;
 use tokio::fs;
 
+/// JPEG quality of the Compress button
+const COMPRESS_QUALITY: u8 = 60;
+
+/// Source format of an uploaded image, from its MIME type or file name
+///
+/// `None` for files the converter cannot read.
+pub fn image_format(mime_type: Option<&str>, file_name: Option<&str>) -> Option<&'static str> {
+    let from_mime = mime_type.and_then(|mime| match mime {
+        "image/png" => Some("png"),
+        "image/jpeg" => Some("jpeg"),
+        "image/gif" => Some("gif"),
+        "image/webp" => Some("webp"),
+        "image/bmp" => Some("bmp"),
+        _ => None,
+    });
+    let from_name = || {
+        let (_, extension) = file_name?.rsplit_once('.')?;
+        match extension.to_lowercase().as_str() {
+            "png" => Some("png"),
+            "jpg" | "jpeg" => Some("jpeg"),
+            "gif" => Some("gif"),
+            "webp" => Some("webp"),
+            "bmp" => Some("bmp"),
+            _ => None,
+        }
+    };
+    from_mime.or_else(from_name)
+}
+
+/// A file kept by the converter
+#[derive(Debug, Clone)]
+pub struct StoredFile {
+    /// Same form as `result_file_path` of the conversion it belongs to
+    pub path: PathBuf,
+    pub size: u64,
+    pub modified: SystemTime,
+    /// A download in the temporary directory rather than a result
+    pub temporary: bool,
+}
+
 /// File conversion service
 #[derive(Clone)]
 pub struct ConverterService {
@@ -33,11 +78,6 @@ impl ConverterService {
     ) -> Result<PathBuf> {
         let format = self.parse_image_format(target_format)?;
 
-        // Read image
-        let img = image::open(input_path).map_err(|e| {
-            AppError::FileProcessing(format!("Failed to open image: {}", e))
-        })?;
-
         // Generate path for output file
         let output_filename = format!(
             "{}.{}",
@@ -46,15 +86,64 @@ impl ConverterService {
         );
         let output_path = Path::new(&self.output_dir).join(output_filename);
 
-        // Save in new format
-        img.save_with_format(&output_path, format).map_err(|e| {
-            AppError::FileProcessing(format!("Failed to convert image: {}", e))
-        })?;
+        let input_path = input_path.to_path_buf();
+        let target = output_path.clone();
+        blocking(move || {
+            // Read image
+            let img = image::open(&input_path).map_err(|e| {
+                AppError::FileProcessing(format!("Failed to open image: {}", e))
+            })?;
+
+            // Save in new format
+            img.save_with_format(&target, format).map_err(|e| {
+                AppError::FileProcessing(format!("Failed to convert image: {}", e))
+            })
+        })
+        .await?;
 
         tracing::info!("Converted image to {:?}: {:?}", format, output_path);
         Ok(output_path)
     }
 
+    /// Fresh path in the temporary directory, e.g. for a download
+    pub fn temp_path(&self, extension: &str) -> PathBuf {
+        Path::new(&self.temp_dir).join(format!("{}.{}", uuid::Uuid::new_v4(), extension))
+    }
+
+    /// Convert an image for a button of the conversion menu: a target
+    /// format or "compress"
+    pub async fn convert(&self, input_path: &Path, target: &str) -> Result<PathBuf> {
+        match target {
+            "compress" => self.compress_image(input_path).await,
+            format => self.convert_image(input_path, format).await,
+        }
+    }
+
+    /// Re-encode as a smaller JPEG; transparency is dropped
+    pub async fn compress_image(&self, input_path: &Path) -> Result<PathBuf> {
+        let output_filename = format!("{}.jpg", uuid::Uuid::new_v4());
+        let output_path = Path::new(&self.output_dir).join(output_filename);
+
+        let input_path = input_path.to_path_buf();
+        let target = output_path.clone();
+        blocking(move || {
+            let img = image::open(&input_path).map_err(|e| {
+                AppError::FileProcessing(format!("Failed to open image: {}", e))
+            })?;
+
+            let file = std::fs::File::create(&target)?;
+            let mut encoder =
+                JpegEncoder::new_with_quality(std::io::BufWriter::new(file), COMPRESS_QUALITY);
+            encoder.encode_image(&img.to_rgb8()).map_err(|e| {
+                AppError::FileProcessing(format!("Failed to compress image: {}", e))
+            })
+        })
+        .await?;
+
+        tracing::info!("Compressed image: {:?}", output_path);
+        Ok(output_path)
+    }
+
     /// Resize image
     pub async fn resize_image(
         &self,
@@ -62,18 +151,23 @@ impl ConverterService {
         width: u32,
         height: u32,
     ) -> Result<PathBuf> {
-        let img = image::open(input_path).map_err(|e| {
-            AppError::FileProcessing(format!("Failed to open image: {}", e))
-        })?;
-
-        let resized = img.resize(width, height, image::imageops::FilterType::Lanczos3);
-
         let output_filename = format!("{}_{}x{}.png", uuid::Uuid::new_v4(), width, height);
         let output_path = Path::new(&self.output_dir).join(output_filename);
 
-        resized.save(&output_path).map_err(|e| {
-            AppError::FileProcessing(format!("Failed to save resized image: {}", e))
-        })?;
+        let input_path = input_path.to_path_buf();
+        let target = output_path.clone();
+        blocking(move || {
+            let img = image::open(&input_path).map_err(|e| {
+                AppError::FileProcessing(format!("Failed to open image: {}", e))
+            })?;
+
+            let resized = img.resize(width, height, image::imageops::FilterType::Lanczos3);
+
+            resized.save(&target).map_err(|e| {
+                AppError::FileProcessing(format!("Failed to save resized image: {}", e))
+            })
+        })
+        .await?;
 
         tracing::info!("Resized image to {}x{}: {:?}", width, height, output_path);
         Ok(output_path)
@@ -94,10 +188,77 @@ impl ConverterService {
         }
     }
 
-    /// Cleanup old files
-    pub async fn cleanup_old_files(&self, max_age_hours: u64) -> Result<()> {
-        // TODO: Implement cleanup logic
-        tracing::debug!("Cleanup old files (max age: {} hours)", max_age_hours);
-        Ok(())
+    /// Files in the temporary and output directories, for cleanup
+    pub async fn stored_files(&self) -> Result<Vec<StoredFile>> {
+        let mut files = Vec::new();
+        for (dir, temporary) in [(&self.temp_dir, true), (&self.output_dir, false)] {
+            let mut entries = fs::read_dir(dir).await?;
+            while let Some(entry) = entries.next_entry().await? {
+                let metadata = entry.metadata().await?;
+                if !metadata.is_file() {
+                    continue;
+                }
+                files.push(StoredFile {
+                    path: entry.path(),
+                    size: metadata.len(),
+                    modified: metadata.modified()?,
+                    temporary,
+                });
+            }
+        }
+        Ok(files)
+    }
+}
+
+/// Run CPU-bound image work on the blocking pool
+async fn blocking<T, F>(work: F) -> Result<T>
+where
+    F: FnOnce() -> Result<T> + Send + 'static,
+    T: Send + 'static,
+{
+    tokio::task::spawn_blocking(work)
+        .await
+        .map_err(|e| AppError::Internal(format!("Image task failed: {}", e)))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_image_format() {
+        assert_eq!(image_format(Some("image/png"), Some("scan.bin")), Some("png"));
+        assert_eq!(image_format(None, Some("IMG_0001.JPG")), Some("jpeg"));
+        assert_eq!(image_format(Some("application/octet-stream"), Some("a.webp")), Some("webp"));
+        assert_eq!(image_format(Some("image/heic"), Some("photo.heic")), None);
+        assert_eq!(image_format(Some("application/pdf"), None), None);
+    }
+
+    #[tokio::test]
+    async fn test_convert_and_compress() {
+        let dir = std::env::temp_dir().join(format!("converter-{}", uuid::Uuid::new_v4()));
+        let service = ConverterService::new(
+            dir.join("tmp").to_string_lossy().into_owned(),
+            dir.join("out").to_string_lossy().into_owned(),
+        );
+        service.init().await.unwrap();
+
+        let input = dir.join("tmp").join("input.png");
+        image::RgbaImage::from_pixel(16, 16, image::Rgba([200, 10, 10, 128]))
+            .save(&input)
+            .unwrap();
+
+        for (target, format) in [
+            ("jpeg", ImageFormat::Jpeg),
+            ("webp", ImageFormat::WebP),
+            ("compress", ImageFormat::Jpeg),
+        ] {
+            let output = service.convert(&input, target).await.unwrap();
+            let data = std::fs::read(&output).unwrap();
+            assert_eq!(image::guess_format(&data).unwrap(), format, "{}", target);
+        }
+        assert!(service.convert(&input, "pdf").await.is_err());
+
+        std::fs::remove_dir_all(dir).unwrap();
     }
 }
@@ -3,8 +3,13 @@
 // Models correspond to database tables
 // Use derive(sqlx::FromRow) for automatic mapping
 
-use crate::shared::types::{ConversionStatus, DbId, Priority, TelegramUserId, Timestamp, TodoStatus};
-use chrono::Utc;
+use crate::shared::timezone::{resolve_local, user_timezone};
+use crate::shared::types::{
+    ConversionStatus, DbId, DeliveryStatus, NotifyChannel, Priority, RecurrencePattern, TelegramUserId,
+    Timestamp, TodoStatus,
+};
+use chrono::{DateTime, NaiveTime, TimeZone, Utc};
+use chrono_tz::Tz;
 use serde::{Deserialize, Serialize};
 
 /// User model
@@ -17,6 +22,32 @@ pub struct User {
     pub language_code: String,
     pub created_at: Timestamp,
     pub last_active_at: Timestamp,
+    pub weekly_review: bool,
+    pub last_review_at: Option<Timestamp>,
+    /// IANA zone name, e.g. `Europe/Moscow`
+    pub timezone: String,
+    /// Minutes before a task's due date to remind automatically
+    pub due_reminder_offsets: Vec<i32>,
+    /// The user blocked the bot; reminders are paused until /start
+    pub is_blocked: bool,
+    pub blocked_at: Option<Timestamp>,
+    /// Daily quiet hours in the user's zone; may wrap past midnight
+    pub quiet_start: Option<NaiveTime>,
+    pub quiet_end: Option<NaiveTime>,
+    /// Send silently during quiet hours instead of holding reminders
+    pub quiet_silent: bool,
+    /// Do-not-disturb set with /dnd
+    pub dnd_until: Option<Timestamp>,
+    /// Secret of the ICS feed URL; `None` while the feed is off
+    pub calendar_token: Option<String>,
+    /// Channels reminders go to unless they name their own
+    pub notify_channels: Vec<String>,
+    /// Confirmed address for the email channel
+    pub email: Option<String>,
+    /// Address waiting for its confirmation code (/notify email confirm)
+    pub pending_email: Option<String>,
+    /// URL for the webhook channel
+    pub webhook_url: Option<String>,
 }
 
 /// Data for creating a new user
@@ -33,6 +64,63 @@ impl User {
     pub fn touch(&mut self) {
         self.last_active_at = Utc::now();
     }
+
+    /// User time zone (UTC if the stored name is unknown)
+    pub fn tz(&self) -> Tz {
+        user_timezone(&self.timezone)
+    }
+
+    /// Current time in the user's zone
+    pub fn now(&self) -> DateTime<Tz> {
+        Utc::now().with_timezone(&self.tz())
+    }
+
+    /// End of quiet hours or do-not-disturb, if `now` falls into them
+    pub fn quiet_until(&self, now: Timestamp) -> Option<Timestamp> {
+        let mut until = now;
+        // Do-not-disturb may end inside quiet hours and the other way round
+        for _ in 0..2 {
+            if let Some(dnd) = self.dnd_until.filter(|dnd| *dnd > until) {
+                until = dnd;
+            }
+            if let Some(end) = self.quiet_hours_end(until) {
+                until = end;
+            }
+        }
+        (until > now).then_some(until)
+    }
+
+    /// End of the quiet hours window `at` falls into
+    fn quiet_hours_end(&self, at: Timestamp) -> Option<Timestamp> {
+        let (start, end) = (self.quiet_start?, self.quiet_end?);
+        let tz = self.tz();
+        let local = at.with_timezone(&tz);
+        let (date, time) = (local.date_naive(), local.time());
+
+        let end_date = if start <= end {
+            (start <= time && time < end).then_some(date)?
+        } else if time >= start {
+            date.succ_opt()?
+        } else {
+            (time < end).then_some(date)?
+        };
+
+        resolve_local(&tz, end_date.and_time(end)).map(|end| end.with_timezone(&Utc))
+    }
+
+    /// Default notification channels; Telegram if none are valid
+    pub fn default_channels(&self) -> Vec<NotifyChannel> {
+        parse_channels(&self.notify_channels)
+    }
+
+    /// Name shown to other people: @username, first name or Telegram ID
+    pub fn display_name(&self) -> String {
+        match (&self.username, &self.first_name) {
+            (Some(username), _) => format!("@{}", username),
+            (None, Some(first_name)) => first_name.clone(),
+            (None, None) => format!("user {}", self.telegram_id),
+        }
+    }
 }
 
 /// Task model (Todo)
@@ -47,6 +135,9 @@ pub struct Todo {
     pub created_at: Timestamp,
     pub updated_at: Timestamp,
     pub completed_at: Option<Timestamp>,
+    pub due_date: Option<Timestamp>,
+    pub tags: Vec<String>,
+    pub estimate_minutes: Option<i32>,
 }
 
 /// Data for creating a new task
@@ -56,15 +147,36 @@ pub struct NewTodo {
     pub title: String,
     pub description: Option<String>,
     pub priority: Priority,
+    pub due_date: Option<Timestamp>,
+    pub tags: Vec<String>,
+    pub estimate_minutes: Option<i32>,
 }
 
 /// Data for updating a task
+///
+/// `None` leaves a field unchanged; for nullable columns `Some(None)` clears it.
 #[derive(Debug, Clone, Default)]
 pub struct UpdateTodo {
     pub title: Option<String>,
     pub description: Option<String>,
     pub status: Option<TodoStatus>,
     pub priority: Option<Priority>,
+    pub due_date: Option<Option<Timestamp>>,
+    pub tags: Option<Vec<String>>,
+    pub estimate_minutes: Option<Option<i32>>,
+}
+
+impl UpdateTodo {
+    /// Check if there is nothing to update
+    pub fn is_empty(&self) -> bool {
+        self.title.is_none()
+            && self.description.is_none()
+            && self.status.is_none()
+            && self.priority.is_none()
+            && self.due_date.is_none()
+            && self.tags.is_none()
+            && self.estimate_minutes.is_none()
+    }
 }
 
 impl Todo {
@@ -73,6 +185,16 @@ impl Todo {
         self.status == TodoStatus::Completed
     }
 
+    /// Check if task is still open (pending or in progress)
+    pub fn is_open(&self) -> bool {
+        matches!(self.status, TodoStatus::Pending | TodoStatus::InProgress)
+    }
+
+    /// Check if an open task is past its due date
+    pub fn is_overdue(&self, now: Timestamp) -> bool {
+        self.is_open() && self.due_date.is_some_and(|due| due < now)
+    }
+
     /// Get emoji for status
     pub fn status_emoji(&self) -> &'static str {
         match self.status {
@@ -109,6 +231,159 @@ pub struct Reminder {
     pub is_recurring: bool,
     pub recurrence_pattern: Option<String>,
     pub created_at: Timestamp,
+    pub snooze_count: i32,
+    /// Regular occurrence displaced by snoozing a recurring reminder
+    pub scheduled_at: Option<Timestamp>,
+    /// Repeat interval of an insistent reminder (`None` - single ping)
+    pub nag_interval_minutes: Option<i32>,
+    pub nag_max_repeats: i32,
+    /// Repeats sent so far
+    pub nag_count: i32,
+    pub acknowledged_at: Option<Timestamp>,
+    /// Chat notified when nobody acknowledges
+    pub escalate_to: Option<i64>,
+    pub escalated_at: Option<Timestamp>,
+    /// Set for reminders created automatically before a task's due date
+    pub due_offset_minutes: Option<i32>,
+    /// Failed delivery attempts in a row
+    pub attempts: i32,
+    pub last_error: Option<String>,
+    /// Next delivery attempt after a failure
+    pub retry_at: Option<Timestamp>,
+    /// Delivery given up after too many failures
+    pub dead_lettered_at: Option<Timestamp>,
+    /// Paused while the owner has blocked the bot
+    pub paused_at: Option<Timestamp>,
+    /// Telegram chat the reminder is delivered to
+    pub chat_id: i64,
+    /// Usernames (without @) mentioned when it fires
+    pub mentions: Vec<String>,
+    /// Rings through the owner's quiet hours and do-not-disturb
+    pub is_urgent: bool,
+    /// Channels chosen for this reminder; `None` - the owner's default
+    pub channels: Option<Vec<String>>,
+    /// Message with the command that created it
+    pub source_chat_id: Option<i64>,
+    pub source_message_id: Option<i32>,
+}
+
+/// One delivery of a reminder; recurring reminders have many
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct ReminderDelivery {
+    pub id: DbId,
+    /// `None` once the reminder was cancelled
+    pub reminder_id: Option<DbId>,
+    pub user_id: DbId,
+    /// Text at the time of delivery
+    pub message: Option<String>,
+    /// Time it was due, after snoozing
+    pub scheduled_for: Timestamp,
+    pub status: DeliveryStatus,
+    pub delivered_at: Option<Timestamp>,
+    /// 1 for the first try, more after failures
+    pub attempt: i32,
+    pub snooze_count: i32,
+    pub error: Option<String>,
+    /// `None` if it was skipped without sending
+    pub channel: Option<NotifyChannel>,
+    pub created_at: Timestamp,
+}
+
+impl ReminderDelivery {
+    /// Time from due to sent; `None` unless it was sent
+    pub fn latency(&self) -> Option<chrono::Duration> {
+        self.delivered_at
+            .map(|at| (at - self.scheduled_for).max(chrono::Duration::zero()))
+    }
+
+    pub fn status_emoji(&self) -> &'static str {
+        match self.status {
+            DeliveryStatus::Sent => "✅",
+            DeliveryStatus::Failed => "❌",
+            DeliveryStatus::Skipped => "⏭",
+        }
+    }
+}
+
+/// Stored channel names; unknown ones are ignored, none left means Telegram
+fn parse_channels(names: &[String]) -> Vec<NotifyChannel> {
+    let mut channels = Vec::new();
+    for channel in names.iter().filter_map(|name| name.parse().ok()) {
+        if !channels.contains(&channel) {
+            channels.push(channel);
+        }
+    }
+    if channels.is_empty() {
+        channels.push(NotifyChannel::Telegram);
+    }
+    channels
+}
+
+/// One page of the delivery log, newest first
+#[derive(Debug, Clone)]
+pub struct HistoryPage {
+    pub deliveries: Vec<ReminderDelivery>,
+    /// 1-based
+    pub page: i64,
+    pub pages: i64,
+}
+
+/// Repeat settings of an insistent reminder
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NagPolicy {
+    pub interval_minutes: i32,
+    pub max_repeats: i32,
+    /// Chat ID of a second user or group chat
+    pub escalate_to: Option<i64>,
+}
+
+/// Where and how a reminder is delivered
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReminderTarget {
+    /// Private chat, group or channel; `None` - the owner's private chat
+    pub chat_id: Option<i64>,
+    /// Usernames (without @) to mention in a group
+    pub mentions: Vec<String>,
+    /// Ignore the owner's quiet hours
+    pub urgent: bool,
+    /// Delivery channels; `None` - the owner's default
+    pub channels: Option<Vec<NotifyChannel>>,
+    /// Message the reminder was created from
+    pub source: Option<SourceMessage>,
+}
+
+/// Chat message a reminder was created from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SourceMessage {
+    pub chat_id: i64,
+    pub message_id: i32,
+}
+
+impl SourceMessage {
+    /// t.me link; only supergroups and channels have one
+    pub fn url(&self) -> Option<String> {
+        // Their IDs are -100 followed by the internal ID
+        let internal = -self.chat_id - 1_000_000_000_000;
+        (internal > 0).then(|| format!("https://t.me/c/{}/{}", internal, self.message_id))
+    }
+}
+
+impl ReminderTarget {
+    pub fn chat(chat_id: i64) -> Self {
+        Self {
+            chat_id: Some(chat_id),
+            ..Self::default()
+        }
+    }
+}
+
+/// Changes to a pending reminder; `None` keeps the current value
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReminderEdit {
+    pub remind_at: Option<Timestamp>,
+    pub message: Option<String>,
+    /// `Some(None)` turns a recurring reminder into a one-off
+    pub recurrence: Option<Option<RecurrencePattern>>,
 }
 
 /// Data for creating a new reminder
@@ -120,15 +395,117 @@ pub struct NewReminder {
     pub message: Option<String>,
     pub is_recurring: bool,
     pub recurrence_pattern: Option<String>,
+    pub nag: Option<NagPolicy>,
+    pub due_offset_minutes: Option<i32>,
+    pub target: ReminderTarget,
 }
 
 impl Reminder {
+    /// Mentions as `@alice @bob`, if any
+    pub fn mention_list(&self) -> Option<String> {
+        if self.mentions.is_empty() {
+            return None;
+        }
+        let mentions: Vec<String> = self.mentions.iter().map(|m| format!("@{}", m)).collect();
+        Some(mentions.join(" "))
+    }
+
+    /// When delivery is due: the pending retry, otherwise the reminder time
+    pub fn due_at(&self) -> Timestamp {
+        self.retry_at.unwrap_or(self.remind_at)
+    }
+
+    /// How late a delivery at `now` is, if more than LATE_GRACE
+    /// (the bot was down or the queue fell behind)
+    pub fn late_by(&self, now: Timestamp) -> Option<chrono::Duration> {
+        let late = now - self.due_at();
+        (late > LATE_GRACE).then_some(late)
+    }
+
     /// Check if it's time to send the reminder
     pub fn should_send(&self) -> bool {
         !self.is_sent && self.remind_at <= Utc::now()
     }
+
+    /// Message it was created from, if known
+    pub fn source(&self) -> Option<SourceMessage> {
+        Some(SourceMessage {
+            chat_id: self.source_chat_id?,
+            message_id: self.source_message_id?,
+        })
+    }
+
+    /// Channels it is delivered on: its own choice or the owner's default
+    pub fn delivery_channels(&self, owner: &User) -> Vec<NotifyChannel> {
+        match &self.channels {
+            Some(channels) => parse_channels(channels),
+            None => owner.default_channels(),
+        }
+    }
+
+    /// Parsed recurrence pattern of a recurring reminder
+    pub fn recurrence(&self) -> Option<RecurrencePattern> {
+        if !self.is_recurring {
+            return None;
+        }
+        self.recurrence_pattern.as_deref()?.parse().ok()
+    }
+
+    /// First occurrence of a recurring reminder strictly after `after`
+    ///
+    /// Steps from the regular schedule (`scheduled_at` of a snoozed
+    /// reminder), so snoozing never shifts the series' time of day.
+    pub fn next_occurrence<Z: TimeZone>(&self, after: &DateTime<Z>) -> Option<DateTime<Z>> {
+        let pattern = self.recurrence()?;
+        let tz = after.timezone();
+        let mut next = self
+            .scheduled_at
+            .unwrap_or(self.remind_at)
+            .with_timezone(&tz);
+
+        for _ in 0..MAX_SKIPPED_OCCURRENCES {
+            if next > *after {
+                return Some(next);
+            }
+            next = pattern.next_after(&next)?;
+        }
+
+        // Snoozed far past a frequent schedule
+        pattern.next_after(after)
+    }
+
+    /// Insistent reminder that repeats until acknowledged
+    pub fn is_nagging(&self) -> bool {
+        self.nag_interval_minutes.is_some()
+    }
+
+    /// All repeats went unanswered: next delivery goes to the escalation chat
+    pub fn escalation_due(&self) -> bool {
+        self.is_nagging() && self.nag_count > self.nag_max_repeats
+    }
+
+    /// When to ping again after a delivery at `now`
+    ///
+    /// With escalation one more interval is scheduled after the last
+    /// repeat, giving the user a chance to acknowledge it.
+    pub fn next_nag_at(&self, now: Timestamp) -> Option<Timestamp> {
+        let interval = self.nag_interval_minutes?;
+        if self.acknowledged_at.is_some() {
+            return None;
+        }
+
+        let limit = self.nag_max_repeats + i32::from(self.escalate_to.is_some());
+        (self.nag_count < limit).then(|| now + chrono::Duration::minutes(interval.into()))
+    }
 }
 
+/// Delay after the due time that still counts as on time
+pub const LATE_GRACE: chrono::Duration = chrono::Duration::minutes(2);
+
+/// Occurrences stepped over before `next_occurrence` jumps straight to
+/// the pattern's next match
+const MAX_SKIPPED_OCCURRENCES: usize = 1000;
+
 /// File conversion model
 #[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
 pub struct FileConversion {
@@ -142,15 +519,26 @@ pub struct FileConversion {
     pub error_message: Option<String>,
     pub created_at: Timestamp,
     pub completed_at: Option<Timestamp>,
+    /// Name of an uploaded document; photos have none
+    pub source_file_name: Option<String>,
+    /// Chat the result is sent to; set when the job is queued
+    pub chat_id: Option<i64>,
+    /// Times a worker has picked the job up
+    pub attempts: i32,
+    /// Next attempt after a transient error
+    pub retry_at: Option<Timestamp>,
+    /// Worker converting it and until when
+    pub locked_by: Option<String>,
+    pub locked_until: Option<Timestamp>,
 }
 
-/// Data for creating a new conversion
+/// Data for creating a new conversion; the target is chosen later
 #[derive(Debug, Clone)]
 pub struct NewFileConversion {
     pub user_id: DbId,
     pub source_file_id: String,
     pub source_format: String,
-    pub target_format: String,
+    pub source_file_name: Option<String>,
 }
 
 impl FileConversion {
@@ -165,3 +553,226 @@ impl FileConversion {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reminder(remind_at: Timestamp, scheduled_at: Option<Timestamp>, pattern: &str) -> Reminder {
+        Reminder {
+            id: 1,
+            todo_id: None,
+            user_id: 1,
+            remind_at,
+            message: None,
+            is_sent: false,
+            sent_at: None,
+            is_recurring: true,
+            recurrence_pattern: Some(pattern.to_string()),
+            created_at: remind_at,
+            snooze_count: 0,
+            scheduled_at,
+            nag_interval_minutes: None,
+            nag_max_repeats: 0,
+            nag_count: 0,
+            acknowledged_at: None,
+            escalate_to: None,
+            escalated_at: None,
+            due_offset_minutes: None,
+            attempts: 0,
+            last_error: None,
+            retry_at: None,
+            dead_lettered_at: None,
+            paused_at: None,
+            chat_id: 100,
+            mentions: Vec::new(),
+            is_urgent: false,
+            channels: None,
+            source_chat_id: None,
+            source_message_id: None,
+        }
+    }
+
+    fn at(d: u32, h: u32, mi: u32) -> Timestamp {
+        Utc.with_ymd_and_hms(2026, 10, d, h, mi, 0).unwrap()
+    }
+
+    #[test]
+    fn test_next_occurrence_regular() {
+        let daily = reminder(at(19, 9, 0), None, "daily");
+        assert_eq!(daily.next_occurrence(&at(19, 9, 0)), Some(at(20, 9, 0)));
+    }
+
+    #[test]
+    fn test_next_occurrence_after_snooze() {
+        // Daily 09:00, delivered on the 19th, snoozed to 09:10 on the 19th
+        let snoozed = reminder(at(19, 9, 10), Some(at(20, 9, 0)), "daily");
+        assert_eq!(snoozed.next_occurrence(&at(19, 9, 10)), Some(at(20, 9, 0)));
+
+        // Snoozed past the next occurrence: keeps 09:00
+        let snoozed = reminder(at(20, 18, 0), Some(at(20, 9, 0)), "daily");
+        assert_eq!(snoozed.next_occurrence(&at(20, 18, 0)), Some(at(21, 9, 0)));
+    }
+
+    #[test]
+    fn test_next_occurrence_skips_missed() {
+        // Daily 09:00 delivered after three days of downtime
+        let missed = reminder(at(19, 9, 0), None, "daily");
+        assert_eq!(missed.next_occurrence(&at(22, 14, 0)), Some(at(23, 9, 0)));
+    }
+
+    #[test]
+    fn test_late_by() {
+        let mut r = reminder(at(19, 9, 0), None, "daily");
+        assert_eq!(r.late_by(at(19, 9, 1)), None);
+        assert_eq!(r.late_by(at(19, 10, 0)), Some(chrono::Duration::hours(1)));
+
+        // A scheduled retry is not late
+        r.retry_at = Some(at(19, 10, 0));
+        assert_eq!(r.late_by(at(19, 10, 1)), None);
+    }
+
+    fn quiet_user(start: (u32, u32), end: (u32, u32)) -> User {
+        let now = at(19, 0, 0);
+        User {
+            id: 1,
+            telegram_id: 100,
+            username: None,
+            first_name: None,
+            language_code: "en".to_string(),
+            created_at: now,
+            last_active_at: now,
+            weekly_review: false,
+            last_review_at: None,
+            timezone: "Europe/Berlin".to_string(),
+            due_reminder_offsets: Vec::new(),
+            is_blocked: false,
+            blocked_at: None,
+            quiet_start: NaiveTime::from_hms_opt(start.0, start.1, 0),
+            quiet_end: NaiveTime::from_hms_opt(end.0, end.1, 0),
+            quiet_silent: false,
+            dnd_until: None,
+            calendar_token: None,
+            notify_channels: vec!["telegram".to_string()],
+            email: None,
+            pending_email: None,
+            webhook_url: None,
+        }
+    }
+
+    #[test]
+    fn test_source_message_url() {
+        let group = SourceMessage {
+            chat_id: -1001234567890,
+            message_id: 42,
+        };
+        assert_eq!(group.url().as_deref(), Some("https://t.me/c/1234567890/42"));
+
+        // Private chats and basic groups cannot be linked to
+        let private = SourceMessage {
+            chat_id: 100,
+            message_id: 42,
+        };
+        assert_eq!(private.url(), None);
+        let basic_group = SourceMessage {
+            chat_id: -4001234,
+            message_id: 42,
+        };
+        assert_eq!(basic_group.url(), None);
+    }
+
+    #[test]
+    fn test_delivery_channels() {
+        let mut owner = quiet_user((23, 0), (8, 0));
+        owner.notify_channels = vec!["email".to_string(), "webhook".to_string()];
+        let mut r = reminder(at(19, 9, 0), None, "daily");
+        assert_eq!(
+            r.delivery_channels(&owner),
+            vec![NotifyChannel::Email, NotifyChannel::Webhook]
+        );
+
+        r.channels = Some(vec!["telegram".to_string(), "email".to_string()]);
+        assert_eq!(
+            r.delivery_channels(&owner),
+            vec![NotifyChannel::Telegram, NotifyChannel::Email]
+        );
+
+        // Nothing usable left: falls back to Telegram
+        r.channels = Some(vec!["pager".to_string()]);
+        assert_eq!(r.delivery_channels(&owner), vec![NotifyChannel::Telegram]);
+    }
+
+    #[test]
+    fn test_quiet_hours_over_midnight() {
+        // 23:00-08:00 in Berlin (UTC+2 in October)
+        let user = quiet_user((23, 0), (8, 0));
+        assert_eq!(user.quiet_until(at(19, 20, 59)), None);
+        assert_eq!(user.quiet_until(at(19, 21, 0)), Some(at(20, 6, 0)));
+        assert_eq!(user.quiet_until(at(20, 3, 0)), Some(at(20, 6, 0)));
+        assert_eq!(user.quiet_until(at(20, 6, 0)), None);
+    }
+
+    #[test]
+    fn test_quiet_hours_same_day() {
+        let user = quiet_user((13, 0), (14, 30));
+        assert_eq!(user.quiet_until(at(19, 11, 30)), Some(at(19, 12, 30)));
+        assert_eq!(user.quiet_until(at(19, 12, 30)), None);
+    }
+
+    #[test]
+    fn test_dnd_runs_into_quiet_hours() {
+        let mut user = quiet_user((23, 0), (8, 0));
+        user.quiet_start = None;
+        user.dnd_until = Some(at(19, 10, 0));
+        assert_eq!(user.quiet_until(at(19, 9, 0)), Some(at(19, 10, 0)));
+        assert_eq!(user.quiet_until(at(19, 10, 0)), None);
+
+        // Do-not-disturb until 23:30 local, then quiet hours until 08:00
+        let mut user = quiet_user((23, 0), (8, 0));
+        user.dnd_until = Some(at(19, 21, 30));
+        assert_eq!(user.quiet_until(at(19, 18, 0)), Some(at(20, 6, 0)));
+    }
+
+    fn nagging(nag_count: i32, escalate_to: Option<i64>) -> Reminder {
+        let mut r = reminder(at(19, 9, 0), None, "daily");
+        r.is_recurring = false;
+        r.nag_interval_minutes = Some(5);
+        r.nag_max_repeats = 2;
+        r.nag_count = nag_count;
+        r.escalate_to = escalate_to;
+        r
+    }
+
+    #[test]
+    fn test_nag_repeats() {
+        let now = at(19, 9, 0);
+        assert_eq!(nagging(0, None).next_nag_at(now), Some(at(19, 9, 5)));
+        assert_eq!(nagging(1, None).next_nag_at(now), Some(at(19, 9, 5)));
+        assert_eq!(nagging(2, None).next_nag_at(now), None);
+
+        let mut acked = nagging(0, None);
+        acked.acknowledged_at = Some(now);
+        assert_eq!(acked.next_nag_at(now), None);
+
+        let mut single = nagging(0, None);
+        single.nag_interval_minutes = None;
+        assert_eq!(single.next_nag_at(now), None);
+    }
+
+    #[test]
+    fn test_nag_escalation() {
+        let now = at(19, 9, 0);
+        // One extra interval after the last repeat, then escalate
+        assert_eq!(nagging(2, Some(-100)).next_nag_at(now), Some(at(19, 9, 5)));
+        assert!(!nagging(2, Some(-100)).escalation_due());
+        assert!(nagging(3, Some(-100)).escalation_due());
+        assert_eq!(nagging(3, Some(-100)).next_nag_at(now), None);
+    }
+
+    #[test]
+    fn test_next_occurrence_one_off() {
+        let mut one_off = reminder(at(19, 9, 0), None, "daily");
+        one_off.is_recurring = false;
+        assert_eq!(one_off.next_occurrence(&at(19, 9, 0)), None);
+    }
+}
@@ -20,6 +20,18 @@ pub enum AppError {
     #[error("Telegram error: {0}")]
     Telegram(String),
 
+    /// Telegram flood control: the request may be repeated after the delay
+    #[error("Telegram rate limit, retry after {0:?}")]
+    RateLimited(std::time::Duration),
+
+    /// The chat no longer accepts messages from the bot (blocked, deleted account)
+    #[error("Chat unavailable: {0}")]
+    ChatUnavailable(String),
+
+    /// Email or webhook delivery errors
+    #[error("Notification error: {0}")]
+    Notification(String),
+
     /// Business logic errors
     #[error("Business logic error: {0}")]
     BusinessLogic(String),
@@ -53,6 +65,19 @@ pub enum AppError {
     Internal(String),
 }
 
+impl AppError {
+    /// Text safe to show to the user: input problems verbatim, internal
+    /// failures as a generic message
+    pub fn user_message(&self) -> String {
+        match self {
+            AppError::Validation(msg) | AppError::Parse(msg) | AppError::NotFound(msg) => {
+                msg.clone()
+            }
+            _ => "Something went wrong, please try again later".to_string(),
+        }
+    }
+}
+
 /// Result with our error type
 pub type Result<T> = std::result::Result<T, AppError>;
 
@@ -66,6 +91,25 @@ impl From<anyhow::Error> for AppError {
 /// Conversion from teloxide::RequestError
 impl From<teloxide::RequestError> for AppError {
     fn from(err: teloxide::RequestError) -> Self {
+        use teloxide::{ApiError, RequestError};
+
+        match err {
+            RequestError::RetryAfter(delay) => AppError::RateLimited(delay),
+            RequestError::Api(
+                ApiError::BotBlocked
+                | ApiError::BotKicked
+                | ApiError::BotKickedFromSupergroup
+                | ApiError::UserDeactivated
+                | ApiError::CantInitiateConversation,
+            ) => AppError::ChatUnavailable(err.to_string()),
+            _ => AppError::Telegram(err.to_string()),
+        }
+    }
+}
+
+/// Conversion from teloxide::DownloadError
+impl From<teloxide::DownloadError> for AppError {
+    fn from(err: teloxide::DownloadError) -> Self {
         AppError::Telegram(err.to_string())
     }
 }
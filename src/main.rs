@@ -1,11 +1,22 @@
 // Main entry point for Telegram bot
 use anyhow::Result;
 use telegram_multitool_bot::{
+    bot::state::State,
     config::Config,
+    converter::{
+        cleanup::ConversionCleanup,
+        queue::{ConversionQueue, ConversionWorkers},
+        service::ConverterService,
+    },
     db::pool::create_pool,
     reminder::scheduler::ReminderScheduler,
-    shared::telemetry,
+    shared::{
+        health::{self, Metrics},
+        telemetry,
+    },
+    todo::review::ReviewScheduler,
 };
+use teloxide::dispatching::dialogue::InMemStorage;
 use teloxide::prelude::*;
 
 #[tokio::main]
@@ -32,7 +43,10 @@ async fn main() -> Result<()> {
     tracing::info!("✅ Bot initialized");
 
     // Start reminder scheduler (background task)
-    let scheduler = ReminderScheduler::new(db_pool.clone(), bot.clone());
+    let scheduler = ReminderScheduler::new(db_pool.clone(), bot.clone())
+        .with_catch_up(&config.reminders)
+        .with_notify(&config.notify)?;
+    let scheduler_metrics = scheduler.metrics();
     let scheduler_handle = tokio::spawn(async move {
         if let Err(e) = scheduler.run().await {
             tracing::error!("❌ Reminder scheduler error: {}", e);
@@ -40,6 +54,55 @@ async fn main() -> Result<()> {
     });
     tracing::info!("✅ Reminder scheduler started");
 
+    // Converted files and history are cleaned up (background task)
+    let converter =
+        ConverterService::new(config.app.temp_dir.clone(), config.app.output_dir.clone());
+    converter.init().await?;
+    let cleanup = ConversionCleanup::new(db_pool.clone(), converter.clone(), &config.app);
+    let conversion_metrics = cleanup.metrics();
+    tokio::spawn(async move {
+        if let Err(e) = cleanup.run().await {
+            tracing::error!("❌ Conversion cleanup error: {}", e);
+        }
+    });
+    tracing::info!("✅ Conversion cleanup started");
+
+    // Health checks, scheduler and converter metrics
+    // and calendar feeds (optional)
+    if let Some(port) = config.app.port {
+        let pool = db_pool.clone();
+        let metrics = Metrics {
+            scheduler: scheduler_metrics,
+            conversion: conversion_metrics,
+        };
+        let metrics_token = config.app.metrics_token.clone();
+        tokio::spawn(async move {
+            if let Err(e) = health::serve(port, metrics, metrics_token, pool).await {
+                tracing::error!("❌ Health server error: {}", e);
+            }
+        });
+    }
+
+    // Start weekly review scheduler (background task)
+    let review_scheduler = ReviewScheduler::new(db_pool.clone(), bot.clone());
+    tokio::spawn(async move {
+        if let Err(e) = review_scheduler.run().await {
+            tracing::error!("❌ Weekly review scheduler error: {}", e);
+        }
+    });
+    tracing::info!("✅ Weekly review scheduler started");
+
+    // Start file conversion workers (background task)
+    let conversion_queue = ConversionQueue::new(db_pool.clone());
+    let workers = ConversionWorkers::new(bot.clone(), converter, conversion_queue.clone())
+        .with_workers(config.app.conversion_workers);
+    tokio::spawn(async move {
+        if let Err(e) = workers.run().await {
+            tracing::error!("❌ Conversion workers error: {}", e);
+        }
+    });
+    tracing::info!("✅ Conversion workers started");
+
     // Create command dispatcher
     let handler = telegram_multitool_bot::bot::handlers::schema();
 
@@ -48,7 +111,13 @@ async fn main() -> Result<()> {
 
     // Start bot with long polling
     Dispatcher::builder(bot, handler)
-        .dependencies(dptree::deps![db_pool])
+        .dependencies(dptree::deps![
+            db_pool,
+            InMemStorage::<State>::new(),
+            config.app.clone(),
+            config.notify.clone(),
+            conversion_queue
+        ])
         .enable_ctrlc_handler()
         .build()
         .dispatch()
@@ -0,0 +1,346 @@
+// Calendar export and import of reminders
+//
+// /calendar sends upcoming reminders and due dates as an .ics file; the
+// same calendar is served as a token-protected feed by the health server.
+// Uploaded .ics files become reminders after the user confirms a preview.
+
+use crate::db::models::{ReminderTarget, User};
+use crate::error::Result;
+use crate::reminder::repository::ReminderRepository;
+use crate::reminder::service::ReminderService;
+use crate::shared::ical::{write_calendar, CalendarEvent, ParsedCalendar};
+use crate::shared::types::{DbId, RecurrencePattern, Timestamp};
+use crate::shared::utils::{truncate_text, RecurringSchedule};
+use crate::todo::repository::TodoRepository;
+use chrono::Utc;
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+/// Domain part of event UIDs
+const UID_DOMAIN: &str = "telegram-multitool-bot";
+
+/// Most reminders created from one file
+pub const MAX_IMPORT_EVENTS: usize = 100;
+
+/// Longest imported reminder text, in characters
+const MAX_IMPORTED_TEXT: usize = 160;
+
+/// Reminder to be created from an imported event
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlannedReminder {
+    pub text: String,
+    /// First time it fires, always in the future
+    pub remind_at: Timestamp,
+    pub recurrence: Option<RecurrencePattern>,
+}
+
+/// What an uploaded calendar turns into, shown before importing
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ImportPlan {
+    pub reminders: Vec<PlannedReminder>,
+    /// Event summary and why it is left out
+    pub skipped: Vec<(String, &'static str)>,
+}
+
+/// Upcoming reminders and due dates of open tasks as an .ics calendar
+pub async fn export_calendar(pool: &PgPool, user: &User) -> Result<String> {
+    let tz = user.tz();
+    let now = Utc::now();
+    let reminders = ReminderRepository::new(pool.clone())
+        .find_by_user(user.id)
+        .await?;
+    let todos = TodoRepository::new(pool.clone())
+        .find_open_due_after(user.id, now)
+        .await?;
+
+    let mut events = Vec::with_capacity(reminders.len() + todos.len());
+    for reminder in &reminders {
+        let recurrence = reminder.recurrence();
+        // Cron schedules have no RRULE: only the next run is exported
+        let description = recurrence
+            .as_ref()
+            .filter(|pattern| matches!(pattern, RecurrencePattern::Custom(_)))
+            .map(|pattern| format!("Repeats {}", pattern.describe()));
+        // A snoozed series continues from its regular occurrence
+        let start = match recurrence {
+            Some(_) => reminder.scheduled_at.unwrap_or(reminder.remind_at),
+            None => reminder.remind_at,
+        };
+        let summary = reminder
+            .message
+            .clone()
+            .unwrap_or_else(|| match reminder.todo_id {
+                Some(todo_id) => format!("Task #{}", todo_id),
+                None => "Reminder".to_string(),
+            });
+
+        events.push(CalendarEvent {
+            uid: format!("reminder-{}@{}", reminder.id, UID_DOMAIN),
+            summary,
+            description,
+            start: start.with_timezone(&tz),
+            recurrence,
+        });
+    }
+    for todo in &todos {
+        let Some(due) = todo.due_date else {
+            continue;
+        };
+        events.push(CalendarEvent {
+            uid: format!("todo-{}@{}", todo.id, UID_DOMAIN),
+            summary: format!("Due: {}", todo.title),
+            description: todo.description.clone(),
+            start: due.with_timezone(&tz),
+            recurrence: None,
+        });
+    }
+
+    Ok(write_calendar("Reminders", &events, now))
+}
+
+/// Turn on the ICS feed of a user; an existing token is kept
+pub async fn enable_feed(pool: &PgPool, user_id: DbId) -> Result<String> {
+    let (token,): (String,) = sqlx::query_as(
+        r#"
+        UPDATE users SET calendar_token = COALESCE(calendar_token, $2)
+        WHERE id = $1
+        RETURNING calendar_token
+        "#,
+    )
+    .bind(user_id)
+    .bind(Uuid::new_v4().simple().to_string())
+    .fetch_one(pool)
+    .await?;
+
+    Ok(token)
+}
+
+/// Turn off the ICS feed; its old URL stops working
+pub async fn disable_feed(pool: &PgPool, user_id: DbId) -> Result<()> {
+    sqlx::query("UPDATE users SET calendar_token = NULL WHERE id = $1")
+        .bind(user_id)
+        .execute(pool)
+        .await?;
+
+    tracing::debug!("Calendar feed of user {} disabled", user_id);
+    Ok(())
+}
+
+/// Calendar behind a feed token; `None` if no user has it
+pub async fn feed(pool: &PgPool, token: &str) -> Result<Option<String>> {
+    let user: Option<User> = sqlx::query_as("SELECT * FROM users WHERE calendar_token = $1")
+        .bind(token)
+        .fetch_optional(pool)
+        .await?;
+
+    match user {
+        Some(user) => export_calendar(pool, &user).await.map(Some),
+        None => Ok(None),
+    }
+}
+
+/// Feed URL served by the health server under `base_url`
+pub fn feed_url(base_url: &str, token: &str) -> String {
+    format!("{}/calendar/{}.ics", base_url, token)
+}
+
+/// Decide which events become reminders and when they first fire
+///
+/// A reminder fires at the event's alarm if it has one, otherwise at its
+/// start. For a series the alarm is only used if it is on the same day,
+/// since an earlier day would move the series to other weekdays.
+pub fn plan_import(calendar: ParsedCalendar, tz: Tz, now: Timestamp) -> ImportPlan {
+    let now = now.with_timezone(&tz);
+    let mut plan = ImportPlan {
+        reminders: Vec::new(),
+        skipped: calendar
+            .skipped
+            .into_iter()
+            .map(|skipped| (skipped.summary, skipped.reason))
+            .collect(),
+    };
+
+    for event in calendar.events {
+        let start = event.start.with_timezone(&tz);
+        let alarm = event.alarm_before.map(|before| start - before);
+        let text = truncate_text(&event.summary, MAX_IMPORTED_TEXT);
+
+        let first = match &event.recurrence {
+            Some(pattern) => {
+                let at = alarm
+                    .filter(|at| at.date_naive() == start.date_naive())
+                    .unwrap_or(start);
+                pattern.first_after(at, &now)
+            }
+            None => alarm
+                .filter(|at| *at > now)
+                .or(Some(start).filter(|at| *at > now)),
+        };
+
+        match first {
+            Some(_) if plan.reminders.len() >= MAX_IMPORT_EVENTS => {
+                plan.skipped.push((text, "over the import limit"))
+            }
+            Some(first) => plan.reminders.push(PlannedReminder {
+                text,
+                remind_at: first.with_timezone(&Utc),
+                recurrence: event.recurrence,
+            }),
+            None if event.recurrence.is_some() => plan.skipped.push((text, "never repeats")),
+            None => plan.skipped.push((text, "already past")),
+        }
+    }
+
+    plan.reminders.sort_by_key(|reminder| reminder.remind_at);
+    plan
+}
+
+/// Create the planned reminders in `chat_id`; returns how many were created
+///
+/// A reminder whose time passed while the preview was open is left out.
+pub async fn import(
+    service: &ReminderService,
+    user_id: DbId,
+    chat_id: i64,
+    reminders: Vec<PlannedReminder>,
+) -> usize {
+    let mut created = 0;
+    for reminder in reminders {
+        let target = ReminderTarget::chat(chat_id);
+        let result = match reminder.recurrence {
+            Some(pattern) => {
+                let schedule = RecurringSchedule {
+                    pattern,
+                    first_at: reminder.remind_at,
+                    message: reminder.text,
+                };
+                service
+                    .create_recurring_reminder(user_id, None, schedule, target)
+                    .await
+            }
+            None => {
+                service
+                    .create_reminder_at(
+                        user_id,
+                        None,
+                        reminder.remind_at,
+                        Some(reminder.text),
+                        target,
+                    )
+                    .await
+            }
+        };
+
+        match result {
+            Ok(_) => created += 1,
+            Err(e) => tracing::warn!("Calendar event not imported for user {}: {}", user_id, e),
+        }
+    }
+
+    tracing::info!(
+        "Imported {} reminders from a calendar for user {}",
+        created,
+        user_id
+    );
+    created
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shared::ical::{ImportedEvent, SkippedEvent};
+    use chrono::{Duration, TimeZone};
+
+    fn event(
+        summary: &str,
+        start: Timestamp,
+        recurrence: Option<RecurrencePattern>,
+    ) -> ImportedEvent {
+        ImportedEvent {
+            summary: summary.to_string(),
+            start,
+            recurrence,
+            alarm_before: None,
+        }
+    }
+
+    #[test]
+    fn test_plan_import() {
+        let tz: Tz = "Europe/Moscow".parse().unwrap();
+        // Monday, 2026-10-19 12:00 UTC (15:00 in Moscow)
+        let now = Utc.with_ymd_and_hms(2026, 10, 19, 12, 0, 0).unwrap();
+        let calendar = ParsedCalendar {
+            events: vec![
+                ImportedEvent {
+                    alarm_before: Some(Duration::minutes(30)),
+                    ..event("Dentist", now + Duration::days(1), None)
+                },
+                event("Yesterday", now - Duration::days(1), None),
+                // Alarm already passed, the start is still ahead
+                ImportedEvent {
+                    alarm_before: Some(Duration::hours(2)),
+                    ..event("Call", now + Duration::hours(1), None)
+                },
+                // Weekly on Monday since 2025, 09:00 Moscow
+                ImportedEvent {
+                    alarm_before: Some(Duration::minutes(15)),
+                    ..event(
+                        "Standup",
+                        Utc.with_ymd_and_hms(2025, 1, 6, 6, 0, 0).unwrap(),
+                        Some(RecurrencePattern::Weekly),
+                    )
+                },
+                // The alarm is a day earlier: the series keeps its own time
+                ImportedEvent {
+                    alarm_before: Some(Duration::days(1)),
+                    ..event(
+                        "Rent",
+                        Utc.with_ymd_and_hms(2026, 1, 1, 7, 0, 0).unwrap(),
+                        Some(RecurrencePattern::MonthlyOn(1)),
+                    )
+                },
+            ],
+            skipped: vec![SkippedEvent {
+                summary: "Sprint".to_string(),
+                reason: "repeat rule not supported",
+            }],
+        };
+
+        let plan = plan_import(calendar, tz, now);
+
+        assert_eq!(
+            plan.reminders,
+            vec![
+                PlannedReminder {
+                    text: "Call".to_string(),
+                    remind_at: now + Duration::hours(1),
+                    recurrence: None,
+                },
+                PlannedReminder {
+                    text: "Dentist".to_string(),
+                    remind_at: now + Duration::days(1) - Duration::minutes(30),
+                    recurrence: None,
+                },
+                PlannedReminder {
+                    text: "Standup".to_string(),
+                    remind_at: Utc.with_ymd_and_hms(2026, 10, 26, 5, 45, 0).unwrap(),
+                    recurrence: Some(RecurrencePattern::Weekly),
+                },
+                PlannedReminder {
+                    text: "Rent".to_string(),
+                    remind_at: Utc.with_ymd_and_hms(2026, 11, 1, 7, 0, 0).unwrap(),
+                    recurrence: Some(RecurrencePattern::MonthlyOn(1)),
+                },
+            ]
+        );
+        assert_eq!(
+            plan.skipped,
+            vec![
+                ("Sprint".to_string(), "repeat rule not supported"),
+                ("Yesterday".to_string(), "already past"),
+            ]
+        );
+    }
+}
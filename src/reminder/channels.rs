@@ -0,0 +1,381 @@
+// Notification channel settings - where reminders are delivered
+//
+// /notify picks the default channels and stores the email address and
+// webhook URL they need; /remind via ... overrides the channels of one
+// reminder. Delivery itself is in notifier.rs.
+//
+// An email address is only used once the user proves they own it: the bot
+// mails a code, and /notify email confirm <code> moves the pending address
+// to `email`.
+
+use crate::config::SmtpConfig;
+use crate::db::models::User;
+use crate::error::{validation_error, Result};
+use crate::shared::smtp::{send_mail, Mail};
+use crate::shared::types::{DbId, NotifyChannel};
+use chrono::Duration;
+use sqlx::PgPool;
+use std::net::IpAddr;
+use uuid::Uuid;
+
+/// Longest accepted webhook URL
+const MAX_URL_LENGTH: usize = 2048;
+
+/// How long an email confirmation code is valid
+const CODE_LIFETIME: Duration = Duration::minutes(15);
+
+/// Least time between two confirmation emails of a user
+const CODE_RESEND_DELAY: Duration = Duration::minutes(1);
+
+/// Wrong codes before a new one has to be requested
+const MAX_CODE_ATTEMPTS: i32 = 5;
+
+/// Make `channels` the default of a user
+pub async fn set_default_channels(
+    pool: &PgPool,
+    user_id: DbId,
+    channels: &[NotifyChannel],
+) -> Result<()> {
+    sqlx::query("UPDATE users SET notify_channels = $2 WHERE id = $1")
+        .bind(user_id)
+        .bind(NotifyChannel::names(channels))
+        .execute(pool)
+        .await?;
+
+    tracing::debug!("User {} notifies via {:?}", user_id, channels);
+    Ok(())
+}
+
+/// Mail a confirmation code to `address`, which becomes the user's email
+/// address once `confirm_email` gets the code
+///
+/// `address` must be validated. At most one code is sent per
+/// CODE_RESEND_DELAY, so the bot cannot be used to flood a mailbox.
+pub async fn request_email(
+    pool: &PgPool,
+    smtp: &SmtpConfig,
+    user_id: DbId,
+    address: &str,
+) -> Result<()> {
+    let code = confirmation_code();
+    let stored = sqlx::query(
+        r#"
+        UPDATE users
+        SET pending_email = $2, email_code = $3, email_code_sent_at = NOW(),
+            email_code_attempts = 0
+        WHERE id = $1
+          AND (email_code_sent_at IS NULL
+               OR email_code_sent_at <= NOW() - make_interval(secs => $4))
+        "#,
+    )
+    .bind(user_id)
+    .bind(address)
+    .bind(&code)
+    .bind(CODE_RESEND_DELAY.num_seconds() as f64)
+    .execute(pool)
+    .await?
+    .rows_affected();
+
+    if stored == 0 {
+        return Err(validation_error(
+            "A code was sent less than a minute ago, check your inbox or try again later",
+        ));
+    }
+
+    let body = format!(
+        "Your confirmation code is {}\n\n\
+         Send it to the bot within {} minutes: /notify email confirm {}\n\n\
+         If you did not ask for reminders by email, ignore this message.",
+        code,
+        CODE_LIFETIME.num_minutes(),
+        code
+    );
+    let mail = Mail {
+        to: address,
+        subject: "Confirm your email for reminders",
+        body: &body,
+    };
+    if let Err(e) = send_mail(smtp, &mail).await {
+        tracing::warn!(
+            "Failed to send confirmation code to user {}: {}",
+            user_id,
+            e
+        );
+        return Err(validation_error(format!(
+            "Could not send a code to {}, check the address or try again later",
+            address
+        )));
+    }
+
+    tracing::debug!("Sent email confirmation code to user {}", user_id);
+    Ok(())
+}
+
+/// Make the pending address the user's email address if `code` is the one
+/// mailed to it
+///
+/// Returns the confirmed address; `None` if the code is wrong, expired or
+/// was guessed at too often.
+pub async fn confirm_email(pool: &PgPool, user_id: DbId, code: &str) -> Result<Option<String>> {
+    let confirmed: Option<String> = sqlx::query_scalar(
+        r#"
+        UPDATE users
+        SET email = pending_email, pending_email = NULL, email_code = NULL,
+            email_code_attempts = 0
+        WHERE id = $1
+          AND pending_email IS NOT NULL
+          AND email_code = $2
+          AND email_code_attempts < $3
+          AND email_code_sent_at > NOW() - make_interval(secs => $4)
+        RETURNING email
+        "#,
+    )
+    .bind(user_id)
+    .bind(code.trim())
+    .bind(MAX_CODE_ATTEMPTS)
+    .bind(CODE_LIFETIME.num_seconds() as f64)
+    .fetch_optional(pool)
+    .await?;
+
+    if confirmed.is_none() {
+        sqlx::query(
+            r#"
+            UPDATE users SET email_code_attempts = email_code_attempts + 1
+            WHERE id = $1 AND pending_email IS NOT NULL
+            "#,
+        )
+        .bind(user_id)
+        .execute(pool)
+        .await?;
+    }
+    Ok(confirmed)
+}
+
+/// Remove the email address, confirmed or pending, and drop email from the
+/// default channels
+pub async fn remove_email(pool: &PgPool, user_id: DbId) -> Result<()> {
+    sqlx::query(
+        "UPDATE users SET email = NULL, pending_email = NULL, email_code = NULL WHERE id = $1",
+    )
+    .bind(user_id)
+    .execute(pool)
+    .await?;
+
+    drop_default_channel(pool, user_id, NotifyChannel::Email).await
+}
+
+/// Random six-digit code
+fn confirmation_code() -> String {
+    format!("{:06}", Uuid::new_v4().as_u128() % 1_000_000)
+}
+
+/// Set or remove the webhook URL; removing it also drops the webhook from
+/// the default channels
+pub async fn set_webhook(pool: &PgPool, user_id: DbId, url: Option<&str>) -> Result<()> {
+    sqlx::query("UPDATE users SET webhook_url = $2 WHERE id = $1")
+        .bind(user_id)
+        .bind(url)
+        .execute(pool)
+        .await?;
+
+    if url.is_none() {
+        drop_default_channel(pool, user_id, NotifyChannel::Webhook).await?;
+    }
+    Ok(())
+}
+
+/// Remove a channel from the defaults, falling back to Telegram
+async fn drop_default_channel(pool: &PgPool, user_id: DbId, channel: NotifyChannel) -> Result<()> {
+    sqlx::query(
+        r#"
+        UPDATE users
+        SET notify_channels = COALESCE(
+            NULLIF(array_remove(notify_channels, $2), '{}'),
+            '{telegram}'
+        )
+        WHERE id = $1
+        "#,
+    )
+    .bind(user_id)
+    .bind(channel.to_string())
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// What the user still has to set up before `channels` can be used
+pub fn missing_setup(user: &User, channels: &[NotifyChannel]) -> Option<&'static str> {
+    if channels.contains(&NotifyChannel::Email) && user.email.is_none() {
+        if user.pending_email.is_some() {
+            return Some("Confirm your email address first: /notify email confirm <code>");
+        }
+        return Some("Set your email address first: /notify email you@example.com");
+    }
+    if channels.contains(&NotifyChannel::Webhook) && user.webhook_url.is_none() {
+        return Some("Set your webhook URL first: /notify webhook https://example.com/hook");
+    }
+    None
+}
+
+/// Check an email address; the domain is lowercased
+///
+/// Deliberately strict: the address ends up in SMTP commands and headers.
+pub fn validate_email(address: &str) -> Result<String> {
+    let address = address.trim();
+    let invalid = || validation_error(format!("'{}' is not a valid email address", address));
+
+    let (local, domain) = address.split_once('@').ok_or_else(invalid)?;
+    let local_ok = (1..=64).contains(&local.len())
+        && !local.starts_with('.')
+        && !local.ends_with('.')
+        && local
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || ".!#$%&'*+/=?^_`{|}~-".contains(c));
+    let labels: Vec<&str> = domain.split('.').collect();
+    let domain_ok = labels.len() >= 2
+        && labels.iter().all(|label| {
+            (1..=63).contains(&label.len())
+                && !label.starts_with('-')
+                && !label.ends_with('-')
+                && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+        });
+
+    if !local_ok || !domain_ok || address.len() > 254 {
+        return Err(invalid());
+    }
+    Ok(format!("{}@{}", local, domain.to_lowercase()))
+}
+
+/// Check a webhook URL
+///
+/// Unless `allow_private` is set (development), only https to public hosts
+/// is accepted, so the bot cannot be pointed at its own network.
+pub fn validate_webhook_url(url: &str, allow_private: bool) -> Result<String> {
+    let url = url.trim();
+    if url.len() > MAX_URL_LENGTH {
+        return Err(validation_error("Webhook URL is too long"));
+    }
+
+    let parsed = reqwest::Url::parse(url)
+        .map_err(|_| validation_error(format!("'{}' is not a valid URL", url)))?;
+    let host = parsed
+        .host_str()
+        .ok_or_else(|| validation_error("Webhook URL needs a host"))?;
+
+    match parsed.scheme() {
+        "https" => {}
+        "http" if allow_private => {}
+        _ => return Err(validation_error("Webhook URL must start with https://")),
+    }
+    if !allow_private && is_private_host(host) {
+        return Err(validation_error(
+            "Webhook URL must point to a public address",
+        ));
+    }
+
+    Ok(parsed.to_string())
+}
+
+/// Local names and loopback, private or link-local addresses
+fn is_private_host(host: &str) -> bool {
+    let host = host
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .to_lowercase();
+    if host == "localhost"
+        || [".localhost", ".local", ".internal"]
+            .iter()
+            .any(|suffix| host.ends_with(suffix))
+    {
+        return true;
+    }
+
+    host.parse().is_ok_and(is_private_ip)
+}
+
+/// Loopback, private, link-local and other non-public addresses
+pub fn is_private_ip(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, ..] = ip.octets();
+            ip.is_private()
+                || ip.is_loopback()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                // Carrier-grade NAT, 100.64.0.0/10
+                || (a == 100 && (64..128).contains(&b))
+        }
+        IpAddr::V6(ip) => {
+            let first = ip.segments()[0];
+            ip.is_loopback()
+                || ip.is_unspecified()
+                // Unique local fc00::/7 and link-local fe80::/10
+                || first & 0xfe00 == 0xfc00
+                || first & 0xffc0 == 0xfe80
+                || ip
+                    .to_ipv4_mapped()
+                    .is_some_and(|v4| is_private_ip(v4.into()))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_confirmation_code() {
+        for _ in 0..100 {
+            let code = confirmation_code();
+            assert_eq!(code.len(), 6);
+            assert!(code.chars().all(|c| c.is_ascii_digit()), "{}", code);
+        }
+    }
+
+    #[test]
+    fn test_validate_email() {
+        assert_eq!(
+            validate_email(" Alice.Smith+bot@Example.COM ").unwrap(),
+            "Alice.Smith+bot@example.com"
+        );
+        for bad in [
+            "alice",
+            "alice@localhost",
+            "alice@@example.com",
+            "al ice@example.com",
+            "alice@example.com>\r\nRCPT TO:<eve@example.com",
+            ".alice@example.com",
+            "alice@-example.com",
+        ] {
+            assert!(validate_email(bad).is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn test_validate_webhook_url() {
+        assert_eq!(
+            validate_webhook_url("https://hooks.example.com/r?key=1", false).unwrap(),
+            "https://hooks.example.com/r?key=1"
+        );
+        for bad in [
+            "http://hooks.example.com/r",
+            "ftp://hooks.example.com/r",
+            "https://localhost/hook",
+            "https://127.0.0.1/hook",
+            "https://10.1.2.3/hook",
+            "https://169.254.169.254/latest",
+            "https://[::1]/hook",
+            "https://[fd00::1]/hook",
+            "https://[::ffff:192.168.0.1]/hook",
+            "https://metadata.google.internal/",
+            "not a url",
+        ] {
+            assert!(validate_webhook_url(bad, false).is_err(), "{}", bad);
+        }
+
+        // Development setups may post to a local receiver
+        assert!(validate_webhook_url("http://127.0.0.1:9000/hook", true).is_ok());
+    }
+}
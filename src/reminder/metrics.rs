@@ -0,0 +1,104 @@
+// Scheduler metrics - counters and gauges of the reminder scheduler
+//
+// Updated lock-free by the scheduler loop and delivery tasks; rendered in
+// Prometheus text format by the health server (GET /metrics).
+
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Reminder scheduler metrics
+#[derive(Debug, Default)]
+pub struct SchedulerMetrics {
+    /// Reminders waiting in the in-memory queue
+    pub queued: AtomicU64,
+    /// Deliveries currently being sent
+    pub in_flight: AtomicU64,
+    pub delivered_total: AtomicU64,
+    /// Failed delivery attempts
+    pub failed_total: AtomicU64,
+    pub retried_total: AtomicU64,
+    /// Reminders given up after too many failures
+    pub dead_lettered_total: AtomicU64,
+    /// Users found to have blocked the bot
+    pub blocked_users_total: AtomicU64,
+    /// Reminders dropped by the catch-up policy after downtime
+    pub skipped_total: AtomicU64,
+    /// Reminders postponed to the end of the owner's quiet hours
+    pub held_total: AtomicU64,
+    /// Reminders cancelled or moved after they were queued
+    pub cancelled_total: AtomicU64,
+    pub notifications_total: AtomicU64,
+    pub refills_total: AtomicU64,
+    /// Reminders left in the database because the queue was full
+    pub overflow_total: AtomicU64,
+    /// Delay between due time and dispatch of the last reminder
+    pub last_lag_ms: AtomicU64,
+}
+
+impl SchedulerMetrics {
+    pub fn inc(counter: &AtomicU64) {
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn set(gauge: &AtomicU64, value: u64) {
+        gauge.store(value, Ordering::Relaxed);
+    }
+
+    /// Prometheus text exposition format
+    pub fn render(&self) -> String {
+        let metrics = [
+            ("reminder_queue_size", "gauge", &self.queued),
+            ("reminder_in_flight", "gauge", &self.in_flight),
+            ("reminder_delivered_total", "counter", &self.delivered_total),
+            ("reminder_failed_total", "counter", &self.failed_total),
+            ("reminder_retried_total", "counter", &self.retried_total),
+            (
+                "reminder_dead_lettered_total",
+                "counter",
+                &self.dead_lettered_total,
+            ),
+            (
+                "reminder_blocked_users_total",
+                "counter",
+                &self.blocked_users_total,
+            ),
+            ("reminder_skipped_total", "counter", &self.skipped_total),
+            ("reminder_held_total", "counter", &self.held_total),
+            ("reminder_cancelled_total", "counter", &self.cancelled_total),
+            (
+                "reminder_notifications_total",
+                "counter",
+                &self.notifications_total,
+            ),
+            ("reminder_refills_total", "counter", &self.refills_total),
+            ("reminder_overflow_total", "counter", &self.overflow_total),
+            ("reminder_last_lag_ms", "gauge", &self.last_lag_ms),
+        ];
+
+        let mut output = String::new();
+        for (name, kind, value) in metrics {
+            let _ = writeln!(output, "# TYPE {} {}", name, kind);
+            let _ = writeln!(output, "{} {}", name, value.load(Ordering::Relaxed));
+        }
+        output
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let metrics = SchedulerMetrics::default();
+        SchedulerMetrics::inc(&metrics.delivered_total);
+        SchedulerMetrics::inc(&metrics.delivered_total);
+        SchedulerMetrics::set(&metrics.queued, 7);
+
+        let text = metrics.render();
+        assert!(
+            text.contains("# TYPE reminder_delivered_total counter\nreminder_delivered_total 2\n")
+        );
+        assert!(text.contains("reminder_queue_size 7\n"));
+    }
+}
@@ -4,3 +4,8 @@ pub mod repository;
 pub mod service;
 pub mod scheduler;
 pub mod notifier;
+pub mod render;
+pub mod queue;
+pub mod metrics;
+pub mod calendar;
+pub mod channels;
@@ -1,6 +1,9 @@
 // Reminder models
 
-pub use crate::db::models::{NewReminder, Reminder};
+pub use crate::db::models::{
+    HistoryPage, NagPolicy, NewReminder, Reminder, ReminderDelivery, ReminderEdit, ReminderTarget,
+    SourceMessage, LATE_GRACE,
+};
 use serde::{Deserialize, Serialize};
 
 /// DTO for displaying reminder
@@ -1,55 +1,454 @@
 // Reminder notifier - sending notifications пользователям
 
-use crate::error::Result;
+use crate::bot::keyboards;
+use crate::config::{NotifyConfig, SmtpConfig};
+use crate::db::models::{Todo, User};
+use crate::error::{AppError, Result};
+use crate::reminder::channels::{is_private_ip, validate_webhook_url};
 use crate::reminder::models::Reminder;
+use crate::reminder::render::{render_reminder, SummaryPart};
+use crate::shared::smtp::{send_mail, Mail};
+use crate::shared::types::NotifyChannel;
+use crate::shared::utils::{format_datetime, truncate_text};
+use async_trait::async_trait;
+use chrono::Utc;
+use chrono_tz::Tz;
+use hyper::client::connect::dns::Name;
+use reqwest::dns::{Addrs, Resolve, Resolving};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
 use teloxide::prelude::*;
-use teloxide::types::ChatId;
+use teloxide::types::{ChatId, MessageId, ParseMode};
+
+/// Longest wait for a webhook endpoint
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Longest email subject taken from the reminder text, in characters
+const MAX_SUBJECT: usize = 60;
+
+/// Reminder to deliver, with what channels need to address and format it
+pub struct Notification<'a> {
+    pub reminder: &'a Reminder,
+    /// Linked task, if any
+    pub todo: Option<&'a Todo>,
+    pub owner: &'a User,
+    /// Quiet hours: without sound where the channel supports it
+    pub silent: bool,
+}
+
+/// Channel reminders can be delivered on
+#[async_trait]
+pub trait Notifier: Send + Sync {
+    fn channel(&self) -> NotifyChannel;
+
+    async fn notify(&self, notification: &Notification<'_>) -> Result<()>;
+}
+
+/// Notifiers the scheduler fans out to
+#[derive(Clone)]
+pub struct Notifiers {
+    telegram: ReminderNotifier,
+    others: Vec<Arc<dyn Notifier>>,
+}
+
+impl Notifiers {
+    /// Telegram only; webhooks and email are added with `with`
+    pub fn new(bot: Bot) -> Self {
+        Self {
+            telegram: ReminderNotifier::new(bot),
+            others: Vec::new(),
+        }
+    }
+
+    /// Add a notifier, replacing the one for the same channel
+    pub fn with(mut self, notifier: impl Notifier + 'static) -> Self {
+        self.others
+            .retain(|other| other.channel() != notifier.channel());
+        self.others.push(Arc::new(notifier));
+        self
+    }
+
+    /// Telegram is also used for summaries and escalations
+    pub fn telegram(&self) -> &ReminderNotifier {
+        &self.telegram
+    }
+
+    /// Deliver on one channel
+    pub async fn notify(
+        &self,
+        channel: NotifyChannel,
+        notification: &Notification<'_>,
+    ) -> Result<()> {
+        if channel == NotifyChannel::Telegram {
+            return self.telegram.notify(notification).await;
+        }
+        match self.others.iter().find(|other| other.channel() == channel) {
+            Some(notifier) => notifier.notify(notification).await,
+            None => Err(AppError::Notification(format!(
+                "The {} channel is not configured",
+                channel
+            ))),
+        }
+    }
+}
 
 /// Отправитель напоминаний
+#[derive(Clone)]
 pub struct ReminderNotifier {
     bot: Bot,
 }
 
+#[async_trait]
+impl Notifier for ReminderNotifier {
+    fn channel(&self) -> NotifyChannel {
+        NotifyChannel::Telegram
+    }
+
+    async fn notify(&self, notification: &Notification<'_>) -> Result<()> {
+        self.send_reminder(
+            notification.reminder,
+            notification.todo,
+            notification.owner.tz(),
+            notification.silent,
+        )
+        .await
+    }
+}
+
 impl ReminderNotifier {
     pub fn new(bot: Bot) -> Self {
         Self { bot }
     }
 
-    /// Отправить напоминание пользователю
-    pub async fn send_reminder(&self, reminder: &Reminder) -> Result<()> {
-        let chat_id = ChatId(reminder.user_id as i64);
+    /// Отправить напоминание в его чат (личный, группу или канал)
+    ///
+    /// `todo` is the linked task, if any: its title and a complete button
+    /// are added. `tz` is the owner's time zone and `silent` delivers it
+    /// without sound (quiet hours). In the chat it was set from, the
+    /// reminder replies to the command that created it.
+    pub async fn send_reminder(
+        &self,
+        reminder: &Reminder,
+        todo: Option<&Todo>,
+        tz: Tz,
+        silent: bool,
+    ) -> Result<()> {
+        let chat_id = ChatId(reminder.chat_id);
 
-        let message = self.format_reminder_message(reminder);
+        let message = render_reminder(reminder, todo, tz, Utc::now());
 
-        self.bot
+        let mut request = self
+            .bot
             .send_message(chat_id, message)
+            .parse_mode(ParseMode::MarkdownV2)
+            .disable_notification(silent);
+        if let Some(source) = reminder.source().filter(|s| s.chat_id == reminder.chat_id) {
+            // The command may have been deleted since
+            request = request
+                .reply_to_message_id(MessageId(source.message_id))
+                .allow_sending_without_reply(true);
+        }
+
+        request
+            .reply_markup(if reminder.is_nagging() {
+                keyboards::reminder_ack(reminder.id)
+            } else {
+                keyboards::reminder_actions(reminder.id, todo.map(|t| t.id))
+            })
+            .await
+            .map_err(|e| {
+                tracing::error!(
+                    "Failed to send reminder {} to chat {}: {}",
+                    reminder.id,
+                    reminder.chat_id,
+                    e
+                );
+                crate::error::AppError::from(e)
+            })?;
+
+        Ok(())
+    }
+
+    /// One message of a summary of reminders missed while the bot was down
+    /// (see `render_missed_summary`)
+    pub async fn send_missed_summary(
+        &self,
+        chat_id: ChatId,
+        part: &SummaryPart,
+        silent: bool,
+    ) -> Result<()> {
+        self.bot
+            .send_message(chat_id, &part.text)
+            .disable_notification(silent)
             .await
             .map_err(|e| {
-                tracing::error!("Failed to send reminder to user {}: {}", reminder.user_id, e);
-                crate::error::AppError::Telegram(e.to_string())
+                tracing::error!(
+                    "Failed to send missed reminders summary to chat {}: {}",
+                    chat_id,
+                    e
+                );
+                crate::error::AppError::from(e)
             })?;
 
         Ok(())
     }
 
-    /// Форматировать сообщение напоминания
-    fn format_reminder_message(&self, reminder: &Reminder) -> String {
-        let mut message = String::from("🔔 *Reminder!*\n\n");
+    /// Tell the escalation chat that `owner` did not acknowledge a reminder
+    pub async fn send_escalation(
+        &self,
+        reminder: &Reminder,
+        owner: &User,
+        chat_id: ChatId,
+    ) -> Result<()> {
+        let message = format!(
+            "🚨 {} has not acknowledged a reminder after {} notifications:\n\n{}",
+            owner.display_name(),
+            reminder.nag_max_repeats + 1,
+            reminder.message.as_deref().unwrap_or("You have a reminder!")
+        );
 
-        if let Some(ref msg) = reminder.message {
-            message.push_str(msg);
-        } else {
-            message.push_str("You have a reminder!");
-        }
+        self.bot.send_message(chat_id, message).await.map_err(|e| {
+            tracing::error!(
+                "Failed to escalate reminder {} to chat {}: {}",
+                reminder.id,
+                chat_id,
+                e
+            );
+            crate::error::AppError::from(e)
+        })?;
 
-        if reminder.todo_id.is_some() {
-            message.push_str(&format!("\n\n📝 Related to task #{}", reminder.todo_id.unwrap()));
+        Ok(())
+    }
+
+    /// Follow-up for the escalation chat once the owner acknowledges
+    pub async fn send_acknowledged(
+        &self,
+        reminder: &Reminder,
+        owner: &User,
+        chat_id: ChatId,
+    ) -> Result<()> {
+        let message = format!(
+            "✅ {} acknowledged the reminder: {}",
+            owner.display_name(),
+            reminder.message.as_deref().unwrap_or("You have a reminder!")
+        );
+
+        self.bot.send_message(chat_id, message).await?;
+        Ok(())
+    }
+}
+
+/// Email through the configured SMTP relay to the owner's address
+pub struct EmailNotifier {
+    smtp: SmtpConfig,
+}
+
+impl EmailNotifier {
+    pub fn new(smtp: SmtpConfig) -> Self {
+        Self { smtp }
+    }
+}
+
+#[async_trait]
+impl Notifier for EmailNotifier {
+    fn channel(&self) -> NotifyChannel {
+        NotifyChannel::Email
+    }
+
+    async fn notify(&self, notification: &Notification<'_>) -> Result<()> {
+        let to = notification.owner.email.as_deref().ok_or_else(|| {
+            AppError::Notification("No email address, set one with /notify email".to_string())
+        })?;
+        let (subject, body) = format_email(notification);
+
+        send_mail(
+            &self.smtp,
+            &Mail {
+                to,
+                subject: &subject,
+                body: &body,
+            },
+        )
+        .await
+    }
+}
+
+/// Subject and plain-text body of a reminder email
+fn format_email(notification: &Notification<'_>) -> (String, String) {
+    let reminder = notification.reminder;
+    let text = reminder.message.as_deref().unwrap_or("You have a reminder!");
+    let subject = format!("⏰ {}", truncate_text(text, MAX_SUBJECT));
+
+    let tz = notification.owner.tz();
+    let mut body = format!(
+        "{}\n\nDue: {}",
+        text,
+        format_datetime(&reminder.remind_at.with_timezone(&tz))
+    );
+    if let Some(todo) = notification.todo {
+        body.push_str(&format!("\nTask #{}: {}", todo.id, todo.title));
+    }
+    if let Some(pattern) = reminder.recurrence() {
+        body.push_str(&format!("\nRepeats {}", pattern.describe()));
+    }
+    body.push_str(&format!(
+        "\n\nReminder #{}. Manage your reminders in Telegram: /listreminders",
+        reminder.id
+    ));
+
+    (subject, body)
+}
+
+/// JSON POST to the owner's webhook URL; any 2xx answer counts as delivered
+///
+/// Unless private addresses are allowed (development), the URL is checked
+/// again before each call, names must resolve to public addresses only and
+/// redirects are not followed, so a webhook cannot reach the bot's network.
+pub struct WebhookNotifier {
+    client: reqwest::Client,
+    allow_private: bool,
+}
+
+impl WebhookNotifier {
+    pub fn new(config: &NotifyConfig) -> Result<Self> {
+        let allow_private = config.webhook_allow_private;
+        let mut builder = reqwest::Client::builder()
+            .timeout(WEBHOOK_TIMEOUT)
+            .user_agent(concat!("telegram-multitool-bot/", env!("CARGO_PKG_VERSION")))
+            .redirect(reqwest::redirect::Policy::none());
+        if !allow_private {
+            // A proxy would resolve the host itself
+            builder = builder.no_proxy().dns_resolver(Arc::new(PublicResolver));
         }
+        let client = builder.build().map_err(|e| {
+            AppError::Internal(format!("Failed to create the webhook client: {}", e))
+        })?;
+
+        Ok(Self {
+            client,
+            allow_private,
+        })
+    }
+}
+
+#[async_trait]
+impl Notifier for WebhookNotifier {
+    fn channel(&self) -> NotifyChannel {
+        NotifyChannel::Webhook
+    }
+
+    async fn notify(&self, notification: &Notification<'_>) -> Result<()> {
+        let url = notification.owner.webhook_url.as_deref().ok_or_else(|| {
+            AppError::Notification("No webhook URL, set one with /notify webhook".to_string())
+        })?;
+        // Addresses given as IPs never reach the resolver
+        validate_webhook_url(url, self.allow_private).map_err(|e| {
+            AppError::Notification(format!("Webhook URL rejected: {}", e.user_message()))
+        })?;
 
-        if reminder.is_recurring {
-            message.push_str("\n\n🔄 This is a recurring reminder");
+        let response = self
+            .client
+            .post(url)
+            .json(&webhook_payload(notification))
+            .send()
+            .await
+            .map_err(|e| AppError::Notification(format!("Webhook request failed: {}", e)))?;
+
+        let status = response.status();
+        if !status.is_success() {
+            return Err(AppError::Notification(format!(
+                "Webhook answered {}",
+                status
+            )));
         }
+        Ok(())
+    }
+}
+
+/// DNS for webhook calls that refuses names with a private address, also
+/// when they change after the URL was saved
+struct PublicResolver;
+
+impl Resolve for PublicResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let host = name.as_str().to_string();
+        Box::pin(async move {
+            let addresses: Vec<SocketAddr> =
+                tokio::net::lookup_host((host.as_str(), 0)).await?.collect();
+            if addresses.iter().any(|address| is_private_ip(address.ip())) {
+                return Err(format!("{} resolves to a private address", host).into());
+            }
+            Ok(Box::new(addresses.into_iter()) as Addrs)
+        })
+    }
+}
+
+/// Body of a webhook call
+fn webhook_payload(notification: &Notification<'_>) -> serde_json::Value {
+    let reminder = notification.reminder;
+    serde_json::json!({
+        "event": "reminder.due",
+        "reminder": {
+            "id": reminder.id,
+            "text": reminder.message,
+            "remind_at": reminder.remind_at,
+            "recurrence": reminder.recurrence().map(|pattern| pattern.describe()),
+            "urgent": reminder.is_urgent,
+            "attempt": reminder.attempts + 1,
+        },
+        "task": notification.todo.map(|todo| serde_json::json!({
+            "id": todo.id,
+            "title": todo.title,
+        })),
+        "user": {
+            "telegram_id": notification.owner.telegram_id,
+            "timezone": notification.owner.timezone,
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    #[tokio::test]
+    async fn test_public_resolver() {
+        let error = PublicResolver
+            .resolve(Name::from_str("localhost").unwrap())
+            .await
+            .err()
+            .unwrap();
+        assert_eq!(error.to_string(), "localhost resolves to a private address");
+    }
+
+    #[tokio::test]
+    async fn test_webhook_redirect_not_followed() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut buffer = [0; 4096];
+            let _ = stream.read(&mut buffer).await;
+            let _ = stream
+                .write_all(
+                    b"HTTP/1.1 302 Found\r\nLocation: http://169.254.169.254/\r\n\
+                      Content-Length: 0\r\nConnection: close\r\n\r\n",
+                )
+                .await;
+        });
+
+        let config = NotifyConfig {
+            smtp: None,
+            webhook_allow_private: true,
+        };
+        let notifier = WebhookNotifier::new(&config).unwrap();
+        let response = notifier.client.post(&url).send().await.unwrap();
 
-        message
+        // Answered with the redirect itself, which does not count as delivered
+        assert_eq!(response.status(), reqwest::StatusCode::FOUND);
     }
 }
@@ -0,0 +1,181 @@
+// Reminder queue - in-memory timer queue of the scheduler
+//
+// Min-heap ordered by due time plus a map with the current due time of
+// every queued reminder. Edits and cancellations only touch the map; heap
+// entries that no longer match it are skipped when popped and compacted
+// away once they outnumber live ones.
+
+use crate::shared::types::{DbId, Timestamp};
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+
+/// Bounded queue of reminders due soon
+pub struct ReminderQueue {
+    heap: BinaryHeap<Reverse<(Timestamp, DbId)>>,
+    due: HashMap<DbId, Timestamp>,
+    capacity: usize,
+}
+
+impl ReminderQueue {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            heap: BinaryHeap::new(),
+            due: HashMap::new(),
+            capacity,
+        }
+    }
+
+    /// Add reminder or move it to a new due time
+    ///
+    /// Returns `false` if the queue is full; the reminder stays in the
+    /// database and is picked up by a later refill.
+    pub fn upsert(&mut self, id: DbId, at: Timestamp) -> bool {
+        match self.due.get(&id) {
+            Some(current) if *current == at => return true,
+            Some(_) => {}
+            None if self.due.len() >= self.capacity => return false,
+            None => {}
+        }
+
+        self.due.insert(id, at);
+        self.heap.push(Reverse((at, id)));
+        self.compact();
+        true
+    }
+
+    /// Drop reminder (cancelled, sent or moved out of the horizon)
+    pub fn remove(&mut self, id: DbId) -> bool {
+        let removed = self.due.remove(&id).is_some();
+        self.compact();
+        removed
+    }
+
+    /// Due time of the earliest live reminder
+    pub fn next_due(&mut self) -> Option<Timestamp> {
+        self.skip_stale();
+        self.heap.peek().map(|Reverse((at, _))| *at)
+    }
+
+    /// Take all reminders due at or before `now` with their due times,
+    /// earliest first
+    pub fn pop_due(&mut self, now: Timestamp) -> Vec<(DbId, Timestamp)> {
+        let mut due = Vec::new();
+        while let Some(at) = self.next_due() {
+            if at > now {
+                break;
+            }
+            if let Some(Reverse((at, id))) = self.heap.pop() {
+                self.due.remove(&id);
+                due.push((id, at));
+            }
+        }
+        due
+    }
+
+    pub fn contains(&self, id: DbId) -> bool {
+        self.due.contains_key(&id)
+    }
+
+    pub fn len(&self) -> usize {
+        self.due.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.due.is_empty()
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.due.len() >= self.capacity
+    }
+
+    pub fn clear(&mut self) {
+        self.heap.clear();
+        self.due.clear();
+    }
+
+    /// Pop heap entries that were edited or removed since they were pushed
+    fn skip_stale(&mut self) {
+        while let Some(Reverse((at, id))) = self.heap.peek() {
+            if self.due.get(id) == Some(at) {
+                break;
+            }
+            self.heap.pop();
+        }
+    }
+
+    /// Rebuild the heap when stale entries dominate, keeping memory
+    /// proportional to the live reminders
+    fn compact(&mut self) {
+        if self.heap.len() <= 2 * self.due.len() + 64 {
+            return;
+        }
+        self.heap = self
+            .due
+            .iter()
+            .map(|(id, at)| Reverse((*at, *id)))
+            .collect();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, TimeZone, Utc};
+
+    fn t(minutes: i64) -> Timestamp {
+        Utc.with_ymd_and_hms(2026, 10, 19, 12, 0, 0).unwrap() + Duration::minutes(minutes)
+    }
+
+    #[test]
+    fn test_pops_in_due_order() {
+        let mut queue = ReminderQueue::new(10);
+        queue.upsert(1, t(5));
+        queue.upsert(2, t(1));
+        queue.upsert(3, t(3));
+
+        assert_eq!(queue.next_due(), Some(t(1)));
+        assert_eq!(queue.pop_due(t(3)), vec![(2, t(1)), (3, t(3))]);
+        assert!(queue.pop_due(t(3)).is_empty());
+        assert_eq!(queue.len(), 1);
+    }
+
+    #[test]
+    fn test_edit_and_cancel() {
+        let mut queue = ReminderQueue::new(10);
+        queue.upsert(1, t(1));
+        queue.upsert(2, t(2));
+
+        // Moved later: the old heap entry is ignored
+        queue.upsert(1, t(10));
+        assert_eq!(queue.next_due(), Some(t(2)));
+
+        // Cancelled: never popped
+        assert!(queue.remove(2));
+        assert!(!queue.remove(2));
+        assert!(queue.pop_due(t(5)).is_empty());
+        assert_eq!(queue.pop_due(t(10)), vec![(1, t(10))]);
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn test_capacity() {
+        let mut queue = ReminderQueue::new(2);
+        assert!(queue.upsert(1, t(1)));
+        assert!(queue.upsert(2, t(2)));
+        assert!(queue.is_full());
+        assert!(!queue.upsert(3, t(3)));
+        // Editing a queued reminder is always possible
+        assert!(queue.upsert(2, t(0)));
+        assert_eq!(queue.pop_due(t(5)), vec![(2, t(0)), (1, t(1))]);
+    }
+
+    #[test]
+    fn test_stale_entries_are_compacted() {
+        let mut queue = ReminderQueue::new(10);
+        for minute in 0..1000 {
+            queue.upsert(1, t(minute));
+        }
+        assert!(queue.heap.len() <= 2 * queue.len() + 64);
+        assert_eq!(queue.pop_due(t(1000)), vec![(1, t(999))]);
+    }
+}
@@ -0,0 +1,324 @@
+// Reminder rendering - the Telegram message a reminder is delivered as
+//
+// Built with shared::markdown and sent as MarkdownV2: the reminder and task
+// text are escaped, so whatever the user typed shows up as typed. Summaries
+// of missed reminders are plain text, split to fit Telegram's message limit.
+
+use crate::db::models::{Reminder, Todo};
+use crate::shared::markdown::Markdown;
+use crate::shared::types::Timestamp;
+use crate::shared::utils::format_datetime;
+use crate::todo::handlers::format_minutes;
+use chrono_tz::Tz;
+use std::ops::Range;
+
+/// Longest Telegram message, in characters
+const MAX_MESSAGE_CHARS: usize = 4096;
+
+/// One message of a missed reminders summary
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SummaryPart {
+    pub text: String,
+    /// Indices of the reminders it lists
+    pub reminders: Range<usize>,
+}
+
+/// Message for a due reminder
+///
+/// `todo` is the linked task, if loaded; `tz` is the owner's time zone.
+pub fn render_reminder(reminder: &Reminder, todo: Option<&Todo>, tz: Tz, now: Timestamp) -> String {
+    let mut md = Markdown::new();
+    md.text("🔔 ").bold("Reminder!");
+
+    if let Some(mentions) = reminder.mention_list() {
+        md.newline().text(&mentions);
+    }
+    match reminder.message.as_deref() {
+        Some(text) => md.quote(text),
+        None => md.newline().italic("You have a reminder!"),
+    };
+
+    if let Some(late) = reminder.late_by(now) {
+        md.paragraph().text(&format!(
+            "⏰ Late by {}",
+            format_minutes(late.num_minutes())
+        ));
+    }
+
+    if let Some(todo) = todo {
+        md.paragraph()
+            .text("📝 ")
+            .bold(&todo.title)
+            .text(&format!(" (task #{})", todo.id));
+        if let Some(due) = todo.due_date {
+            md.newline().text(&format!(
+                "📅 Due {}",
+                format_datetime(&due.with_timezone(&tz))
+            ));
+        }
+    } else if let Some(todo_id) = reminder.todo_id {
+        md.paragraph()
+            .text(&format!("📝 Related to task #{}", todo_id));
+    }
+    if let Some(offset) = reminder.due_offset_minutes {
+        md.newline()
+            .text(&format!("⏳ Due in {}", format_minutes(offset.into())));
+    }
+
+    if reminder.is_recurring {
+        md.paragraph();
+        match reminder.recurrence() {
+            Some(pattern) => md.text(&format!("🔄 Repeats {}", pattern.describe())),
+            None => md.text("🔄 This is a recurring reminder"),
+        };
+    }
+    if reminder.is_nagging() {
+        md.paragraph().text(&format!(
+            "🔁 Ping {} of {}, press Acknowledge to stop",
+            reminder.nag_count + 1,
+            reminder.nag_max_repeats + 1
+        ));
+    }
+    if reminder.snooze_count > 0 {
+        md.paragraph()
+            .text(&format!("😴 Snoozed {} time(s)", reminder.snooze_count));
+    }
+
+    // In the chat it was set from, the reminder replies to the command instead
+    let link = reminder
+        .source()
+        .filter(|source| source.chat_id != reminder.chat_id)
+        .and_then(|source| source.url());
+    if let Some(url) = link {
+        md.paragraph().text("🔗 ").link("Original message", &url);
+    }
+
+    md.finish()
+}
+
+/// Summary of reminders missed while the bot was offline, each with its
+/// owner's time zone
+///
+/// Split into as many messages as needed, each under MAX_MESSAGE_CHARS;
+/// reminders keep their order and are never split across messages.
+pub fn render_missed_summary(reminders: &[(Reminder, Tz)]) -> Vec<SummaryPart> {
+    let mut parts = Vec::new();
+    let mut text = format!(
+        "📬 You missed {} reminders while the bot was offline:\n",
+        reminders.len()
+    );
+    let mut start = 0;
+
+    for (index, (reminder, tz)) in reminders.iter().enumerate() {
+        let mut line = missed_line(reminder, *tz);
+        if index > start && text.chars().count() + line.chars().count() > MAX_MESSAGE_CHARS {
+            parts.push(SummaryPart {
+                text,
+                reminders: start..index,
+            });
+            text = "📬 More missed reminders:\n".to_string();
+            start = index;
+        }
+
+        // A single line longer than a message is cut
+        let room = MAX_MESSAGE_CHARS - text.chars().count();
+        if line.chars().count() > room {
+            line = line.chars().take(room - 1).collect();
+            line.push('…');
+        }
+        text.push_str(&line);
+    }
+
+    parts.push(SummaryPart {
+        text,
+        reminders: start..reminders.len(),
+    });
+    parts
+}
+
+/// Summary line of one missed reminder
+fn missed_line(reminder: &Reminder, tz: Tz) -> String {
+    let mut line = format!(
+        "\n• {} — ",
+        reminder.remind_at.with_timezone(&tz).format("%d.%m %H:%M")
+    );
+    if let Some(mentions) = reminder.mention_list() {
+        line.push_str(&mentions);
+        line.push(' ');
+    }
+    line.push_str(
+        reminder
+            .message
+            .as_deref()
+            .unwrap_or("You have a reminder!"),
+    );
+    if let Some(pattern) = reminder.recurrence() {
+        line.push_str(&format!(" (repeats {})", pattern.describe()));
+    }
+    line
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shared::types::TodoStatus;
+    use chrono::{Duration, TimeZone, Utc};
+
+    fn at(h: u32, mi: u32) -> Timestamp {
+        Utc.with_ymd_and_hms(2026, 10, 19, h, mi, 0).unwrap()
+    }
+
+    fn reminder(message: Option<&str>) -> Reminder {
+        Reminder {
+            id: 7,
+            todo_id: None,
+            user_id: 1,
+            remind_at: at(9, 0),
+            message: message.map(str::to_string),
+            is_sent: false,
+            sent_at: None,
+            is_recurring: false,
+            recurrence_pattern: None,
+            created_at: at(8, 0),
+            snooze_count: 0,
+            scheduled_at: None,
+            nag_interval_minutes: None,
+            nag_max_repeats: 0,
+            nag_count: 0,
+            acknowledged_at: None,
+            escalate_to: None,
+            escalated_at: None,
+            due_offset_minutes: None,
+            attempts: 0,
+            last_error: None,
+            retry_at: None,
+            dead_lettered_at: None,
+            paused_at: None,
+            chat_id: 100,
+            mentions: Vec::new(),
+            is_urgent: false,
+            channels: None,
+            source_chat_id: None,
+            source_message_id: None,
+        }
+    }
+
+    #[test]
+    fn test_render_escapes_text() {
+        let moscow: Tz = "Europe/Moscow".parse().unwrap();
+        let rendered = render_reminder(
+            &reminder(Some("Pay *rent* (1.5k)\n_today_!")),
+            None,
+            moscow,
+            at(9, 0),
+        );
+
+        assert_eq!(
+            rendered,
+            "🔔 *Reminder\\!*\n>Pay \\*rent\\* \\(1\\.5k\\)\n>\\_today\\_\\!"
+        );
+    }
+
+    #[test]
+    fn test_render_task_and_source() {
+        let moscow: Tz = "Europe/Moscow".parse().unwrap();
+        let todo = Todo {
+            id: 3,
+            user_id: 1,
+            title: "Q3 report [draft]".to_string(),
+            description: None,
+            status: TodoStatus::Pending,
+            priority: 2,
+            created_at: at(8, 0),
+            updated_at: at(8, 0),
+            completed_at: None,
+            due_date: Some(at(15, 0)),
+            tags: Vec::new(),
+            estimate_minutes: None,
+        };
+        let reminder = Reminder {
+            todo_id: Some(3),
+            mentions: vec!["alice".to_string()],
+            snooze_count: 1,
+            source_chat_id: Some(-1001234567890),
+            source_message_id: Some(42),
+            ..reminder(None)
+        };
+
+        let rendered = render_reminder(
+            &reminder,
+            Some(&todo),
+            moscow,
+            at(9, 0) + Duration::hours(1),
+        );
+
+        assert_eq!(
+            rendered,
+            "🔔 *Reminder\\!*\n@alice\n_You have a reminder\\!_\n\n\
+             ⏰ Late by 1h\n\n\
+             📝 *Q3 report \\[draft\\]* \\(task \\#3\\)\n\
+             📅 Due 2026\\-10\\-19 18:00 MSK\n\n\
+             😴 Snoozed 1 time\\(s\\)\n\n\
+             🔗 [Original message](https://t.me/c/1234567890/42)"
+        );
+
+        // Set in the chat it fires in: answered as a reply, no link
+        let here = Reminder {
+            source_chat_id: Some(100),
+            ..reminder
+        };
+        assert!(!render_reminder(&here, Some(&todo), moscow, at(9, 0)).contains("🔗"));
+    }
+
+    #[test]
+    fn test_render_missed_summary() {
+        let moscow: Tz = "Europe/Moscow".parse().unwrap();
+        let few = vec![
+            (reminder(Some("Call mom")), moscow),
+            (reminder(None), chrono_tz::UTC),
+        ];
+
+        assert_eq!(
+            render_missed_summary(&few),
+            vec![SummaryPart {
+                text: "📬 You missed 2 reminders while the bot was offline:\n\
+                       \n• 19.10 12:00 — Call mom\
+                       \n• 19.10 09:00 — You have a reminder!"
+                    .to_string(),
+                reminders: 0..2,
+            }]
+        );
+
+        // 500 characters each: about eight per message
+        let text = "x".repeat(500);
+        let many: Vec<_> = (0..50)
+            .map(|_| (reminder(Some(&text)), chrono_tz::UTC))
+            .collect();
+        let parts = render_missed_summary(&many);
+
+        assert!(parts.len() > 1);
+        assert_eq!(parts[0].reminders.start, 0);
+        assert_eq!(parts.last().unwrap().reminders.end, many.len());
+        for (part, next) in parts.iter().zip(&parts[1..]) {
+            assert_eq!(part.reminders.end, next.reminders.start);
+        }
+        for part in &parts {
+            assert!(part.text.chars().count() <= MAX_MESSAGE_CHARS);
+            assert_eq!(part.text.matches('•').count(), part.reminders.len());
+        }
+        assert!(parts[1].text.starts_with("📬 More missed reminders:"));
+
+        // One line over the limit is cut
+        let huge = vec![(
+            Reminder {
+                mentions: vec!["a".repeat(30); 200],
+                ..reminder(None)
+            },
+            chrono_tz::UTC,
+        )];
+        let parts = render_missed_summary(&huge);
+        assert_eq!(parts.len(), 1);
+        assert_eq!(parts[0].text.chars().count(), MAX_MESSAGE_CHARS);
+        assert!(parts[0].text.ends_with('…'));
+    }
+}
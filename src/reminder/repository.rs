@@ -1,10 +1,24 @@
 // Reminder repository
 
-use crate::db::models::{NewReminder, Reminder};
+use crate::db::models::{NewReminder, Reminder, ReminderDelivery, ReminderEdit, User};
 use crate::error::{not_found, Result};
-use crate::shared::types::{DbId, Timestamp};
+use crate::shared::timezone::user_timezone;
+use crate::shared::types::{DbId, DeliveryStatus, NotifyChannel, Timestamp};
+use chrono::{Duration, Utc};
+use chrono_tz::Tz;
 use sqlx::PgPool;
 
+/// Outcome of claiming a reminder for delivery
+#[derive(Debug)]
+pub enum Claim {
+    /// Leased to this worker: deliver it
+    Claimed(Box<Reminder>),
+    /// Another worker holds it until the given time
+    Leased(Timestamp),
+    /// Deleted, already sent, paused or moved to the future
+    Gone,
+}
+
 #[derive(Clone)]
 pub struct ReminderRepository {
     pool: PgPool,
@@ -17,10 +31,18 @@ impl ReminderRepository {
 
     /// Созyesть новое напоминание
     pub async fn create(&self, new_reminder: NewReminder) -> Result<Reminder> {
+        let nag = new_reminder.nag;
         let reminder = sqlx::query_as::<_, Reminder>(
             r#"
-            INSERT INTO reminders (user_id, todo_id, remind_at, message, is_recurring, recurrence_pattern)
-            VALUES ($1, $2, $3, $4, $5, $6)
+            INSERT INTO reminders (
+                user_id, todo_id, remind_at, message, is_recurring, recurrence_pattern,
+                nag_interval_minutes, nag_max_repeats, escalate_to, due_offset_minutes,
+                chat_id, mentions, is_urgent, channels, source_chat_id, source_message_id
+            )
+            VALUES (
+                $1, $2, $3, $4, $5, $6, $7, $8, $9, $10,
+                COALESCE($11, (SELECT telegram_id FROM users WHERE id = $1)), $12, $13, $14, $15, $16
+            )
             RETURNING *
             "#,
         )
@@ -30,6 +52,16 @@ impl ReminderRepository {
         .bind(&new_reminder.message)
         .bind(new_reminder.is_recurring)
         .bind(&new_reminder.recurrence_pattern)
+        .bind(nag.map(|n| n.interval_minutes))
+        .bind(nag.map_or(0, |n| n.max_repeats))
+        .bind(nag.and_then(|n| n.escalate_to))
+        .bind(new_reminder.due_offset_minutes)
+        .bind(new_reminder.target.chat_id)
+        .bind(&new_reminder.target.mentions)
+        .bind(new_reminder.target.urgent)
+        .bind(new_reminder.target.channels.as_deref().map(NotifyChannel::names))
+        .bind(new_reminder.target.source.map(|source| source.chat_id))
+        .bind(new_reminder.target.source.map(|source| source.message_id))
         .fetch_one(&self.pool)
         .await?;
 
@@ -48,39 +80,530 @@ impl ReminderRepository {
         Ok(reminder)
     }
 
-    /// Get all unsent reminders that are due to be sent
-    pub async fn get_pending_reminders(&self, before: Timestamp) -> Result<Vec<Reminder>> {
-        let reminders = sqlx::query_as::<_, Reminder>(
+    /// Time zone of the reminder owner (recurrences follow local time)
+    pub async fn find_owner_timezone(&self, user_id: DbId) -> Result<Tz> {
+        let name: String = sqlx::query_scalar("SELECT timezone FROM users WHERE id = $1")
+            .bind(user_id)
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(user_timezone(&name))
+    }
+
+    /// Owner of a reminder (escalation messages mention them)
+    pub async fn find_owner(&self, user_id: DbId) -> Result<User> {
+        let user = sqlx::query_as::<_, User>("SELECT * FROM users WHERE id = $1")
+            .bind(user_id)
+            .fetch_optional(&self.pool)
+            .await?
+            .ok_or_else(|| not_found(format!("User {} not found", user_id)))?;
+
+        Ok(user)
+    }
+
+    /// Claim a due reminder for delivery by `worker` for `lease`
+    ///
+    /// Only one worker holds a reminder at a time: rows locked by a concurrent
+    /// claim are skipped, and a lease blocks other workers until it expires
+    /// (the holder crashed) or the delivery is recorded.
+    pub async fn claim(&self, id: DbId, worker: &str, lease: Duration) -> Result<Claim> {
+        let claimed = sqlx::query_as::<_, Reminder>(
             r#"
-            SELECT * FROM reminders
-            WHERE is_sent = FALSE AND remind_at <= $1
-            ORDER BY remind_at ASC
-            LIMIT 100
+            UPDATE reminders
+            SET locked_by = $2, locked_until = NOW() + make_interval(secs => $3)
+            WHERE id = (
+                SELECT id FROM reminders
+                WHERE id = $1
+                  AND is_sent = FALSE
+                  AND dead_lettered_at IS NULL
+                  AND paused_at IS NULL
+                  AND COALESCE(retry_at, remind_at) <= NOW()
+                  AND (locked_until IS NULL OR locked_until <= NOW())
+                FOR UPDATE SKIP LOCKED
+            )
+            RETURNING *
+            "#,
+        )
+        .bind(id)
+        .bind(worker)
+        .bind(lease.num_seconds() as f64)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        if let Some(reminder) = claimed {
+            return Ok(Claim::Claimed(Box::new(reminder)));
+        }
+
+        // Why not: gone, not due, or held by another worker
+        let state = sqlx::query_as::<_, (bool, bool, Option<Timestamp>)>(
+            r#"
+            SELECT COALESCE(
+                       is_sent = FALSE
+                       AND dead_lettered_at IS NULL
+                       AND paused_at IS NULL
+                       AND COALESCE(retry_at, remind_at) <= NOW(),
+                       FALSE
+                   ),
+                   COALESCE(locked_until > NOW(), FALSE),
+                   locked_until
+            FROM reminders WHERE id = $1
+            "#,
+        )
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(match state {
+            Some((true, true, Some(until))) => Claim::Leased(until),
+            // Being claimed right now: the lease is visible shortly
+            Some((true, _, _)) => Claim::Leased(Utc::now() + lease),
+            _ => Claim::Gone,
+        })
+    }
+
+    /// Extend the lease `worker` holds on reminders `ids`
+    ///
+    /// Returns how many are still leased to it; finished ones are not.
+    pub async fn renew(&self, ids: &[DbId], worker: &str, lease: Duration) -> Result<u64> {
+        let renewed = sqlx::query(
+            r#"
+            UPDATE reminders SET locked_until = NOW() + make_interval(secs => $3)
+            WHERE id = ANY($1) AND locked_by = $2
+            "#,
+        )
+        .bind(ids)
+        .bind(worker)
+        .bind(lease.num_seconds() as f64)
+        .execute(&self.pool)
+        .await?
+        .rows_affected();
+
+        Ok(renewed)
+    }
+
+    /// IDs and next delivery times of deliverable reminders due up to
+    /// `before`, earliest first
+    pub async fn find_pending_schedule(
+        &self,
+        before: Timestamp,
+        limit: i64,
+    ) -> Result<Vec<(DbId, Timestamp)>> {
+        let schedule = sqlx::query_as::<_, (DbId, Timestamp)>(
+            r#"
+            SELECT id, COALESCE(retry_at, remind_at) AS due_at FROM reminders
+            WHERE is_sent = FALSE
+              AND dead_lettered_at IS NULL
+              AND paused_at IS NULL
+              AND COALESCE(retry_at, remind_at) <= $1
+            ORDER BY due_at ASC
+            LIMIT $2
             "#,
         )
         .bind(before)
+        .bind(limit)
         .fetch_all(&self.pool)
         .await?;
 
-        tracing::debug!("Found {} pending reminders", reminders.len());
-        Ok(reminders)
+        Ok(schedule)
     }
 
     /// Mark reminder as sent
-    pub async fn mark_as_sent(&self, id: DbId) -> Result<()> {
-        sqlx::query(
+    ///
+    /// With a `worker`, only while it holds the lease; returns whether the
+    /// reminder was updated.
+    pub async fn mark_as_sent(&self, id: DbId, worker: Option<&str>) -> Result<bool> {
+        let updated = sqlx::query(
+            r#"
+            UPDATE reminders
+            SET is_sent = TRUE, sent_at = CURRENT_TIMESTAMP, retry_at = NULL,
+                locked_by = NULL, locked_until = NULL
+            WHERE id = $1 AND ($2::text IS NULL OR locked_by = $2)
+            "#,
+        )
+        .bind(id)
+        .bind(worker)
+        .execute(&self.pool)
+        .await?
+        .rows_affected();
+
+        tracing::debug!("Marked reminder {} as sent", id);
+        Ok(updated == 1)
+    }
+
+    /// Move a recurring reminder to its next occurrence
+    ///
+    /// With a `worker`, only while it holds the lease; returns whether the
+    /// reminder was updated.
+    pub async fn reschedule_next(
+        &self,
+        id: DbId,
+        next_at: Timestamp,
+        worker: Option<&str>,
+    ) -> Result<bool> {
+        let updated = sqlx::query(
             r#"
             UPDATE reminders
-            SET is_sent = TRUE, sent_at = CURRENT_TIMESTAMP
+            SET remind_at = $2, is_sent = FALSE, sent_at = CURRENT_TIMESTAMP, scheduled_at = NULL,
+                attempts = 0, retry_at = NULL, locked_by = NULL, locked_until = NULL
+            WHERE id = $1 AND ($3::text IS NULL OR locked_by = $3)
+            "#,
+        )
+        .bind(id)
+        .bind(next_at)
+        .bind(worker)
+        .execute(&self.pool)
+        .await?
+        .rows_affected();
+
+        tracing::debug!("Rescheduled recurring reminder {} to {}", id, next_at);
+        Ok(updated == 1)
+    }
+
+    /// Snooze: move the same reminder to `until` and count the snooze
+    pub async fn snooze(&self, id: DbId, until: Timestamp) -> Result<Reminder> {
+        let reminder = sqlx::query_as::<_, Reminder>(
+            r#"
+            UPDATE reminders
+            SET remind_at = $2,
+                is_sent = FALSE,
+                snooze_count = snooze_count + 1,
+                scheduled_at = CASE WHEN is_recurring THEN COALESCE(scheduled_at, remind_at) END,
+                attempts = 0,
+                retry_at = NULL,
+                dead_lettered_at = NULL,
+                locked_by = NULL,
+                locked_until = NULL
             WHERE id = $1
+            RETURNING *
+            "#,
+        )
+        .bind(id)
+        .bind(until)
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| not_found(format!("Reminder {} not found", id)))?;
+
+        tracing::debug!("Snoozed reminder {} until {}", id, until);
+        Ok(reminder)
+    }
+
+    /// Change time, text or recurrence of a pending reminder
+    ///
+    /// A new time starts over: failed attempts and insistent repeats are
+    /// reset, and the NOTIFY trigger moves it in every scheduler's queue.
+    /// Returns `None` if it was sent meanwhile or is being delivered.
+    pub async fn edit(&self, id: DbId, edit: &ReminderEdit) -> Result<Option<Reminder>> {
+        let rescheduled = edit.remind_at.is_some();
+        let recurrence = edit.recurrence.as_ref();
+
+        let reminder = sqlx::query_as::<_, Reminder>(
+            r#"
+            UPDATE reminders
+            SET remind_at = COALESCE($2, remind_at),
+                message = COALESCE($3, message),
+                is_recurring = CASE WHEN $4 THEN $5::text IS NOT NULL ELSE is_recurring END,
+                recurrence_pattern = CASE WHEN $4 THEN $5 ELSE recurrence_pattern END,
+                scheduled_at = CASE WHEN $6 THEN NULL ELSE scheduled_at END,
+                nag_count = CASE WHEN $6 THEN 0 ELSE nag_count END,
+                escalated_at = CASE WHEN $6 THEN NULL ELSE escalated_at END,
+                attempts = CASE WHEN $6 THEN 0 ELSE attempts END,
+                retry_at = CASE WHEN $6 THEN NULL ELSE retry_at END,
+                last_error = CASE WHEN $6 THEN NULL ELSE last_error END,
+                dead_lettered_at = CASE WHEN $6 THEN NULL ELSE dead_lettered_at END
+            WHERE id = $1 AND is_sent = FALSE
+              AND (locked_until IS NULL OR locked_until < NOW())
+            RETURNING *
+            "#,
+        )
+        .bind(id)
+        .bind(edit.remind_at)
+        .bind(&edit.message)
+        .bind(recurrence.is_some())
+        .bind(recurrence.and_then(|pattern| pattern.as_ref().map(|p| p.to_string())))
+        .bind(rescheduled)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        tracing::debug!("Edited reminder {}: {:?}", id, edit);
+        Ok(reminder)
+    }
+
+    /// Hold delivery until the owner's quiet hours end; not a failed attempt
+    ///
+    /// Returns `false` if `worker` no longer holds the lease.
+    pub async fn hold(&self, id: DbId, worker: &str, until: Timestamp) -> Result<bool> {
+        let updated = sqlx::query(
+            r#"
+            UPDATE reminders
+            SET retry_at = $3, last_error = NULL,
+                locked_by = NULL, locked_until = NULL
+            WHERE id = $1 AND locked_by = $2 AND is_sent = FALSE
             "#,
         )
         .bind(id)
+        .bind(worker)
+        .bind(until)
+        .execute(&self.pool)
+        .await?
+        .rows_affected();
+
+        tracing::debug!("Reminder {} held until {}", id, until);
+        Ok(updated == 1)
+    }
+
+    /// Deliver reminders held for quiet hours now (settings changed)
+    pub async fn release_held(&self, user_id: DbId) -> Result<u64> {
+        let result = sqlx::query(
+            r#"
+            UPDATE reminders
+            SET retry_at = NOW()
+            WHERE user_id = $1 AND is_sent = FALSE
+              AND retry_at > NOW() AND last_error IS NULL
+            "#,
+        )
+        .bind(user_id)
         .execute(&self.pool)
         .await?;
 
-        tracing::debug!("Marked reminder {} as sent", id);
-        Ok(())
+        Ok(result.rows_affected())
+    }
+
+    /// Try a failed delivery again at `at`
+    ///
+    /// Returns `false` if `worker` no longer holds the lease.
+    pub async fn schedule_retry(
+        &self,
+        id: DbId,
+        worker: &str,
+        at: Timestamp,
+        attempts: i32,
+        error: &str,
+    ) -> Result<bool> {
+        let updated = sqlx::query(
+            r#"
+            UPDATE reminders
+            SET retry_at = $3, attempts = $4, last_error = $5,
+                locked_by = NULL, locked_until = NULL
+            WHERE id = $1 AND locked_by = $2 AND is_sent = FALSE
+            "#,
+        )
+        .bind(id)
+        .bind(worker)
+        .bind(at)
+        .bind(attempts)
+        .bind(error)
+        .execute(&self.pool)
+        .await?
+        .rows_affected();
+
+        tracing::debug!("Reminder {} retries at {} (attempt {})", id, at, attempts);
+        Ok(updated == 1)
+    }
+
+    /// Give up delivering a reminder; it stays listed but is never sent
+    ///
+    /// Returns `false` if `worker` no longer holds the lease.
+    pub async fn dead_letter(
+        &self,
+        id: DbId,
+        worker: &str,
+        attempts: i32,
+        error: &str,
+    ) -> Result<bool> {
+        let updated = sqlx::query(
+            r#"
+            UPDATE reminders
+            SET dead_lettered_at = CURRENT_TIMESTAMP, attempts = $3, last_error = $4,
+                retry_at = NULL, locked_by = NULL, locked_until = NULL
+            WHERE id = $1 AND locked_by = $2 AND is_sent = FALSE
+            "#,
+        )
+        .bind(id)
+        .bind(worker)
+        .bind(attempts)
+        .bind(error)
+        .execute(&self.pool)
+        .await?
+        .rows_affected();
+
+        tracing::debug!("Reminder {} dead-lettered after {} attempts", id, attempts);
+        Ok(updated == 1)
+    }
+
+    /// The user blocked the bot: mark them and pause their pending reminders
+    /// (in every chat: they cannot manage them meanwhile)
+    ///
+    /// Returns the number of paused reminders.
+    pub async fn block_user(&self, user_id: DbId) -> Result<u64> {
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query(
+            "UPDATE users SET is_blocked = TRUE, blocked_at = CURRENT_TIMESTAMP WHERE id = $1",
+        )
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+
+        let paused = sqlx::query(
+            r#"
+            UPDATE reminders
+            SET paused_at = CURRENT_TIMESTAMP, locked_by = NULL, locked_until = NULL
+            WHERE user_id = $1 AND is_sent = FALSE AND paused_at IS NULL
+            "#,
+        )
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+
+        tx.commit().await?;
+
+        tracing::debug!("User {} blocked the bot, paused {} reminders", user_id, paused);
+        Ok(paused)
+    }
+
+    /// The bot was removed from a group or channel: pause reminders for it
+    ///
+    /// Returns the number of paused reminders.
+    pub async fn pause_chat(&self, chat_id: i64) -> Result<u64> {
+        let paused = sqlx::query(
+            r#"
+            UPDATE reminders
+            SET paused_at = CURRENT_TIMESTAMP, locked_by = NULL, locked_until = NULL
+            WHERE chat_id = $1 AND is_sent = FALSE AND paused_at IS NULL
+            "#,
+        )
+        .bind(chat_id)
+        .execute(&self.pool)
+        .await?
+        .rows_affected();
+
+        tracing::debug!("Chat {} is unavailable, paused {} reminders", chat_id, paused);
+        Ok(paused)
+    }
+
+    /// The bot is back in a group or channel: resume its paused reminders
+    pub async fn resume_chat(&self, chat_id: i64) -> Result<u64> {
+        let resumed = sqlx::query(
+            r#"
+            UPDATE reminders
+            SET paused_at = NULL, attempts = 0, retry_at = NULL
+            WHERE chat_id = $1 AND paused_at IS NOT NULL
+            "#,
+        )
+        .bind(chat_id)
+        .execute(&self.pool)
+        .await?
+        .rows_affected();
+
+        Ok(resumed)
+    }
+
+    /// The user is back: clear the block and resume paused reminders
+    ///
+    /// Returns the number of resumed reminders.
+    pub async fn unblock_user(&self, user_id: DbId) -> Result<u64> {
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query("UPDATE users SET is_blocked = FALSE, blocked_at = NULL WHERE id = $1")
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await?;
+
+        let resumed = sqlx::query(
+            r#"
+            UPDATE reminders
+            SET paused_at = NULL, attempts = 0, retry_at = NULL
+            WHERE user_id = $1 AND paused_at IS NOT NULL
+            "#,
+        )
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+
+        tx.commit().await?;
+
+        Ok(resumed)
+    }
+
+    /// Ping an insistent reminder again at `at`, unless it was acknowledged
+    ///
+    /// Returns `false` if it was not updated: acknowledged, or `worker` no
+    /// longer holds the lease.
+    pub async fn schedule_nag(&self, id: DbId, worker: &str, at: Timestamp) -> Result<bool> {
+        let updated = sqlx::query(
+            r#"
+            UPDATE reminders
+            SET remind_at = $3, is_sent = FALSE, sent_at = CURRENT_TIMESTAMP, nag_count = nag_count + 1,
+                attempts = 0, retry_at = NULL, locked_by = NULL, locked_until = NULL
+            WHERE id = $1 AND locked_by = $2 AND acknowledged_at IS NULL
+            "#,
+        )
+        .bind(id)
+        .bind(worker)
+        .bind(at)
+        .execute(&self.pool)
+        .await?
+        .rows_affected();
+
+        tracing::debug!("Reminder {} will nag again at {}", id, at);
+        Ok(updated == 1)
+    }
+
+    /// Acknowledge reminder and stop its repeats
+    ///
+    /// Returns `None` if it was already acknowledged.
+    pub async fn acknowledge(&self, id: DbId) -> Result<Option<Reminder>> {
+        let reminder = sqlx::query_as::<_, Reminder>(
+            r#"
+            UPDATE reminders
+            SET acknowledged_at = CURRENT_TIMESTAMP, is_sent = TRUE
+            WHERE id = $1 AND acknowledged_at IS NULL
+            RETURNING *
+            "#,
+        )
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        tracing::debug!("Acknowledged reminder {}", id);
+        Ok(reminder)
+    }
+
+    /// Finish unacknowledged reminder as escalated
+    ///
+    /// Returns `false` if it was acknowledged or escalated in the meantime,
+    /// or `worker` no longer holds the lease.
+    pub async fn mark_escalated(&self, id: DbId, worker: &str) -> Result<bool> {
+        let result = sqlx::query(
+            r#"
+            UPDATE reminders
+            SET is_sent = TRUE, sent_at = CURRENT_TIMESTAMP, escalated_at = CURRENT_TIMESTAMP,
+                retry_at = NULL, locked_by = NULL, locked_until = NULL
+            WHERE id = $1 AND locked_by = $2 AND acknowledged_at IS NULL AND escalated_at IS NULL
+            "#,
+        )
+        .bind(id)
+        .bind(worker)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// End a recurring series: the pending occurrence becomes the last one
+    pub async fn stop_recurrence(&self, id: DbId) -> Result<Reminder> {
+        let reminder = sqlx::query_as::<_, Reminder>(
+            "UPDATE reminders SET is_recurring = FALSE WHERE id = $1 RETURNING *",
+        )
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| not_found(format!("Reminder {} not found", id)))?;
+
+        tracing::debug!("Stopped recurrence of reminder {}", id);
+        Ok(reminder)
     }
 
     /// Get all user reminders
@@ -99,6 +622,96 @@ impl ReminderRepository {
         Ok(reminders)
     }
 
+    /// Record a delivery in the reminder's history, one row per channel
+    pub async fn log_delivery(
+        &self,
+        reminder: &Reminder,
+        channel: Option<NotifyChannel>,
+        status: DeliveryStatus,
+        error: Option<&str>,
+    ) -> Result<()> {
+        let delivered_at = (status == DeliveryStatus::Sent).then(Utc::now);
+
+        sqlx::query(
+            r#"
+            INSERT INTO reminder_deliveries (
+                reminder_id, user_id, message, scheduled_for, status, delivered_at,
+                attempt, snooze_count, error, channel
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+            "#,
+        )
+        .bind(reminder.id)
+        .bind(reminder.user_id)
+        .bind(&reminder.message)
+        .bind(reminder.remind_at)
+        .bind(status)
+        .bind(delivered_at)
+        .bind(reminder.attempts + 1)
+        .bind(reminder.snooze_count)
+        .bind(error)
+        .bind(channel)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Delivery log of a user, newest first
+    pub async fn find_history(
+        &self,
+        user_id: DbId,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<ReminderDelivery>> {
+        let deliveries = sqlx::query_as::<_, ReminderDelivery>(
+            r#"
+            SELECT * FROM reminder_deliveries
+            WHERE user_id = $1
+            ORDER BY created_at DESC, id DESC
+            LIMIT $2 OFFSET $3
+            "#,
+        )
+        .bind(user_id)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(deliveries)
+    }
+
+    /// Number of logged deliveries of a user
+    pub async fn count_history(&self, user_id: DbId) -> Result<i64> {
+        let (count,): (i64,) =
+            sqlx::query_as("SELECT COUNT(*) FROM reminder_deliveries WHERE user_id = $1")
+                .bind(user_id)
+                .fetch_one(&self.pool)
+                .await?;
+
+        Ok(count)
+    }
+
+    /// Delete pending reminders of a task; `automatic_only` keeps the ones
+    /// the user created
+    pub async fn delete_pending_for_todo(&self, todo_id: DbId, automatic_only: bool) -> Result<u64> {
+        let result = sqlx::query(
+            r#"
+            DELETE FROM reminders
+            WHERE todo_id = $1
+              AND is_sent = FALSE
+              AND (NOT $2 OR due_offset_minutes IS NOT NULL)
+            "#,
+        )
+        .bind(todo_id)
+        .bind(automatic_only)
+        .execute(&self.pool)
+        .await?;
+
+        tracing::debug!("Deleted {} pending reminders of todo {}", result.rows_affected(), todo_id);
+        Ok(result.rows_affected())
+    }
+
     /// Delete reminder
     pub async fn delete(&self, id: DbId) -> Result<()> {
         let result = sqlx::query("DELETE FROM reminders WHERE id = $1")
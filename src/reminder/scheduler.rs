@@ -1,148 +1,1061 @@
-// Reminder scheduler - reminder scheduler using Tokio tasks
+// Reminder scheduler - event-driven delivery of reminders
 //
 // Architecture:
-// 1. Background task checks for new reminders every 30 сек
-// 2. For each pending reminder, a Tokio task with delay is created
-// 3. When time comes, task sends notification
+// 1. One loop owns a bounded in-memory queue (`ReminderQueue`) of the
+//    reminders due within QUEUE_HORIZON
+// 2. Postgres NOTIFY on `reminder_changes` (migration 013) reports every
+//    created, edited, sent or deleted reminder; the queue is updated right
+//    away, so new reminders fire on time and cancelled ones never fire
+// 3. The loop sleeps until the earliest due reminder, the next notification
+//    or the next refill, which reloads the horizon from the database
+//    (catches reminders beyond the horizon and missed notifications)
+// 4. Due reminders are claimed (lease in `locked_by`/`locked_until`, see
+//    migration 014) and delivered by at most MAX_CONCURRENT_DELIVERIES tasks;
+//    recurring ones move to their next occurrence after sending. Several bot
+//    instances can share one database: each reminder is claimed by one of
+//    them, and a crashed instance's lease expires after DELIVERY_LEASE. The
+//    lease is renewed every LEASE_RENEWAL while a delivery runs, and only
+//    the instance holding it can record the outcome
+// 5. Failed sends are retried with exponential backoff (Telegram's
+//    RetryAfter is honoured) and dead-lettered after MAX_DELIVERY_ATTEMPTS;
+//    if the user blocked the bot (or removed it from the group), reminders
+//    are paused until /start
+// 6. Insistent reminders are re-sent every N minutes until acknowledged,
+//    then escalated to a second chat if configured
+// 7. Reminders that became due while the bot was down follow the catch-up
+//    policy (REMINDER_CATCH_UP): sent late with a note, collapsed into a
+//    summary per chat (split into messages within Telegram's limit), or
+//    skipped past a maximum age. Recurring reminders continue with their
+//    next future occurrence, missed ones are not repeated
+// 8. During the owner's quiet hours or do-not-disturb, private reminders are
+//    held until the window ends (or sent silently if the user chose so);
+//    urgent ones ring through
+// 9. Each reminder goes to its own channels or the owner's default ones
+//    (Telegram, email, webhook - see `Notifiers`); every channel's outcome
+//    is logged, and only a delivery failing on all of them is retried
 //
-// Scales up to ~10K concurrent reminders
+// Memory is bounded by QUEUE_CAPACITY; queue metrics are in `SchedulerMetrics`.
 
-use crate::error::Result;
-use crate::reminder::notifier::ReminderNotifier;
-use crate::reminder::repository::ReminderRepository;
+use crate::config::{CatchUpPolicy, NotifyConfig, ReminderConfig};
+use crate::db::models::User;
+use crate::error::{AppError, Result};
+use crate::reminder::metrics::SchedulerMetrics;
+use crate::reminder::models::{Reminder, LATE_GRACE};
+use crate::reminder::notifier::{EmailNotifier, Notification, Notifiers, WebhookNotifier};
+use crate::reminder::queue::ReminderQueue;
+use crate::reminder::render::render_missed_summary;
+use crate::reminder::repository::{Claim, ReminderRepository};
+use crate::shared::types::{DbId, DeliveryStatus, NotifyChannel, Timestamp};
+use crate::todo::repository::TodoRepository;
 use chrono::Utc;
+use chrono_tz::Tz;
+use serde::Deserialize;
+use sqlx::postgres::PgListener;
 use sqlx::PgPool;
-use std::collections::HashSet;
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::sync::Arc;
+use teloxide::types::ChatId;
 use teloxide::Bot;
-use tokio::sync::Mutex;
-use tokio::time::{sleep, Duration};
+use tokio::sync::{mpsc, Semaphore};
+use tokio::time::{sleep, sleep_until, Duration, Instant};
+use uuid::Uuid;
+
+/// Channel the reminders trigger notifies on
+const NOTIFY_CHANNEL: &str = "reminder_changes";
+
+/// How far ahead reminders are kept in memory
+const QUEUE_HORIZON: chrono::Duration = chrono::Duration::hours(1);
+
+/// Most reminders kept in memory
+const QUEUE_CAPACITY: usize = 10_000;
+
+/// Full reload of the queue from the database
+const REFILL_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+/// Parallel sends (Telegram allows about 30 messages per second)
+const MAX_CONCURRENT_DELIVERIES: usize = 25;
+
+/// Most missed reminders claimed by one summary delivery
+const MAX_MISSED_BATCH: usize = 100;
+
+/// Notifications buffered between the listener and the scheduler loop
+const EVENT_BUFFER: usize = 1024;
+
+/// How long a claimed reminder is reserved for one instance
+const DELIVERY_LEASE: chrono::Duration = chrono::Duration::minutes(2);
+
+/// Renewal period of the lease of a reminder being delivered, well within
+/// DELIVERY_LEASE
+const LEASE_RENEWAL: Duration = Duration::from_secs(30);
+
+/// Failed attempts before a reminder is given up (dead-lettered)
+const MAX_DELIVERY_ATTEMPTS: i32 = 5;
+
+/// Backoff after the first failure, doubled with every further one
+const FIRST_RETRY_DELAY: chrono::Duration = chrono::Duration::seconds(30);
+
+/// Longest backoff between attempts
+const MAX_RETRY_DELAY: chrono::Duration = chrono::Duration::hours(1);
+
+/// Pause before reconnecting a failed listener
+const LISTENER_RETRY_DELAY: Duration = Duration::from_secs(5);
+
+/// How missed reminders are delivered
+#[derive(Debug, Clone, Copy)]
+struct CatchUp {
+    policy: CatchUpPolicy,
+    /// With `CatchUpPolicy::Skip`: older reminders are dropped
+    max_age: chrono::Duration,
+}
+
+impl Default for CatchUp {
+    fn default() -> Self {
+        Self {
+            policy: CatchUpPolicy::default(),
+            max_age: chrono::Duration::hours(1),
+        }
+    }
+}
+
+/// Owner's quiet hours at delivery time
+enum Quiet {
+    Off,
+    /// Deliver when quiet hours end
+    Hold(Timestamp),
+    /// Deliver now without sound
+    Silent,
+}
+
+/// Payload of a `reminder_changes` notification
+#[derive(Debug, Clone, Deserialize)]
+struct ReminderChange {
+    id: DbId,
+    remind_at: Option<Timestamp>,
+    /// `false` once sent or deleted
+    pending: bool,
+}
+
+/// Delivery task result sent back to the scheduler loop
+struct Finished {
+    id: DbId,
+    /// Set when another instance holds the lease
+    leased_until: Option<Timestamp>,
+}
+
+enum SchedulerEvent {
+    Changed(ReminderChange),
+    /// Notifications may have been missed: reload the queue
+    Resync,
+}
 
 /// Reminder scheduler
 pub struct ReminderScheduler {
     pool: PgPool,
-    bot: Bot,
-    scheduled_ids: Arc<Mutex<HashSet<i32>>>, // Track scheduled reminders
+    notifiers: Notifiers,
+    /// Lease owner name of this instance
+    worker_id: Arc<str>,
+    metrics: Arc<SchedulerMetrics>,
+    catch_up: CatchUp,
+}
+
+/// State owned by the scheduler loop
+struct QueueState {
+    queue: ReminderQueue,
+    /// Reminders being delivered right now
+    in_flight: HashSet<DbId>,
+    /// Changes after this time are left to the next refill
+    horizon_end: Timestamp,
+    /// Last refill hit QUEUE_CAPACITY
+    overflowed: bool,
 }
 
 impl ReminderScheduler {
     pub fn new(pool: PgPool, bot: Bot) -> Self {
+        let host = std::env::var("HOSTNAME").unwrap_or_else(|_| "bot".to_string());
         Self {
             pool,
-            bot,
-            scheduled_ids: Arc::new(Mutex::new(HashSet::new())),
+            notifiers: Notifiers::new(bot),
+            worker_id: format!("{}-{}", host, Uuid::new_v4()).into(),
+            metrics: Arc::new(SchedulerMetrics::default()),
+            catch_up: CatchUp::default(),
+        }
+    }
+
+    /// Delivery of reminders missed while the bot was down
+    pub fn with_catch_up(mut self, config: &ReminderConfig) -> Self {
+        self.catch_up = CatchUp {
+            policy: config.catch_up,
+            max_age: chrono::Duration::minutes(config.catch_up_max_age_minutes),
+        };
+        self
+    }
+
+    /// Webhook channel, and email if an SMTP relay is configured
+    pub fn with_notify(mut self, config: &NotifyConfig) -> Result<Self> {
+        self.notifiers = self.notifiers.with(WebhookNotifier::new(config)?);
+        if let Some(smtp) = &config.smtp {
+            self.notifiers = self.notifiers.with(EmailNotifier::new(smtp.clone()));
         }
+        Ok(self)
+    }
+
+    /// Use a fixed lease owner name instead of a generated one
+    pub fn with_worker_id(mut self, worker_id: impl Into<String>) -> Self {
+        self.worker_id = worker_id.into().into();
+        self
+    }
+
+    /// Shared metrics, updated while the scheduler runs
+    pub fn metrics(&self) -> Arc<SchedulerMetrics> {
+        Arc::clone(&self.metrics)
     }
 
     /// Start scheduler (background task)
     pub async fn run(self) -> Result<()> {
-        tracing::info!("Starting reminder scheduler...");
+        tracing::info!("Starting reminder scheduler {}...", self.worker_id);
+
+        let (events_tx, mut events) = mpsc::channel(EVENT_BUFFER);
+        tokio::spawn(listen(self.pool.clone(), events_tx));
+
+        let (done_tx, mut done) = mpsc::unbounded_channel::<Finished>();
+        let deliveries = Arc::new(Semaphore::new(MAX_CONCURRENT_DELIVERIES));
+
+        let mut state = QueueState {
+            queue: ReminderQueue::new(QUEUE_CAPACITY),
+            in_flight: HashSet::new(),
+            horizon_end: Utc::now(),
+            overflowed: false,
+        };
+        let mut next_refill = Instant::now();
 
         loop {
-            if let Err(e) = self.check_and_schedule().await {
-                tracing::error!("Scheduler error: {}", e);
+            let refill_due = Instant::now() >= next_refill
+                || (state.overflowed && state.queue.len() < QUEUE_CAPACITY / 2);
+            if refill_due {
+                if let Err(e) = self.refill(&mut state).await {
+                    tracing::error!("Scheduler refill failed: {}", e);
+                }
+                next_refill = Instant::now() + REFILL_INTERVAL;
             }
 
-            // Check every 30 секунд
-            sleep(Duration::from_secs(30)).await;
+            self.dispatch_due(&mut state, &done_tx, &deliveries);
+
+            SchedulerMetrics::set(&self.metrics.queued, state.queue.len() as u64);
+            SchedulerMetrics::set(&self.metrics.in_flight, state.in_flight.len() as u64);
+
+            let wake_at = match state.queue.next_due() {
+                Some(due) => next_refill.min(instant_at(due)),
+                None => next_refill,
+            };
+
+            tokio::select! {
+                Some(event) = events.recv() => match event {
+                    SchedulerEvent::Changed(change) => self.apply_change(&mut state, change),
+                    SchedulerEvent::Resync => next_refill = Instant::now(),
+                },
+                Some(finished) = done.recv() => {
+                    state.in_flight.remove(&finished.id);
+                    // Another instance holds it: retry when its lease ends
+                    if let Some(at) = finished.leased_until {
+                        self.apply_change(&mut state, ReminderChange {
+                            id: finished.id,
+                            remind_at: Some(at),
+                            pending: true,
+                        });
+                    }
+                }
+                _ = sleep_until(wake_at) => {}
+            }
         }
     }
 
-    /// Check and schedule new reminders
-    async fn check_and_schedule(&self) -> Result<()> {
+    /// Reload the queue with everything due within the horizon
+    async fn refill(&self, state: &mut QueueState) -> Result<()> {
+        let horizon_end = Utc::now() + QUEUE_HORIZON;
         let repo = ReminderRepository::new(self.pool.clone());
-        let reminders = repo.get_pending_reminders(Utc::now()).await?;
+        let schedule = repo
+            .find_pending_schedule(horizon_end, QUEUE_CAPACITY as i64)
+            .await?;
 
-        let mut scheduled = self.scheduled_ids.lock().await;
+        state.queue.clear();
+        for (id, at) in &schedule {
+            state.queue.upsert(*id, *at);
+        }
 
-        for reminder in reminders {
-            // Skip already scheduled
-            if scheduled.contains(&reminder.id) {
+        // Full queue: later reminders wait for a refill once it drains
+        state.overflowed = schedule.len() >= QUEUE_CAPACITY;
+        state.horizon_end = match schedule.last() {
+            Some((_, last)) if state.overflowed => *last,
+            _ => horizon_end,
+        };
+
+        SchedulerMetrics::inc(&self.metrics.refills_total);
+        tracing::debug!("Scheduler queue reloaded: {} reminders", state.queue.len());
+        Ok(())
+    }
+
+    /// Apply a notification to the queue
+    fn apply_change(&self, state: &mut QueueState, change: ReminderChange) {
+        SchedulerMetrics::inc(&self.metrics.notifications_total);
+
+        let due = change
+            .remind_at
+            .filter(|at| change.pending && *at <= state.horizon_end);
+
+        match due {
+            Some(at) => {
+                if !state.queue.upsert(change.id, at) {
+                    state.overflowed = true;
+                    SchedulerMetrics::inc(&self.metrics.overflow_total);
+                }
+            }
+            None => {
+                if state.queue.remove(change.id) {
+                    SchedulerMetrics::inc(&self.metrics.cancelled_total);
+                }
+            }
+        }
+    }
+
+    /// Start delivery tasks for every reminder that is due
+    fn dispatch_due(
+        &self,
+        state: &mut QueueState,
+        done_tx: &mpsc::UnboundedSender<Finished>,
+        deliveries: &Arc<Semaphore>,
+    ) {
+        let now = Utc::now();
+        let mut missed = Vec::new();
+
+        for (id, at) in state.queue.pop_due(now) {
+            // Still being sent: its completion updates the row again
+            if !state.in_flight.insert(id) {
                 continue;
             }
 
-            // Check if it's time to send now
-            let now = Utc::now();
-            if reminder.remind_at <= now {
-                self.send_reminder_now(reminder.clone()).await;
+            if self.catch_up.policy == CatchUpPolicy::Summary && now - at > LATE_GRACE {
+                missed.push(id);
             } else {
-                // Schedule for future
-                self.schedule_reminder(reminder.clone()).await;
+                self.spawn_delivery(vec![id], done_tx, deliveries);
             }
+        }
 
-            scheduled.insert(reminder.id);
+        // Bounded, so the leases of one batch are renewed together
+        for batch in missed.chunks(MAX_MISSED_BATCH) {
+            self.spawn_delivery(batch.to_vec(), done_tx, deliveries);
         }
+    }
 
-        tracing::debug!("Scheduler check completed. Total scheduled: {}", scheduled.len());
+    /// Deliver one reminder, or several missed ones as summaries
+    fn spawn_delivery(
+        &self,
+        ids: Vec<DbId>,
+        done_tx: &mpsc::UnboundedSender<Finished>,
+        deliveries: &Arc<Semaphore>,
+    ) {
+        let notifiers = self.notifiers.clone();
+        let pool = self.pool.clone();
+        let metrics = Arc::clone(&self.metrics);
+        let worker_id = Arc::clone(&self.worker_id);
+        let catch_up = self.catch_up;
+        let deliveries = Arc::clone(deliveries);
+        let done_tx = done_tx.clone();
 
-        // Clear old IDs (optional, to prevent infinite growth)
-        if scheduled.len() > 100_000 {
-            scheduled.clear();
-            tracing::warn!("Cleared scheduled IDs cache (reached 100K)");
+        tokio::spawn(async move {
+            let finished = match deliveries.acquire_owned().await {
+                Ok(_permit) => match ids[..] {
+                    [id] => vec![Finished {
+                        id,
+                        leased_until: deliver_due(
+                            &notifiers, pool, &metrics, &worker_id, catch_up, id,
+                        )
+                        .await,
+                    }],
+                    _ => deliver_missed(&notifiers, pool, &metrics, &worker_id, ids).await,
+                },
+                Err(_) => ids
+                    .into_iter()
+                    .map(|id| Finished {
+                        id,
+                        leased_until: None,
+                    })
+                    .collect(),
+            };
+            for finished in finished {
+                let _ = done_tx.send(finished);
+            }
+        });
+    }
+}
+
+/// Forward `reminder_changes` notifications to the scheduler loop
+///
+/// Runs in its own task: the listener is reconnected on failure and a
+/// resync is requested, since notifications sent meanwhile are lost.
+async fn listen(pool: PgPool, events: mpsc::Sender<SchedulerEvent>) {
+    loop {
+        let mut listener = match PgListener::connect_with(&pool).await {
+            Ok(listener) => listener,
+            Err(e) => {
+                tracing::error!("Reminder listener failed to connect: {}", e);
+                sleep(LISTENER_RETRY_DELAY).await;
+                continue;
+            }
+        };
+        if let Err(e) = listener.listen(NOTIFY_CHANNEL).await {
+            tracing::error!("Failed to listen on {}: {}", NOTIFY_CHANNEL, e);
+            sleep(LISTENER_RETRY_DELAY).await;
+            continue;
         }
 
-        Ok(())
+        loop {
+            let event = match listener.try_recv().await {
+                Ok(Some(notification)) => {
+                    match serde_json::from_str::<ReminderChange>(notification.payload()) {
+                        Ok(change) => SchedulerEvent::Changed(change),
+                        Err(e) => {
+                            tracing::warn!("Malformed reminder notification: {}", e);
+                            continue;
+                        }
+                    }
+                }
+                // Reconnects on the next call
+                Ok(None) => {
+                    tracing::warn!("Reminder listener lost its connection");
+                    SchedulerEvent::Resync
+                }
+                Err(e) => {
+                    tracing::error!("Reminder listener error: {}", e);
+                    break;
+                }
+            };
+
+            if events.send(event).await.is_err() {
+                return;
+            }
+        }
+
+        let _ = events.send(SchedulerEvent::Resync).await;
+        sleep(LISTENER_RETRY_DELAY).await;
     }
+}
 
-    /// Schedule reminder for future
-    async fn schedule_reminder(&self, reminder: crate::db::models::Reminder) {
-        let bot = self.bot.clone();
-        let pool = self.pool.clone();
-        let scheduled_ids = Arc::clone(&self.scheduled_ids);
+/// Tokio deadline for a wall-clock time (now if it has passed)
+fn instant_at(at: Timestamp) -> Instant {
+    let delay = (at - Utc::now()).to_std().unwrap_or(Duration::ZERO);
+    Instant::now() + delay
+}
 
-        tokio::spawn(async move {
-            // Calculate wait time
-            let now = Utc::now();
-            let remind_at = reminder.remind_at;
-            let delay = (remind_at - now).to_std().unwrap_or(Duration::from_secs(0));
-
-            if delay.as_secs() > 0 {
-                tracing::debug!(
-                    "Scheduled reminder {} to be sent in {} seconds",
+/// Claim a reminder for this instance
+///
+/// `Err` carries the lease end if another instance is delivering it.
+async fn claim(
+    repo: &ReminderRepository,
+    metrics: &SchedulerMetrics,
+    worker_id: &str,
+    id: DbId,
+) -> std::result::Result<Option<Reminder>, Timestamp> {
+    match repo.claim(id, worker_id, DELIVERY_LEASE).await {
+        Ok(Claim::Claimed(reminder)) => Ok(Some(*reminder)),
+        Ok(Claim::Leased(until)) => {
+            tracing::debug!("Reminder {} is leased by another instance", id);
+            Err(until)
+        }
+        // Cancelled, sent or moved after it was queued
+        Ok(Claim::Gone) => {
+            SchedulerMetrics::inc(&metrics.cancelled_total);
+            Ok(None)
+        }
+        Err(e) => {
+            tracing::error!("Failed to claim reminder {}: {}", id, e);
+            SchedulerMetrics::inc(&metrics.failed_total);
+            Ok(None)
+        }
+    }
+}
+
+/// Claim a due reminder and deliver it unless it changed meanwhile
+///
+/// Returns the lease end if another instance is delivering it.
+async fn deliver_due(
+    notifiers: &Notifiers,
+    pool: PgPool,
+    metrics: &SchedulerMetrics,
+    worker_id: &str,
+    catch_up: CatchUp,
+    id: DbId,
+) -> Option<Timestamp> {
+    let repo = ReminderRepository::new(pool.clone());
+    let reminder = match claim(&repo, metrics, worker_id, id).await {
+        Ok(Some(reminder)) => reminder,
+        Ok(None) => return None,
+        Err(until) => return Some(until),
+    };
+
+    let too_old = reminder
+        .late_by(Utc::now())
+        .is_some_and(|late| late > catch_up.max_age);
+    if catch_up.policy == CatchUpPolicy::Skip && too_old {
+        // Recurring ones move on to their next occurrence
+        log_delivery(&repo, &reminder, None, DeliveryStatus::Skipped, None).await;
+        match complete_delivery(&repo, worker_id, &reminder).await {
+            Ok(true) => {
+                SchedulerMetrics::inc(&metrics.skipped_total);
+                tracing::info!("Skipped reminder {} missed during downtime", reminder.id);
+            }
+            Ok(false) => lease_lost(reminder.id),
+            Err(e) => tracing::error!("Failed to skip reminder {}: {}", reminder.id, e),
+        }
+        return None;
+    }
+
+    send_claimed(notifiers, pool, metrics, &repo, worker_id, &reminder).await;
+    None
+}
+
+/// Deliver claimed reminder, recording a failure for retry
+///
+/// The lease is renewed meanwhile; once it is lost the delivery stops, as
+/// another instance may be sending the reminder.
+async fn send_claimed(
+    notifiers: &Notifiers,
+    pool: PgPool,
+    metrics: &SchedulerMetrics,
+    repo: &ReminderRepository,
+    worker_id: &str,
+    reminder: &Reminder,
+) {
+    let lag = (Utc::now() - reminder.due_at()).num_milliseconds().max(0) as u64;
+    SchedulerMetrics::set(&metrics.last_lag_ms, lag);
+
+    let Some(silent) = check_quiet(repo, metrics, worker_id, reminder).await else {
+        return;
+    };
+
+    let ids = [reminder.id];
+    let delivery = tokio::select! {
+        delivery = deliver(notifiers, pool, worker_id, reminder, silent) => delivery,
+        () = keep_lease(repo, worker_id, &ids) => {
+            lease_lost(reminder.id);
+            return;
+        }
+    };
+
+    match delivery {
+        Ok(true) => {
+            SchedulerMetrics::inc(&metrics.delivered_total);
+            tracing::info!("Reminder {} sent successfully", reminder.id);
+        }
+        Ok(false) => lease_lost(reminder.id),
+        Err(e) => {
+            SchedulerMetrics::inc(&metrics.failed_total);
+            tracing::error!("Failed to deliver reminder {}: {}", reminder.id, e);
+            // Not recorded: retried by a later refill once the lease expires
+            match record_failure(repo, metrics, worker_id, reminder, e).await {
+                Ok(true) => {}
+                Ok(false) => lease_lost(reminder.id),
+                Err(e) => tracing::error!(
+                    "Failed to record failure of reminder {}: {}",
                     reminder.id,
-                    delay.as_secs()
-                );
+                    e
+                ),
+            }
+        }
+    }
+}
+
+/// Renew the lease on reminders `ids` every LEASE_RENEWAL; returns once
+/// none of them is leased to this instance any more
+async fn keep_lease(repo: &ReminderRepository, worker_id: &str, ids: &[DbId]) {
+    loop {
+        sleep(LEASE_RENEWAL).await;
+        match repo.renew(ids, worker_id, DELIVERY_LEASE).await {
+            Ok(0) => return,
+            Ok(_) => {}
+            // Retried on the next tick, the lease outlasts a few failures
+            Err(e) => tracing::warn!("Failed to renew lease on reminders {:?}: {}", ids, e),
+        }
+    }
+}
+
+/// The reminder was not updated: our lease expired and another instance
+/// took it over, or it was acknowledged meanwhile
+fn lease_lost(id: DbId) {
+    tracing::warn!(
+        "Reminder {} is no longer leased to this instance, outcome not recorded",
+        id
+    );
+}
 
-                // Wait until needed time
-                sleep(delay).await;
+/// Deliver reminders missed during downtime as one summary per chat
+///
+/// Insistent and task reminders keep their buttons and are sent on their
+/// own, as are reminders for other channels and the only missed reminder
+/// of a chat.
+async fn deliver_missed(
+    notifiers: &Notifiers,
+    pool: PgPool,
+    metrics: &SchedulerMetrics,
+    worker_id: &str,
+    ids: Vec<DbId>,
+) -> Vec<Finished> {
+    let repo = ReminderRepository::new(pool.clone());
+    let mut finished = Vec::with_capacity(ids.len());
+    let mut claimed = Vec::new();
+
+    for id in ids {
+        let leased_until = match claim(&repo, metrics, worker_id, id).await {
+            Ok(Some(reminder)) => {
+                claimed.push(reminder);
+                None
             }
+            Ok(None) => None,
+            Err(until) => Some(until),
+        };
+        finished.push(Finished { id, leased_until });
+    }
 
-            // Send reminder
-            let notifier = ReminderNotifier::new(bot);
-            if let Err(e) = notifier.send_reminder(&reminder).await {
-                tracing::error!("Failed to send reminder {}: {}", reminder.id, e);
-            } else {
-                // Отмечаем как отправленное
-                let repo = ReminderRepository::new(pool);
-                if let Err(e) = repo.mark_as_sent(reminder.id).await {
-                    tracing::error!("Failed to mark reminder {} as sent: {}", reminder.id, e);
+    let ids: Vec<DbId> = claimed.iter().map(|reminder| reminder.id).collect();
+    tokio::select! {
+        () = send_missed(notifiers, pool, metrics, &repo, worker_id, claimed) => {}
+        () = keep_lease(&repo, worker_id, &ids) => {
+            tracing::warn!("Missed reminders {:?} are no longer leased to this instance", ids);
+        }
+    }
+
+    finished
+}
+
+/// Send claimed missed reminders, summarised per chat where possible
+async fn send_missed(
+    notifiers: &Notifiers,
+    pool: PgPool,
+    metrics: &SchedulerMetrics,
+    repo: &ReminderRepository,
+    worker_id: &str,
+    claimed: Vec<Reminder>,
+) {
+    // Per chat: silent delivery and the reminders to summarise
+    let mut chats: BTreeMap<i64, (bool, Vec<Reminder>)> = BTreeMap::new();
+    let mut owners = HashMap::new();
+
+    for reminder in claimed {
+        let telegram_only = owner(repo, &mut owners, reminder.user_id)
+            .await
+            .is_some_and(|owner| reminder.delivery_channels(owner) == [NotifyChannel::Telegram]);
+        if reminder.is_nagging() || reminder.todo_id.is_some() || !telegram_only {
+            send_claimed(notifiers, pool.clone(), metrics, repo, worker_id, &reminder).await;
+        } else if let Some(silent) = check_quiet(repo, metrics, worker_id, &reminder).await {
+            let chat = chats.entry(reminder.chat_id).or_default();
+            chat.0 |= silent;
+            chat.1.push(reminder);
+        }
+    }
+
+    for (chat_id, (silent, reminders)) in chats {
+        if let [reminder] = &reminders[..] {
+            send_claimed(notifiers, pool.clone(), metrics, repo, worker_id, reminder).await;
+            continue;
+        }
+
+        let mut summary = Vec::with_capacity(reminders.len());
+        for reminder in reminders {
+            let tz = owners
+                .get(&reminder.user_id)
+                .map_or(chrono_tz::UTC, User::tz);
+            summary.push((reminder, tz));
+        }
+
+        // Each message's reminders are finished as soon as it is sent
+        for part in render_missed_summary(&summary) {
+            let sent = notifiers
+                .telegram()
+                .send_missed_summary(ChatId(chat_id), &part, silent)
+                .await;
+            let reminders = &summary[part.reminders];
+            record_summary(metrics, repo, worker_id, chat_id, reminders, sent).await;
+        }
+    }
+}
+
+/// Record the outcome of the reminders one summary message listed
+async fn record_summary(
+    metrics: &SchedulerMetrics,
+    repo: &ReminderRepository,
+    worker_id: &str,
+    chat_id: i64,
+    reminders: &[(Reminder, Tz)],
+    sent: Result<()>,
+) {
+    match sent {
+        Ok(()) => {
+            for (reminder, _) in reminders {
+                log_delivery(
+                    repo,
+                    reminder,
+                    Some(NotifyChannel::Telegram),
+                    DeliveryStatus::Sent,
+                    None,
+                )
+                .await;
+                match complete_delivery(repo, worker_id, reminder).await {
+                    Ok(true) => SchedulerMetrics::inc(&metrics.delivered_total),
+                    Ok(false) => lease_lost(reminder.id),
+                    Err(e) => tracing::error!("Failed to complete reminder {}: {}", reminder.id, e),
                 }
+            }
+            tracing::info!(
+                "Sent summary of {} missed reminders to chat {}",
+                reminders.len(),
+                chat_id
+            );
+        }
+        Err(e) => {
+            SchedulerMetrics::inc(&metrics.failed_total);
+            for (reminder, _) in reminders {
+                let error = match &e {
+                    AppError::RateLimited(delay) => AppError::RateLimited(*delay),
+                    AppError::ChatUnavailable(reason) => AppError::ChatUnavailable(reason.clone()),
+                    other => AppError::Telegram(other.to_string()),
+                };
+                log_delivery(
+                    repo,
+                    reminder,
+                    Some(NotifyChannel::Telegram),
+                    DeliveryStatus::Failed,
+                    Some(&error.to_string()),
+                )
+                .await;
+                match record_failure(repo, metrics, worker_id, reminder, error).await {
+                    Ok(true) => {}
+                    Ok(false) => lease_lost(reminder.id),
+                    Err(e) => tracing::error!(
+                        "Failed to record failure of reminder {}: {}",
+                        reminder.id,
+                        e
+                    ),
+                }
+            }
+        }
+    }
+}
 
-                // Remove from scheduled list
-                let mut scheduled = scheduled_ids.lock().await;
-                scheduled.remove(&reminder.id);
+/// Owner of a reminder, looked up once per batch
+async fn owner<'a>(
+    repo: &ReminderRepository,
+    owners: &'a mut HashMap<DbId, User>,
+    user_id: DbId,
+) -> Option<&'a User> {
+    if let Entry::Vacant(entry) = owners.entry(user_id) {
+        match repo.find_owner(user_id).await {
+            Ok(user) => {
+                entry.insert(user);
+            }
+            Err(e) => tracing::warn!("Owner {} of missed reminders unknown: {}", user_id, e),
+        }
+    }
+    owners.get(&user_id)
+}
 
-                tracing::info!("Reminder {} sent successfully", reminder.id);
+/// Apply the owner's quiet hours to a claimed reminder
+///
+/// Returns `None` if it was held until they end, otherwise whether to send
+/// it silently.
+async fn check_quiet(
+    repo: &ReminderRepository,
+    metrics: &SchedulerMetrics,
+    worker_id: &str,
+    reminder: &Reminder,
+) -> Option<bool> {
+    let quiet = match quiet_hours(repo, reminder).await {
+        Ok(quiet) => quiet,
+        Err(e) => {
+            tracing::warn!("Quiet hours of reminder {} unknown: {}", reminder.id, e);
+            Quiet::Off
+        }
+    };
+
+    match quiet {
+        Quiet::Off => Some(false),
+        Quiet::Silent => Some(true),
+        Quiet::Hold(until) => {
+            match repo.hold(reminder.id, worker_id, until).await {
+                Ok(true) => SchedulerMetrics::inc(&metrics.held_total),
+                Ok(false) => lease_lost(reminder.id),
+                // The lease expires and a later refill tries again
+                Err(e) => tracing::error!("Failed to hold reminder {}: {}", reminder.id, e),
             }
-        });
+            None
+        }
     }
+}
 
-    /// Send reminder immediately
-    async fn send_reminder_now(&self, reminder: crate::db::models::Reminder) {
-        let bot = self.bot.clone();
-        let pool = self.pool.clone();
+/// Quiet hours are personal: reminders in groups and channels, urgent
+/// ones and escalations are not affected
+async fn quiet_hours(repo: &ReminderRepository, reminder: &Reminder) -> Result<Quiet> {
+    if reminder.is_urgent || reminder.escalation_due() || !ChatId(reminder.chat_id).is_user() {
+        return Ok(Quiet::Off);
+    }
 
-        tokio::spawn(async move {
-            let notifier = ReminderNotifier::new(bot);
-            if let Err(e) = notifier.send_reminder(&reminder).await {
-                tracing::error!("Failed to send reminder {}: {}", reminder.id, e);
-            } else {
-                let repo = ReminderRepository::new(pool);
-                if let Err(e) = repo.mark_as_sent(reminder.id).await {
-                    tracing::error!("Failed to mark reminder {} as sent: {}", reminder.id, e);
+    let owner = repo.find_owner(reminder.user_id).await?;
+    Ok(match owner.quiet_until(Utc::now()) {
+        None => Quiet::Off,
+        Some(_) if owner.quiet_silent => Quiet::Silent,
+        Some(until) => Quiet::Hold(until),
+    })
+}
+
+/// Schedule a retry, give up, or pause the owner's reminders
+///
+/// Returns `false` if the lease was lost and nothing was recorded.
+async fn record_failure(
+    repo: &ReminderRepository,
+    metrics: &SchedulerMetrics,
+    worker_id: &str,
+    reminder: &Reminder,
+    error: AppError,
+) -> Result<bool> {
+    let message = error.to_string();
+
+    match error {
+        // Flood control is not the reminder's fault: no attempt is counted
+        AppError::RateLimited(delay) => {
+            let at = Utc::now() + chrono::Duration::from_std(delay).unwrap_or(MAX_RETRY_DELAY);
+            let retried = repo
+                .schedule_retry(reminder.id, worker_id, at, reminder.attempts, &message)
+                .await?;
+            if retried {
+                SchedulerMetrics::inc(&metrics.retried_total);
+            }
+            Ok(retried)
+        }
+        // Removed from a group or channel: only reminders for it stop
+        AppError::ChatUnavailable(_) if !ChatId(reminder.chat_id).is_user() => {
+            let paused = repo.pause_chat(reminder.chat_id).await?;
+            tracing::warn!(
+                "Chat {} is unavailable, paused {} reminders",
+                reminder.chat_id,
+                paused
+            );
+            Ok(true)
+        }
+        AppError::ChatUnavailable(_) => {
+            let paused = repo.block_user(reminder.user_id).await?;
+            SchedulerMetrics::inc(&metrics.blocked_users_total);
+            tracing::warn!(
+                "User {} blocked the bot, paused {} reminders",
+                reminder.user_id,
+                paused
+            );
+            Ok(true)
+        }
+        _ => {
+            let attempts = reminder.attempts + 1;
+            if attempts >= MAX_DELIVERY_ATTEMPTS {
+                let dead = repo
+                    .dead_letter(reminder.id, worker_id, attempts, &message)
+                    .await?;
+                if dead {
+                    SchedulerMetrics::inc(&metrics.dead_lettered_total);
+                    tracing::warn!(
+                        "Giving up on reminder {} after {} attempts",
+                        reminder.id,
+                        attempts
+                    );
                 }
-                tracing::info!("Reminder {} sent immediately", reminder.id);
+                return Ok(dead);
             }
-        });
+
+            let at = Utc::now() + retry_delay(attempts);
+            let retried = repo
+                .schedule_retry(reminder.id, worker_id, at, attempts, &message)
+                .await?;
+            if retried {
+                SchedulerMetrics::inc(&metrics.retried_total);
+            }
+            Ok(retried)
+        }
+    }
+}
+
+/// Exponential backoff: 30s, 1m, 2m, 4m, ... up to MAX_RETRY_DELAY
+fn retry_delay(attempts: i32) -> chrono::Duration {
+    let exponent = attempts.clamp(1, 16) as u32 - 1;
+    (FIRST_RETRY_DELAY * 2i32.pow(exponent)).min(MAX_RETRY_DELAY)
+}
+
+/// Send reminder on each of its channels and record the delivery
+///
+/// It counts as delivered if any channel succeeds; failed channels are only
+/// logged. If all fail, the Telegram error (if any) decides whether it is
+/// retried or the chat paused. Returns `false` if the lease was lost before
+/// the delivery was recorded.
+async fn deliver(
+    notifiers: &Notifiers,
+    pool: PgPool,
+    worker_id: &str,
+    reminder: &Reminder,
+    silent: bool,
+) -> Result<bool> {
+    let todo = match reminder.todo_id {
+        Some(todo_id) => TodoRepository::new(pool.clone())
+            .find_by_id(todo_id)
+            .await
+            .ok(),
+        None => None,
+    };
+    let repo = ReminderRepository::new(pool);
+
+    // Task was closed without cancelling its reminders
+    if todo.as_ref().is_some_and(|todo| !todo.is_open()) {
+        return repo.mark_as_sent(reminder.id, Some(worker_id)).await;
+    }
+
+    if reminder.escalation_due() {
+        return escalate(notifiers, &repo, worker_id, reminder).await;
+    }
+
+    let owner = repo.find_owner(reminder.user_id).await?;
+    let notification = Notification {
+        reminder,
+        todo: todo.as_ref(),
+        owner: &owner,
+        silent,
+    };
+
+    let mut delivered = false;
+    let mut failure = None;
+    for channel in reminder.delivery_channels(&owner) {
+        match notifiers.notify(channel, &notification).await {
+            Ok(()) => {
+                delivered = true;
+                log_delivery(&repo, reminder, Some(channel), DeliveryStatus::Sent, None).await;
+            }
+            Err(e) => {
+                tracing::warn!(
+                    "Reminder {} not delivered by {}: {}",
+                    reminder.id,
+                    channel,
+                    e
+                );
+                let message = e.to_string();
+                log_delivery(
+                    &repo,
+                    reminder,
+                    Some(channel),
+                    DeliveryStatus::Failed,
+                    Some(&message),
+                )
+                .await;
+                if failure.is_none() || channel == NotifyChannel::Telegram {
+                    failure = Some(e);
+                }
+            }
+        }
+    }
+
+    match failure {
+        Some(e) if !delivered => Err(e),
+        _ => complete_delivery(&repo, worker_id, reminder).await,
+    }
+}
+
+/// Add a delivery to the history; a failure here must not cause a resend
+async fn log_delivery(
+    repo: &ReminderRepository,
+    reminder: &Reminder,
+    channel: Option<NotifyChannel>,
+    status: DeliveryStatus,
+    error: Option<&str>,
+) {
+    if let Err(e) = repo.log_delivery(reminder, channel, status, error).await {
+        tracing::warn!("Failed to log delivery of reminder {}: {}", reminder.id, e);
+    }
+}
+
+/// Nobody acknowledged an insistent reminder: notify the escalation chat
+async fn escalate(
+    notifiers: &Notifiers,
+    repo: &ReminderRepository,
+    worker_id: &str,
+    reminder: &Reminder,
+) -> Result<bool> {
+    let Some(chat_id) = reminder.escalate_to else {
+        return repo.mark_as_sent(reminder.id, Some(worker_id)).await;
+    };
+
+    // Acknowledged after the row was fetched, or the lease was lost
+    if !repo.mark_escalated(reminder.id, worker_id).await? {
+        return Ok(false);
+    }
+
+    let owner = repo.find_owner(reminder.user_id).await?;
+
+    // Already recorded as escalated, so a failed send is not retried
+    if let Err(e) = notifiers
+        .telegram()
+        .send_escalation(reminder, &owner, ChatId(chat_id))
+        .await
+    {
+        tracing::warn!(
+            "Escalation of reminder {} was not delivered: {}",
+            reminder.id,
+            e
+        );
+    }
+    Ok(true)
+}
+
+/// Mark one-off reminder as sent or move recurring one to its next occurrence;
+/// an unacknowledged insistent reminder pings again after its interval
+///
+/// Returns `false` if nothing was updated, as the lease was lost.
+async fn complete_delivery(
+    repo: &ReminderRepository,
+    worker_id: &str,
+    reminder: &Reminder,
+) -> Result<bool> {
+    let worker = Some(worker_id);
+    if let Some(next_at) = reminder.next_nag_at(Utc::now()) {
+        return repo.schedule_nag(reminder.id, worker_id, next_at).await;
+    }
+
+    if !reminder.is_recurring {
+        return repo.mark_as_sent(reminder.id, worker).await;
+    }
+
+    // Same local time of day in the owner's zone, across DST changes;
+    // occurrences missed while the bot was down are skipped, not repeated
+    let tz = repo.find_owner_timezone(reminder.user_id).await?;
+    let local = reminder.remind_at.max(Utc::now()).with_timezone(&tz);
+
+    match reminder
+        .next_occurrence(&local)
+        .map(|next| next.with_timezone(&Utc))
+    {
+        Some(next_at) => repo.reschedule_next(reminder.id, next_at, worker).await,
+        None => {
+            tracing::warn!(
+                "Reminder {} has unsupported recurrence {:?}, marking as sent",
+                reminder.id,
+                reminder.recurrence_pattern
+            );
+            repo.mark_as_sent(reminder.id, worker).await
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_retry_delay() {
+        assert_eq!(retry_delay(1), chrono::Duration::seconds(30));
+        assert_eq!(retry_delay(2), chrono::Duration::minutes(1));
+        assert_eq!(retry_delay(4), chrono::Duration::minutes(4));
+        assert_eq!(retry_delay(8), MAX_RETRY_DELAY);
+        assert_eq!(retry_delay(1000), MAX_RETRY_DELAY);
     }
 }
@@ -1,12 +1,26 @@
 // Reminder service
 
-use crate::db::models::NewReminder;
+use crate::db::models::{
+    HistoryPage, NagPolicy, NewReminder, ReminderEdit, ReminderTarget, Todo,
+};
 use crate::error::{validation_error, Result};
 use crate::reminder::models::Reminder;
 use crate::reminder::repository::ReminderRepository;
-use crate::shared::types::DbId;
-use crate::shared::utils::parse_relative_time;
-use chrono::Utc;
+use crate::shared::cron::CronSchedule;
+use crate::shared::types::{DbId, RecurrencePattern, Timestamp};
+use crate::shared::time_parser::parse_reminder_time;
+use crate::shared::utils::RecurringSchedule;
+use chrono::{Duration, Utc};
+use chrono_tz::Tz;
+
+/// Upper bound for repeats of an insistent reminder
+pub const MAX_NAG_REPEATS: i32 = 50;
+
+/// Longest repeat interval of an insistent reminder
+const MAX_NAG_INTERVAL_MINUTES: i32 = 24 * 60;
+
+/// Deliveries per page of /reminderhistory
+pub const HISTORY_PAGE_SIZE: i64 = 10;
 
 #[derive(Clone)]
 pub struct ReminderService {
@@ -25,21 +39,31 @@ impl ReminderService {
         todo_id: Option<DbId>,
         time_input: &str,
         message: Option<String>,
+        target: ReminderTarget,
     ) -> Result<Reminder> {
         // Parse time
-        let remind_at = parse_relative_time(time_input)
-            .ok_or_else(|| validation_error("Invalid time format. Use: 30m, 2h, 1d"))?;
+        let remind_at = parse_reminder_time(time_input, &Utc::now()).ok_or_else(|| {
+            validation_error("Invalid time format. Use: 30m, 2h, tomorrow 9am, 20.10 14:30")
+        })?;
+
+        self.create_reminder_at(user_id, todo_id, remind_at, message, target)
+            .await
+    }
 
+    /// Create one-off reminder at an already parsed time
+    pub async fn create_reminder_at(
+        &self,
+        user_id: DbId,
+        todo_id: Option<DbId>,
+        remind_at: Timestamp,
+        message: Option<String>,
+        target: ReminderTarget,
+    ) -> Result<Reminder> {
         if remind_at <= Utc::now() {
             return Err(validation_error("Reminder time must be in the future"));
         }
 
-        // Validation сообщения
-        if let Some(ref msg) = message {
-            if msg.len() > 500 {
-                return Err(validation_error("Message is too long (max 500 chars)"));
-            }
-        }
+        validate_message(&message)?;
 
         let new_reminder = NewReminder {
             user_id,
@@ -48,28 +72,304 @@ impl ReminderService {
             message,
             is_recurring: false,
             recurrence_pattern: None,
+            nag: None,
+            due_offset_minutes: None,
+            target,
+        };
+
+        self.repo.create(new_reminder).await
+    }
+
+    /// Create reminder about a task at an already parsed time
+    pub async fn create_todo_reminder(
+        &self,
+        user_id: DbId,
+        todo: &Todo,
+        remind_at: Timestamp,
+        target: ReminderTarget,
+    ) -> Result<Reminder> {
+        if !todo.is_open() {
+            return Err(validation_error("Task is already closed"));
+        }
+
+        self.create_reminder_at(user_id, Some(todo.id), remind_at, None, target)
+            .await
+    }
+
+    /// Recreate automatic reminders of a task from the owner's offsets
+    ///
+    /// Called whenever the due date or status changes; offsets that are
+    /// already in the past are skipped. Returns the number created.
+    pub async fn sync_due_reminders(&self, todo: &Todo, offsets: &[i32]) -> Result<usize> {
+        self.repo.delete_pending_for_todo(todo.id, true).await?;
+
+        let due = match todo.due_date {
+            Some(due) if todo.is_open() => due,
+            _ => return Ok(0),
+        };
+
+        let times = due_reminder_times(due, offsets, Utc::now());
+        for (offset, remind_at) in &times {
+            let new_reminder = NewReminder {
+                user_id: todo.user_id,
+                todo_id: Some(todo.id),
+                remind_at: *remind_at,
+                message: None,
+                is_recurring: false,
+                recurrence_pattern: None,
+                nag: None,
+                due_offset_minutes: Some(*offset),
+                target: ReminderTarget::default(),
+            };
+            self.repo.create(new_reminder).await?;
+        }
+
+        Ok(times.len())
+    }
+
+    /// Drop pending reminders of a completed, cancelled or deleted task
+    pub async fn cancel_todo_reminders(&self, todo_id: DbId) -> Result<()> {
+        self.repo.delete_pending_for_todo(todo_id, false).await?;
+        Ok(())
+    }
+
+    /// Create insistent one-off reminder that repeats until acknowledged
+    pub async fn create_nagging_reminder(
+        &self,
+        user_id: DbId,
+        remind_at: Timestamp,
+        message: Option<String>,
+        nag: NagPolicy,
+        target: ReminderTarget,
+    ) -> Result<Reminder> {
+        if remind_at <= Utc::now() {
+            return Err(validation_error("Reminder time must be in the future"));
+        }
+        if !(1..=MAX_NAG_INTERVAL_MINUTES).contains(&nag.interval_minutes) {
+            return Err(validation_error(
+                "Repeat interval must be between 1 minute and 24 hours",
+            ));
+        }
+        if !(1..=MAX_NAG_REPEATS).contains(&nag.max_repeats) {
+            return Err(validation_error(format!(
+                "Number of repeats must be between 1 and {}",
+                MAX_NAG_REPEATS
+            )));
+        }
+
+        validate_message(&message)?;
+
+        let new_reminder = NewReminder {
+            user_id,
+            todo_id: None,
+            remind_at,
+            message,
+            is_recurring: false,
+            recurrence_pattern: None,
+            nag: Some(nag),
+            due_offset_minutes: None,
+            target,
+        };
+
+        self.repo.create(new_reminder).await
+    }
+
+    /// Create recurring reminder starting at the schedule's first occurrence
+    pub async fn create_recurring_reminder(
+        &self,
+        user_id: DbId,
+        todo_id: Option<DbId>,
+        schedule: RecurringSchedule,
+        target: ReminderTarget,
+    ) -> Result<Reminder> {
+        if schedule.first_at <= Utc::now() {
+            return Err(validation_error("Reminder time must be in the future"));
+        }
+
+        let message = Some(schedule.message).filter(|m| !m.is_empty());
+        validate_message(&message)?;
+
+        let new_reminder = NewReminder {
+            user_id,
+            todo_id,
+            remind_at: schedule.first_at,
+            message,
+            is_recurring: true,
+            recurrence_pattern: Some(schedule.pattern.to_string()),
+            nag: None,
+            due_offset_minutes: None,
+            target,
+        };
+
+        self.repo.create(new_reminder).await
+    }
+
+    /// Create reminder that fires on a cron schedule, starting at its next run
+    ///
+    /// The expression is evaluated in the user's time zone `tz`.
+    pub async fn create_cron_reminder(
+        &self,
+        user_id: DbId,
+        schedule: &CronSchedule,
+        message: String,
+        tz: Tz,
+        target: ReminderTarget,
+    ) -> Result<Reminder> {
+        let remind_at = schedule
+            .next_after(&Utc::now().with_timezone(&tz))
+            .ok_or_else(|| validation_error("Cron expression never fires"))?
+            .with_timezone(&Utc);
+
+        let message = Some(message).filter(|m| !m.is_empty());
+        validate_message(&message)?;
+
+        let pattern = RecurrencePattern::Custom(schedule.expression().to_string());
+        let new_reminder = NewReminder {
+            user_id,
+            todo_id: None,
+            remind_at,
+            message,
+            is_recurring: true,
+            recurrence_pattern: Some(pattern.to_string()),
+            nag: None,
+            due_offset_minutes: None,
+            target,
         };
 
         self.repo.create(new_reminder).await
     }
 
+    /// Snooze delivered reminder until `until`
+    ///
+    /// The same row is rescheduled; a recurring reminder continues with its
+    /// schedule after the snoozed delivery.
+    pub async fn snooze(&self, id: DbId, until: Timestamp) -> Result<Reminder> {
+        if until <= Utc::now() {
+            return Err(validation_error("Snooze time must be in the future"));
+        }
+
+        self.repo.snooze(id, until).await
+    }
+
+    /// Mark reminder as done: a one-off reminder will not fire again, a
+    /// snoozed recurring one goes back to its regular schedule
+    pub async fn mark_done(&self, reminder: &Reminder, tz: Tz) -> Result<()> {
+        if !reminder.is_recurring {
+            self.repo.mark_as_sent(reminder.id, None).await?;
+            return Ok(());
+        }
+        if reminder.scheduled_at.is_none() {
+            // Pending occurrence is already the regular one
+            return Ok(());
+        }
+
+        match reminder.next_occurrence(&Utc::now().with_timezone(&tz)) {
+            Some(next_at) => {
+                self.repo
+                    .reschedule_next(reminder.id, next_at.with_timezone(&Utc), None)
+                    .await?;
+            }
+            None => {
+                self.repo.mark_as_sent(reminder.id, None).await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Acknowledge insistent reminder; `None` if it already was
+    pub async fn acknowledge(&self, id: DbId) -> Result<Option<Reminder>> {
+        self.repo.acknowledge(id).await
+    }
+
+    /// Change time, text or recurrence of a pending reminder
+    pub async fn edit_reminder(&self, id: DbId, edit: ReminderEdit) -> Result<Reminder> {
+        if edit.remind_at.is_some_and(|at| at <= Utc::now()) {
+            return Err(validation_error("Reminder time must be in the future"));
+        }
+        if edit.message.as_deref().is_some_and(str::is_empty) {
+            return Err(validation_error("Reminder text cannot be empty"));
+        }
+        validate_message(&edit.message)?;
+
+        self.repo.edit(id, &edit).await?.ok_or_else(|| {
+            validation_error("The reminder is being sent right now, try again in a minute")
+        })
+    }
+
+    /// End a recurring series after its pending occurrence
+    pub async fn stop_recurrence(&self, id: DbId) -> Result<Reminder> {
+        self.repo.stop_recurrence(id).await
+    }
+
     /// Get user reminders
     pub async fn get_user_reminders(&self, user_id: DbId) -> Result<Vec<Reminder>> {
         self.repo.find_by_user(user_id).await
     }
 
+    /// Page of the user's delivery log; out of range pages are clamped
+    pub async fn history(&self, user_id: DbId, page: i64) -> Result<HistoryPage> {
+        let total = self.repo.count_history(user_id).await?;
+        let pages = ((total + HISTORY_PAGE_SIZE - 1) / HISTORY_PAGE_SIZE).max(1);
+        let page = page.clamp(1, pages);
+
+        let deliveries = self
+            .repo
+            .find_history(user_id, HISTORY_PAGE_SIZE, (page - 1) * HISTORY_PAGE_SIZE)
+            .await?;
+
+        Ok(HistoryPage {
+            deliveries,
+            page,
+            pages,
+        })
+    }
+
     /// Delete reminder
     pub async fn delete_reminder(&self, id: DbId) -> Result<()> {
         self.repo.delete(id).await
     }
 
-    /// Get pending reminders for scheduler
-    pub async fn get_pending_reminders(&self) -> Result<Vec<Reminder>> {
-        self.repo.get_pending_reminders(Utc::now()).await
-    }
-
     /// Mark reminder as sent
     pub async fn mark_sent(&self, id: DbId) -> Result<()> {
-        self.repo.mark_as_sent(id).await
+        self.repo.mark_as_sent(id, None).await?;
+        Ok(())
+    }
+}
+
+/// Reminder times `offset` minutes before `due` that are still ahead of `now`
+fn due_reminder_times(due: Timestamp, offsets: &[i32], now: Timestamp) -> Vec<(i32, Timestamp)> {
+    offsets
+        .iter()
+        .map(|&offset| (offset, due - Duration::minutes(offset.into())))
+        .filter(|(_, at)| *at > now)
+        .collect()
+}
+
+/// Validation сообщения
+fn validate_message(message: &Option<String>) -> Result<()> {
+    if let Some(ref msg) = message {
+        if msg.len() > 500 {
+            return Err(validation_error("Message is too long (max 500 chars)"));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_due_reminder_times() {
+        let due = Utc.with_ymd_and_hms(2026, 10, 21, 18, 0, 0).unwrap();
+        let now = Utc.with_ymd_and_hms(2026, 10, 20, 19, 0, 0).unwrap();
+
+        // A day before is already past, an hour before is not
+        assert_eq!(
+            due_reminder_times(due, &[24 * 60, 60], now),
+            vec![(60, Utc.with_ymd_and_hms(2026, 10, 21, 17, 0, 0).unwrap())]
+        );
+        assert!(due_reminder_times(due, &[], now).is_empty());
     }
 }
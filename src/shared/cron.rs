@@ -0,0 +1,457 @@
+// Cron schedules - standard 5-field cron expressions for reminders
+//
+// Format: "minute hour day-of-month month day-of-week"
+// Example: "0 9 * * 1-5" - at 09:00, Monday through Friday
+//
+// Parsing and next-run calculation use the croner crate;
+// the human-readable explanation is built here.
+
+use crate::error::{validation_error, Result};
+use chrono::{DateTime, Duration, TimeZone, Weekday};
+use croner::Cron;
+
+/// Minimum time between two runs (anti-spam)
+const MIN_INTERVAL_MINUTES: i64 = 5;
+
+/// How many upcoming runs are checked against the minimum interval
+const INTERVAL_CHECK_RUNS: usize = 10;
+
+/// Validated cron schedule
+#[derive(Debug, Clone)]
+pub struct CronSchedule {
+    expr: String,
+    cron: Cron,
+}
+
+impl CronSchedule {
+    /// Parse and validate a 5-field cron expression
+    pub fn parse(expr: &str) -> Result<Self> {
+        let expr = expr.split_whitespace().collect::<Vec<_>>().join(" ");
+
+        if expr.split(' ').count() != 5 && !expr.starts_with('@') {
+            return Err(validation_error(
+                "Cron expression must have 5 fields: minute hour day month weekday",
+            ));
+        }
+
+        let cron = Cron::new(&expr)
+            .parse()
+            .map_err(|e| validation_error(format!("Invalid cron expression: {}", e)))?;
+
+        let schedule = Self { expr, cron };
+        schedule.check_interval()?;
+        Ok(schedule)
+    }
+
+    /// Expression with normalized whitespace
+    pub fn expression(&self) -> &str {
+        &self.expr
+    }
+
+    /// Next run strictly after `after`, evaluated in `after`'s time zone
+    pub fn next_after<Tz: TimeZone>(&self, after: &DateTime<Tz>) -> Option<DateTime<Tz>> {
+        self.cron.find_next_occurrence(after, false).ok()
+    }
+
+    /// Next `count` runs after `after`
+    pub fn upcoming<Tz: TimeZone>(&self, after: &DateTime<Tz>, count: usize) -> Vec<DateTime<Tz>> {
+        let mut runs = Vec::with_capacity(count);
+        let mut current = after.clone();
+
+        while runs.len() < count {
+            match self.next_after(&current) {
+                Some(next) => {
+                    current = next.clone();
+                    runs.push(next);
+                }
+                None => break,
+            }
+        }
+
+        runs
+    }
+
+    /// Human-readable explanation, e.g. "at 09:00, Monday through Friday"
+    pub fn describe(&self) -> String {
+        describe_expression(self.expression())
+            .unwrap_or_else(|| format!("on cron schedule {}", self.expression()))
+    }
+
+    /// Reject schedules that fire more often than MIN_INTERVAL_MINUTES
+    fn check_interval(&self) -> Result<()> {
+        let start = chrono::Utc::now();
+        let runs = self.upcoming(&start, INTERVAL_CHECK_RUNS);
+
+        if runs.is_empty() {
+            return Err(validation_error("Cron expression never fires"));
+        }
+
+        let too_frequent = runs
+            .windows(2)
+            .any(|w| w[1] - w[0] < Duration::minutes(MIN_INTERVAL_MINUTES));
+        if too_frequent {
+            return Err(validation_error(format!(
+                "Cron reminders cannot fire more often than every {} minutes",
+                MIN_INTERVAL_MINUTES
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+/// Split `/cron` arguments into expression and message
+///
+/// The expression is either quoted (`"0 9 * * 1-5" Check dashboards`),
+/// a nickname (`@daily Backup`) or the first five words.
+pub fn split_cron_command(text: &str) -> Option<(String, String)> {
+    let text = text.trim();
+
+    let (expr, message) = if let Some(rest) = text.strip_prefix(['"', '“']) {
+        let end = rest.find(['"', '”'])?;
+        let closing_len = rest[end..].chars().next()?.len_utf8();
+        (&rest[..end], &rest[end + closing_len..])
+    } else if text.starts_with('@') {
+        text.split_once(char::is_whitespace).unwrap_or((text, ""))
+    } else {
+        let mut end = 0;
+        for word in text.split_whitespace().take(5) {
+            end = text[end..].find(word)? + end + word.len();
+        }
+        (&text[..end], &text[end..])
+    };
+
+    let expr = expr.split_whitespace().collect::<Vec<_>>().join(" ");
+    if expr.is_empty() {
+        return None;
+    }
+
+    Some((expr, message.trim().to_string()))
+}
+
+/// Explain a 5-field cron expression in English
+///
+/// Returns `None` for syntax the explainer does not cover
+/// (nicknames, `L`, `W`, `#`).
+pub fn describe_expression(expr: &str) -> Option<String> {
+    let fields: Vec<&str> = expr.split_whitespace().collect();
+    let [minute, hour, dom, month, dow] = fields[..] else {
+        return None;
+    };
+
+    let mut parts = vec![describe_time(minute, hour)?];
+
+    match (dom, dow) {
+        ("*", "*") => {}
+        (dom, "*") => {
+            let days = describe_list(dom, |n| Some(n.to_string()))?;
+            parts.push(format!("on day {} of the month", days));
+        }
+        ("*", dow) => parts.push(describe_list(dow, weekday_name)?),
+        (dom, dow) => {
+            // Classic cron: either condition matches
+            let days = describe_list(dom, |n| Some(n.to_string()))?;
+            let weekdays = describe_list(dow, weekday_name)?;
+            parts.push(format!("on day {} of the month and on {}", days, weekdays));
+        }
+    }
+
+    if month != "*" {
+        parts.push(format!("in {}", describe_list(month, month_name)?));
+    }
+
+    Some(parts.join(", "))
+}
+
+/// Minute and hour fields
+fn describe_time(minute: &str, hour: &str) -> Option<String> {
+    let minutes = parse_numbers(minute);
+    let hours = parse_numbers(hour);
+
+    match (minutes, hours, minute, hour) {
+        // 30 9 / 0 9,18
+        (Some(ms), Some(hs), _, _) => {
+            let mut times = Vec::new();
+            for h in &hs {
+                for m in &ms {
+                    times.push(format!("{:02}:{:02}", h, m));
+                }
+            }
+            Some(format!("at {}", times.join(", ")))
+        }
+        // 0 * / 15,45 *
+        (Some(ms), None, _, "*") => {
+            let ms: Vec<String> = ms.iter().map(|m| m.to_string()).collect();
+            Some(format!("at minute {} of every hour", ms.join(", ")))
+        }
+        // 0 */2 / 0 9-17
+        (Some(ms), None, _, hour) => {
+            let ms: Vec<String> = ms.iter().map(|m| format!("{:02}", m)).collect();
+            let hours = describe_step_or_range(hour, "hour")?;
+            Some(format!("at minute {} {}", ms.join(", "), hours))
+        }
+        // */15 *
+        (None, None, minute, "*") => describe_step_or_range(minute, "minute"),
+        // */15 9-17
+        (None, None, minute, hour) => {
+            let minutes = describe_step_or_range(minute, "minute")?;
+            let hours = describe_step_or_range(hour, "hour")?;
+            Some(format!("{}, {}", minutes, hours))
+        }
+        _ => None,
+    }
+}
+
+/// `*/15` -> "every 15 minutes", `9-17` -> "from 9 through 17 (hour)"
+fn describe_step_or_range(field: &str, unit: &str) -> Option<String> {
+    if field == "*" {
+        return Some(format!("every {}", unit));
+    }
+
+    if let Some((range, step)) = field.split_once('/') {
+        let step: u32 = step.parse().ok()?;
+        let base = format!("every {} {}s", step, unit);
+        return match range {
+            "*" => Some(base),
+            range => {
+                let (from, to) = range.split_once('-')?;
+                Some(format!("{} from {} through {}", base, from, to))
+            }
+        };
+    }
+
+    let (from, to) = field.split_once('-')?;
+    let (from, to): (u32, u32) = (from.parse().ok()?, to.parse().ok()?);
+    Some(match unit {
+        "hour" => format!("between {:02}:00 and {:02}:59", from, to),
+        _ => format!("every {} from {} through {}", unit, from, to),
+    })
+}
+
+/// Comma list of numbers (no ranges or steps)
+fn parse_numbers(field: &str) -> Option<Vec<u32>> {
+    field.split(',').map(|n| n.parse().ok()).collect()
+}
+
+/// Comma list of values and `a-b` ranges, named with `name`
+fn describe_list(field: &str, name: impl Fn(u32) -> Option<String>) -> Option<String> {
+    let mut items = Vec::new();
+
+    for item in field.split(',') {
+        if item.contains('/') {
+            return None;
+        }
+        match item.split_once('-') {
+            Some((from, to)) => {
+                let from = name(parse_named(from)?)?;
+                let to = name(parse_named(to)?)?;
+                items.push(format!("{} through {}", from, to));
+            }
+            None => items.push(name(parse_named(item)?)?),
+        }
+    }
+
+    Some(items.join(", "))
+}
+
+/// Number or English abbreviation (MON, JAN)
+fn parse_named(value: &str) -> Option<u32> {
+    if let Ok(n) = value.parse() {
+        return Some(n);
+    }
+
+    let upper = value.to_uppercase();
+    if let Ok(day) = upper.parse::<Weekday>() {
+        return Some(day.num_days_from_sunday());
+    }
+
+    const MONTHS: [&str; 12] = [
+        "JAN", "FEB", "MAR", "APR", "MAY", "JUN", "JUL", "AUG", "SEP", "OCT", "NOV", "DEC",
+    ];
+    MONTHS
+        .iter()
+        .position(|m| *m == upper)
+        .map(|i| i as u32 + 1)
+}
+
+/// 0 and 7 are Sunday
+fn weekday_name(n: u32) -> Option<String> {
+    const NAMES: [&str; 8] = [
+        "Sunday",
+        "Monday",
+        "Tuesday",
+        "Wednesday",
+        "Thursday",
+        "Friday",
+        "Saturday",
+        "Sunday",
+    ];
+    NAMES.get(n as usize).map(|s| s.to_string())
+}
+
+fn month_name(n: u32) -> Option<String> {
+    const NAMES: [&str; 12] = [
+        "January",
+        "February",
+        "March",
+        "April",
+        "May",
+        "June",
+        "July",
+        "August",
+        "September",
+        "October",
+        "November",
+        "December",
+    ];
+    NAMES
+        .get((n as usize).checked_sub(1)?)
+        .map(|s| s.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn at(y: i32, mo: u32, d: u32, h: u32, mi: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(y, mo, d, h, mi, 0).unwrap()
+    }
+
+    #[test]
+    fn test_parse_validation() {
+        assert!(CronSchedule::parse("0 9 * * 1-5").is_ok());
+        assert!(CronSchedule::parse("  0   9 * *   MON-FRI ").is_ok());
+        assert!(CronSchedule::parse("@daily").is_ok());
+
+        // Wrong field count, including 6-field (seconds) expressions
+        assert!(CronSchedule::parse("0 9 * *").is_err());
+        assert!(CronSchedule::parse("0 0 9 * * 1").is_err());
+        // Out of range
+        assert!(CronSchedule::parse("60 9 * * *").is_err());
+        assert!(CronSchedule::parse("0 25 * * *").is_err());
+        assert!(CronSchedule::parse("nonsense here a b c").is_err());
+        // Too frequent
+        assert!(CronSchedule::parse("* * * * *").is_err());
+        assert!(CronSchedule::parse("*/2 * * * *").is_err());
+        assert!(CronSchedule::parse("*/5 * * * *").is_ok());
+    }
+
+    #[test]
+    fn test_next_runs() {
+        let schedule = CronSchedule::parse("0 9 * * 1-5").unwrap();
+        // Friday 2026-10-23 10:00 -> Monday 09:00
+        let friday = at(2026, 10, 23, 10, 0);
+        assert_eq!(schedule.next_after(&friday), Some(at(2026, 10, 26, 9, 0)));
+
+        let runs = schedule.upcoming(&friday, 5);
+        assert_eq!(
+            runs,
+            vec![
+                at(2026, 10, 26, 9, 0),
+                at(2026, 10, 27, 9, 0),
+                at(2026, 10, 28, 9, 0),
+                at(2026, 10, 29, 9, 0),
+                at(2026, 10, 30, 9, 0),
+            ]
+        );
+
+        // Strictly after: a run exactly at `after` is skipped
+        assert_eq!(
+            schedule.next_after(&at(2026, 10, 26, 9, 0)),
+            Some(at(2026, 10, 27, 9, 0))
+        );
+    }
+
+    #[test]
+    fn test_next_runs_in_time_zone() {
+        let schedule = CronSchedule::parse("0 9 * * *").unwrap();
+        let moscow = chrono::FixedOffset::east_opt(3 * 3600).unwrap();
+        let after = moscow.with_ymd_and_hms(2026, 10, 19, 12, 0, 0).unwrap();
+
+        let next = schedule.next_after(&after).unwrap();
+        assert_eq!(
+            next,
+            moscow.with_ymd_and_hms(2026, 10, 20, 9, 0, 0).unwrap()
+        );
+        assert_eq!(next.with_timezone(&Utc), at(2026, 10, 20, 6, 0));
+    }
+
+    #[test]
+    fn test_split_cron_command() {
+        let split = |s: &str| split_cron_command(s);
+        let pair = |e: &str, m: &str| Some((e.to_string(), m.to_string()));
+
+        assert_eq!(
+            split(r#""0 9 * * 1-5" Check dashboards"#),
+            pair("0 9 * * 1-5", "Check dashboards")
+        );
+        assert_eq!(
+            split("“0 9 * * 1-5” Check dashboards"),
+            pair("0 9 * * 1-5", "Check dashboards")
+        );
+        assert_eq!(
+            split("0 9 * * 1-5 Check dashboards"),
+            pair("0 9 * * 1-5", "Check dashboards")
+        );
+        assert_eq!(
+            split("  */30  9-17 * * *  Stretch "),
+            pair("*/30 9-17 * * *", "Stretch")
+        );
+        assert_eq!(split("@daily Backup"), pair("@daily", "Backup"));
+        assert_eq!(split("0 9 * * 1-5"), pair("0 9 * * 1-5", ""));
+
+        assert_eq!(split(r#""0 9 * * 1-5 Check"#), None);
+        assert_eq!(split(""), None);
+        assert_eq!(split(r#""" Text"#), None);
+    }
+
+    #[test]
+    fn test_describe() {
+        let cases = [
+            ("0 9 * * 1-5", "at 09:00, Monday through Friday"),
+            (
+                "30 8 * * MON,WED,FRI",
+                "at 08:30, Monday, Wednesday, Friday",
+            ),
+            ("0 9,18 * * *", "at 09:00, 18:00"),
+            ("0 10 1,15 * *", "at 10:00, on day 1, 15 of the month"),
+            ("0 12 1 1 *", "at 12:00, on day 1 of the month, in January"),
+            ("0 9 * 6-8 0", "at 09:00, Sunday, in June through August"),
+            ("15 * * * *", "at minute 15 of every hour"),
+            ("0 */2 * * *", "at minute 00 every 2 hours"),
+            (
+                "0 9-17 * * 1-5",
+                "at minute 00 between 09:00 and 17:59, Monday through Friday",
+            ),
+            ("*/15 * * * *", "every 15 minutes"),
+            (
+                "*/30 9-17 * * *",
+                "every 30 minutes, between 09:00 and 17:59",
+            ),
+            (
+                "0 9 13 * 5",
+                "at 09:00, on day 13 of the month and on Friday",
+            ),
+        ];
+
+        for (expr, expected) in cases {
+            assert_eq!(
+                describe_expression(expr).as_deref(),
+                Some(expected),
+                "{}",
+                expr
+            );
+        }
+    }
+
+    #[test]
+    fn test_describe_fallback() {
+        assert_eq!(describe_expression("0 9 L * *"), None);
+        assert_eq!(describe_expression("0 9 * * FRI#2"), None);
+        assert_eq!(describe_expression("@daily"), None);
+
+        let schedule = CronSchedule::parse("0 9 L * *").unwrap();
+        assert_eq!(schedule.describe(), "on cron schedule 0 9 L * *");
+    }
+}
@@ -0,0 +1,138 @@
+// Durations - one parser for every "<n><unit>" input in the bot
+//
+// Used by /remind (30m, 1h30m), /track (45m) and quick-add estimates (~2h).
+// Units: s (seconds), m (minutes), h (hours), d (days), w (weeks);
+// parts can be combined: 1h30m, 2d4h, 1w2d.
+
+use crate::error::{validation_error, AppError, Result};
+use chrono::Duration;
+
+/// Longest accepted duration
+pub const MAX_DURATION_DAYS: i64 = 365;
+
+/// Parse compound duration like `45s`, `30m`, `1h30m`, `2d4h`, `2w`
+pub fn parse_duration(input: &str) -> Result<Duration> {
+    let input = input.trim().to_lowercase();
+    if input.is_empty() {
+        return Err(validation_error(
+            "Duration is empty. Use e.g. 30m, 1h30m or 2d",
+        ));
+    }
+
+    let mut total = Duration::zero();
+    let mut number = String::new();
+
+    for c in input.chars() {
+        if c.is_ascii_digit() {
+            number.push(c);
+            continue;
+        }
+
+        if number.is_empty() {
+            return Err(validation_error(format!(
+                "Invalid duration '{}': expected a number before '{}'",
+                input, c
+            )));
+        }
+
+        // Anything longer than the maximum is rejected below; this only
+        // keeps the arithmetic from overflowing
+        let n: i64 = number
+            .parse()
+            .ok()
+            .filter(|n| *n <= MAX_DURATION_DAYS * 24 * 60 * 60)
+            .ok_or_else(too_long)?;
+        number.clear();
+
+        total += match c {
+            's' => Duration::seconds(n),
+            'm' => Duration::minutes(n),
+            'h' => Duration::hours(n),
+            'd' => Duration::days(n),
+            'w' => Duration::weeks(n),
+            _ => {
+                return Err(validation_error(format!(
+                    "Unknown time unit '{}' in '{}'. Use s, m, h, d or w",
+                    c, input
+                )));
+            }
+        };
+
+        if total > Duration::days(MAX_DURATION_DAYS) {
+            return Err(too_long());
+        }
+    }
+
+    if !number.is_empty() {
+        return Err(validation_error(format!(
+            "Missing time unit after {} in '{}'. Use s, m, h, d or w",
+            number, input
+        )));
+    }
+
+    if total <= Duration::zero() {
+        return Err(validation_error("Duration must be positive"));
+    }
+
+    Ok(total)
+}
+
+fn too_long() -> AppError {
+    validation_error(format!(
+        "Duration is too long (max {} days)",
+        MAX_DURATION_DAYS
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_simple_units() {
+        assert_eq!(parse_duration("45s").unwrap(), Duration::seconds(45));
+        assert_eq!(parse_duration("30m").unwrap(), Duration::minutes(30));
+        assert_eq!(parse_duration("500m").unwrap(), Duration::minutes(500));
+        assert_eq!(parse_duration("2H").unwrap(), Duration::hours(2));
+        assert_eq!(parse_duration("300d").unwrap(), Duration::days(300));
+        assert_eq!(parse_duration("2w").unwrap(), Duration::weeks(2));
+    }
+
+    #[test]
+    fn test_compound() {
+        assert_eq!(parse_duration("1h30m").unwrap(), Duration::minutes(90));
+        assert_eq!(parse_duration("2d4h").unwrap(), Duration::hours(52));
+        assert_eq!(parse_duration("1w2d").unwrap(), Duration::days(9));
+        assert_eq!(parse_duration("1m30s").unwrap(), Duration::seconds(90));
+        assert_eq!(parse_duration(" 1h1h ").unwrap(), Duration::hours(2));
+    }
+
+    #[test]
+    fn test_limits() {
+        assert!(parse_duration("365d").is_ok());
+        assert!(parse_duration("52w").is_ok());
+
+        let err = parse_duration("366d").unwrap_err().to_string();
+        assert!(err.contains("too long"), "{}", err);
+        let err = parse_duration("53w").unwrap_err().to_string();
+        assert!(err.contains("too long"), "{}", err);
+        let err = parse_duration("99999999999999999999m")
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("too long"), "{}", err);
+    }
+
+    #[test]
+    fn test_errors() {
+        let message = |s: &str| parse_duration(s).unwrap_err().to_string();
+
+        assert!(message("").contains("empty"));
+        assert!(message("0m").contains("positive"));
+        assert!(message("90").contains("Missing time unit after 90"));
+        assert!(message("1h30").contains("Missing time unit after 30"));
+        assert!(message("5x").contains("Unknown time unit 'x'"));
+        assert!(message("h").contains("expected a number"));
+        assert!(message("-5m").contains("expected a number"));
+        assert!(message("1.5h").contains("Unknown time unit '.'"));
+    }
+}
@@ -0,0 +1,277 @@
+// Health server - liveness and metrics endpoint on PORT
+//
+// Minimal HTTP/1.1 over a TcpListener: one request per connection,
+// GET /health answers "ok", GET /metrics the scheduler and converter
+// metrics and GET /calendar/<token>.ics a user's reminders as an ICS feed.
+//
+// The port is public (calendar feeds), so metrics need METRICS_TOKEN as a
+// bearer token, or a connection from the same host if none is set. Slow
+// clients time out after IO_TIMEOUT and at most MAX_CONNECTIONS are served
+// at once.
+
+use crate::converter::metrics::ConversionMetrics;
+use crate::error::Result;
+use crate::reminder::calendar;
+use crate::reminder::metrics::SchedulerMetrics;
+use sqlx::PgPool;
+use std::io;
+use std::net::IpAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::Semaphore;
+use tokio::time::timeout;
+
+/// Largest request head that is read
+const MAX_REQUEST_BYTES: usize = 4096;
+
+/// Time a client has to send its request, and to take the response
+const IO_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Connections served at once; further ones wait to be accepted
+const MAX_CONNECTIONS: usize = 64;
+
+const TEXT: &str = "text/plain; version=0.0.4";
+
+/// Metrics served on GET /metrics
+#[derive(Debug, Clone, Default)]
+pub struct Metrics {
+    pub scheduler: Arc<SchedulerMetrics>,
+    pub conversion: Arc<ConversionMetrics>,
+}
+
+impl Metrics {
+    fn render(&self) -> String {
+        self.scheduler.render() + &self.conversion.render()
+    }
+}
+
+/// Serve health checks, metrics and calendar feeds until the process exits
+///
+/// `metrics_token` is required for GET /metrics; without it metrics are
+/// only served to local connections.
+pub async fn serve(
+    port: u16,
+    metrics: Metrics,
+    metrics_token: Option<String>,
+    pool: PgPool,
+) -> Result<()> {
+    let listener = TcpListener::bind(("0.0.0.0", port)).await?;
+    tracing::info!("Health server listening on port {}", port);
+
+    let connections = Arc::new(Semaphore::new(MAX_CONNECTIONS));
+    let metrics_token: Option<Arc<str>> = metrics_token.map(Into::into);
+    loop {
+        let Ok(permit) = Arc::clone(&connections).acquire_owned().await else {
+            return Ok(());
+        };
+        let (stream, peer) = listener.accept().await?;
+        let metrics = metrics.clone();
+        let metrics_token = metrics_token.clone();
+        let pool = pool.clone();
+        tokio::spawn(async move {
+            let allowed =
+                |request: &str| metrics_allowed(request, peer.ip(), metrics_token.as_deref());
+            if let Err(e) = respond(stream, &metrics, allowed, &pool).await {
+                tracing::debug!("Health request failed: {}", e);
+            }
+            drop(permit);
+        });
+    }
+}
+
+async fn respond(
+    mut stream: TcpStream,
+    metrics: &Metrics,
+    metrics_allowed: impl Fn(&str) -> bool,
+    pool: &PgPool,
+) -> Result<()> {
+    let mut buffer = vec![0; MAX_REQUEST_BYTES];
+    let read = timeout(IO_TIMEOUT, stream.read(&mut buffer))
+        .await
+        .map_err(io::Error::from)??;
+    let request = String::from_utf8_lossy(&buffer[..read]);
+
+    let (status, content_type, body) = match feed_token(&request) {
+        Some(token) => calendar_feed(pool, token).await,
+        None => {
+            let (status, body) = route(&request, metrics, metrics_allowed(&request));
+            (status, TEXT, body)
+        }
+    };
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        content_type,
+        body.len(),
+        body
+    );
+    timeout(IO_TIMEOUT, stream.write_all(response.as_bytes()))
+        .await
+        .map_err(io::Error::from)??;
+    Ok(())
+}
+
+/// Status line and body for a raw request
+///
+/// `metrics_allowed`: the client may read GET /metrics.
+fn route(request: &str, metrics: &Metrics, metrics_allowed: bool) -> (&'static str, String) {
+    let mut parts = request.split_whitespace();
+    let method = parts.next().unwrap_or_default();
+    let path = parts.next().unwrap_or_default();
+
+    match (method, path) {
+        ("GET", "/health") => ("200 OK", "ok\n".to_string()),
+        ("GET", "/metrics") if metrics_allowed => ("200 OK", metrics.render()),
+        ("GET", "/metrics") => ("403 Forbidden", "forbidden\n".to_string()),
+        _ => ("404 Not Found", "not found\n".to_string()),
+    }
+}
+
+/// With a token, a request needs it as `Authorization: Bearer <token>`;
+/// without one, only connections from the same host are allowed
+fn metrics_allowed(request: &str, peer: IpAddr, token: Option<&str>) -> bool {
+    let Some(token) = token else {
+        return peer.is_loopback();
+    };
+
+    let bearer = request
+        .lines()
+        .skip(1)
+        .take_while(|line| !line.is_empty())
+        .filter_map(|line| line.split_once(':'))
+        .find(|(name, _)| name.trim().eq_ignore_ascii_case("authorization"))
+        .and_then(|(_, value)| value.trim().strip_prefix("Bearer "));
+
+    bearer.is_some_and(|given| constant_time_eq(given.trim().as_bytes(), token.as_bytes()))
+}
+
+/// Compare secrets without leaking the length of the matching prefix
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+/// Token of a `GET /calendar/<token>.ics` request
+fn feed_token(request: &str) -> Option<&str> {
+    let mut parts = request.split_whitespace();
+    if parts.next()? != "GET" {
+        return None;
+    }
+    let path = parts.next()?;
+    let path = path.split_once('?').map_or(path, |(path, _)| path);
+
+    let token = path.strip_prefix("/calendar/")?.strip_suffix(".ics")?;
+    let valid =
+        !token.is_empty() && token.len() <= 64 && token.chars().all(|c| c.is_ascii_alphanumeric());
+    valid.then_some(token)
+}
+
+/// Status, content type and body of a calendar feed
+async fn calendar_feed(pool: &PgPool, token: &str) -> (&'static str, &'static str, String) {
+    match calendar::feed(pool, token).await {
+        Ok(Some(body)) => ("200 OK", "text/calendar; charset=utf-8", body),
+        Ok(None) => ("404 Not Found", TEXT, "not found\n".to_string()),
+        Err(e) => {
+            tracing::error!("Failed to build calendar feed: {}", e);
+            ("500 Internal Server Error", TEXT, "error\n".to_string())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_route() {
+        let metrics = Metrics::default();
+
+        assert_eq!(
+            route("GET /health HTTP/1.1\r\n\r\n", &metrics, false),
+            ("200 OK", "ok\n".to_string())
+        );
+
+        let (status, body) = route("GET /metrics HTTP/1.1\r\n\r\n", &metrics, true);
+        assert_eq!(status, "200 OK");
+        assert!(body.contains("reminder_queue_size 0"));
+        assert!(body.contains("conversion_cleanup_runs_total 0"));
+        assert_eq!(
+            route("GET /metrics HTTP/1.1\r\n\r\n", &metrics, false),
+            ("403 Forbidden", "forbidden\n".to_string())
+        );
+
+        assert_eq!(
+            route("POST /health HTTP/1.1\r\n\r\n", &metrics, true).0,
+            "404 Not Found"
+        );
+        assert_eq!(route("", &metrics, true).0, "404 Not Found");
+    }
+
+    #[test]
+    fn test_metrics_allowed() {
+        let local: IpAddr = "127.0.0.1".parse().unwrap();
+        let remote: IpAddr = "203.0.113.7".parse().unwrap();
+        let plain = "GET /metrics HTTP/1.1\r\nHost: bot\r\n\r\n";
+        let bearer = |token: &str| {
+            format!(
+                "GET /metrics HTTP/1.1\r\nHost: bot\r\nauthorization: Bearer {}\r\n\r\n",
+                token
+            )
+        };
+
+        // No token configured: same host only
+        assert!(metrics_allowed(plain, local, None));
+        assert!(metrics_allowed(plain, "::1".parse().unwrap(), None));
+        assert!(!metrics_allowed(plain, remote, None));
+        assert!(!metrics_allowed(&bearer("s3cret"), remote, None));
+
+        // Token configured: required from everyone
+        let token = Some("s3cret");
+        assert!(metrics_allowed(&bearer("s3cret"), remote, token));
+        assert!(!metrics_allowed(&bearer("s3cre"), remote, token));
+        assert!(!metrics_allowed(&bearer("S3CRET"), remote, token));
+        assert!(!metrics_allowed(plain, local, token));
+        assert!(!metrics_allowed(
+            "GET /metrics HTTP/1.1\r\n\r\nAuthorization: Bearer s3cret",
+            remote,
+            token
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_slow_client_times_out() {
+        tokio::time::pause();
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let _client = TcpStream::connect(address).await.unwrap();
+        let (stream, _) = listener.accept().await.unwrap();
+
+        // Connects but never sends a request
+        let pool = sqlx::postgres::PgPoolOptions::new()
+            .connect_lazy("postgres://localhost/unused")
+            .unwrap();
+        let result = respond(stream, &Metrics::default(), |_| true, &pool).await;
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_feed_token() {
+        assert_eq!(
+            feed_token("GET /calendar/0f3a9c.ics HTTP/1.1\r\n\r\n"),
+            Some("0f3a9c")
+        );
+        assert_eq!(
+            feed_token("GET /calendar/0f3a9c.ics?refresh=1 HTTP/1.1\r\n\r\n"),
+            Some("0f3a9c")
+        );
+        assert_eq!(
+            feed_token("POST /calendar/0f3a9c.ics HTTP/1.1\r\n\r\n"),
+            None
+        );
+        assert_eq!(feed_token("GET /calendar/.ics HTTP/1.1\r\n\r\n"), None);
+        assert_eq!(feed_token("GET /calendar/../x.ics HTTP/1.1\r\n\r\n"), None);
+        assert_eq!(feed_token("GET /health HTTP/1.1\r\n\r\n"), None);
+    }
+}
@@ -0,0 +1,692 @@
+// iCalendar (RFC 5545) - export of reminders and due dates, import of events
+//
+// Only what a reminder can express is supported: VEVENTs with a start
+// time, RRULEs that map onto RecurrencePattern and the first VALARM.
+
+use crate::error::{validation_error, Result};
+use crate::shared::duration::MAX_DURATION_DAYS;
+use crate::shared::timezone::resolve_local;
+use crate::shared::types::{RecurrencePattern, Timestamp};
+use chrono::{DateTime, Datelike, Duration, NaiveDate, NaiveDateTime, Utc, Weekday};
+use chrono_tz::Tz;
+
+/// Product identifier of exported calendars
+const PRODUCT_ID: &str = "-//telegram-multitool-bot//Reminders//EN";
+
+/// Content lines longer than this (in bytes) are folded
+const LINE_LIMIT: usize = 75;
+
+/// All-day events have no time of their own: remind at 09:00
+const ALL_DAY_TIME: (u32, u32) = (9, 0);
+
+/// Event of an exported calendar
+#[derive(Debug, Clone, PartialEq)]
+pub struct CalendarEvent {
+    /// Stable across exports, so subscribed calendars update in place
+    pub uid: String,
+    pub summary: String,
+    pub description: Option<String>,
+    pub start: DateTime<Tz>,
+    pub recurrence: Option<RecurrencePattern>,
+}
+
+/// Event read from an uploaded calendar
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImportedEvent {
+    pub summary: String,
+    /// First occurrence as written in the file
+    pub start: Timestamp,
+    pub recurrence: Option<RecurrencePattern>,
+    /// Lead time of the event's first alarm
+    pub alarm_before: Option<Duration>,
+}
+
+/// Event that cannot become a reminder
+#[derive(Debug, Clone, PartialEq)]
+pub struct SkippedEvent {
+    pub summary: String,
+    pub reason: &'static str,
+}
+
+/// Events of an uploaded calendar
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ParsedCalendar {
+    pub events: Vec<ImportedEvent>,
+    pub skipped: Vec<SkippedEvent>,
+}
+
+/// Render a calendar; `now` is the DTSTAMP of every event
+pub fn write_calendar(name: &str, events: &[CalendarEvent], now: Timestamp) -> String {
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        format!("PRODID:{}", PRODUCT_ID),
+        "CALSCALE:GREGORIAN".to_string(),
+        format!("X-WR-CALNAME:{}", escape_text(name)),
+    ];
+
+    for event in events {
+        lines.push("BEGIN:VEVENT".to_string());
+        lines.push(format!("UID:{}", event.uid));
+        lines.push(format!("DTSTAMP:{}", format_utc(now)));
+        match event.recurrence.as_ref().and_then(rrule) {
+            // Local time and zone: the series keeps its time across DST
+            Some(rule) => {
+                lines.push(format!(
+                    "DTSTART;TZID={}:{}",
+                    event.start.timezone().name(),
+                    event.start.format("%Y%m%dT%H%M%S")
+                ));
+                lines.push(format!("RRULE:{}", rule));
+            }
+            None => lines.push(format!(
+                "DTSTART:{}",
+                format_utc(event.start.with_timezone(&Utc))
+            )),
+        }
+        lines.push(format!("SUMMARY:{}", escape_text(&event.summary)));
+        if let Some(description) = &event.description {
+            lines.push(format!("DESCRIPTION:{}", escape_text(description)));
+        }
+        // The calendar app rings together with the bot
+        lines.push("BEGIN:VALARM".to_string());
+        lines.push("ACTION:DISPLAY".to_string());
+        lines.push(format!("DESCRIPTION:{}", escape_text(&event.summary)));
+        lines.push("TRIGGER:PT0S".to_string());
+        lines.push("END:VALARM".to_string());
+        lines.push("END:VEVENT".to_string());
+    }
+    lines.push("END:VCALENDAR".to_string());
+
+    lines.iter().map(|line| fold_line(line)).collect()
+}
+
+/// RRULE of a pattern; `None` for cron schedules
+pub fn rrule(pattern: &RecurrencePattern) -> Option<String> {
+    Some(match pattern {
+        RecurrencePattern::Daily => "FREQ=DAILY".to_string(),
+        RecurrencePattern::Weekly => "FREQ=WEEKLY".to_string(),
+        RecurrencePattern::WeeklyOn(days) if !days.is_empty() => {
+            let days: Vec<&str> = days.iter().map(|day| weekday_code(*day)).collect();
+            format!("FREQ=WEEKLY;BYDAY={}", days.join(","))
+        }
+        RecurrencePattern::WeeklyOn(_) => return None,
+        RecurrencePattern::Monthly => "FREQ=MONTHLY".to_string(),
+        // Day 31 is clamped to the month's end, which is what -1 means
+        RecurrencePattern::MonthlyOn(31) => "FREQ=MONTHLY;BYMONTHDAY=-1".to_string(),
+        RecurrencePattern::MonthlyOn(day) => format!("FREQ=MONTHLY;BYMONTHDAY={}", day),
+        RecurrencePattern::Yearly { month, day } => {
+            format!("FREQ=YEARLY;BYMONTH={};BYMONTHDAY={}", month, day)
+        }
+        RecurrencePattern::Custom(_) => return None,
+    })
+}
+
+/// Read the VEVENTs of a calendar; times without a zone are in `tz`
+pub fn parse_calendar(input: &str, tz: Tz) -> Result<ParsedCalendar> {
+    let lines = unfold(input);
+    if !lines
+        .first()
+        .is_some_and(|line| line.eq_ignore_ascii_case("BEGIN:VCALENDAR"))
+    {
+        return Err(validation_error("This is not an iCalendar (.ics) file"));
+    }
+
+    let mut calendar = ParsedCalendar::default();
+    let mut event: Option<EventFields> = None;
+    let mut in_alarm = false;
+
+    for line in &lines {
+        let Some(property) = Property::parse(line) else {
+            continue;
+        };
+        let value = property.value;
+
+        match property.name.as_str() {
+            "BEGIN" if value.eq_ignore_ascii_case("VEVENT") => {
+                event = Some(EventFields::default());
+            }
+            "END" if value.eq_ignore_ascii_case("VEVENT") => {
+                if let Some(fields) = event.take() {
+                    match fields.finish(tz) {
+                        Ok(imported) => calendar.events.push(imported),
+                        Err(skipped) => calendar.skipped.push(skipped),
+                    }
+                }
+            }
+            "BEGIN" if value.eq_ignore_ascii_case("VALARM") => in_alarm = true,
+            "END" if value.eq_ignore_ascii_case("VALARM") => in_alarm = false,
+            _ => {
+                let Some(fields) = event.as_mut() else {
+                    continue;
+                };
+                if in_alarm {
+                    if property.name == "TRIGGER" && fields.alarm_before.is_none() {
+                        fields.alarm_before = parse_trigger(&property);
+                    }
+                    continue;
+                }
+                match property.name.as_str() {
+                    "SUMMARY" => fields.summary = unescape_text(value),
+                    "DTSTART" => fields.start = Some(parse_date_time(&property, tz)),
+                    "RRULE" => fields.rrule = Some(value.to_string()),
+                    "STATUS" => fields.cancelled = value.eq_ignore_ascii_case("CANCELLED"),
+                    "RECURRENCE-ID" => fields.is_override = true,
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    Ok(calendar)
+}
+
+/// Properties of a VEVENT collected so far
+#[derive(Default)]
+struct EventFields {
+    summary: String,
+    /// `Some(None)` if DTSTART could not be read
+    start: Option<Option<Timestamp>>,
+    rrule: Option<String>,
+    alarm_before: Option<Duration>,
+    cancelled: bool,
+    /// Changed single occurrence of a series
+    is_override: bool,
+}
+
+impl EventFields {
+    fn finish(self, tz: Tz) -> std::result::Result<ImportedEvent, SkippedEvent> {
+        let summary = match self.summary.trim() {
+            "" => "Event".to_string(),
+            summary => summary.to_string(),
+        };
+        let skip = |reason| {
+            Err(SkippedEvent {
+                summary: summary.clone(),
+                reason,
+            })
+        };
+
+        if self.cancelled {
+            return skip("cancelled");
+        }
+        if self.is_override {
+            return skip("changed occurrence of a series");
+        }
+        let Some(Some(start)) = self.start else {
+            return skip("no start time");
+        };
+        let recurrence = match &self.rrule {
+            None => None,
+            Some(rule) => match parse_rrule(rule, start.with_timezone(&tz).date_naive()) {
+                Some(pattern) => Some(pattern),
+                None => return skip("repeat rule not supported"),
+            },
+        };
+
+        Ok(ImportedEvent {
+            summary,
+            start,
+            recurrence,
+            alarm_before: self.alarm_before,
+        })
+    }
+}
+
+/// Content line: `NAME;PARAM=value:VALUE`
+struct Property<'a> {
+    name: String,
+    params: Vec<(String, String)>,
+    value: &'a str,
+}
+
+impl<'a> Property<'a> {
+    fn parse(line: &'a str) -> Option<Self> {
+        // The value starts at the first colon outside a quoted parameter
+        let mut quoted = false;
+        let colon = line.char_indices().find_map(|(i, c)| {
+            if c == '"' {
+                quoted = !quoted;
+            }
+            (c == ':' && !quoted).then_some(i)
+        })?;
+
+        let mut head = line[..colon].split(';');
+        let name = head.next()?.trim().to_ascii_uppercase();
+        let params = head
+            .filter_map(|param| param.split_once('='))
+            .map(|(key, value)| {
+                (
+                    key.to_ascii_uppercase(),
+                    value.trim_matches('"').to_string(),
+                )
+            })
+            .collect();
+
+        Some(Self {
+            name,
+            params,
+            value: line[colon + 1..].trim(),
+        })
+    }
+
+    fn param(&self, key: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// Join folded lines (a leading space or tab continues the previous one)
+fn unfold(input: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for raw in input.trim_start_matches('\u{feff}').split('\n') {
+        let raw = raw.strip_suffix('\r').unwrap_or(raw);
+        match (raw.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(rest), Some(last)) => last.push_str(rest),
+            _ if raw.trim().is_empty() => {}
+            _ => lines.push(raw.to_string()),
+        }
+    }
+    lines
+}
+
+/// DTSTART in UTC, with TZID, floating (read in `tz`) or an all-day date
+fn parse_date_time(property: &Property, tz: Tz) -> Option<Timestamp> {
+    let value = property.value;
+
+    if property.param("VALUE") == Some("DATE") || value.len() == 8 {
+        let date = NaiveDate::parse_from_str(value, "%Y%m%d").ok()?;
+        let (hour, minute) = ALL_DAY_TIME;
+        let local = resolve_local(&tz, date.and_hms_opt(hour, minute, 0)?)?;
+        return Some(local.with_timezone(&Utc));
+    }
+
+    if let Some(utc) = value.strip_suffix(['Z', 'z']) {
+        let naive = NaiveDateTime::parse_from_str(utc, "%Y%m%dT%H%M%S").ok()?;
+        return Some(naive.and_utc());
+    }
+
+    let naive = NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S").ok()?;
+    // Windows zone names (Outlook) are not IANA: fall back to the user's
+    let zone = property
+        .param("TZID")
+        .and_then(|id| id.parse::<Tz>().ok())
+        .unwrap_or(tz);
+    resolve_local(&zone, naive).map(|local| local.with_timezone(&Utc))
+}
+
+/// RRULE that maps onto a pattern; series that end (COUNT, UNTIL) or skip
+/// occurrences (INTERVAL > 1) are not supported
+fn parse_rrule(rule: &str, start: NaiveDate) -> Option<RecurrencePattern> {
+    let mut freq = None;
+    let mut by_day = None;
+    let mut by_month_day = None;
+    let mut by_month = None;
+
+    for part in rule.split(';').filter(|part| !part.is_empty()) {
+        let (key, value) = part.split_once('=')?;
+        match key.to_ascii_uppercase().as_str() {
+            "FREQ" => freq = Some(value.to_ascii_uppercase()),
+            "INTERVAL" if value == "1" => {}
+            "WKST" => {}
+            "BYDAY" => {
+                let mut days = value
+                    .split(',')
+                    .map(weekday_from_code)
+                    .collect::<Option<Vec<_>>>()?;
+                days.sort_by_key(|day| day.num_days_from_monday());
+                days.dedup();
+                by_day = Some(days);
+            }
+            "BYMONTHDAY" => by_month_day = Some(value.parse::<i32>().ok()?),
+            "BYMONTH" => by_month = Some(value.parse::<u32>().ok()?),
+            _ => return None,
+        }
+    }
+
+    Some(match (freq?.as_str(), by_day, by_month_day, by_month) {
+        ("DAILY", None, None, None) => RecurrencePattern::Daily,
+        // Working days are often written as a daily rule
+        ("DAILY" | "WEEKLY", Some(days), None, None) => RecurrencePattern::WeeklyOn(days),
+        ("WEEKLY", None, None, None) => RecurrencePattern::Weekly,
+        ("MONTHLY", None, None, None) => RecurrencePattern::Monthly,
+        ("MONTHLY", None, Some(-1), None) => RecurrencePattern::MonthlyOn(31),
+        ("MONTHLY", None, Some(day @ 1..=31), None) => RecurrencePattern::MonthlyOn(day as u32),
+        ("YEARLY", None, None, None) => RecurrencePattern::Yearly {
+            month: start.month(),
+            day: start.day(),
+        },
+        ("YEARLY", None, Some(day @ 1..=31), Some(month @ 1..=12)) => RecurrencePattern::Yearly {
+            month,
+            day: day as u32,
+        },
+        _ => return None,
+    })
+}
+
+/// Lead time of an alarm before the event's start
+///
+/// Alarms relative to the end, at absolute times or after the start are
+/// ignored.
+fn parse_trigger(property: &Property) -> Option<Duration> {
+    if property.param("RELATED") == Some("END") || property.param("VALUE") == Some("DATE-TIME") {
+        return None;
+    }
+    let offset = parse_ical_duration(property.value)?;
+    (offset <= Duration::zero()).then(|| -offset)
+}
+
+/// Duration value: `-PT15M`, `P1D`, `-P1W`, `PT1H30M`
+///
+/// `None` for durations longer than MAX_DURATION_DAYS, like `parse_duration`.
+fn parse_ical_duration(value: &str) -> Option<Duration> {
+    let (negative, rest) = match value.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, value.strip_prefix('+').unwrap_or(value)),
+    };
+    let rest = rest.strip_prefix(['P', 'p'])?;
+
+    let mut total = Duration::zero();
+    let mut number = String::new();
+    let mut in_time = false;
+    for c in rest.chars() {
+        match c.to_ascii_uppercase() {
+            'T' => in_time = true,
+            '0'..='9' => number.push(c),
+            unit => {
+                let n: i64 = number.parse().ok()?;
+                number.clear();
+                let part = match (unit, in_time) {
+                    ('W', false) => Duration::try_weeks(n),
+                    ('D', false) => Duration::try_days(n),
+                    ('H', true) => Duration::try_hours(n),
+                    ('M', true) => Duration::try_minutes(n),
+                    ('S', true) => Duration::try_seconds(n),
+                    _ => return None,
+                };
+                total = total.checked_add(&part?)?;
+                if total > Duration::days(MAX_DURATION_DAYS) {
+                    return None;
+                }
+            }
+        }
+    }
+    if !number.is_empty() {
+        return None;
+    }
+
+    Some(if negative { -total } else { total })
+}
+
+fn weekday_code(day: Weekday) -> &'static str {
+    match day {
+        Weekday::Mon => "MO",
+        Weekday::Tue => "TU",
+        Weekday::Wed => "WE",
+        Weekday::Thu => "TH",
+        Weekday::Fri => "FR",
+        Weekday::Sat => "SA",
+        Weekday::Sun => "SU",
+    }
+}
+
+/// Plain weekday code; `1MO` (first Monday) and the like are not supported
+fn weekday_from_code(code: &str) -> Option<Weekday> {
+    Some(match code.trim().to_ascii_uppercase().as_str() {
+        "MO" => Weekday::Mon,
+        "TU" => Weekday::Tue,
+        "WE" => Weekday::Wed,
+        "TH" => Weekday::Thu,
+        "FR" => Weekday::Fri,
+        "SA" => Weekday::Sat,
+        "SU" => Weekday::Sun,
+        _ => return None,
+    })
+}
+
+fn format_utc(at: Timestamp) -> String {
+    at.format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+/// Escape TEXT values: backslash, semicolon, comma and newlines
+fn escape_text(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace("\r\n", "\\n")
+        .replace('\n', "\\n")
+}
+
+fn unescape_text(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        match (c, c == '\\') {
+            (_, true) => match chars.next() {
+                Some('n' | 'N') => result.push('\n'),
+                Some(next) => result.push(next),
+                None => {}
+            },
+            (c, false) => result.push(c),
+        }
+    }
+    result
+}
+
+/// Fold a content line into CRLF-terminated lines of at most 75 bytes,
+/// never splitting a character
+fn fold_line(line: &str) -> String {
+    let mut folded = String::with_capacity(line.len() + 8);
+    let mut width = 0;
+    for c in line.chars() {
+        if width + c.len_utf8() > LINE_LIMIT {
+            folded.push_str("\r\n ");
+            width = 1;
+        }
+        folded.push(c);
+        width += c.len_utf8();
+    }
+    folded.push_str("\r\n");
+    folded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn moscow() -> Tz {
+        "Europe/Moscow".parse().unwrap()
+    }
+
+    #[test]
+    fn test_write_calendar() {
+        let now = Utc.with_ymd_and_hms(2026, 10, 18, 12, 0, 0).unwrap();
+        let events = [
+            CalendarEvent {
+                uid: "reminder-1@test".to_string(),
+                summary: "Standup, daily; room 5".to_string(),
+                description: None,
+                start: moscow().with_ymd_and_hms(2026, 10, 19, 10, 0, 0).unwrap(),
+                recurrence: Some(RecurrencePattern::WeeklyOn(vec![
+                    Weekday::Mon,
+                    Weekday::Fri,
+                ])),
+            },
+            CalendarEvent {
+                uid: "todo-2@test".to_string(),
+                summary: "Due: report".to_string(),
+                description: Some("Line one\nline two".to_string()),
+                start: moscow().with_ymd_and_hms(2026, 10, 20, 18, 0, 0).unwrap(),
+                recurrence: None,
+            },
+        ];
+
+        let calendar = write_calendar("Reminders", &events, now);
+
+        assert!(calendar.starts_with("BEGIN:VCALENDAR\r\nVERSION:2.0\r\n"));
+        assert!(calendar.ends_with("END:VCALENDAR\r\n"));
+        assert!(calendar.contains("DTSTART;TZID=Europe/Moscow:20261019T100000\r\n"));
+        assert!(calendar.contains("RRULE:FREQ=WEEKLY;BYDAY=MO,FR\r\n"));
+        assert!(calendar.contains("SUMMARY:Standup\\, daily\\; room 5\r\n"));
+        assert!(calendar.contains("DTSTART:20261020T150000Z\r\n"));
+        assert!(calendar.contains("DESCRIPTION:Line one\\nline two\r\n"));
+        assert!(calendar.contains("DTSTAMP:20261018T120000Z\r\n"));
+        assert_eq!(calendar.matches("BEGIN:VALARM").count(), 2);
+    }
+
+    #[test]
+    fn test_fold_line() {
+        let line = "SUMMARY:".to_string() + &"я".repeat(60);
+        let folded = fold_line(&line);
+
+        assert!(folded.split("\r\n").all(|part| part.len() <= LINE_LIMIT));
+        assert_eq!(unfold(&folded), vec![line]);
+        assert_eq!(fold_line("VERSION:2.0"), "VERSION:2.0\r\n");
+    }
+
+    #[test]
+    fn test_rrule_round_trip() {
+        let start = NaiveDate::from_ymd_opt(2026, 3, 14).unwrap();
+        let patterns = [
+            RecurrencePattern::Daily,
+            RecurrencePattern::Weekly,
+            RecurrencePattern::WeeklyOn(vec![Weekday::Mon, Weekday::Wed]),
+            RecurrencePattern::Monthly,
+            RecurrencePattern::MonthlyOn(15),
+            RecurrencePattern::MonthlyOn(31),
+            RecurrencePattern::Yearly { month: 3, day: 14 },
+        ];
+        for pattern in patterns {
+            let rule = rrule(&pattern).unwrap();
+            assert_eq!(parse_rrule(&rule, start), Some(pattern), "{}", rule);
+        }
+
+        assert_eq!(
+            rrule(&RecurrencePattern::Custom("0 9 * * 1-5".to_string())),
+            None
+        );
+        assert_eq!(
+            parse_rrule("FREQ=DAILY;BYDAY=FR,MO,TU,WE,TH", start),
+            Some(RecurrencePattern::WeeklyOn(
+                crate::shared::types::WORKDAYS.to_vec()
+            ))
+        );
+        assert_eq!(
+            parse_rrule("FREQ=YEARLY", start),
+            Some(RecurrencePattern::Yearly { month: 3, day: 14 })
+        );
+        assert_eq!(parse_rrule("FREQ=WEEKLY;INTERVAL=2", start), None);
+        assert_eq!(parse_rrule("FREQ=DAILY;COUNT=5", start), None);
+        assert_eq!(parse_rrule("FREQ=MONTHLY;BYDAY=1MO", start), None);
+    }
+
+    #[test]
+    fn test_parse_calendar() {
+        let input = "BEGIN:VCALENDAR\r\n\
+            VERSION:2.0\r\n\
+            BEGIN:VEVENT\r\n\
+            SUMMARY:Dentist\\, bring\r\n  card\r\n\
+            DTSTART:20261020T070000Z\r\n\
+            BEGIN:VALARM\r\n\
+            TRIGGER:-PT30M\r\n\
+            END:VALARM\r\n\
+            END:VEVENT\r\n\
+            BEGIN:VEVENT\r\n\
+            SUMMARY:Standup\r\n\
+            DTSTART;TZID=\"Europe/Berlin\":20261019T093000\r\n\
+            RRULE:FREQ=WEEKLY;BYDAY=MO,TU,WE,TH,FR\r\n\
+            BEGIN:VALARM\r\n\
+            TRIGGER:-P99999999999999W\r\n\
+            END:VALARM\r\n\
+            END:VEVENT\r\n\
+            BEGIN:VEVENT\r\n\
+            SUMMARY:Birthday\r\n\
+            DTSTART;VALUE=DATE:20261105\r\n\
+            RRULE:FREQ=YEARLY\r\n\
+            END:VEVENT\r\n\
+            BEGIN:VEVENT\r\n\
+            SUMMARY:Floating\r\n\
+            DTSTART:20261021T120000\r\n\
+            END:VEVENT\r\n\
+            BEGIN:VEVENT\r\n\
+            SUMMARY:Sprint\r\n\
+            DTSTART:20261020T080000Z\r\n\
+            RRULE:FREQ=WEEKLY;INTERVAL=2\r\n\
+            END:VEVENT\r\n\
+            BEGIN:VEVENT\r\n\
+            SUMMARY:Dropped\r\n\
+            STATUS:CANCELLED\r\n\
+            DTSTART:20261020T080000Z\r\n\
+            END:VEVENT\r\n\
+            END:VCALENDAR\r\n";
+
+        let calendar = parse_calendar(input, moscow()).unwrap();
+
+        assert_eq!(
+            calendar.events,
+            vec![
+                ImportedEvent {
+                    summary: "Dentist, bring card".to_string(),
+                    start: Utc.with_ymd_and_hms(2026, 10, 20, 7, 0, 0).unwrap(),
+                    recurrence: None,
+                    alarm_before: Some(Duration::minutes(30)),
+                },
+                ImportedEvent {
+                    summary: "Standup".to_string(),
+                    start: Utc.with_ymd_and_hms(2026, 10, 19, 7, 30, 0).unwrap(),
+                    recurrence: Some(RecurrencePattern::WeeklyOn(
+                        crate::shared::types::WORKDAYS.to_vec()
+                    )),
+                    alarm_before: None,
+                },
+                ImportedEvent {
+                    summary: "Birthday".to_string(),
+                    start: Utc.with_ymd_and_hms(2026, 11, 5, 6, 0, 0).unwrap(),
+                    recurrence: Some(RecurrencePattern::Yearly { month: 11, day: 5 }),
+                    alarm_before: None,
+                },
+                ImportedEvent {
+                    summary: "Floating".to_string(),
+                    start: Utc.with_ymd_and_hms(2026, 10, 21, 9, 0, 0).unwrap(),
+                    recurrence: None,
+                    alarm_before: None,
+                },
+            ]
+        );
+        assert_eq!(
+            calendar.skipped,
+            vec![
+                SkippedEvent {
+                    summary: "Sprint".to_string(),
+                    reason: "repeat rule not supported"
+                },
+                SkippedEvent {
+                    summary: "Dropped".to_string(),
+                    reason: "cancelled"
+                },
+            ]
+        );
+
+        assert!(parse_calendar("hello", moscow()).is_err());
+    }
+
+    #[test]
+    fn test_parse_ical_duration() {
+        assert_eq!(parse_ical_duration("-PT15M"), Some(Duration::minutes(-15)));
+        assert_eq!(parse_ical_duration("PT1H30M"), Some(Duration::minutes(90)));
+        assert_eq!(parse_ical_duration("-P1DT2H"), Some(Duration::hours(-26)));
+        assert_eq!(parse_ical_duration("-P1W"), Some(Duration::weeks(-1)));
+        assert_eq!(parse_ical_duration("PT0S"), Some(Duration::zero()));
+        assert_eq!(parse_ical_duration("P1H"), None);
+        assert_eq!(parse_ical_duration("15M"), None);
+
+        // Out of range: ignored instead of overflowing
+        assert_eq!(parse_ical_duration("-P99999999999999W"), None);
+        assert_eq!(parse_ical_duration("PT9223372036854775807S"), None);
+        assert_eq!(parse_ical_duration("-P366D"), None);
+        assert_eq!(parse_ical_duration("-P365D"), Some(Duration::days(-365)));
+    }
+}
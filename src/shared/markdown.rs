@@ -0,0 +1,116 @@
+// MarkdownV2 builder - Telegram formatting that user text cannot break
+//
+// Every string passed in is escaped; markup only comes from the builder's
+// own methods. Send the result with ParseMode::MarkdownV2.
+
+use crate::shared::utils::escape_markdown;
+
+/// Message text in MarkdownV2
+#[derive(Debug, Clone, Default)]
+pub struct Markdown {
+    out: String,
+}
+
+impl Markdown {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Plain text
+    pub fn text(&mut self, text: &str) -> &mut Self {
+        self.out.push_str(&escape_markdown(text));
+        self
+    }
+
+    pub fn bold(&mut self, text: &str) -> &mut Self {
+        self.out.push('*');
+        self.text(text);
+        self.out.push('*');
+        self
+    }
+
+    pub fn italic(&mut self, text: &str) -> &mut Self {
+        self.out.push('_');
+        self.text(text);
+        self.out.push('_');
+        self
+    }
+
+    /// Inline link; inside the URL only `)` and `\` need escaping
+    pub fn link(&mut self, label: &str, url: &str) -> &mut Self {
+        self.out.push('[');
+        self.text(label);
+        self.out.push_str("](");
+        self.out
+            .push_str(&url.replace('\\', "\\\\").replace(')', "\\)"));
+        self.out.push(')');
+        self
+    }
+
+    /// Block quote on lines of its own
+    pub fn quote(&mut self, text: &str) -> &mut Self {
+        if !self.out.is_empty() && !self.out.ends_with('\n') {
+            self.out.push('\n');
+        }
+        for line in text.lines() {
+            self.out.push('>');
+            self.text(line);
+            self.out.push('\n');
+        }
+        self
+    }
+
+    pub fn newline(&mut self) -> &mut Self {
+        self.out.push('\n');
+        self
+    }
+
+    /// Start a new paragraph after a blank line
+    pub fn paragraph(&mut self) -> &mut Self {
+        if !self.out.is_empty() {
+            while !self.out.ends_with("\n\n") {
+                self.out.push('\n');
+            }
+        }
+        self
+    }
+
+    pub fn finish(&self) -> String {
+        self.out.trim_end_matches('\n').to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_markdown_escapes_user_text() {
+        let mut md = Markdown::new();
+        md.bold("Reminder!")
+            .newline()
+            .text("Pay *rent* (1.5k) ")
+            .italic("_now_")
+            .newline()
+            .link("Open [chat]", "https://t.me/c/1/2?q=(x)");
+
+        assert_eq!(
+            md.finish(),
+            "*Reminder\\!*\nPay \\*rent\\* \\(1\\.5k\\) _\\_now\\__\n\
+             [Open \\[chat\\]](https://t.me/c/1/2?q=(x\\))"
+        );
+    }
+
+    #[test]
+    fn test_markdown_quote() {
+        let mut md = Markdown::new();
+        md.text("Said:")
+            .quote("> not a quote\n\nline 3")
+            .text("after");
+        assert_eq!(md.finish(), "Said:\n>\\> not a quote\n>\n>line 3\nafter");
+
+        let mut md = Markdown::new();
+        md.paragraph().quote("quoted").paragraph().text("next");
+        assert_eq!(md.finish(), ">quoted\n\nnext");
+    }
+}
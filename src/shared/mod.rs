@@ -1,4 +1,12 @@
 // Shared utilities - общие типы и утилиты
+pub mod cron;
+pub mod duration;
+pub mod health;
+pub mod ical;
+pub mod markdown;
+pub mod smtp;
+pub mod time_parser;
+pub mod timezone;
 pub mod types;
 pub mod utils;
 pub mod telemetry;
@@ -0,0 +1,436 @@
+// SMTP client - plain mail submission for the email notification channel
+//
+// Speaks just enough RFC 5321 to hand one message to a relay: EHLO,
+// STARTTLS when offered (or TLS from the start, SMTP_SECURITY=tls),
+// optional AUTH PLAIN, MAIL/RCPT/DATA. Credentials never travel
+// unencrypted, except to a relay on this machine.
+
+use crate::config::{SmtpConfig, SmtpSecurity};
+use crate::error::{AppError, Result};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use chrono::{DateTime, Utc};
+use std::net::IpAddr;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+use tokio::time::{timeout, Duration};
+use tokio_native_tls::{native_tls, TlsConnector, TlsStream};
+use uuid::Uuid;
+
+/// Limit for the whole conversation with the relay
+const SMTP_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Base64 body line length (RFC 2045 allows up to 76 characters)
+const BODY_LINE: usize = 76;
+
+/// Subject bytes per encoded word, so each stays under 75 characters
+const HEADER_CHUNK: usize = 45;
+
+/// Plain-text message
+#[derive(Debug, Clone)]
+pub struct Mail<'a> {
+    pub to: &'a str,
+    pub subject: &'a str,
+    pub body: &'a str,
+}
+
+/// Hand a message to the relay
+pub async fn send_mail(config: &SmtpConfig, mail: &Mail<'_>) -> Result<()> {
+    timeout(SMTP_TIMEOUT, transaction(config, mail))
+        .await
+        .map_err(|_| AppError::Notification(format!("SMTP server {} timed out", config.host)))?
+}
+
+async fn transaction(config: &SmtpConfig, mail: &Mail<'_>) -> Result<()> {
+    let stream = TcpStream::connect((config.host.as_str(), config.port)).await?;
+    let peer = stream.peer_addr()?.ip();
+
+    if config.security == SmtpSecurity::Tls {
+        let mut session = Session::new(encrypt(config, stream).await?);
+        session.check("greeting", 2).await?;
+        session.ehlo(config).await?;
+        return session.deliver(config, mail, true).await;
+    }
+
+    let mut session = Session::new(stream);
+    session.check("greeting", 2).await?;
+    let extensions = session.ehlo(config).await?;
+    if extensions.iter().any(|extension| extension == "STARTTLS") {
+        session.command("STARTTLS", "STARTTLS", 2).await?;
+        let mut session = Session::new(encrypt(config, session.stream.into_inner()).await?);
+        session.ehlo(config).await?;
+        return session.deliver(config, mail, true).await;
+    }
+    session.deliver(config, mail, may_send_plain(peer)).await
+}
+
+/// TLS over `stream`, checking the certificate against SMTP_HOST
+async fn encrypt(config: &SmtpConfig, stream: TcpStream) -> Result<TlsStream<TcpStream>> {
+    let tls_error = |e: native_tls::Error| {
+        AppError::Notification(format!("TLS with {} failed: {}", config.host, e))
+    };
+    let connector = TlsConnector::from(native_tls::TlsConnector::new().map_err(tls_error)?);
+    connector
+        .connect(&config.host, stream)
+        .await
+        .map_err(tls_error)
+}
+
+/// Credentials may go unencrypted only to a relay on this machine
+fn may_send_plain(peer: IpAddr) -> bool {
+    peer.is_loopback()
+}
+
+/// Connection to the relay
+struct Session<S> {
+    stream: BufReader<S>,
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin> Session<S> {
+    fn new(stream: S) -> Self {
+        Self {
+            stream: BufReader::new(stream),
+        }
+    }
+
+    /// Greet the server; returns the keywords of the extensions it offers
+    async fn ehlo(&mut self, config: &SmtpConfig) -> Result<Vec<String>> {
+        let reply = self
+            .command("EHLO", &format!("EHLO {}", domain(&config.from)), 2)
+            .await?;
+        // The first line is the server's name
+        Ok(reply
+            .iter()
+            .skip(1)
+            .filter_map(|line| line.get(4..)?.split_whitespace().next())
+            .map(str::to_uppercase)
+            .collect())
+    }
+
+    /// Authenticate if configured and hand over the message; `secure` -
+    /// the connection is encrypted or local
+    async fn deliver(&mut self, config: &SmtpConfig, mail: &Mail<'_>, secure: bool) -> Result<()> {
+        if let (Some(username), Some(password)) = (&config.username, &config.password) {
+            if !secure {
+                return Err(AppError::Notification(format!(
+                    "SMTP server {} does not offer STARTTLS, not sending the password unencrypted",
+                    config.host
+                )));
+            }
+            let credentials = STANDARD.encode(format!("\0{}\0{}", username, password));
+            self.command("AUTH", &format!("AUTH PLAIN {}", credentials), 2)
+                .await?;
+        }
+        self.command("MAIL", &format!("MAIL FROM:<{}>", config.from), 2)
+            .await?;
+        self.command("RCPT", &format!("RCPT TO:<{}>", mail.to), 2)
+            .await?;
+        self.command("DATA", "DATA", 3).await?;
+
+        let message = format_message(&config.from, mail, Utc::now(), Uuid::new_v4());
+        self.command("message", &format!("{}\r\n.", message), 2)
+            .await?;
+
+        // Already accepted: a failed goodbye changes nothing
+        let _ = self.command("QUIT", "QUIT", 2).await;
+        Ok(())
+    }
+
+    /// Send a line and check the reply; `stage` names it in errors
+    async fn command(&mut self, stage: &str, line: &str, class: u16) -> Result<Vec<String>> {
+        self.stream
+            .get_mut()
+            .write_all(format!("{}\r\n", line).as_bytes())
+            .await?;
+        self.check(stage, class).await
+    }
+
+    /// Read a reply, possibly multi-line, and compare its first digit;
+    /// returns the reply's lines
+    async fn check(&mut self, stage: &str, class: u16) -> Result<Vec<String>> {
+        let mut reply = Vec::new();
+        loop {
+            let mut line = String::new();
+            if self.stream.read_line(&mut line).await? == 0 {
+                return Err(AppError::Notification(format!(
+                    "SMTP server closed the connection at {}",
+                    stage
+                )));
+            }
+
+            let line = line.trim_end();
+            let code: u16 = line
+                .get(..3)
+                .and_then(|code| code.parse().ok())
+                .ok_or_else(|| AppError::Notification(format!("Malformed SMTP reply: {}", line)))?;
+
+            reply.push(line.to_string());
+            // "250-..." continues, "250 ..." ends the reply
+            if line.as_bytes().get(3) == Some(&b'-') {
+                continue;
+            }
+            if code / 100 != class {
+                return Err(AppError::Notification(format!(
+                    "SMTP {} rejected: {}",
+                    stage, line
+                )));
+            }
+            return Ok(reply);
+        }
+    }
+}
+
+/// Headers and a base64 body; base64 lines never start with a dot, so
+/// the terminating "." cannot appear inside the message
+fn format_message(from: &str, mail: &Mail<'_>, date: DateTime<Utc>, id: Uuid) -> String {
+    let mut message = format!(
+        "From: <{}>\r\n\
+         To: <{}>\r\n\
+         Subject: {}\r\n\
+         Date: {}\r\n\
+         Message-ID: <{}@{}>\r\n\
+         MIME-Version: 1.0\r\n\
+         Content-Type: text/plain; charset=utf-8\r\n\
+         Content-Transfer-Encoding: base64\r\n\
+         \r\n",
+        from,
+        mail.to,
+        encode_header(mail.subject),
+        date.to_rfc2822(),
+        id.simple(),
+        domain(from)
+    );
+
+    let body = STANDARD.encode(mail.body.replace("\r\n", "\n").replace('\n', "\r\n"));
+    let lines: Vec<&str> = body
+        .as_bytes()
+        .chunks(BODY_LINE)
+        .map(|chunk| std::str::from_utf8(chunk).unwrap_or_default())
+        .collect();
+    message.push_str(&lines.join("\r\n"));
+    message
+}
+
+/// Header value as is if plain ASCII, otherwise as folded UTF-8 encoded
+/// words (RFC 2047); line breaks are replaced so no header can be injected
+fn encode_header(text: &str) -> String {
+    let text: String = text
+        .chars()
+        .map(|c| if c.is_control() { ' ' } else { c })
+        .collect();
+    if text.is_ascii() {
+        return text;
+    }
+
+    let mut words = Vec::new();
+    let mut chunk = String::new();
+    for c in text.chars() {
+        if chunk.len() + c.len_utf8() > HEADER_CHUNK {
+            words.push(format!("=?UTF-8?B?{}?=", STANDARD.encode(&chunk)));
+            chunk.clear();
+        }
+        chunk.push(c);
+    }
+    if !chunk.is_empty() {
+        words.push(format!("=?UTF-8?B?{}?=", STANDARD.encode(&chunk)));
+    }
+    words.join("\r\n ")
+}
+
+/// Domain of an address, for EHLO and message IDs
+fn domain(address: &str) -> &str {
+    address
+        .rsplit_once('@')
+        .map(|(_, domain)| domain)
+        .filter(|domain| !domain.is_empty())
+        .unwrap_or("localhost")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::AsyncReadExt;
+    use tokio::net::TcpListener;
+    use tokio::task::JoinHandle;
+
+    const PLAIN: &str = "250-sink\r\n250 AUTH PLAIN\r\n";
+
+    fn mail() -> Mail<'static> {
+        Mail {
+            to: "alice@example.com",
+            subject: "Напоминание: позвонить маме",
+            body: "Call mom\n.\nDue 18:00",
+        }
+    }
+
+    fn decode_body(message: &str) -> String {
+        let (_, body) = message.split_once("\r\n\r\n").unwrap();
+        let bytes = STANDARD.decode(body.replace("\r\n", "")).unwrap();
+        String::from_utf8(bytes).unwrap()
+    }
+
+    /// Local SMTP sink: records the session, offers the extensions in
+    /// `ehlo_reply` and answers RCPT with `rcpt_reply`
+    async fn sink(
+        ehlo_reply: &'static str,
+        rcpt_reply: &'static str,
+    ) -> (SmtpConfig, JoinHandle<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        let session = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut stream = BufReader::new(stream);
+            let mut log = String::new();
+            stream
+                .get_mut()
+                .write_all(b"220 sink ready\r\n")
+                .await
+                .unwrap();
+
+            loop {
+                let mut line = String::new();
+                if stream.read_line(&mut line).await.unwrap() == 0 {
+                    return log;
+                }
+                log.push_str(&line);
+                let reply = match line.split([' ', ':']).next().unwrap().trim() {
+                    "EHLO" => ehlo_reply,
+                    "AUTH" => "235 ok\r\n",
+                    "RCPT" => rcpt_reply,
+                    "DATA" => {
+                        stream
+                            .get_mut()
+                            .write_all(b"354 go ahead\r\n")
+                            .await
+                            .unwrap();
+                        let mut data = Vec::new();
+                        while !data.ends_with(b"\r\n.\r\n") {
+                            let mut byte = [0];
+                            stream.read_exact(&mut byte).await.unwrap();
+                            data.push(byte[0]);
+                        }
+                        log.push_str(&String::from_utf8(data).unwrap());
+                        "250 queued\r\n"
+                    }
+                    // Not a TLS server: the handshake fails
+                    "STARTTLS" => {
+                        let _ = stream
+                            .get_mut()
+                            .write_all(b"220 go ahead\r\nnot tls\r\n")
+                            .await;
+                        return log;
+                    }
+                    "QUIT" => {
+                        let _ = stream.get_mut().write_all(b"221 bye\r\n").await;
+                        return log;
+                    }
+                    _ => "250 ok\r\n",
+                };
+                stream.get_mut().write_all(reply.as_bytes()).await.unwrap();
+            }
+        });
+
+        let config = SmtpConfig {
+            host: "127.0.0.1".to_string(),
+            port,
+            security: SmtpSecurity::StartTls,
+            from: "bot@example.com".to_string(),
+            username: Some("bot".to_string()),
+            password: Some("secret".to_string()),
+        };
+        (config, session)
+    }
+
+    #[test]
+    fn test_format_message() {
+        let id = Uuid::nil();
+        let date = Utc::now();
+        let message = format_message("bot@example.com", &mail(), date, id);
+
+        assert!(message.starts_with("From: <bot@example.com>\r\nTo: <alice@example.com>\r\n"));
+        assert!(message.contains(&format!("Message-ID: <{}@example.com>", id.simple())));
+        assert!(message.contains("Subject: =?UTF-8?B?"));
+        assert_eq!(decode_body(&message), "Call mom\r\n.\r\nDue 18:00");
+        assert!(message
+            .split("\r\n")
+            .all(|line| line.len() <= 998 && line != "."));
+    }
+
+    #[test]
+    fn test_encode_header() {
+        assert_eq!(encode_header("Call\r\nBcc: x"), "Call  Bcc: x");
+
+        let encoded = encode_header(&"я".repeat(40));
+        let words: Vec<&str> = encoded.split("\r\n ").collect();
+        assert_eq!(words.len(), 2);
+        assert!(words.iter().all(|word| word.len() <= 75));
+        let decoded: String = words
+            .iter()
+            .map(|word| {
+                let base64 = word.trim_start_matches("=?UTF-8?B?").trim_end_matches("?=");
+                String::from_utf8(STANDARD.decode(base64).unwrap()).unwrap()
+            })
+            .collect();
+        assert_eq!(decoded, "я".repeat(40));
+    }
+
+    #[tokio::test]
+    async fn test_send_mail_to_sink() {
+        let (config, session) = sink(PLAIN, "250 ok\r\n").await;
+
+        send_mail(&config, &mail()).await.unwrap();
+        let log = session.await.unwrap();
+
+        assert!(log.contains("EHLO example.com\r\n"));
+        assert!(log.contains(&format!(
+            "AUTH PLAIN {}\r\n",
+            STANDARD.encode("\0bot\0secret")
+        )));
+        assert!(
+            log.contains("MAIL FROM:<bot@example.com>\r\nRCPT TO:<alice@example.com>\r\nDATA\r\n")
+        );
+        let message = log.split_once("DATA\r\n").unwrap().1;
+        let message = message.strip_suffix("\r\n.\r\nQUIT\r\n").unwrap();
+        assert_eq!(decode_body(message), "Call mom\r\n.\r\nDue 18:00");
+    }
+
+    #[tokio::test]
+    async fn test_send_mail_rejected() {
+        let (config, _session) = sink(PLAIN, "550 no such user\r\n").await;
+
+        let error = send_mail(&config, &mail()).await.unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Notification error: SMTP RCPT rejected: 550 no such user"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_send_mail_starttls() {
+        let (config, session) = sink(
+            "250-sink\r\n250-STARTTLS\r\n250 AUTH PLAIN\r\n",
+            "250 ok\r\n",
+        )
+        .await;
+
+        let error = send_mail(&config, &mail()).await.unwrap_err();
+        let log = session.await.unwrap();
+
+        // Upgraded before anything else was sent, and gave up when TLS failed
+        assert!(
+            error.to_string().contains("TLS with 127.0.0.1 failed"),
+            "{}",
+            error
+        );
+        assert!(log.ends_with("EHLO example.com\r\nSTARTTLS\r\n"), "{}", log);
+    }
+
+    #[test]
+    fn test_may_send_plain() {
+        assert!(may_send_plain("127.0.0.1".parse().unwrap()));
+        assert!(may_send_plain("::1".parse().unwrap()));
+        assert!(!may_send_plain("10.0.0.5".parse().unwrap()));
+        assert!(!may_send_plain("203.0.113.7".parse().unwrap()));
+    }
+}
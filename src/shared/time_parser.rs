@@ -0,0 +1,669 @@
+// Time parser - reminder times in English and Russian
+//
+// Supported phrases (case-insensitive):
+//   relative:  30m, 1h30m, 45s, 2w, in 2 weeks, in an hour, через 2 часа, через полчаса
+//   absolute:  2026-10-20 14:30, 20.10 14:30, 20.10.2026
+//   clock:     at 18:00, 9am, 9:30pm, noon, в 9, в 7 вечера
+//   dates:     today, tomorrow, day after tomorrow, monday, next monday,
+//              сегодня, завтра, послезавтра, в понедельник, в следующую пятницу
+//   combined:  tomorrow 9am, 9am tomorrow, завтра в 9, next friday at 18:00
+//
+// Dates without a time use DEFAULT_REMINDER_TIME; a time without a date is
+// today, or tomorrow if it has already passed. Task reminders also accept
+// `<duration> before [due]`.
+
+use crate::error::Result;
+use crate::shared::duration::parse_duration;
+use crate::shared::timezone::resolve_local;
+use crate::shared::utils::{parse_clock_time, parse_due_date};
+use chrono::{
+    DateTime, Datelike, Duration, Months, NaiveDate, NaiveTime, TimeZone, Timelike, Weekday,
+};
+
+/// Time of day used when a reminder date has no explicit time
+const DEFAULT_REMINDER_TIME: (u32, u32) = (9, 0);
+
+/// Longest time phrase, in words ("в следующую пятницу в 7 вечера")
+const MAX_PHRASE_WORDS: usize = 6;
+
+/// Largest number accepted in a relative offset ("in 10000 minutes")
+const MAX_OFFSET_UNITS: i64 = 10_000;
+
+/// Parse a whole string as a reminder time relative to `now`
+///
+/// The result is in `now`'s time zone; it may be in the past
+/// (e.g. "today 9am" in the afternoon), callers validate that.
+pub fn parse_reminder_time<Tz: TimeZone>(input: &str, now: &DateTime<Tz>) -> Option<DateTime<Tz>> {
+    let lowered = input.to_lowercase();
+    let words: Vec<&str> = lowered.split_whitespace().collect();
+    parse_phrase(&words, now)
+}
+
+/// Split `<time phrase> <message>` into reminder time and message
+///
+/// The longest leading phrase that parses wins, so
+/// "tomorrow 9am Call mom" gives ("tomorrow 9am", "Call mom").
+pub fn split_reminder_time<Tz: TimeZone>(
+    input: &str,
+    now: &DateTime<Tz>,
+) -> Option<(DateTime<Tz>, String)> {
+    let input = input.trim();
+    let lowered = input.to_lowercase();
+    let words: Vec<&str> = lowered.split_whitespace().collect();
+
+    for n in (1..=words.len().min(MAX_PHRASE_WORDS)).rev() {
+        if let Some(at) = parse_phrase(&words[..n], now) {
+            let message = skip_words(input, n);
+            return Some((at, message.trim().to_string()));
+        }
+    }
+
+    None
+}
+
+/// Offset of `<duration> before [due]`, e.g. `1d before due`
+///
+/// Returns `None` if `input` is not of that form, so callers can fall
+/// back to `parse_reminder_time`.
+pub fn parse_before_due(input: &str) -> Option<Result<Duration>> {
+    let lowered = input.trim().to_lowercase();
+    let offset = lowered
+        .strip_suffix(" before due")
+        .or_else(|| lowered.strip_suffix(" before"))?;
+
+    Some(parse_duration(offset.trim()))
+}
+
+/// Daily window such as `23:00-08:00`, `23-8` or `10pm-7am`
+///
+/// The end may be earlier than the start (the window wraps past midnight).
+pub fn parse_time_range(input: &str) -> Option<(NaiveTime, NaiveTime)> {
+    let lowered = input.trim().to_lowercase();
+    let (start, end) = lowered.split_once(['-', '–'])?;
+    let (start, end) = (parse_time_of_day(start)?, parse_time_of_day(end)?);
+    (start != end).then_some((start, end))
+}
+
+/// Whole string as a time of day; a bare hour is accepted
+fn parse_time_of_day(s: &str) -> Option<NaiveTime> {
+    let words: Vec<&str> = s.split_whitespace().collect();
+    match take_time(&words) {
+        Some((time, used)) if used == words.len() => Some(time),
+        Some(_) => None,
+        None => match words[..] {
+            [word] => {
+                let (h, m) = parse_hour_minute(word)?;
+                NaiveTime::from_hms_opt(h, m, 0)
+            }
+            _ => None,
+        },
+    }
+}
+
+/// Rest of `input` after its first `n` words
+fn skip_words(input: &str, n: usize) -> &str {
+    let mut rest = input;
+    for _ in 0..n {
+        rest = rest.trim_start();
+        let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+        rest = &rest[end..];
+    }
+    rest
+}
+
+fn parse_phrase<Tz: TimeZone>(words: &[&str], now: &DateTime<Tz>) -> Option<DateTime<Tz>> {
+    match words {
+        [] => None,
+        // "30m", "1h30m", "1h 30m"
+        words if words.iter().all(|w| parse_short_offset(w).is_some()) => {
+            let mut offset = Offset::default();
+            for word in words {
+                offset.duration += parse_short_offset(word)?.duration;
+            }
+            offset.apply(now)
+        }
+        ["in" | "через", rest @ ..] => parse_offset(rest)?.apply(now),
+        _ => parse_date_time(words, now),
+    }
+}
+
+/// Date and/or time in either order: "tomorrow 9am", "9am tomorrow", "at 18:00"
+fn parse_date_time<Tz: TimeZone>(words: &[&str], now: &DateTime<Tz>) -> Option<DateTime<Tz>> {
+    let today = now.naive_local().date();
+
+    let (date, rest) = match take_date(words, today) {
+        Some((date, used)) => (Some(date), &words[used..]),
+        None => (None, words),
+    };
+    let (time, rest) = match take_time(rest) {
+        Some((time, used)) => (Some(time), &rest[used..]),
+        None => (None, rest),
+    };
+    let (date, rest) = match (date, take_date(rest, today)) {
+        (None, Some((date, used))) if time.is_some() => (Some(date), &rest[used..]),
+        (date, _) => (date, rest),
+    };
+
+    if !rest.is_empty() {
+        return None;
+    }
+
+    let default_time =
+        || NaiveTime::from_hms_opt(DEFAULT_REMINDER_TIME.0, DEFAULT_REMINDER_TIME.1, 0);
+
+    match (date, time) {
+        (None, None) => None,
+        // Time only: today, or tomorrow if already passed
+        (None, Some(time)) => {
+            let at = local(&now.timezone(), today, time)?;
+            if at > *now {
+                Some(at)
+            } else {
+                local(&now.timezone(), today.succ_opt()?, time)
+            }
+        }
+        (Some(date), time) => {
+            let time = time.or_else(default_time)?;
+            let at = local(&now.timezone(), date.day, time)?;
+            // "monday 9am" on a Monday afternoon means next week
+            if date.weekday && at <= *now {
+                local(&now.timezone(), date.day + Duration::weeks(1), time)
+            } else {
+                Some(at)
+            }
+        }
+    }
+}
+
+/// Date found in a phrase
+#[derive(Debug, Clone, Copy)]
+struct PhraseDate {
+    day: NaiveDate,
+    /// Given as a plain weekday name (rolls over to next week if passed)
+    weekday: bool,
+}
+
+/// Date words at the start of `words`; returns the date and words used
+fn take_date(words: &[&str], today: NaiveDate) -> Option<(PhraseDate, usize)> {
+    let exact = |day: NaiveDate, used: usize| {
+        Some((
+            PhraseDate {
+                day,
+                weekday: false,
+            },
+            used,
+        ))
+    };
+
+    match words {
+        ["day", "after", "tomorrow", ..] => exact(today + Duration::days(2), 3),
+        ["послезавтра", ..] => exact(today + Duration::days(2), 1),
+        ["next", day, ..] | ["следующий" | "следующую" | "следующее", day, ..] => {
+            exact(next_weekday(today, parse_weekday(day)?, false), 2)
+        }
+        ["в" | "во", "следующий" | "следующую" | "следующее", day, ..] => {
+            exact(next_weekday(today, parse_weekday(day)?, false), 3)
+        }
+        ["on" | "в" | "во", day, ..] if parse_weekday(day).is_some() => {
+            let day = next_weekday(today, parse_weekday(day)?, true);
+            Some((PhraseDate { day, weekday: true }, 2))
+        }
+        [word, ..] => {
+            if let Some(weekday) = parse_weekday(word) {
+                let day = next_weekday(today, weekday, true);
+                return Some((PhraseDate { day, weekday: true }, 1));
+            }
+            exact(parse_due_date(word, today)?, 1)
+        }
+        [] => None,
+    }
+}
+
+/// Nearest `weekday` after `today` (or today itself if `include_today`)
+fn next_weekday(today: NaiveDate, weekday: Weekday, include_today: bool) -> NaiveDate {
+    let ahead = (7 + weekday.num_days_from_monday() as i64
+        - today.weekday().num_days_from_monday() as i64)
+        % 7;
+    let ahead = if ahead == 0 && !include_today {
+        7
+    } else {
+        ahead
+    };
+    today + Duration::days(ahead)
+}
+
+/// English (`monday`, `mon`) or Russian (`понедельник`, `пн`, `среду`) weekday
+fn parse_weekday(s: &str) -> Option<Weekday> {
+    if let Ok(day) = s.parse::<Weekday>() {
+        return Some(day);
+    }
+
+    let day = match s {
+        "понедельник" | "пн" => Weekday::Mon,
+        "вторник" | "вт" => Weekday::Tue,
+        "среда" | "среду" | "ср" => Weekday::Wed,
+        "четверг" | "чт" => Weekday::Thu,
+        "пятница" | "пятницу" | "пт" => Weekday::Fri,
+        "суббота" | "субботу" | "сб" => Weekday::Sat,
+        "воскресенье" | "вс" => Weekday::Sun,
+        _ => return None,
+    };
+    Some(day)
+}
+
+/// Time words at the start of `words`; returns the time and words used
+///
+/// A bare hour ("9") needs a prefix ("at 9", "в 9") or a suffix ("9 am", "9 утра").
+fn take_time(words: &[&str]) -> Option<(NaiveTime, usize)> {
+    let (prefixed, words) = match words {
+        ["at" | "в" | "во" | "к", rest @ ..] => (true, rest),
+        _ => (false, words),
+    };
+    let used = usize::from(prefixed);
+
+    let word = *words.first()?;
+    let named = match word {
+        "noon" | "полдень" => NaiveTime::from_hms_opt(12, 0, 0),
+        "midnight" | "полночь" => NaiveTime::from_hms_opt(0, 0, 0),
+        _ => None,
+    };
+    if let Some(time) = named {
+        return Some((time, used + 1));
+    }
+
+    // 9am, 9:30pm
+    if let Some((clock, suffix)) = split_meridiem(word) {
+        let (h, m) = parse_hour_minute(clock)?;
+        return Some((apply_meridiem(h, m, suffix)?, used + 1));
+    }
+
+    let (h, m) = parse_hour_minute(word)?;
+
+    // 9 am, в 7 вечера
+    if let Some(suffix) = words.get(1).filter(|s| is_meridiem(s)) {
+        return Some((apply_meridiem(h, m, suffix)?, used + 2));
+    }
+
+    if word.contains(':') {
+        return Some((parse_clock_time(word)?, used + 1));
+    }
+
+    if prefixed {
+        return Some((NaiveTime::from_hms_opt(h, m, 0)?, used + 1));
+    }
+
+    None
+}
+
+/// `9` or `9:30` as (hour, minute)
+fn parse_hour_minute(s: &str) -> Option<(u32, u32)> {
+    if s.contains(':') {
+        let time = parse_clock_time(s)?;
+        return Some((time.hour(), time.minute()));
+    }
+
+    if s.is_empty() || s.len() > 2 {
+        return None;
+    }
+    let hour: u32 = s.parse().ok()?;
+    (hour < 24).then_some((hour, 0))
+}
+
+fn is_meridiem(s: &str) -> bool {
+    matches!(s, "am" | "pm" | "утра" | "дня" | "вечера" | "ночи")
+}
+
+/// `9am` -> ("9", "am")
+fn split_meridiem(s: &str) -> Option<(&str, &str)> {
+    ["am", "pm"]
+        .into_iter()
+        .find_map(|suffix| Some((s.strip_suffix(suffix)?, suffix)))
+        .filter(|(clock, _)| !clock.is_empty())
+}
+
+/// 12-hour clock: `am`/`утра`/`ночи` keep morning hours, `pm`/`дня`/`вечера` add 12
+fn apply_meridiem(hour: u32, minute: u32, suffix: &str) -> Option<NaiveTime> {
+    if !(1..=12).contains(&hour) {
+        return None;
+    }
+
+    let hour = match suffix {
+        "am" | "утра" | "ночи" => hour % 12,
+        "pm" | "вечера" | "дня" => hour % 12 + 12,
+        _ => return None,
+    };
+
+    NaiveTime::from_hms_opt(hour, minute, 0)
+}
+
+/// Offset added to the current time
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct Offset {
+    months: u32,
+    duration: Duration,
+}
+
+impl Offset {
+    fn apply<Tz: TimeZone>(self, now: &DateTime<Tz>) -> Option<DateTime<Tz>> {
+        now.clone()
+            .checked_add_months(Months::new(self.months))?
+            .checked_add_signed(self.duration)
+    }
+
+    fn add(mut self, amount: i64, unit: TimeUnit) -> Option<Self> {
+        if !(1..=MAX_OFFSET_UNITS).contains(&amount) {
+            return None;
+        }
+
+        match unit {
+            TimeUnit::Minutes => self.duration += Duration::minutes(amount),
+            TimeUnit::Hours => self.duration += Duration::hours(amount),
+            TimeUnit::Days => self.duration += Duration::days(amount),
+            TimeUnit::Weeks => self.duration += Duration::weeks(amount),
+            TimeUnit::Months => self.months = self.months.checked_add(amount as u32)?,
+        }
+        Some(self)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TimeUnit {
+    Minutes,
+    Hours,
+    Days,
+    Weeks,
+    Months,
+}
+
+/// Compact duration: `30m`, `1h30m`, `45s`, `2w`
+fn parse_short_offset(s: &str) -> Option<Offset> {
+    let duration = parse_duration(s).ok()?;
+    Some(Offset {
+        months: 0,
+        duration,
+    })
+}
+
+/// Words after "in"/"через": `2 weeks`, `an hour`, `1 hour 30 minutes`, `2h`, `полчаса`
+fn parse_offset(words: &[&str]) -> Option<Offset> {
+    if words.is_empty() {
+        return None;
+    }
+
+    let mut offset = Offset::default();
+    let mut rest = words;
+
+    loop {
+        rest = match rest {
+            [] => return Some(offset),
+            ["полчаса", tail @ ..] | ["half", "an", "hour", tail @ ..] => {
+                offset = offset.add(30, TimeUnit::Minutes)?;
+                tail
+            }
+            [word, tail @ ..] if parse_short_offset(word).is_some() => {
+                offset.duration += parse_short_offset(word)?.duration;
+                tail
+            }
+            [amount, unit, tail @ ..] if parse_amount(amount).is_some() => {
+                offset = offset.add(parse_amount(amount)?, parse_unit(unit)?)?;
+                tail
+            }
+            // "через час", "через неделю"
+            [unit, tail @ ..] => {
+                offset = offset.add(1, parse_unit(unit)?)?;
+                tail
+            }
+        };
+    }
+}
+
+/// Digits, or `a`/`an`/`one` and Russian equivalents
+fn parse_amount(s: &str) -> Option<i64> {
+    match s {
+        "a" | "an" | "one" | "один" | "одну" | "одна" => Some(1),
+        "пару" => Some(2),
+        _ => s.parse().ok(),
+    }
+}
+
+fn parse_unit(s: &str) -> Option<TimeUnit> {
+    let unit = match s {
+        "m" | "min" | "mins" | "minute" | "minutes" | "мин" | "минута" | "минуту" | "минуты"
+        | "минут" => TimeUnit::Minutes,
+        "h" | "hr" | "hrs" | "hour" | "hours" | "ч" | "час" | "часа" | "часов" => {
+            TimeUnit::Hours
+        }
+        "d" | "day" | "days" | "день" | "дня" | "дней" | "сутки" | "суток" => {
+            TimeUnit::Days
+        }
+        "w" | "week" | "weeks" | "неделя" | "неделю" | "недели" | "недель" => {
+            TimeUnit::Weeks
+        }
+        "month" | "months" | "месяц" | "месяца" | "месяцев" => TimeUnit::Months,
+        _ => return None,
+    };
+    Some(unit)
+}
+
+/// Local date and time in `tz`
+fn local<Tz: TimeZone>(tz: &Tz, date: NaiveDate, time: NaiveTime) -> Option<DateTime<Tz>> {
+    resolve_local(tz, date.and_time(time))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{FixedOffset, Utc};
+
+    /// Monday 2026-10-19 12:00 UTC
+    fn fixed_now() -> DateTime<Utc> {
+        at(2026, 10, 19, 12, 0)
+    }
+
+    fn at(y: i32, mo: u32, d: u32, h: u32, mi: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(y, mo, d, h, mi, 0).unwrap()
+    }
+
+    fn parse(s: &str) -> Option<DateTime<Utc>> {
+        parse_reminder_time(s, &fixed_now())
+    }
+
+    #[test]
+    fn test_parse_before_due() {
+        assert_eq!(parse_before_due("1d before due").unwrap().unwrap(), Duration::days(1));
+        assert_eq!(parse_before_due("2h30m BEFORE").unwrap().unwrap(), Duration::minutes(150));
+        assert!(parse_before_due("5x before").unwrap().is_err());
+        assert!(parse_before_due("tomorrow 9am").is_none());
+    }
+
+    #[test]
+    fn test_relative() {
+        assert_eq!(parse("30m"), Some(at(2026, 10, 19, 12, 30)));
+        assert_eq!(parse("2h"), Some(at(2026, 10, 19, 14, 0)));
+        assert_eq!(parse("1d"), Some(at(2026, 10, 20, 12, 0)));
+        assert_eq!(parse("1w"), Some(at(2026, 10, 26, 12, 0)));
+        assert_eq!(parse("1h30m"), Some(at(2026, 10, 19, 13, 30)));
+        assert_eq!(parse("1h 30m"), Some(at(2026, 10, 19, 13, 30)));
+        assert_eq!(parse("45s"), Some(Utc.with_ymd_and_hms(2026, 10, 19, 12, 0, 45).unwrap()));
+        assert_eq!(parse("500m"), Some(at(2026, 10, 19, 20, 20)));
+        assert_eq!(parse("in 2 weeks"), Some(at(2026, 11, 2, 12, 0)));
+        assert_eq!(parse("in an hour"), Some(at(2026, 10, 19, 13, 0)));
+        assert_eq!(
+            parse("in 1 hour 30 minutes"),
+            Some(at(2026, 10, 19, 13, 30))
+        );
+        assert_eq!(parse("in 45m"), Some(at(2026, 10, 19, 12, 45)));
+        assert_eq!(parse("in half an hour"), Some(at(2026, 10, 19, 12, 30)));
+        assert_eq!(parse("in 1 month"), Some(at(2026, 11, 19, 12, 0)));
+
+        assert_eq!(parse("0m"), None);
+        assert_eq!(parse("-5m"), None);
+        assert_eq!(parse("in"), None);
+        assert_eq!(parse("in 2 parsecs"), None);
+        assert_eq!(parse("in 99999 days"), None);
+    }
+
+    #[test]
+    fn test_relative_russian() {
+        assert_eq!(parse("через 2 часа"), Some(at(2026, 10, 19, 14, 0)));
+        assert_eq!(parse("через 5 минут"), Some(at(2026, 10, 19, 12, 5)));
+        assert_eq!(parse("через час"), Some(at(2026, 10, 19, 13, 0)));
+        assert_eq!(parse("через полчаса"), Some(at(2026, 10, 19, 12, 30)));
+        assert_eq!(parse("через 3 дня"), Some(at(2026, 10, 22, 12, 0)));
+        assert_eq!(parse("через неделю"), Some(at(2026, 10, 26, 12, 0)));
+        assert_eq!(parse("через пару недель"), Some(at(2026, 11, 2, 12, 0)));
+        assert_eq!(parse("Через 1 месяц"), Some(at(2026, 11, 19, 12, 0)));
+    }
+
+    #[test]
+    fn test_absolute_dates() {
+        assert_eq!(parse("2026-10-20 14:30"), Some(at(2026, 10, 20, 14, 30)));
+        assert_eq!(parse("20.10 14:30"), Some(at(2026, 10, 20, 14, 30)));
+        assert_eq!(parse("20.10.2026 8:05"), Some(at(2026, 10, 20, 8, 5)));
+        // Date only: default time
+        assert_eq!(parse("20.10"), Some(at(2026, 10, 20, 9, 0)));
+        // DD.MM already passed this year: next year
+        assert_eq!(parse("01.03"), Some(at(2027, 3, 1, 9, 0)));
+
+        assert_eq!(parse("31.02 10:00"), None);
+        assert_eq!(parse("2026-10-20 25:00"), None);
+    }
+
+    #[test]
+    fn test_clock_times() {
+        // Later today
+        assert_eq!(parse("at 18:00"), Some(at(2026, 10, 19, 18, 0)));
+        assert_eq!(parse("18:00"), Some(at(2026, 10, 19, 18, 0)));
+        assert_eq!(parse("9pm"), Some(at(2026, 10, 19, 21, 0)));
+        assert_eq!(parse("at 9:30 pm"), Some(at(2026, 10, 19, 21, 30)));
+        // Already passed: tomorrow
+        assert_eq!(parse("9am"), Some(at(2026, 10, 20, 9, 0)));
+        assert_eq!(parse("at 9"), Some(at(2026, 10, 20, 9, 0)));
+        assert_eq!(parse("noon"), Some(at(2026, 10, 20, 12, 0)));
+        assert_eq!(parse("midnight"), Some(at(2026, 10, 20, 0, 0)));
+        assert_eq!(parse("12am"), Some(at(2026, 10, 20, 0, 0)));
+        assert_eq!(parse("12pm"), Some(at(2026, 10, 20, 12, 0)));
+
+        // A bare number is not a time
+        assert_eq!(parse("9"), None);
+        assert_eq!(parse("13pm"), None);
+        assert_eq!(parse("at 24"), None);
+    }
+
+    #[test]
+    fn test_clock_times_russian() {
+        assert_eq!(parse("в 18:00"), Some(at(2026, 10, 19, 18, 0)));
+        assert_eq!(parse("в 7 вечера"), Some(at(2026, 10, 19, 19, 0)));
+        assert_eq!(parse("в 3 дня"), Some(at(2026, 10, 19, 15, 0)));
+        assert_eq!(parse("в 9"), Some(at(2026, 10, 20, 9, 0)));
+        assert_eq!(parse("в 9 утра"), Some(at(2026, 10, 20, 9, 0)));
+        assert_eq!(parse("в 2 ночи"), Some(at(2026, 10, 20, 2, 0)));
+        assert_eq!(parse("в полдень"), Some(at(2026, 10, 20, 12, 0)));
+    }
+
+    #[test]
+    fn test_day_words() {
+        assert_eq!(parse("tomorrow"), Some(at(2026, 10, 20, 9, 0)));
+        assert_eq!(parse("tomorrow 9am"), Some(at(2026, 10, 20, 9, 0)));
+        assert_eq!(parse("9am tomorrow"), Some(at(2026, 10, 20, 9, 0)));
+        assert_eq!(parse("tomorrow at 18:30"), Some(at(2026, 10, 20, 18, 30)));
+        assert_eq!(parse("today 18:00"), Some(at(2026, 10, 19, 18, 0)));
+        assert_eq!(
+            parse("day after tomorrow 10am"),
+            Some(at(2026, 10, 21, 10, 0))
+        );
+        assert_eq!(parse("Завтра в 9"), Some(at(2026, 10, 20, 9, 0)));
+        assert_eq!(parse("завтра"), Some(at(2026, 10, 20, 9, 0)));
+        assert_eq!(parse("сегодня в 7 вечера"), Some(at(2026, 10, 19, 19, 0)));
+        assert_eq!(parse("послезавтра в 10:00"), Some(at(2026, 10, 21, 10, 0)));
+
+        // "today" without a time can be in the past; callers reject it
+        assert_eq!(parse("today 9am"), Some(at(2026, 10, 19, 9, 0)));
+        assert_eq!(parse("tomorrow tomorrow"), None);
+    }
+
+    #[test]
+    fn test_weekdays() {
+        // Today is Monday
+        assert_eq!(parse("next monday"), Some(at(2026, 10, 26, 9, 0)));
+        assert_eq!(parse("next friday at 18:00"), Some(at(2026, 10, 23, 18, 0)));
+        assert_eq!(parse("friday"), Some(at(2026, 10, 23, 9, 0)));
+        assert_eq!(parse("on wed 10am"), Some(at(2026, 10, 21, 10, 0)));
+        // Plain weekday that already passed today rolls to next week
+        assert_eq!(parse("monday 9am"), Some(at(2026, 10, 26, 9, 0)));
+        assert_eq!(parse("monday 18:00"), Some(at(2026, 10, 19, 18, 0)));
+
+        assert_eq!(parse("в пятницу"), Some(at(2026, 10, 23, 9, 0)));
+        assert_eq!(parse("во вторник в 10:00"), Some(at(2026, 10, 20, 10, 0)));
+        assert_eq!(
+            parse("в следующий понедельник"),
+            Some(at(2026, 10, 26, 9, 0))
+        );
+        assert_eq!(
+            parse("в следующую пятницу в 7 вечера"),
+            Some(at(2026, 10, 23, 19, 0))
+        );
+        assert_eq!(parse("ср 18:00"), Some(at(2026, 10, 21, 18, 0)));
+    }
+
+    #[test]
+    fn test_local_time_zone() {
+        // 12:00 UTC is 15:00 in UTC+3
+        let moscow = FixedOffset::east_opt(3 * 3600).unwrap();
+        let now = fixed_now().with_timezone(&moscow);
+
+        let tomorrow = parse_reminder_time("завтра в 9", &now).unwrap();
+        assert_eq!(tomorrow.with_timezone(&Utc), at(2026, 10, 20, 6, 0));
+
+        // 14:00 local has already passed
+        let clock = parse_reminder_time("at 14:00", &now).unwrap();
+        assert_eq!(clock.with_timezone(&Utc), at(2026, 10, 20, 11, 0));
+    }
+
+    #[test]
+    fn test_split_reminder_time() {
+        let split = |s: &str| split_reminder_time(s, &fixed_now());
+
+        assert_eq!(
+            split("tomorrow 9am Call mom"),
+            Some((at(2026, 10, 20, 9, 0), "Call mom".to_string()))
+        );
+        assert_eq!(
+            split("15m Check email"),
+            Some((at(2026, 10, 19, 12, 15), "Check email".to_string()))
+        );
+        assert_eq!(
+            split("через 2 часа  Позвонить маме"),
+            Some((at(2026, 10, 19, 14, 0), "Позвонить маме".to_string()))
+        );
+        assert_eq!(
+            split("in 2 weeks Renew passport"),
+            Some((at(2026, 11, 2, 12, 0), "Renew passport".to_string()))
+        );
+        assert_eq!(
+            split("20.10 14:30 Dentist\nbring card"),
+            Some((at(2026, 10, 20, 14, 30), "Dentist\nbring card".to_string()))
+        );
+        assert_eq!(
+            split("at 18:00"),
+            Some((at(2026, 10, 19, 18, 0), String::new()))
+        );
+
+        assert_eq!(split("Call mom tomorrow"), None);
+        assert_eq!(split(""), None);
+    }
+
+    #[test]
+    fn test_parse_time_range() {
+        let time = |h, m| NaiveTime::from_hms_opt(h, m, 0).unwrap();
+
+        assert_eq!(parse_time_range("23:00-08:00"), Some((time(23, 0), time(8, 0))));
+        assert_eq!(parse_time_range("23-7:30"), Some((time(23, 0), time(7, 30))));
+        assert_eq!(parse_time_range("10pm - 7am"), Some((time(22, 0), time(7, 0))));
+        assert_eq!(parse_time_range("13:00–14:00"), Some((time(13, 0), time(14, 0))));
+
+        assert_eq!(parse_time_range("9-9"), None);
+        assert_eq!(parse_time_range("23:00"), None);
+        assert_eq!(parse_time_range("late-early"), None);
+    }
+}
@@ -0,0 +1,182 @@
+// Time zones - per-user time zone parsing and detection
+//
+// Users pick a zone with /timezone Europe/Moscow (IANA name), a fixed
+// offset (/timezone UTC+3) or by sharing their location. Everything is
+// stored in UTC; the zone is only used for parsing input and display.
+
+use chrono::{DateTime, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use chrono_tz::Tz;
+
+/// Zone used when a user has not picked one
+pub const DEFAULT_TIMEZONE: Tz = Tz::UTC;
+
+/// Parse IANA name (`Europe/Moscow`, case-insensitive) or whole-hour
+/// offset (`UTC+3`, `GMT-5`, `+03:00`)
+pub fn parse_timezone(input: &str) -> Option<Tz> {
+    let input = input.trim();
+    if input.is_empty() {
+        return None;
+    }
+
+    let named = chrono_tz::TZ_VARIANTS
+        .iter()
+        .find(|tz| tz.name().eq_ignore_ascii_case(input));
+    if let Some(tz) = named {
+        return Some(*tz);
+    }
+
+    let upper = input.to_uppercase();
+    let offset = upper
+        .strip_prefix("UTC")
+        .or_else(|| upper.strip_prefix("GMT"))
+        .unwrap_or(&upper);
+
+    fixed_offset_zone(parse_offset_hours(offset)?)
+}
+
+/// `+3`, `-05`, `+03:00`; only whole hours map onto IANA zones
+fn parse_offset_hours(s: &str) -> Option<i32> {
+    let (sign, rest) = match s.chars().next()? {
+        '+' => (1, &s[1..]),
+        '-' => (-1, &s[1..]),
+        _ => return None,
+    };
+
+    let (hours, minutes) = rest.split_once(':').unwrap_or((rest, "00"));
+    if minutes != "00" || hours.is_empty() || hours.len() > 2 {
+        return None;
+    }
+
+    let hours: i32 = hours.parse().ok()?;
+    Some(sign * hours)
+}
+
+/// `Etc/GMT-3` is UTC+3 (POSIX sign convention)
+fn fixed_offset_zone(hours: i32) -> Option<Tz> {
+    if hours == 0 {
+        return Some(Tz::UTC);
+    }
+    if !(-12..=14).contains(&hours) {
+        return None;
+    }
+
+    format!("Etc/GMT{:+}", -hours).parse().ok()
+}
+
+/// Approximate zone from a shared location (15° of longitude per hour)
+///
+/// Gives a fixed offset without daylight saving rules; users who need
+/// those can set their IANA zone by name.
+pub fn timezone_from_location(longitude: f64) -> Tz {
+    let hours = (longitude / 15.0).round() as i32;
+    fixed_offset_zone(hours.clamp(-12, 12)).unwrap_or(DEFAULT_TIMEZONE)
+}
+
+/// Stored zone name, falling back to UTC for unknown names
+pub fn user_timezone(name: &str) -> Tz {
+    name.parse().unwrap_or(DEFAULT_TIMEZONE)
+}
+
+/// Start of the local day containing `now`, in UTC
+pub fn local_midnight(now: DateTime<Utc>, tz: Tz) -> DateTime<Utc> {
+    let date = now.with_timezone(&tz).date_naive();
+    start_of_day(date, tz)
+}
+
+/// Start of `date` in `tz`, in UTC
+pub fn start_of_day(date: NaiveDate, tz: Tz) -> DateTime<Utc> {
+    let midnight = date.and_hms_opt(0, 0, 0).expect("midnight is a valid time");
+    resolve_local(&tz, midnight)
+        .map(|t| t.with_timezone(&Utc))
+        .unwrap_or_else(|| midnight.and_utc())
+}
+
+/// Local wall-clock time in `tz`
+///
+/// Ambiguous times (DST fall-back) take the earlier instant; skipped
+/// times (DST gaps) move forward by an hour.
+pub fn resolve_local<Z: TimeZone>(tz: &Z, naive: NaiveDateTime) -> Option<DateTime<Z>> {
+    tz.from_local_datetime(&naive).earliest().or_else(|| {
+        tz.from_local_datetime(&(naive + chrono::Duration::hours(1)))
+            .earliest()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_timezone() {
+        assert_eq!(parse_timezone("Europe/Moscow"), Some(Tz::Europe__Moscow));
+        assert_eq!(parse_timezone("europe/moscow"), Some(Tz::Europe__Moscow));
+        assert_eq!(
+            parse_timezone(" America/New_York "),
+            Some(Tz::America__New_York)
+        );
+        assert_eq!(parse_timezone("UTC"), Some(Tz::UTC));
+
+        assert_eq!(parse_timezone("UTC+3"), Some(Tz::Etc__GMTMinus3));
+        assert_eq!(parse_timezone("gmt-5"), Some(Tz::Etc__GMTPlus5));
+        assert_eq!(parse_timezone("+03:00"), Some(Tz::Etc__GMTMinus3));
+        assert_eq!(parse_timezone("UTC+0"), Some(Tz::UTC));
+
+        assert_eq!(parse_timezone("Mars/Olympus"), None);
+        assert_eq!(parse_timezone("UTC+5:30"), None);
+        assert_eq!(parse_timezone("UTC+15"), None);
+        assert_eq!(parse_timezone(""), None);
+    }
+
+    #[test]
+    fn test_timezone_from_location() {
+        // Moscow, New York, Greenwich
+        assert_eq!(timezone_from_location(37.6), Tz::Etc__GMTMinus3);
+        assert_eq!(timezone_from_location(-74.0), Tz::Etc__GMTPlus5);
+        assert_eq!(timezone_from_location(0.1), Tz::UTC);
+        assert_eq!(timezone_from_location(179.9), Tz::Etc__GMTMinus12);
+    }
+
+    #[test]
+    fn test_user_timezone_fallback() {
+        assert_eq!(user_timezone("Europe/Berlin"), Tz::Europe__Berlin);
+        assert_eq!(user_timezone("garbage"), DEFAULT_TIMEZONE);
+    }
+
+    #[test]
+    fn test_resolve_local_dst() {
+        let tz = Tz::Europe__Berlin;
+        // 2026-03-29 02:30 does not exist in Berlin
+        let gap = NaiveDate::from_ymd_opt(2026, 3, 29)
+            .unwrap()
+            .and_hms_opt(2, 30, 0)
+            .unwrap();
+        assert_eq!(
+            resolve_local(&tz, gap).unwrap().with_timezone(&Utc),
+            Utc.with_ymd_and_hms(2026, 3, 29, 1, 30, 0).unwrap()
+        );
+
+        // 2026-10-25 02:30 happens twice; the first one is in summer time
+        let overlap = NaiveDate::from_ymd_opt(2026, 10, 25)
+            .unwrap()
+            .and_hms_opt(2, 30, 0)
+            .unwrap();
+        assert_eq!(
+            resolve_local(&tz, overlap).unwrap().with_timezone(&Utc),
+            Utc.with_ymd_and_hms(2026, 10, 25, 0, 30, 0).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_local_midnight() {
+        let now = Utc.with_ymd_and_hms(2026, 10, 19, 22, 30, 0).unwrap();
+        // Already Tuesday in Moscow
+        assert_eq!(
+            local_midnight(now, Tz::Europe__Moscow),
+            Utc.with_ymd_and_hms(2026, 10, 19, 21, 0, 0).unwrap()
+        );
+        assert_eq!(
+            local_midnight(now, Tz::UTC),
+            Utc.with_ymd_and_hms(2026, 10, 19, 0, 0, 0).unwrap()
+        );
+    }
+}
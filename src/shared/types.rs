@@ -1,6 +1,8 @@
 // Shared types - common data types
 
-use chrono::{DateTime, Utc};
+use crate::shared::cron::{self, CronSchedule};
+use crate::shared::timezone::resolve_local;
+use chrono::{DateTime, Datelike, Months, NaiveDate, TimeZone, Utc, Weekday};
 use serde::{Deserialize, Serialize};
 
 /// Telegram user ID
@@ -11,7 +13,7 @@ pub type DbId = i32;
 
 /// Task status
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
-#[sqlx(type_name = "VARCHAR", rename_all = "lowercase")]
+#[sqlx(type_name = "VARCHAR", rename_all = "snake_case")]
 pub enum TodoStatus {
     Pending,
     InProgress,
@@ -19,14 +21,60 @@ pub enum TodoStatus {
     Cancelled,
 }
 
-impl ToString for TodoStatus {
-    fn to_string(&self) -> String {
-        match self {
-            TodoStatus::Pending => "pending".to_string(),
-            TodoStatus::InProgress => "in_progress".to_string(),
-            TodoStatus::Completed => "completed".to_string(),
-            TodoStatus::Cancelled => "cancelled".to_string(),
+/// Outcome of one reminder delivery
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "VARCHAR", rename_all = "snake_case")]
+pub enum DeliveryStatus {
+    Sent,
+    Failed,
+    /// Missed during downtime and dropped by the catch-up policy
+    Skipped,
+}
+
+/// Channel a reminder is delivered on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "VARCHAR", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum NotifyChannel {
+    Telegram,
+    Email,
+    /// HTTP POST with a JSON payload
+    Webhook,
+}
+
+impl NotifyChannel {
+    /// Parse a list like `email,telegram`; duplicates are dropped
+    pub fn parse_list(text: &str) -> Result<Vec<Self>, crate::error::AppError> {
+        let mut channels = Vec::new();
+        for name in text.split([',', '+']).map(str::trim).filter(|n| !n.is_empty()) {
+            let channel: Self = name.parse()?;
+            if !channels.contains(&channel) {
+                channels.push(channel);
+            }
+        }
+        if channels.is_empty() {
+            return Err(crate::error::validation_error(
+                "Name at least one channel: telegram, email or webhook",
+            ));
         }
+        Ok(channels)
+    }
+
+    /// Names as stored in the database
+    pub fn names(channels: &[Self]) -> Vec<String> {
+        channels.iter().map(Self::to_string).collect()
+    }
+}
+
+impl std::fmt::Display for TodoStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            TodoStatus::Pending => "pending",
+            TodoStatus::InProgress => "in_progress",
+            TodoStatus::Completed => "completed",
+            TodoStatus::Cancelled => "cancelled",
+        };
+        f.write_str(s)
     }
 }
 
@@ -47,6 +95,33 @@ impl std::str::FromStr for TodoStatus {
     }
 }
 
+impl std::fmt::Display for NotifyChannel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            NotifyChannel::Telegram => "telegram",
+            NotifyChannel::Email => "email",
+            NotifyChannel::Webhook => "webhook",
+        };
+        f.write_str(s)
+    }
+}
+
+impl std::str::FromStr for NotifyChannel {
+    type Err = crate::error::AppError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "telegram" | "tg" => Ok(NotifyChannel::Telegram),
+            "email" | "mail" | "почта" => Ok(NotifyChannel::Email),
+            "webhook" | "hook" => Ok(NotifyChannel::Webhook),
+            _ => Err(crate::error::validation_error(format!(
+                "Unknown channel '{}': use telegram, email or webhook",
+                s
+            ))),
+        }
+    }
+}
+
 /// Task priority (1 - highest, 5 - lowest)
 pub type Priority = i32;
 
@@ -63,33 +138,177 @@ pub enum ConversionStatus {
     Failed,
 }
 
-impl ToString for ConversionStatus {
-    fn to_string(&self) -> String {
-        match self {
-            ConversionStatus::Pending => "pending".to_string(),
-            ConversionStatus::Processing => "processing".to_string(),
-            ConversionStatus::Completed => "completed".to_string(),
-            ConversionStatus::Failed => "failed".to_string(),
-        }
+impl std::fmt::Display for ConversionStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            ConversionStatus::Pending => "pending",
+            ConversionStatus::Processing => "processing",
+            ConversionStatus::Completed => "completed",
+            ConversionStatus::Failed => "failed",
+        };
+        f.write_str(s)
     }
 }
 
 /// Reminder recurrence pattern
+///
+/// Time of day comes from the previous occurrence, except for `Custom`.
+/// Stored as text: `daily`, `weekly`, `weekly:mon,fri`, `monthly`,
+/// `monthly:15`, `yearly:03-14`; anything else is `Custom`.
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum RecurrencePattern {
     Daily,
+    /// Every 7 days
     Weekly,
+    /// On the given weekdays
+    WeeklyOn(Vec<Weekday>),
+    /// Same day as the previous occurrence, clamped to month end
     Monthly,
+    /// On day N of every month (last day for shorter months)
+    MonthlyOn(u32),
+    /// Every year on month/day (Feb 29 falls on Feb 28 in common years)
+    Yearly { month: u32, day: u32 },
+    /// Cron expression, e.g. `0 9 * * 1-5`
     Custom(String),
 }
 
-impl ToString for RecurrencePattern {
-    fn to_string(&self) -> String {
+impl RecurrencePattern {
+    /// Check if `date` is an occurrence day of the pattern
+    pub fn matches(&self, date: NaiveDate) -> bool {
+        match self {
+            RecurrencePattern::Daily | RecurrencePattern::Weekly | RecurrencePattern::Monthly => {
+                true
+            }
+            RecurrencePattern::WeeklyOn(days) => days.contains(&date.weekday()),
+            RecurrencePattern::MonthlyOn(day) => {
+                clamp_day(date.year(), date.month(), *day) == Some(date)
+            }
+            RecurrencePattern::Yearly { month, day } => {
+                clamp_day(date.year(), *month, *day) == Some(date)
+            }
+            RecurrencePattern::Custom(expr) => {
+                let Ok(schedule) = CronSchedule::parse(expr) else {
+                    return false;
+                };
+                let start = date.and_hms_opt(0, 0, 0).map(|t| t.and_utc());
+                let next = start.and_then(|t| schedule.next_after(&(t - chrono::Duration::seconds(1))));
+                next.is_some_and(|t| t.date_naive() == date)
+            }
+        }
+    }
+
+    /// Next occurrence strictly after `prev`, at the same local time of day
+    ///
+    /// `Custom` runs are taken from the cron expression in `prev`'s time zone;
+    /// returns `None` if the expression is invalid.
+    pub fn next_after<Tz: TimeZone>(&self, prev: &DateTime<Tz>) -> Option<DateTime<Tz>> {
+        let local = prev.naive_local();
+        let (date, time) = (local.date(), local.time());
+
+        let next_date = match self {
+            RecurrencePattern::Daily => date.succ_opt()?,
+            RecurrencePattern::Weekly => date + chrono::Duration::weeks(1),
+            RecurrencePattern::WeeklyOn(days) => {
+                if days.is_empty() {
+                    return None;
+                }
+                (1..=7)
+                    .map(|n| date + chrono::Duration::days(n))
+                    .find(|d| days.contains(&d.weekday()))?
+            }
+            RecurrencePattern::Monthly => date.checked_add_months(Months::new(1))?,
+            RecurrencePattern::MonthlyOn(day) => (0..=12)
+                .filter_map(|n| {
+                    let first = date.with_day(1)?.checked_add_months(Months::new(n))?;
+                    clamp_day(first.year(), first.month(), *day)
+                })
+                .find(|d| *d > date)?,
+            RecurrencePattern::Yearly { month, day } => (0..=1)
+                .filter_map(|n| clamp_day(date.year() + n, *month, *day))
+                .find(|d| *d > date)?,
+            RecurrencePattern::Custom(expr) => {
+                return CronSchedule::parse(expr).ok()?.next_after(prev);
+            }
+        };
+
+        resolve_local(&prev.timezone(), next_date.and_time(time))
+    }
+
+    /// First occurrence after `now`, at `start`'s time of day
+    ///
+    /// `start` itself counts if it falls on a matching day; used when a
+    /// one-off reminder becomes recurring.
+    pub fn first_after<Tz: TimeZone>(
+        &self,
+        start: DateTime<Tz>,
+        now: &DateTime<Tz>,
+    ) -> Option<DateTime<Tz>> {
+        let mut next = if self.matches(start.date_naive()) {
+            start
+        } else {
+            self.next_after(&start)?
+        };
+
+        // Bounded: a daily series from a date years back still ends quickly
+        for _ in 0..10_000 {
+            if next > *now {
+                return Some(next);
+            }
+            next = self.next_after(&next)?;
+        }
+        None
+    }
+
+    /// Human-readable description, e.g. "every Mon, Wed"
+    pub fn describe(&self) -> String {
         match self {
-            RecurrencePattern::Daily => "daily".to_string(),
-            RecurrencePattern::Weekly => "weekly".to_string(),
-            RecurrencePattern::Monthly => "monthly".to_string(),
-            RecurrencePattern::Custom(s) => s.clone(),
+            RecurrencePattern::Daily => "every day".to_string(),
+            RecurrencePattern::Weekly => "every week".to_string(),
+            RecurrencePattern::WeeklyOn(days) if *days == WORKDAYS => "every weekday".to_string(),
+            RecurrencePattern::WeeklyOn(days) => {
+                let names: Vec<String> = days.iter().map(|d| d.to_string()).collect();
+                format!("every {}", names.join(", "))
+            }
+            RecurrencePattern::Monthly => "every month".to_string(),
+            RecurrencePattern::MonthlyOn(day) => format!("every month on day {}", day),
+            RecurrencePattern::Yearly { month, day } => {
+                format!("every year on {:02}.{:02}", day, month)
+            }
+            RecurrencePattern::Custom(expr) => cron::describe_expression(expr)
+                .unwrap_or_else(|| format!("on cron schedule {}", expr)),
+        }
+    }
+}
+
+/// Monday to Friday
+pub const WORKDAYS: [Weekday; 5] = [
+    Weekday::Mon,
+    Weekday::Tue,
+    Weekday::Wed,
+    Weekday::Thu,
+    Weekday::Fri,
+];
+
+/// Date for `day` of month, clamped to the last day of that month
+fn clamp_day(year: i32, month: u32, day: u32) -> Option<NaiveDate> {
+    (1..=day.min(31))
+        .rev()
+        .find_map(|d| NaiveDate::from_ymd_opt(year, month, d))
+}
+
+impl std::fmt::Display for RecurrencePattern {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RecurrencePattern::Daily => f.write_str("daily"),
+            RecurrencePattern::Weekly => f.write_str("weekly"),
+            RecurrencePattern::WeeklyOn(days) => {
+                let names: Vec<String> = days.iter().map(|d| d.to_string().to_lowercase()).collect();
+                write!(f, "weekly:{}", names.join(","))
+            }
+            RecurrencePattern::Monthly => f.write_str("monthly"),
+            RecurrencePattern::MonthlyOn(day) => write!(f, "monthly:{}", day),
+            RecurrencePattern::Yearly { month, day } => write!(f, "yearly:{:02}-{:02}", month, day),
+            RecurrencePattern::Custom(s) => f.write_str(s),
         }
     }
 }
@@ -98,11 +317,166 @@ impl std::str::FromStr for RecurrencePattern {
     type Err = crate::error::AppError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s.to_lowercase().as_str() {
-            "daily" => Ok(RecurrencePattern::Daily),
-            "weekly" => Ok(RecurrencePattern::Weekly),
-            "monthly" => Ok(RecurrencePattern::Monthly),
-            _ => Ok(RecurrencePattern::Custom(s.to_string())),
+        let lower = s.to_lowercase();
+        let parsed = match lower.split_once(':') {
+            None => match lower.as_str() {
+                "daily" => Some(RecurrencePattern::Daily),
+                "weekly" => Some(RecurrencePattern::Weekly),
+                "monthly" => Some(RecurrencePattern::Monthly),
+                _ => None,
+            },
+            Some(("weekly", days)) => days
+                .split(',')
+                .map(|d| d.parse::<Weekday>().ok())
+                .collect::<Option<Vec<_>>>()
+                .map(RecurrencePattern::WeeklyOn),
+            Some(("monthly", day)) => day
+                .parse()
+                .ok()
+                .filter(|d| (1..=31).contains(d))
+                .map(RecurrencePattern::MonthlyOn),
+            Some(("yearly", date)) => date.split_once('-').and_then(|(m, d)| {
+                let (month, day) = (m.parse().ok()?, d.parse().ok()?);
+                // Validate against a leap year so Feb 29 is accepted
+                NaiveDate::from_ymd_opt(2024, month, day)?;
+                Some(RecurrencePattern::Yearly { month, day })
+            }),
+            Some(_) => None,
+        };
+
+        Ok(parsed.unwrap_or_else(|| RecurrencePattern::Custom(s.to_string())))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn at(y: i32, mo: u32, d: u32, h: u32, mi: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(y, mo, d, h, mi, 0).unwrap()
+    }
+
+    #[test]
+    fn test_parse_channel_list() {
+        assert_eq!(
+            NotifyChannel::parse_list("email, TG,email").unwrap(),
+            vec![NotifyChannel::Email, NotifyChannel::Telegram]
+        );
+        assert_eq!(
+            NotifyChannel::parse_list("webhook+telegram").unwrap(),
+            vec![NotifyChannel::Webhook, NotifyChannel::Telegram]
+        );
+        assert!(NotifyChannel::parse_list(",").is_err());
+        assert!(NotifyChannel::parse_list("email,sms").is_err());
+    }
+
+    #[test]
+    fn test_recurrence_roundtrip() {
+        let patterns = [
+            RecurrencePattern::Daily,
+            RecurrencePattern::Weekly,
+            RecurrencePattern::WeeklyOn(WORKDAYS.to_vec()),
+            RecurrencePattern::Monthly,
+            RecurrencePattern::MonthlyOn(31),
+            RecurrencePattern::Yearly { month: 2, day: 29 },
+        ];
+
+        for pattern in patterns {
+            let parsed: RecurrencePattern = pattern.to_string().parse().unwrap();
+            assert_eq!(parsed, pattern);
         }
+
+        assert_eq!(
+            "monthly:32".parse::<RecurrencePattern>().unwrap(),
+            RecurrencePattern::Custom("monthly:32".to_string())
+        );
+    }
+
+    #[test]
+    fn test_custom_cron_pattern() {
+        let pattern: RecurrencePattern = "0 9 * * 1-5".parse().unwrap();
+        assert_eq!(pattern, RecurrencePattern::Custom("0 9 * * 1-5".to_string()));
+
+        // Friday 09:00 -> Monday 09:00, Monday 12:00 -> Tuesday 09:00
+        assert_eq!(pattern.next_after(&at(2026, 10, 23, 9, 0)), Some(at(2026, 10, 26, 9, 0)));
+        assert_eq!(pattern.next_after(&at(2026, 10, 19, 12, 0)), Some(at(2026, 10, 20, 9, 0)));
+
+        assert!(pattern.matches(NaiveDate::from_ymd_opt(2026, 10, 19).unwrap()));
+        assert!(!pattern.matches(NaiveDate::from_ymd_opt(2026, 10, 24).unwrap()));
+        assert_eq!(pattern.describe(), "at 09:00, Monday through Friday");
+
+        let invalid = RecurrencePattern::Custom("not a cron".to_string());
+        assert_eq!(invalid.next_after(&at(2026, 10, 19, 9, 0)), None);
+        assert!(!invalid.matches(NaiveDate::from_ymd_opt(2026, 10, 19).unwrap()));
+    }
+
+    #[test]
+    fn test_next_daily_and_weekly() {
+        let prev = at(2026, 10, 19, 9, 0); // Monday
+        assert_eq!(RecurrencePattern::Daily.next_after(&prev), Some(at(2026, 10, 20, 9, 0)));
+        assert_eq!(RecurrencePattern::Weekly.next_after(&prev), Some(at(2026, 10, 26, 9, 0)));
+    }
+
+    #[test]
+    fn test_next_weekdays() {
+        let workdays = RecurrencePattern::WeeklyOn(WORKDAYS.to_vec());
+        // Friday -> Monday
+        assert_eq!(workdays.next_after(&at(2026, 10, 23, 9, 0)), Some(at(2026, 10, 26, 9, 0)));
+        // Monday -> Tuesday
+        assert_eq!(workdays.next_after(&at(2026, 10, 19, 9, 0)), Some(at(2026, 10, 20, 9, 0)));
+
+        let mon_thu = RecurrencePattern::WeeklyOn(vec![Weekday::Mon, Weekday::Thu]);
+        assert_eq!(mon_thu.next_after(&at(2026, 10, 19, 9, 0)), Some(at(2026, 10, 22, 9, 0)));
+        assert_eq!(RecurrencePattern::WeeklyOn(vec![]).next_after(&at(2026, 10, 19, 9, 0)), None);
+    }
+
+    #[test]
+    fn test_next_monthly_clamps_without_drift() {
+        let on_31 = RecurrencePattern::MonthlyOn(31);
+        let jan = at(2026, 1, 31, 10, 0);
+        let feb = on_31.next_after(&jan).unwrap();
+        assert_eq!(feb, at(2026, 2, 28, 10, 0));
+        // Back to 31 in March, not stuck on 28
+        assert_eq!(on_31.next_after(&feb), Some(at(2026, 3, 31, 10, 0)));
+        assert_eq!(on_31.next_after(&at(2026, 3, 31, 10, 0)), Some(at(2026, 4, 30, 10, 0)));
+
+        // Earlier in the same month
+        let on_15 = RecurrencePattern::MonthlyOn(15);
+        assert_eq!(on_15.next_after(&at(2026, 10, 10, 8, 0)), Some(at(2026, 10, 15, 8, 0)));
+        assert_eq!(on_15.next_after(&at(2026, 12, 15, 8, 0)), Some(at(2027, 1, 15, 8, 0)));
+    }
+
+    #[test]
+    fn test_next_yearly() {
+        let birthday = RecurrencePattern::Yearly { month: 3, day: 14 };
+        assert_eq!(birthday.next_after(&at(2026, 3, 14, 9, 0)), Some(at(2027, 3, 14, 9, 0)));
+        assert_eq!(birthday.next_after(&at(2026, 1, 1, 9, 0)), Some(at(2026, 3, 14, 9, 0)));
+
+        let leap = RecurrencePattern::Yearly { month: 2, day: 29 };
+        assert_eq!(leap.next_after(&at(2027, 1, 1, 9, 0)), Some(at(2027, 2, 28, 9, 0)));
+        assert_eq!(leap.next_after(&at(2027, 2, 28, 9, 0)), Some(at(2028, 2, 29, 9, 0)));
+    }
+
+    #[test]
+    fn test_first_after() {
+        let now = at(2026, 10, 19, 12, 0); // Monday
+        let workdays = RecurrencePattern::WeeklyOn(WORKDAYS.to_vec());
+
+        // Later today on a matching day
+        assert_eq!(workdays.first_after(at(2026, 10, 19, 18, 0), &now), Some(at(2026, 10, 19, 18, 0)));
+        // Already passed today: tomorrow at the same time
+        assert_eq!(workdays.first_after(at(2026, 10, 19, 9, 0), &now), Some(at(2026, 10, 20, 9, 0)));
+        // Saturday is not a workday
+        assert_eq!(workdays.first_after(at(2026, 10, 24, 9, 0), &now), Some(at(2026, 10, 26, 9, 0)));
+    }
+
+    #[test]
+    fn test_matches() {
+        let date = NaiveDate::from_ymd_opt(2026, 2, 28).unwrap();
+        assert!(RecurrencePattern::MonthlyOn(31).matches(date));
+        assert!(!RecurrencePattern::MonthlyOn(27).matches(date));
+        assert!(RecurrencePattern::Yearly { month: 2, day: 29 }.matches(date));
+        assert!(!RecurrencePattern::WeeklyOn(vec![Weekday::Mon]).matches(date));
     }
 }
@@ -1,88 +1,863 @@
 // Shared utilities - common utility functions
 
-use chrono::{DateTime, Duration, Utc};
-
-/// Parse relative time (например: "1h", "30m", "2d")
-pub fn parse_relative_time(input: &str) -> Option<DateTime<Utc>> {
-    let input = input.trim().to_lowercase();
-
-    // Parse into number and time unit
-    let (num_str, unit) = if input.ends_with('m') {
-        (&input[..input.len() - 1], 'm')
-    } else if input.ends_with('h') {
-        (&input[..input.len() - 1], 'h')
-    } else if input.ends_with('d') {
-        (&input[..input.len() - 1], 'd')
+use crate::error::{validation_error, Result};
+use crate::shared::duration::parse_duration;
+use crate::shared::timezone::resolve_local;
+use crate::shared::types::{NotifyChannel, RecurrencePattern, WORKDAYS};
+use chrono::{DateTime, Datelike, Duration, NaiveDate, NaiveTime, TimeZone, Utc, Weekday};
+
+/// Due time used when a quick-add date has no explicit time
+const DEFAULT_DUE_TIME: (u32, u32) = (23, 59);
+
+/// Time of day used when a recurring schedule has no explicit time
+const DEFAULT_RECURRING_TIME: (u32, u32) = (9, 0);
+
+/// `/nag` repeats every 5 minutes, 6 times (half an hour) unless told otherwise
+const DEFAULT_NAG_INTERVAL_MINUTES: i64 = 5;
+const DEFAULT_NAG_REPEATS: i32 = 6;
+
+/// Todo fields recognized by quick-add syntax
+///
+/// Example: `Fix login bug !1 #backend @tomorrow 18:00 ~2h`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct QuickAdd {
+    /// Title with all recognized markers removed
+    pub title: String,
+    /// `!1` (highest) .. `!5` (lowest)
+    pub priority: Option<i32>,
+    /// `#tag`, lowercased and without `#`
+    pub tags: Vec<String>,
+    /// `@today`, `@tomorrow`, `@fri`, `@2026-10-20`, `@20.10`, optionally followed by `HH:MM`,
+    /// or `@18:00` alone
+    pub due: Option<DateTime<Utc>>,
+    /// `~45m`, `~2h`, `~1h30m`, `~1d`
+    pub estimate_minutes: Option<i64>,
+}
+
+/// Parse quick-add markers out of todo text
+///
+/// Tokens that look like markers but cannot be parsed stay in the title,
+/// so `#1` or `email@host` are never lost.
+/// Dates and times are read in `now`'s time zone.
+pub fn parse_quick_add<Tz: TimeZone>(input: &str, now: DateTime<Tz>) -> QuickAdd {
+    let tokens: Vec<&str> = input.split_whitespace().collect();
+    let mut result = QuickAdd::default();
+    let mut title = Vec::new();
+
+    let mut i = 0;
+    while i < tokens.len() {
+        let token = tokens[i];
+        i += 1;
+
+        if let Some(priority) = token.strip_prefix('!').and_then(parse_priority_marker) {
+            result.priority = Some(priority);
+        } else if let Some(tag) = token.strip_prefix('#').and_then(parse_tag_marker) {
+            if !result.tags.contains(&tag) {
+                result.tags.push(tag);
+            }
+        } else if let Some(minutes) = token.strip_prefix('~').and_then(parse_estimate_marker) {
+            result.estimate_minutes = Some(minutes);
+        } else if let Some(date) = token.strip_prefix('@') {
+            let next = tokens.get(i).copied();
+            match parse_due_marker(date, next, &now) {
+                Some((due, consumed_next)) => {
+                    result.due = Some(due);
+                    if consumed_next {
+                        i += 1;
+                    }
+                }
+                None => title.push(token),
+            }
+        } else {
+            title.push(token);
+        }
+    }
+
+    result.title = title.join(" ");
+    result
+}
+
+/// `1`..`5`
+fn parse_priority_marker(s: &str) -> Option<i32> {
+    s.parse().ok().filter(|p| (1..=5).contains(p))
+}
+
+/// Letters, digits, `_` and `-`; must contain at least one letter
+fn parse_tag_marker(s: &str) -> Option<String> {
+    let valid = !s.is_empty()
+        && s.chars().all(|c| c.is_alphanumeric() || c == '_' || c == '-')
+        && s.chars().any(|c| c.is_alphabetic());
+
+    valid.then(|| s.to_lowercase())
+}
+
+/// Compound estimate (`45m`, `1h30m`, `2d`) in whole minutes
+fn parse_estimate_marker(s: &str) -> Option<i64> {
+    let minutes = parse_duration(s).ok()?.num_minutes();
+    (minutes > 0).then_some(minutes)
+}
+
+/// Parse `@<date> [HH:MM]` marker; returns due time and whether `next` was consumed
+fn parse_due_marker<Tz: TimeZone>(
+    date: &str,
+    next: Option<&str>,
+    now: &DateTime<Tz>,
+) -> Option<(DateTime<Utc>, bool)> {
+    let tz = now.timezone();
+    let today = now.naive_local().date();
+
+    // Time only: today, or tomorrow if that time has already passed
+    if let Some(time) = parse_clock_time(date) {
+        let mut due = resolve_local(&tz, today.and_time(time))?;
+        if due <= *now {
+            due = resolve_local(&tz, today.succ_opt()?.and_time(time))?;
+        }
+        return Some((due.with_timezone(&Utc), false));
+    }
+
+    let day = parse_due_date(date, today)?;
+
+    let (time, consumed_next) = match next.and_then(parse_clock_time) {
+        Some(time) => (time, true),
+        None => {
+            let (h, m) = DEFAULT_DUE_TIME;
+            (NaiveTime::from_hms_opt(h, m, 0)?, false)
+        }
+    };
+
+    let due = resolve_local(&tz, day.and_time(time))?;
+    Some((due.with_timezone(&Utc), consumed_next))
+}
+
+/// `today`, `tomorrow`, weekday names, `YYYY-MM-DD`, `DD.MM`, `DD.MM.YYYY`
+pub(crate) fn parse_due_date(s: &str, today: NaiveDate) -> Option<NaiveDate> {
+    let s = s.to_lowercase();
+
+    match s.as_str() {
+        "today" | "сегодня" => return Some(today),
+        "tomorrow" | "tmr" | "завтра" => return today.succ_opt(),
+        _ => {}
+    }
+
+    // Weekday: nearest such day, today included
+    if let Ok(weekday) = s.parse::<Weekday>() {
+        let ahead = (7 + weekday.num_days_from_monday() as i64
+            - today.weekday().num_days_from_monday() as i64)
+            % 7;
+        return Some(today + Duration::days(ahead));
+    }
+
+    if let Ok(date) = NaiveDate::parse_from_str(&s, "%Y-%m-%d") {
+        return Some(date);
+    }
+
+    if let Ok(date) = NaiveDate::parse_from_str(&s, "%d.%m.%Y") {
+        return Some(date);
+    }
+
+    // DD.MM: this year, or next year if already passed
+    let (day, month) = s.split_once('.')?;
+    let (day, month): (u32, u32) = (day.parse().ok()?, month.parse().ok()?);
+    let date = NaiveDate::from_ymd_opt(today.year(), month, day)?;
+    if date < today {
+        NaiveDate::from_ymd_opt(today.year() + 1, month, day)
+    } else {
+        Some(date)
+    }
+}
+
+/// `HH:MM` or `H:MM`
+pub(crate) fn parse_clock_time(s: &str) -> Option<NaiveTime> {
+    let (h, m) = s.split_once(':')?;
+    if h.is_empty() || h.len() > 2 || m.len() != 2 {
+        return None;
+    }
+
+    NaiveTime::from_hms_opt(h.parse().ok()?, m.parse().ok()?, 0)
+}
+
+/// Recurring schedule parsed from `every ...` text
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecurringSchedule {
+    pub pattern: RecurrencePattern,
+    /// First occurrence, always after `now`
+    pub first_at: DateTime<Utc>,
+    /// Text after the schedule (reminder message)
+    pub message: String,
+}
+
+/// Parse recurring schedule: `every <rule> [at] [HH:MM] <message>`
+///
+/// Rules: `day`, `weekday`, `week`, `monday` / `mon,wed,fri`,
+/// `month [on] <N>`, `year [on] <MM-DD|DD.MM>`.
+/// Without a day, month and year rules start from today's date.
+/// Dates and times are read in `now`'s time zone.
+pub fn parse_recurring<Tz: TimeZone>(input: &str, now: DateTime<Tz>) -> Option<RecurringSchedule> {
+    let mut tokens = input.split_whitespace().peekable();
+    if !tokens.next()?.eq_ignore_ascii_case("every") {
+        return None;
+    }
+
+    let today = now.naive_local().date();
+    let rule = tokens.next()?.to_lowercase();
+    let pattern = match rule.as_str() {
+        "day" | "daily" => RecurrencePattern::Daily,
+        "weekday" | "weekdays" | "workday" | "workdays" => RecurrencePattern::WeeklyOn(WORKDAYS.to_vec()),
+        "week" => RecurrencePattern::Weekly,
+        "month" => {
+            if tokens.peek().is_some_and(|t| t.eq_ignore_ascii_case("on")) {
+                tokens.next();
+            }
+            let day = tokens.peek().and_then(|t| parse_day_of_month(t));
+            if day.is_some() {
+                tokens.next();
+            }
+            RecurrencePattern::MonthlyOn(day.unwrap_or_else(|| today.day()))
+        }
+        "year" => {
+            if tokens.peek().is_some_and(|t| t.eq_ignore_ascii_case("on")) {
+                tokens.next();
+            }
+            let date = tokens.peek().and_then(|t| parse_month_day(t));
+            if date.is_some() {
+                tokens.next();
+            }
+            let (month, day) = date.unwrap_or_else(|| (today.month(), today.day()));
+            RecurrencePattern::Yearly { month, day }
+        }
+        days => RecurrencePattern::WeeklyOn(parse_weekday_list(days)?),
+    };
+
+    if tokens.peek().is_some_and(|t| t.eq_ignore_ascii_case("at")) {
+        tokens.next();
+    }
+    let time = match tokens.peek().and_then(|t| parse_clock_time(t)) {
+        Some(time) => {
+            tokens.next();
+            time
+        }
+        None => {
+            let (h, m) = DEFAULT_RECURRING_TIME;
+            NaiveTime::from_hms_opt(h, m, 0)?
+        }
+    };
+
+    // First occurrence: today if it matches and has not passed yet
+    let candidate = resolve_local(&now.timezone(), today.and_time(time))?;
+    let first_at = if candidate > now && pattern.matches(today) {
+        candidate
     } else {
+        pattern.next_after(&candidate)?
+    };
+
+    Some(RecurringSchedule {
+        pattern,
+        first_at: first_at.with_timezone(&Utc),
+        message: tokens.collect::<Vec<_>>().join(" "),
+    })
+}
+
+/// `15`, `15th`, `1st`
+fn parse_day_of_month(s: &str) -> Option<u32> {
+    let digits = s.trim_end_matches(|c: char| c.is_ascii_alphabetic());
+    digits.parse().ok().filter(|d| (1..=31).contains(d))
+}
+
+/// `MM-DD` or `DD.MM` as (month, day); Feb 29 is allowed
+fn parse_month_day(s: &str) -> Option<(u32, u32)> {
+    let (month, day): (u32, u32) = if let Some((m, d)) = s.split_once('-') {
+        (m.parse().ok()?, d.parse().ok()?)
+    } else {
+        let (d, m) = s.split_once('.')?;
+        (m.parse().ok()?, d.parse().ok()?)
+    };
+
+    NaiveDate::from_ymd_opt(2024, month, day).map(|_| (month, day))
+}
+
+/// `monday`, `mondays`, `mon,wed,fri`
+fn parse_weekday_list(s: &str) -> Option<Vec<Weekday>> {
+    let mut days = Vec::new();
+    for name in s.split(',').filter(|n| !n.is_empty()) {
+        let day = name
+            .parse::<Weekday>()
+            .or_else(|_| name.trim_end_matches('s').parse::<Weekday>())
+            .ok()?;
+        if !days.contains(&day) {
+            days.push(day);
+        }
+    }
+
+    if days.is_empty() {
         return None;
+    }
+    days.sort_by_key(|d| d.num_days_from_monday());
+    Some(days)
+}
+
+/// Settings of an insistent reminder parsed from `/nag` options
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NagOptions {
+    /// Pause between repeats
+    pub interval: Duration,
+    /// Repeats after the first ping
+    pub max_repeats: i32,
+    /// `@username` or chat ID to notify if nobody acknowledges
+    pub escalate: Option<String>,
+    /// Remaining text: reminder time and message
+    pub rest: String,
+}
+
+/// Parse leading `/nag` options: `[every <duration>] [max <n>] [escalate <target>]`
+///
+/// Options may come in any order; parsing stops at the first other word.
+pub fn parse_nag_options(input: &str) -> Result<NagOptions> {
+    let mut options = NagOptions {
+        interval: Duration::minutes(DEFAULT_NAG_INTERVAL_MINUTES),
+        max_repeats: DEFAULT_NAG_REPEATS,
+        escalate: None,
+        rest: String::new(),
     };
 
-    let num: i64 = num_str.parse().ok()?;
-    let now = Utc::now();
+    let mut tokens = input.split_whitespace().peekable();
+    while let Some(keyword) = tokens.peek().map(|t| t.to_lowercase()) {
+        if !matches!(keyword.as_str(), "every" | "max" | "escalate") {
+            break;
+        }
+        tokens.next();
 
-    match unit {
-        'm' => Some(now + Duration::minutes(num)),
-        'h' => Some(now + Duration::hours(num)),
-        'd' => Some(now + Duration::days(num)),
-        _ => None,
+        let value = tokens
+            .next()
+            .ok_or_else(|| validation_error(format!("Missing value after '{}'", keyword)))?;
+        match keyword.as_str() {
+            "every" => options.interval = parse_duration(value)?,
+            "max" => {
+                options.max_repeats = value.parse().map_err(|_| {
+                    validation_error(format!("Invalid number of repeats '{}'", value))
+                })?
+            }
+            _ => options.escalate = Some(value.to_string()),
+        }
     }
+
+    options.rest = tokens.collect::<Vec<_>>().join(" ");
+    Ok(options)
 }
 
-/// Format date for user display
-pub fn format_datetime(dt: &DateTime<Utc>) -> String {
-    dt.format("%Y-%m-%d %H:%M UTC").to_string()
+/// Split leading `@username` mentions off a reminder text
+///
+/// `"@alice @bob Standup"` gives `(["alice", "bob"], "Standup")`. Text made
+/// of mentions only is left as it is.
+pub fn split_mentions(text: &str) -> (Vec<String>, String) {
+    let mut mentions = Vec::new();
+    let mut rest = text.trim_start();
+
+    while let Some(word) = rest.split_whitespace().next() {
+        let Some(username) = word.strip_prefix('@').filter(|u| is_username(u)) else {
+            break;
+        };
+        mentions.push(username.to_string());
+        rest = rest[word.len()..].trim_start();
+    }
+
+    if rest.is_empty() {
+        return (Vec::new(), text.to_string());
+    }
+    (mentions, rest.to_string())
+}
+
+/// Arguments of `/editreminder <id> time=<when> text=<text> repeat=<rule>`
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct EditArgs {
+    pub time: Option<String>,
+    pub text: Option<String>,
+    pub repeat: Option<String>,
+}
+
+/// Parse `key=value` pairs of /editreminder (after the ID)
+///
+/// A value runs until the next key, so it may contain spaces:
+/// `time=tomorrow 9am text=Call mom`.
+pub fn parse_edit_args(input: &str) -> Result<EditArgs> {
+    const KEYS: [&str; 3] = ["time=", "text=", "repeat="];
+
+    // Byte offsets of the words that start a key
+    let mut starts = Vec::new();
+    let mut offset = 0;
+    for word in input.split_whitespace() {
+        let at = offset + input[offset..].find(word).unwrap_or(0);
+        offset = at + word.len();
+        let lowered = word.to_lowercase();
+        if let Some(key) = KEYS.iter().find(|key| lowered.starts_with(*key)) {
+            starts.push((at, *key));
+        }
+    }
+
+    let first = starts.first().map_or(input.len(), |(at, _)| *at);
+    if starts.is_empty() || !input[..first].trim().is_empty() {
+        return Err(validation_error("Use time=<when>, text=<text> or repeat=<rule>"));
+    }
+
+    let mut args = EditArgs::default();
+    for (i, (at, key)) in starts.iter().enumerate() {
+        let end = starts.get(i + 1).map_or(input.len(), |(next, _)| *next);
+        let value = input[at + key.len()..end].trim().to_string();
+        if value.is_empty() {
+            return Err(validation_error(format!("Missing value after '{}'", key)));
+        }
+
+        let slot = match *key {
+            "time=" => &mut args.time,
+            "text=" => &mut args.text,
+            _ => &mut args.repeat,
+        };
+        if slot.replace(value).is_some() {
+            return Err(validation_error(format!("'{}' is given twice", key)));
+        }
+    }
+
+    Ok(args)
+}
+
+/// Split a leading `urgent` (`срочно`, `!`) marker off command arguments
+pub fn split_urgent(text: &str) -> (bool, String) {
+    let text = text.trim_start();
+    let word = text.split_whitespace().next().unwrap_or_default();
+    if matches!(word.to_lowercase().as_str(), "urgent" | "срочно" | "!") {
+        (true, text[word.len()..].trim_start().to_string())
+    } else {
+        (false, text.to_string())
+    }
+}
+
+/// Split a leading `via email,telegram` off command arguments
+pub fn split_channels(text: &str) -> Result<(Option<Vec<NotifyChannel>>, String)> {
+    let text = text.trim_start();
+    let mut words = text.splitn(3, char::is_whitespace);
+    match (words.next(), words.next()) {
+        (Some(via), Some(list)) if via.eq_ignore_ascii_case("via") => Ok((
+            Some(NotifyChannel::parse_list(list)?),
+            words.next().unwrap_or_default().trim_start().to_string(),
+        )),
+        _ => Ok((None, text.to_string())),
+    }
+}
+
+/// Telegram username: 5-32 letters, digits and underscores
+fn is_username(name: &str) -> bool {
+    (5..=32).contains(&name.len()) && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Format date for user display in its own time zone
+///
+/// Convert with `with_timezone(&user.tz())` first to show local time.
+pub fn format_datetime<Tz: TimeZone>(dt: &DateTime<Tz>) -> String
+where
+    Tz::Offset: std::fmt::Display,
+{
+    dt.format("%Y-%m-%d %H:%M %Z").to_string()
+}
+
+/// Short duration for delivery latency: 4s, 3m 5s, 2h 10m, 1d 3h
+pub fn format_latency(latency: chrono::Duration) -> String {
+    let seconds = latency.num_seconds().max(0);
+    match (seconds / 86400, seconds % 86400 / 3600, seconds % 3600 / 60, seconds % 60) {
+        (0, 0, 0, 0) if latency.num_milliseconds() > 0 => "<1s".to_string(),
+        (0, 0, 0, s) => format!("{}s", s),
+        (0, 0, m, 0) => format!("{}m", m),
+        (0, 0, m, s) => format!("{}m {}s", m, s),
+        (0, h, m, _) if m > 0 => format!("{}h {}m", h, m),
+        (0, h, _, _) => format!("{}h", h),
+        (d, h, _, _) if h > 0 => format!("{}d {}h", d, h),
+        (d, _, _, _) => format!("{}d", d),
+    }
 }
 
 /// Truncate text to specified length with "..."
 pub fn truncate_text(text: &str, max_len: usize) -> String {
-    if text.len() <= max_len {
+    // Count characters, not bytes: titles are often Cyrillic
+    if text.chars().count() <= max_len {
         text.to_string()
     } else {
-        format!("{}...", &text[..max_len - 3])
+        let head: String = text.chars().take(max_len.saturating_sub(3)).collect();
+        format!("{}...", head)
     }
 }
 
-/// Эскейпинг специальных символов для Markdown
+/// Эскейпинг специальных символов для MarkdownV2 (включая сам `\`)
 pub fn escape_markdown(text: &str) -> String {
-    text.replace('_', "\\_")
-        .replace('*', "\\*")
-        .replace('[', "\\[")
-        .replace(']', "\\]")
-        .replace('(', "\\(")
-        .replace(')', "\\)")
-        .replace('~', "\\~")
-        .replace('`', "\\`")
-        .replace('>', "\\>")
-        .replace('#', "\\#")
-        .replace('+', "\\+")
-        .replace('-', "\\-")
-        .replace('=', "\\=")
-        .replace('|', "\\|")
-        .replace('{', "\\{")
-        .replace('}', "\\}")
-        .replace('.', "\\.")
-        .replace('!', "\\!")
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if "\\_*[]()~`>#+-=|{}.!".contains(c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn fixed_now() -> DateTime<Utc> {
+        // Monday, 2026-10-19 12:00 UTC
+        chrono::TimeZone::with_ymd_and_hms(&Utc, 2026, 10, 19, 12, 0, 0).unwrap()
+    }
+
+    fn at(y: i32, mo: u32, d: u32, h: u32, mi: u32) -> DateTime<Utc> {
+        chrono::TimeZone::with_ymd_and_hms(&Utc, y, mo, d, h, mi, 0).unwrap()
+    }
+
+    #[test]
+    fn test_quick_add_full_example() {
+        let parsed = parse_quick_add("Fix login bug !1 #backend @tomorrow 18:00 ~2h", fixed_now());
+
+        assert_eq!(
+            parsed,
+            QuickAdd {
+                title: "Fix login bug".to_string(),
+                priority: Some(1),
+                tags: vec!["backend".to_string()],
+                due: Some(at(2026, 10, 20, 18, 0)),
+                estimate_minutes: Some(120),
+            }
+        );
+    }
+
+    #[test]
+    fn test_quick_add_plain_text() {
+        let parsed = parse_quick_add("Buy milk", fixed_now());
+        assert_eq!(parsed.title, "Buy milk");
+        assert_eq!(parsed.priority, None);
+        assert!(parsed.tags.is_empty());
+        assert_eq!(parsed.due, None);
+        assert_eq!(parsed.estimate_minutes, None);
+    }
+
     #[test]
-    fn test_parse_relative_time() {
-        let result = parse_relative_time("30m");
-        assert!(result.is_some());
+    fn test_quick_add_markers_anywhere() {
+        let parsed = parse_quick_add("#home !3 Clean the garage ~1h30m", fixed_now());
+        assert_eq!(parsed.title, "Clean the garage");
+        assert_eq!(parsed.priority, Some(3));
+        assert_eq!(parsed.tags, vec!["home"]);
+        assert_eq!(parsed.estimate_minutes, Some(90));
+    }
 
-        let result = parse_relative_time("2h");
-        assert!(result.is_some());
+    #[test]
+    fn test_quick_add_priority() {
+        assert_eq!(parse_quick_add("a !5", fixed_now()).priority, Some(5));
+        // Out of range and non-numeric stay in title
+        let parsed = parse_quick_add("a !7 !! wow!", fixed_now());
+        assert_eq!(parsed.priority, None);
+        assert_eq!(parsed.title, "a !7 !! wow!");
+        // Last marker wins
+        assert_eq!(parse_quick_add("a !1 !4", fixed_now()).priority, Some(4));
+    }
 
-        let result = parse_relative_time("invalid");
-        assert!(result.is_none());
+    #[test]
+    fn test_quick_add_tags() {
+        let parsed = parse_quick_add("Deploy #Backend #ops #backend #срочно", fixed_now());
+        assert_eq!(parsed.tags, vec!["backend", "ops", "срочно"]);
+        assert_eq!(parsed.title, "Deploy");
+
+        // Issue numbers and bare hashes are not tags
+        let parsed = parse_quick_add("Close #123 and # later", fixed_now());
+        assert!(parsed.tags.is_empty());
+        assert_eq!(parsed.title, "Close #123 and # later");
+    }
+
+    #[test]
+    fn test_quick_add_estimate() {
+        assert_eq!(parse_quick_add("a ~45m", fixed_now()).estimate_minutes, Some(45));
+        assert_eq!(parse_quick_add("a ~1d", fixed_now()).estimate_minutes, Some(1440));
+        assert_eq!(parse_quick_add("a ~2d4h", fixed_now()).estimate_minutes, Some(3120));
+        assert_eq!(parse_quick_add("a ~1w", fixed_now()).estimate_minutes, Some(10080));
+
+        for bad in ["a ~", "a ~2", "a ~h", "a ~2x", "a ~0m", "a ~30s"] {
+            let parsed = parse_quick_add(bad, fixed_now());
+            assert_eq!(parsed.estimate_minutes, None, "{}", bad);
+            assert_eq!(parsed.title, bad);
+        }
+    }
+
+    #[test]
+    fn test_quick_add_relative_dates() {
+        let now = fixed_now();
+        assert_eq!(parse_quick_add("a @today", now).due, Some(at(2026, 10, 19, 23, 59)));
+        assert_eq!(parse_quick_add("a @tomorrow", now).due, Some(at(2026, 10, 20, 23, 59)));
+        assert_eq!(parse_quick_add("a @tmr 9:30", now).due, Some(at(2026, 10, 20, 9, 30)));
+        assert_eq!(parse_quick_add("a @завтра 10:00", now).due, Some(at(2026, 10, 20, 10, 0)));
+    }
+
+    #[test]
+    fn test_quick_add_weekdays() {
+        let now = fixed_now(); // Monday
+        assert_eq!(parse_quick_add("a @mon", now).due, Some(at(2026, 10, 19, 23, 59)));
+        assert_eq!(parse_quick_add("a @fri", now).due, Some(at(2026, 10, 23, 23, 59)));
+        assert_eq!(parse_quick_add("a @Friday 08:00", now).due, Some(at(2026, 10, 23, 8, 0)));
+        assert_eq!(parse_quick_add("a @sun", now).due, Some(at(2026, 10, 25, 23, 59)));
+    }
+
+    #[test]
+    fn test_quick_add_absolute_dates() {
+        let now = fixed_now();
+        assert_eq!(
+            parse_quick_add("a @2026-12-31 10:00", now).due,
+            Some(at(2026, 12, 31, 10, 0))
+        );
+        assert_eq!(parse_quick_add("a @25.10", now).due, Some(at(2026, 10, 25, 23, 59)));
+        // Already passed this year -> next year
+        assert_eq!(parse_quick_add("a @01.02", now).due, Some(at(2027, 2, 1, 23, 59)));
+        assert_eq!(parse_quick_add("a @01.02.2028", now).due, Some(at(2028, 2, 1, 23, 59)));
+    }
+
+    #[test]
+    fn test_quick_add_time_only() {
+        let now = fixed_now(); // 12:00
+        assert_eq!(parse_quick_add("a @18:00", now).due, Some(at(2026, 10, 19, 18, 0)));
+        assert_eq!(parse_quick_add("a @09:00", now).due, Some(at(2026, 10, 20, 9, 0)));
+    }
+
+    #[test]
+    fn test_quick_add_time_not_consumed_without_date() {
+        // Time after a non-date token stays in the title
+        let parsed = parse_quick_add("Call at 18:00", fixed_now());
+        assert_eq!(parsed.title, "Call at 18:00");
+        assert_eq!(parsed.due, None);
+
+        // Date followed by a word: word stays in title
+        let parsed = parse_quick_add("Report @fri draft", fixed_now());
+        assert_eq!(parsed.title, "Report draft");
+        assert_eq!(parsed.due, Some(at(2026, 10, 23, 23, 59)));
+    }
+
+    #[test]
+    fn test_quick_add_invalid_dates_stay_in_title() {
+        for text in ["Mail bob@example.com", "a @someday", "a @31.02", "a @25:00", "a @"] {
+            let parsed = parse_quick_add(text, fixed_now());
+            assert_eq!(parsed.due, None, "{}", text);
+            assert_eq!(parsed.title, text);
+        }
+    }
+
+    #[test]
+    fn test_quick_add_only_markers() {
+        let parsed = parse_quick_add("!2 #x ~1h", fixed_now());
+        assert_eq!(parsed.title, "");
+        assert_eq!(parsed.priority, Some(2));
+    }
+
+    #[test]
+    fn test_parse_recurring_daily() {
+        let now = fixed_now(); // Monday 12:00
+        let parsed = parse_recurring("every day 09:00 Standup", now).unwrap();
+        assert_eq!(parsed.pattern, RecurrencePattern::Daily);
+        assert_eq!(parsed.first_at, at(2026, 10, 20, 9, 0));
+        assert_eq!(parsed.message, "Standup");
+
+        let parsed = parse_recurring("Every day at 18:30 Walk the dog", now).unwrap();
+        assert_eq!(parsed.first_at, at(2026, 10, 19, 18, 30));
+        assert_eq!(parsed.message, "Walk the dog");
+    }
+
+    #[test]
+    fn test_parse_recurring_weekdays() {
+        let now = fixed_now();
+        let parsed = parse_recurring("every weekday 13:00 Lunch", now).unwrap();
+        assert_eq!(parsed.pattern, RecurrencePattern::WeeklyOn(WORKDAYS.to_vec()));
+        assert_eq!(parsed.first_at, at(2026, 10, 19, 13, 0));
+
+        let parsed = parse_recurring("every fri,mon 10:00 Sync", now).unwrap();
+        assert_eq!(parsed.pattern, RecurrencePattern::WeeklyOn(vec![Weekday::Mon, Weekday::Fri]));
+        assert_eq!(parsed.first_at, at(2026, 10, 23, 10, 0));
+
+        let parsed = parse_recurring("every sundays Call mom", now).unwrap();
+        assert_eq!(parsed.pattern, RecurrencePattern::WeeklyOn(vec![Weekday::Sun]));
+        assert_eq!(parsed.first_at, at(2026, 10, 25, 9, 0));
+        assert_eq!(parsed.message, "Call mom");
+    }
+
+    #[test]
+    fn test_parse_recurring_monthly_and_yearly() {
+        let now = fixed_now();
+        let parsed = parse_recurring("every month on 1st 10:00 Pay rent", now).unwrap();
+        assert_eq!(parsed.pattern, RecurrencePattern::MonthlyOn(1));
+        assert_eq!(parsed.first_at, at(2026, 11, 1, 10, 0));
+
+        let parsed = parse_recurring("every month 25 Invoice", now).unwrap();
+        assert_eq!(parsed.first_at, at(2026, 10, 25, 9, 0));
+
+        let parsed = parse_recurring("every year on 14.03 Mom's birthday", now).unwrap();
+        assert_eq!(parsed.pattern, RecurrencePattern::Yearly { month: 3, day: 14 });
+        assert_eq!(parsed.first_at, at(2027, 3, 14, 9, 0));
+        assert_eq!(parsed.message, "Mom's birthday");
+
+        let parsed = parse_recurring("every year 12-31 23:00 New year", now).unwrap();
+        assert_eq!(parsed.first_at, at(2026, 12, 31, 23, 0));
+    }
+
+    #[test]
+    fn test_parse_recurring_invalid() {
+        let now = fixed_now();
+        assert!(parse_recurring("tomorrow 9:00 x", now).is_none());
+        assert!(parse_recurring("every", now).is_none());
+        assert!(parse_recurring("every blursday 10:00 x", now).is_none());
+        // Invalid day stays in the message
+        let parsed = parse_recurring("every month 40 x", now).unwrap();
+        assert_eq!(parsed.pattern, RecurrencePattern::MonthlyOn(19));
+        assert_eq!(parsed.message, "40 x");
+    }
+
+    #[test]
+    fn test_user_time_zone() {
+        // 12:00 UTC is 15:00 in Moscow
+        let moscow = fixed_now().with_timezone(&chrono_tz::Europe::Moscow);
+
+        let parsed = parse_quick_add("a @tomorrow 09:00", moscow);
+        assert_eq!(parsed.due, Some(at(2026, 10, 20, 6, 0)));
+        // 14:00 local has passed: tomorrow
+        assert_eq!(parse_quick_add("a @14:00", moscow).due, Some(at(2026, 10, 20, 11, 0)));
+
+        let parsed = parse_recurring("every day 09:00 Standup", moscow).unwrap();
+        assert_eq!(parsed.first_at, at(2026, 10, 20, 6, 0));
+
+        // 21:30 UTC Monday is already Tuesday in Moscow
+        let late = at(2026, 10, 19, 21, 30).with_timezone(&chrono_tz::Europe::Moscow);
+        let parsed = parse_recurring("every weekday 09:00 Standup", late).unwrap();
+        assert_eq!(parsed.first_at, at(2026, 10, 20, 6, 0));
+    }
+
+    #[test]
+    fn test_format_datetime() {
+        let dt = at(2026, 10, 19, 12, 0);
+        assert_eq!(format_datetime(&dt), "2026-10-19 12:00 UTC");
+        assert_eq!(
+            format_datetime(&dt.with_timezone(&chrono_tz::Europe::Moscow)),
+            "2026-10-19 15:00 MSK"
+        );
     }
 
     #[test]
     fn test_truncate_text() {
         assert_eq!(truncate_text("Hello", 10), "Hello");
         assert_eq!(truncate_text("Hello World!", 8), "Hello...");
+        assert_eq!(truncate_text("Привет, мир!", 9), "Привет...");
+    }
+
+    #[test]
+    fn test_parse_nag_options() {
+        let options = parse_nag_options("every 10m max 3 escalate @oncall at 22:00 Handoff").unwrap();
+        assert_eq!(options.interval, Duration::minutes(10));
+        assert_eq!(options.max_repeats, 3);
+        assert_eq!(options.escalate.as_deref(), Some("@oncall"));
+        assert_eq!(options.rest, "at 22:00 Handoff");
+
+        let defaults = parse_nag_options("in 1h Check the deploy").unwrap();
+        assert_eq!(defaults.interval, Duration::minutes(5));
+        assert_eq!(defaults.max_repeats, 6);
+        assert_eq!(defaults.escalate, None);
+        assert_eq!(defaults.rest, "in 1h Check the deploy");
+
+        let reordered = parse_nag_options("escalate -1001234 EVERY 1h 30m Call").unwrap();
+        assert_eq!(reordered.escalate.as_deref(), Some("-1001234"));
+        assert_eq!(reordered.interval, Duration::hours(1));
+        assert_eq!(reordered.rest, "30m Call");
+    }
+
+    #[test]
+    fn test_parse_edit_args() {
+        assert_eq!(
+            parse_edit_args("time=tomorrow 9am text=Call mom, then dad").unwrap(),
+            EditArgs {
+                time: Some("tomorrow 9am".to_string()),
+                text: Some("Call mom, then dad".to_string()),
+                repeat: None,
+            }
+        );
+        assert_eq!(
+            parse_edit_args(" Repeat=every weekday").unwrap().repeat.as_deref(),
+            Some("every weekday")
+        );
+
+        assert!(parse_edit_args("").is_err());
+        assert!(parse_edit_args("tomorrow time=9am").is_err());
+        assert!(parse_edit_args("text= time=9am").is_err());
+        assert!(parse_edit_args("time=9am time=10am").is_err());
+    }
+
+    #[test]
+    fn test_escape_markdown() {
+        assert_eq!(escape_markdown("Pay 1.5k (rent)!"), "Pay 1\\.5k \\(rent\\)\\!");
+        assert_eq!(escape_markdown(r"C:\tmp\*"), r"C:\\tmp\\\*");
+        assert_eq!(escape_markdown("Привет"), "Привет");
+    }
+
+    #[test]
+    fn test_split_urgent() {
+        assert_eq!(split_urgent("urgent 22:00 Pills"), (true, "22:00 Pills".to_string()));
+        assert_eq!(split_urgent("Срочно через час Звонок"), (true, "через час Звонок".to_string()));
+        assert_eq!(split_urgent("! 5m Oven"), (true, "5m Oven".to_string()));
+        assert_eq!(split_urgent("5m urgent call"), (false, "5m urgent call".to_string()));
+    }
+
+    #[test]
+    fn test_split_channels() {
+        assert_eq!(
+            split_channels("via email,telegram 18:00 Pay rent").unwrap(),
+            (
+                Some(vec![NotifyChannel::Email, NotifyChannel::Telegram]),
+                "18:00 Pay rent".to_string()
+            )
+        );
+        assert_eq!(
+            split_channels("5m Check the viaduct").unwrap(),
+            (None, "5m Check the viaduct".to_string())
+        );
+        assert!(split_channels("via fax 5m Oven").is_err());
+    }
+
+    #[test]
+    fn test_split_mentions() {
+        assert_eq!(
+            split_mentions("@alice_1 @bob_smith Standup in 5 minutes"),
+            (
+                vec!["alice_1".to_string(), "bob_smith".to_string()],
+                "Standup in 5 minutes".to_string()
+            )
+        );
+        // Only leading mentions; short or invalid names are text
+        assert_eq!(
+            split_mentions("Ping @alice_1"),
+            (Vec::new(), "Ping @alice_1".to_string())
+        );
+        assert_eq!(split_mentions("@bob Lunch"), (Vec::new(), "@bob Lunch".to_string()));
+        assert_eq!(
+            split_mentions("@alice_1 @bob_smith"),
+            (Vec::new(), "@alice_1 @bob_smith".to_string())
+        );
+    }
+
+    #[test]
+    fn test_parse_nag_options_errors() {
+        let message = |s: &str| parse_nag_options(s).unwrap_err().user_message();
+
+        assert!(message("every").contains("Missing value after 'every'"));
+        assert!(message("every 5 10m Call").contains("Missing time unit"));
+        assert!(message("max lots 10m Call").contains("Invalid number of repeats"));
+    }
+
+    #[test]
+    fn test_format_latency() {
+        let latency = |ms: i64| format_latency(chrono::Duration::milliseconds(ms));
+
+        assert_eq!(latency(0), "0s");
+        assert_eq!(latency(300), "<1s");
+        assert_eq!(latency(4_000), "4s");
+        assert_eq!(latency(185_000), "3m 5s");
+        assert_eq!(latency(120_000), "2m");
+        assert_eq!(latency(7_830_000), "2h 10m");
+        assert_eq!(latency(97_200_000), "1d 3h");
+        assert_eq!(latency(-5_000), "0s");
     }
 }
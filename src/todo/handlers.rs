@@ -5,7 +5,10 @@
 
 use crate::error::Result;
 use crate::shared::types::TodoStatus;
-use crate::todo::models::TodoView;
+use crate::shared::utils::truncate_text;
+use crate::todo::models::{TodoView, WeeklyReview};
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
 
 /// Format task list for user display
 pub fn format_todo_list(todos: Vec<TodoView>) -> String {
@@ -69,6 +72,76 @@ pub fn format_todo_details(todo: &TodoView) -> String {
     output
 }
 
+/// Format weekly review (plain text, titles are not escaped)
+///
+/// Dates are shown in `now`'s time zone.
+pub fn format_weekly_review(review: &WeeklyReview, now: DateTime<Tz>) -> String {
+    let tz = now.timezone();
+    let stats = &review.stats;
+    let mut output = format!(
+        "🗓 Weekly review: {} – {}\n\n\
+         ✅ Completed: {}\n\
+         🆕 Created: {}\n\
+         ⚠️ Slipped past due: {}\n\
+         ⏱ Time tracked: {}\n\
+         📋 Still open: {}\n",
+        review.range.from.with_timezone(&tz).format("%d.%m"),
+        review.range.to.with_timezone(&tz).format("%d.%m"),
+        stats.completed_in_range,
+        stats.created_in_range,
+        stats.slipped_in_range,
+        format_minutes(stats.minutes_tracked),
+        stats.pending + stats.in_progress,
+    );
+
+    if !review.slipped.is_empty() {
+        output.push_str("\n⚠️ Slipped:\n");
+        for todo in &review.slipped {
+            let due = todo
+                .due_date
+                .map(|d| d.with_timezone(&tz).format("%d.%m").to_string());
+            output.push_str(&format!(
+                "• #{} {} (due {})\n",
+                todo.id,
+                truncate_text(&todo.title, 50),
+                due.unwrap_or_default()
+            ));
+        }
+    }
+
+    let oldest: Vec<_> = review
+        .stale
+        .iter()
+        .filter(|t| !review.slipped.iter().any(|s| s.id == t.id))
+        .collect();
+    if !oldest.is_empty() {
+        output.push_str("\n🕸 Oldest open:\n");
+        for todo in oldest {
+            output.push_str(&format!(
+                "• #{} {} ({} days old)\n",
+                todo.id,
+                truncate_text(&todo.title, 50),
+                (now.with_timezone(&Utc) - todo.created_at).num_days()
+            ));
+        }
+    }
+
+    if !review.stale.is_empty() {
+        output.push_str("\n💡 Reschedule or drop stale tasks with the buttons below.");
+    }
+
+    output
+}
+
+/// Format minutes as "3h 20m"
+pub fn format_minutes(minutes: i64) -> String {
+    match (minutes / 60, minutes % 60) {
+        (0, m) => format!("{}m", m),
+        (h, 0) => format!("{}h", h),
+        (h, m) => format!("{}h {}m", h, m),
+    }
+}
+
 /// Escape special characters for Markdown
 fn escape_markdown(text: &str) -> String {
     text.replace('_', "\\_")
@@ -93,4 +166,12 @@ mod tests {
         assert!(escaped.contains("\\*"));
         assert!(escaped.contains("\\_"));
     }
+
+    #[test]
+    fn test_format_minutes() {
+        assert_eq!(format_minutes(0), "0m");
+        assert_eq!(format_minutes(45), "45m");
+        assert_eq!(format_minutes(120), "2h");
+        assert_eq!(format_minutes(200), "3h 20m");
+    }
 }
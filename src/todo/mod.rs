@@ -3,3 +3,4 @@ pub mod models;
 pub mod repository;
 pub mod service;
 pub mod handlers;
+pub mod review;
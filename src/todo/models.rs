@@ -4,21 +4,179 @@
 // Add specific DTOs for API
 
 pub use crate::db::models::{NewTodo, Todo, UpdateTodo};
-use crate::shared::types::{Priority, TodoStatus};
+use crate::error::{validation_error, AppError};
+use crate::todo::repository::TodoStats;
+use crate::shared::types::{DbId, Priority, Timestamp, TodoStatus};
+use chrono::DateTime;
 use serde::{Deserialize, Serialize};
 
 /// Filter for searching tasks
+///
+/// All set conditions are combined with AND; empty lists match everything.
 #[derive(Debug, Clone, Default)]
 pub struct TodoFilter {
-    pub status: Option<TodoStatus>,
-    pub priority: Option<Priority>,
+    /// Any of these statuses
+    pub statuses: Vec<TodoStatus>,
+    /// Any of these priorities
+    pub priorities: Vec<Priority>,
+    /// Case-insensitive substring of title or description
     pub search: Option<String>,
+    /// All of these tags
+    pub tags: Vec<String>,
+    /// Due date at or after
+    pub due_from: Option<Timestamp>,
+    /// Due date before
+    pub due_to: Option<Timestamp>,
+    /// Created at or after
+    pub created_from: Option<Timestamp>,
+    /// Created before
+    pub created_to: Option<Timestamp>,
+}
+
+/// Maximum page size (DoS protection)
+pub const MAX_TODOS: i64 = 1000;
+
+/// Page request for keyset pagination
+#[derive(Debug, Clone)]
+pub struct TodoPage {
+    /// Page size, clamped to 1..=MAX_TODOS
+    pub limit: i64,
+    /// Position after which the page starts (`None` = first page)
+    pub after: Option<TodoCursor>,
+}
+
+impl Default for TodoPage {
+    fn default() -> Self {
+        Self {
+            limit: MAX_TODOS,
+            after: None,
+        }
+    }
+}
+
+/// Page of tasks with the cursor of the next page
+#[derive(Debug, Clone)]
+pub struct TodoPageResult {
+    pub todos: Vec<Todo>,
+    /// `None` if this is the last page
+    pub next: Option<TodoCursor>,
+}
+
+/// Keyset pagination cursor: sort key and id of the last row of a page
+///
+/// Serialized as an opaque string (`c:<micros>:<id>`, `p:<priority>:<id>`,
+/// `t:<id>:<title>`) for use in callback data.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TodoCursor {
+    pub key: CursorKey,
+    pub id: DbId,
+}
+
+/// Sort key value stored in a cursor
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CursorKey {
+    CreatedAt(Timestamp),
+    Priority(Priority),
+    Title(String),
+}
+
+impl TodoCursor {
+    /// Cursor pointing after `todo` for the given sort order
+    pub fn after(todo: &Todo, sort: TodoSort) -> Self {
+        let key = match sort {
+            TodoSort::CreatedAtAsc | TodoSort::CreatedAtDesc => CursorKey::CreatedAt(todo.created_at),
+            TodoSort::PriorityAsc | TodoSort::PriorityDesc => CursorKey::Priority(todo.priority),
+            TodoSort::TitleAsc | TodoSort::TitleDesc => CursorKey::Title(todo.title.clone()),
+        };
+
+        Self { key, id: todo.id }
+    }
+
+    /// Check that the cursor was produced for the given sort order
+    pub fn matches(&self, sort: TodoSort) -> bool {
+        matches!(
+            (&self.key, sort),
+            (CursorKey::CreatedAt(_), TodoSort::CreatedAtAsc | TodoSort::CreatedAtDesc)
+                | (CursorKey::Priority(_), TodoSort::PriorityAsc | TodoSort::PriorityDesc)
+                | (CursorKey::Title(_), TodoSort::TitleAsc | TodoSort::TitleDesc)
+        )
+    }
+}
+
+impl std::fmt::Display for TodoCursor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.key {
+            CursorKey::CreatedAt(ts) => write!(f, "c:{}:{}", ts.timestamp_micros(), self.id),
+            CursorKey::Priority(p) => write!(f, "p:{}:{}", p, self.id),
+            CursorKey::Title(title) => write!(f, "t:{}:{}", self.id, title),
+        }
+    }
+}
+
+impl std::str::FromStr for TodoCursor {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || validation_error(format!("Invalid cursor: {}", s));
+        let mut parts = s.splitn(3, ':');
+        let (kind, first, rest) = match (parts.next(), parts.next(), parts.next()) {
+            (Some(kind), Some(first), Some(rest)) => (kind, first, rest),
+            _ => return Err(invalid()),
+        };
+
+        let (key, id) = match kind {
+            "c" => {
+                let micros = first.parse().map_err(|_| invalid())?;
+                let ts = DateTime::from_timestamp_micros(micros).ok_or_else(invalid)?;
+                (CursorKey::CreatedAt(ts), rest)
+            }
+            "p" => (CursorKey::Priority(first.parse().map_err(|_| invalid())?), rest),
+            "t" => (CursorKey::Title(rest.to_string()), first),
+            _ => return Err(invalid()),
+        };
+
+        Ok(Self {
+            key,
+            id: id.parse().map_err(|_| invalid())?,
+        })
+    }
+}
+
+/// Half-open time range `[from, to)` for statistics
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DateRange {
+    pub from: Timestamp,
+    pub to: Timestamp,
+}
+
+impl DateRange {
+    /// Range covering the last `days` days up to `now`
+    pub fn last_days(now: Timestamp, days: i64) -> Self {
+        Self {
+            from: now - chrono::Duration::days(days),
+            to: now,
+        }
+    }
+}
+
+/// Weekly summary of user activity
+#[derive(Debug, Clone)]
+pub struct WeeklyReview {
+    /// Period covered by the review
+    pub range: DateRange,
+    /// Counters for the period
+    pub stats: TodoStats,
+    /// Open tasks whose due date passed during the period
+    pub slipped: Vec<Todo>,
+    /// Tasks offered for rescheduling or dropping (slipped first, then oldest)
+    pub stale: Vec<Todo>,
 }
 
 /// Task sorting options
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Default)]
 pub enum TodoSort {
     CreatedAtAsc,
+    #[default]
     CreatedAtDesc,
     PriorityAsc,
     PriorityDesc,
@@ -26,12 +184,6 @@ pub enum TodoSort {
     TitleDesc,
 }
 
-impl Default for TodoSort {
-    fn default() -> Self {
-        TodoSort::CreatedAtDesc
-    }
-}
-
 /// DTO for displaying task to user
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TodoView {
@@ -4,10 +4,12 @@
 // All SQL queries are located here
 
 use crate::db::models::{NewTodo, Todo, UpdateTodo};
-use crate::error::{not_found, Result};
-use crate::shared::types::DbId;
-use crate::todo::models::{TodoFilter, TodoSort};
-use sqlx::PgPool;
+use crate::error::{not_found, validation_error, Result};
+use crate::shared::types::{DbId, Timestamp};
+use crate::todo::models::{
+    CursorKey, DateRange, TodoCursor, TodoFilter, TodoPage, TodoPageResult, TodoSort, MAX_TODOS,
+};
+use sqlx::{PgPool, Postgres, QueryBuilder};
 
 /// Repository for working with tasks
 #[derive(Clone)]
@@ -24,8 +26,8 @@ impl TodoRepository {
     pub async fn create(&self, new_todo: NewTodo) -> Result<Todo> {
         let todo = sqlx::query_as::<_, Todo>(
             r#"
-            INSERT INTO todos (user_id, title, description, priority)
-            VALUES ($1, $2, $3, $4)
+            INSERT INTO todos (user_id, title, description, priority, due_date, tags, estimate_minutes)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
             RETURNING *
             "#,
         )
@@ -33,6 +35,9 @@ impl TodoRepository {
         .bind(&new_todo.title)
         .bind(&new_todo.description)
         .bind(new_todo.priority)
+        .bind(new_todo.due_date)
+        .bind(&new_todo.tags)
+        .bind(new_todo.estimate_minutes)
         .fetch_one(&self.pool)
         .await?;
 
@@ -51,252 +56,49 @@ impl TodoRepository {
         Ok(todo)
     }
 
-    /// Find all user tasks
+    /// Find user tasks matching the filter, one page at a time
     pub async fn find_by_user(
         &self,
         user_id: DbId,
         filter: TodoFilter,
         sort: TodoSort,
-    ) -> Result<Vec<Todo>> {
-        // SAFE query construction - use parameters only
-        // ORDER BY безопасен т.к. использует enum (не пользовательский ввод)
-        let order_by = match sort {
-            TodoSort::CreatedAtAsc => "created_at ASC",
-            TodoSort::CreatedAtDesc => "created_at DESC",
-            TodoSort::PriorityAsc => "priority ASC",
-            TodoSort::PriorityDesc => "priority DESC",
-            TodoSort::TitleAsc => "title ASC",
-            TodoSort::TitleDesc => "title DESC",
-        };
-
-        // DoS PROTECTION: limit number of returned records
-        const MAX_TODOS: i64 = 1000;
-
-        // Use different queries depending on filters (безопасно)
-        let todos = match (&filter.status, filter.priority, &filter.search) {
-            (None, None, None) => {
-                // Without filters
-                sqlx::query_as::<_, Todo>(&format!(
-                    "SELECT * FROM todos WHERE user_id = $1 ORDER BY {} LIMIT {}",
-                    order_by, MAX_TODOS
-                ))
-                .bind(user_id)
-                .fetch_all(&self.pool)
-                .await?
-            }
-            (Some(status), None, None) => {
-                // Status only
-                sqlx::query_as::<_, Todo>(&format!(
-                    "SELECT * FROM todos WHERE user_id = $1 AND status = $2 ORDER BY {}",
-                    order_by
-                ))
-                .bind(user_id)
-                .bind(status.to_string())
-                .fetch_all(&self.pool)
-                .await?
-            }
-            (None, Some(priority), None) => {
-                // Priority only
-                sqlx::query_as::<_, Todo>(&format!(
-                    "SELECT * FROM todos WHERE user_id = $1 AND priority = $2 ORDER BY {}",
-                    order_by
-                ))
-                .bind(user_id)
-                .bind(priority)
-                .fetch_all(&self.pool)
-                .await?
-            }
-            (None, None, Some(search)) => {
-                // Search only
-                let search_pattern = format!("%{}%", search);
-                sqlx::query_as::<_, Todo>(&format!(
-                    "SELECT * FROM todos WHERE user_id = $1 AND (title ILIKE $2 OR description ILIKE $2) ORDER BY {}",
-                    order_by
-                ))
-                .bind(user_id)
-                .bind(search_pattern)
-                .fetch_all(&self.pool)
-                .await?
-            }
-            (Some(status), Some(priority), None) => {
-                // Статус + приоритет
-                sqlx::query_as::<_, Todo>(&format!(
-                    "SELECT * FROM todos WHERE user_id = $1 AND status = $2 AND priority = $3 ORDER BY {}",
-                    order_by
-                ))
-                .bind(user_id)
-                .bind(status.to_string())
-                .bind(priority)
-                .fetch_all(&self.pool)
-                .await?
-            }
-            (Some(status), None, Some(search)) => {
-                // Статус + поиск
-                let search_pattern = format!("%{}%", search);
-                sqlx::query_as::<_, Todo>(&format!(
-                    "SELECT * FROM todos WHERE user_id = $1 AND status = $2 AND (title ILIKE $3 OR description ILIKE $3) ORDER BY {}",
-                    order_by
-                ))
-                .bind(user_id)
-                .bind(status.to_string())
-                .bind(search_pattern)
-                .fetch_all(&self.pool)
-                .await?
-            }
-            (None, Some(priority), Some(search)) => {
-                // Приоритет + поиск
-                let search_pattern = format!("%{}%", search);
-                sqlx::query_as::<_, Todo>(&format!(
-                    "SELECT * FROM todos WHERE user_id = $1 AND priority = $2 AND (title ILIKE $3 OR description ILIKE $3) ORDER BY {}",
-                    order_by
-                ))
-                .bind(user_id)
-                .bind(priority)
-                .bind(search_pattern)
-                .fetch_all(&self.pool)
-                .await?
-            }
-            (Some(status), Some(priority), Some(search)) => {
-                // Все фильтры
-                let search_pattern = format!("%{}%", search);
-                sqlx::query_as::<_, Todo>(&format!(
-                    "SELECT * FROM todos WHERE user_id = $1 AND status = $2 AND priority = $3 AND (title ILIKE $4 OR description ILIKE $4) ORDER BY {}",
-                    order_by
-                ))
-                .bind(user_id)
-                .bind(status.to_string())
-                .bind(priority)
-                .bind(search_pattern)
-                .fetch_all(&self.pool)
-                .await?
+        page: TodoPage,
+    ) -> Result<TodoPageResult> {
+        if let Some(cursor) = &page.after {
+            if !cursor.matches(sort) {
+                return Err(validation_error("Cursor does not match sort order"));
             }
+        }
+
+        let limit = page.limit.clamp(1, MAX_TODOS);
+        let mut todos = build_find_query(user_id, &filter, sort, page.after, limit)
+            .build_query_as::<Todo>()
+            .fetch_all(&self.pool)
+            .await?;
+
+        // One extra row is fetched to detect the next page
+        let next = if todos.len() as i64 > limit {
+            todos.truncate(limit as usize);
+            todos.last().map(|todo| TodoCursor::after(todo, sort))
+        } else {
+            None
         };
 
         tracing::debug!("Found {} todos for user {}", todos.len(), user_id);
-        Ok(todos)
+        Ok(TodoPageResult { todos, next })
     }
 
     /// Update task
     pub async fn update(&self, id: DbId, update: UpdateTodo) -> Result<Todo> {
-        // SAFE update - use fixed queries for each field combination
-        if update.title.is_none()
-            && update.description.is_none()
-            && update.status.is_none()
-            && update.priority.is_none()
-        {
+        if update.is_empty() {
             return self.find_by_id(id).await;
         }
 
-        // Используем разные запросы в зависимости от того, какие поля обновляются
-        let todo = match (&update.title, &update.description, &update.status, update.priority) {
-            (Some(title), None, None, None) => {
-                sqlx::query_as::<_, Todo>("UPDATE todos SET title = $1 WHERE id = $2 RETURNING *")
-                    .bind(title)
-                    .bind(id)
-                    .fetch_one(&self.pool)
-                    .await?
-            }
-            (None, Some(desc), None, None) => {
-                sqlx::query_as::<_, Todo>(
-                    "UPDATE todos SET description = $1 WHERE id = $2 RETURNING *",
-                )
-                .bind(desc)
-                .bind(id)
-                .fetch_one(&self.pool)
-                .await?
-            }
-            (None, None, Some(status), None) => {
-                sqlx::query_as::<_, Todo>("UPDATE todos SET status = $1 WHERE id = $2 RETURNING *")
-                    .bind(status.to_string())
-                    .bind(id)
-                    .fetch_one(&self.pool)
-                    .await?
-            }
-            (None, None, None, Some(priority)) => {
-                sqlx::query_as::<_, Todo>(
-                    "UPDATE todos SET priority = $1 WHERE id = $2 RETURNING *",
-                )
-                .bind(priority)
-                .bind(id)
-                .fetch_one(&self.pool)
-                .await?
-            }
-            // Multiple updates
-            (Some(title), Some(desc), None, None) => {
-                sqlx::query_as::<_, Todo>(
-                    "UPDATE todos SET title = $1, description = $2 WHERE id = $3 RETURNING *",
-                )
-                .bind(title)
-                .bind(desc)
-                .bind(id)
-                .fetch_one(&self.pool)
-                .await?
-            }
-            (Some(title), None, Some(status), None) => {
-                sqlx::query_as::<_, Todo>(
-                    "UPDATE todos SET title = $1, status = $2 WHERE id = $3 RETURNING *",
-                )
-                .bind(title)
-                .bind(status.to_string())
-                .bind(id)
-                .fetch_one(&self.pool)
-                .await?
-            }
-            (Some(title), None, None, Some(priority)) => {
-                sqlx::query_as::<_, Todo>(
-                    "UPDATE todos SET title = $1, priority = $2 WHERE id = $3 RETURNING *",
-                )
-                .bind(title)
-                .bind(priority)
-                .bind(id)
-                .fetch_one(&self.pool)
-                .await?
-            }
-            // Update all fields
-            (Some(title), Some(desc), Some(status), Some(priority)) => {
-                sqlx::query_as::<_, Todo>(
-                    "UPDATE todos SET title = $1, description = $2, status = $3, priority = $4 WHERE id = $5 RETURNING *",
-                )
-                .bind(title)
-                .bind(desc)
-                .bind(status.to_string())
-                .bind(priority)
-                .bind(id)
-                .fetch_one(&self.pool)
-                .await?
-            }
-            // Для остальных комбинаций - обновляем all не-None поля
-            _ => {
-                // Fallback: обновляем только те поля, которые заyesны
-                let mut sql = String::from("UPDATE todos SET ");
-                let mut updates = Vec::new();
-
-                if update.title.is_some() {
-                    updates.push("title = COALESCE($1, title)");
-                }
-                if update.description.is_some() {
-                    updates.push("description = COALESCE($2, description)");
-                }
-                if update.status.is_some() {
-                    updates.push("status = COALESCE($3, status)");
-                }
-                if update.priority.is_some() {
-                    updates.push("priority = COALESCE($4, priority)");
-                }
-
-                sql.push_str(&updates.join(", "));
-                sql.push_str(" WHERE id = $5 RETURNING *");
-
-                sqlx::query_as::<_, Todo>(&sql)
-                    .bind(update.title)
-                    .bind(update.description)
-                    .bind(update.status.map(|s| s.to_string()))
-                    .bind(update.priority)
-                    .bind(id)
-                    .fetch_one(&self.pool)
-                    .await?
-            }
-        };
+        let todo = build_update_query(id, update)
+            .build_query_as::<Todo>()
+            .fetch_optional(&self.pool)
+            .await?
+            .ok_or_else(|| not_found(format!("Todo with id {} not found", id)))?;
 
         tracing::debug!("Updated todo {}", id);
         Ok(todo)
@@ -336,8 +138,111 @@ impl TodoRepository {
         Ok(todo)
     }
 
+    /// Set or clear task due date
+    pub async fn set_due_date(&self, id: DbId, due_date: Option<Timestamp>) -> Result<Todo> {
+        let todo = sqlx::query_as::<_, Todo>(
+            "UPDATE todos SET due_date = $1 WHERE id = $2 RETURNING *",
+        )
+        .bind(due_date)
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| not_found(format!("Todo with id {} not found", id)))?;
+
+        tracing::debug!("Set due date of todo {} to {:?}", id, due_date);
+        Ok(todo)
+    }
+
+    /// Record time spent on a task
+    pub async fn add_time_entry(&self, id: DbId, minutes: i32) -> Result<()> {
+        sqlx::query("INSERT INTO todo_time_entries (todo_id, minutes) VALUES ($1, $2)")
+            .bind(id)
+            .bind(minutes)
+            .execute(&self.pool)
+            .await?;
+
+        tracing::debug!("Tracked {} minutes on todo {}", minutes, id);
+        Ok(())
+    }
+
+    /// Open tasks whose due date fell inside the range and has already passed
+    pub async fn find_slipped(
+        &self,
+        user_id: DbId,
+        range: DateRange,
+        now: Timestamp,
+    ) -> Result<Vec<Todo>> {
+        let todos = sqlx::query_as::<_, Todo>(
+            r#"
+            SELECT * FROM todos
+            WHERE user_id = $1
+              AND status IN ('pending', 'in_progress')
+              AND due_date >= $2 AND due_date < $3 AND due_date < $4
+            ORDER BY due_date ASC
+            "#,
+        )
+        .bind(user_id)
+        .bind(range.from)
+        .bind(range.to)
+        .bind(now)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(todos)
+    }
+
+    /// Open tasks due after `after` (candidates for automatic reminders)
+    pub async fn find_open_due_after(&self, user_id: DbId, after: Timestamp) -> Result<Vec<Todo>> {
+        let todos = sqlx::query_as::<_, Todo>(
+            r#"
+            SELECT * FROM todos
+            WHERE user_id = $1
+              AND status IN ('pending', 'in_progress')
+              AND due_date > $2
+            ORDER BY due_date ASC
+            "#,
+        )
+        .bind(user_id)
+        .bind(after)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(todos)
+    }
+
+    /// Oldest open tasks of the user
+    pub async fn find_oldest_open(&self, user_id: DbId, limit: i64) -> Result<Vec<Todo>> {
+        let todos = sqlx::query_as::<_, Todo>(
+            r#"
+            SELECT * FROM todos
+            WHERE user_id = $1 AND status IN ('pending', 'in_progress')
+            ORDER BY created_at ASC
+            LIMIT $2
+            "#,
+        )
+        .bind(user_id)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(todos)
+    }
+
     /// Get user task statistics
-    pub async fn get_user_stats(&self, user_id: DbId) -> Result<TodoStats> {
+    ///
+    /// Status counters always describe the current state of all tasks.
+    /// The `*_in_range` counters and tracked time are limited to `range`
+    /// (all time if `None`).
+    pub async fn get_user_stats(
+        &self,
+        user_id: DbId,
+        range: Option<DateRange>,
+    ) -> Result<TodoStats> {
+        let (from, to) = match range {
+            Some(r) => (Some(r.from), Some(r.to)),
+            None => (None, None),
+        };
+
         let stats = sqlx::query_as::<_, TodoStats>(
             r#"
             SELECT
@@ -345,12 +250,37 @@ impl TodoRepository {
                 COUNT(*) FILTER (WHERE status = 'pending') as pending,
                 COUNT(*) FILTER (WHERE status = 'in_progress') as in_progress,
                 COUNT(*) FILTER (WHERE status = 'completed') as completed,
-                COUNT(*) FILTER (WHERE status = 'cancelled') as cancelled
+                COUNT(*) FILTER (WHERE status = 'cancelled') as cancelled,
+                COUNT(*) FILTER (
+                    WHERE ($2::timestamptz IS NULL OR created_at >= $2)
+                      AND ($3::timestamptz IS NULL OR created_at < $3)
+                ) as created_in_range,
+                COUNT(*) FILTER (
+                    WHERE status = 'completed'
+                      AND ($2::timestamptz IS NULL OR completed_at >= $2)
+                      AND ($3::timestamptz IS NULL OR completed_at < $3)
+                ) as completed_in_range,
+                COUNT(*) FILTER (
+                    WHERE status IN ('pending', 'in_progress')
+                      AND due_date < CURRENT_TIMESTAMP
+                      AND ($2::timestamptz IS NULL OR due_date >= $2)
+                      AND ($3::timestamptz IS NULL OR due_date < $3)
+                ) as slipped_in_range,
+                COALESCE((
+                    SELECT SUM(e.minutes)
+                    FROM todo_time_entries e
+                    JOIN todos t ON t.id = e.todo_id
+                    WHERE t.user_id = $1
+                      AND ($2::timestamptz IS NULL OR e.tracked_at >= $2)
+                      AND ($3::timestamptz IS NULL OR e.tracked_at < $3)
+                ), 0) as minutes_tracked
             FROM todos
             WHERE user_id = $1
             "#,
         )
         .bind(user_id)
+        .bind(from)
+        .bind(to)
         .fetch_one(&self.pool)
         .await?;
 
@@ -366,4 +296,342 @@ pub struct TodoStats {
     pub in_progress: i64,
     pub completed: i64,
     pub cancelled: i64,
+    pub created_in_range: i64,
+    pub completed_in_range: i64,
+    pub slipped_in_range: i64,
+    pub minutes_tracked: i64,
+}
+
+/// Build the SELECT for `find_by_user`
+///
+/// Every user-provided value goes through `push_bind`; only the ORDER BY
+/// column comes from a fixed enum. Fetches `limit + 1` rows.
+fn build_find_query(
+    user_id: DbId,
+    filter: &TodoFilter,
+    sort: TodoSort,
+    after: Option<TodoCursor>,
+    limit: i64,
+) -> QueryBuilder<'static, Postgres> {
+    let (column, direction) = match sort {
+        TodoSort::CreatedAtAsc => ("created_at", "ASC"),
+        TodoSort::CreatedAtDesc => ("created_at", "DESC"),
+        TodoSort::PriorityAsc => ("priority", "ASC"),
+        TodoSort::PriorityDesc => ("priority", "DESC"),
+        TodoSort::TitleAsc => ("title", "ASC"),
+        TodoSort::TitleDesc => ("title", "DESC"),
+    };
+
+    let mut query = QueryBuilder::new("SELECT * FROM todos WHERE user_id = ");
+    query.push_bind(user_id);
+
+    if !filter.statuses.is_empty() {
+        let statuses: Vec<String> = filter.statuses.iter().map(|s| s.to_string()).collect();
+        query.push(" AND status = ANY(").push_bind(statuses).push(")");
+    }
+
+    if !filter.priorities.is_empty() {
+        query
+            .push(" AND priority = ANY(")
+            .push_bind(filter.priorities.clone())
+            .push(")");
+    }
+
+    if let Some(search) = &filter.search {
+        let pattern = format!("%{}%", escape_like(search));
+        query
+            .push(" AND (title ILIKE ")
+            .push_bind(pattern.clone())
+            .push(" OR description ILIKE ")
+            .push_bind(pattern)
+            .push(")");
+    }
+
+    if !filter.tags.is_empty() {
+        query.push(" AND tags @> ").push_bind(filter.tags.clone());
+    }
+
+    if let Some(from) = filter.due_from {
+        query.push(" AND due_date >= ").push_bind(from);
+    }
+
+    if let Some(to) = filter.due_to {
+        query.push(" AND due_date < ").push_bind(to);
+    }
+
+    if let Some(from) = filter.created_from {
+        query.push(" AND created_at >= ").push_bind(from);
+    }
+
+    if let Some(to) = filter.created_to {
+        query.push(" AND created_at < ").push_bind(to);
+    }
+
+    // Keyset pagination: rows strictly after the cursor in sort order,
+    // id breaks ties so pages never overlap or skip rows. Every sort column
+    // is NOT NULL (migration 024): a NULL would fail the tuple comparison
+    if let Some(cursor) = after {
+        let op = if direction == "ASC" { ">" } else { "<" };
+        query.push(format_args!(" AND ({}, id) {} (", column, op));
+        match cursor.key {
+            CursorKey::CreatedAt(ts) => query.push_bind(ts),
+            CursorKey::Priority(p) => query.push_bind(p),
+            CursorKey::Title(title) => query.push_bind(title),
+        };
+        query.push(", ").push_bind(cursor.id).push(")");
+    }
+
+    query.push(format_args!(
+        " ORDER BY {} {}, id {} LIMIT ",
+        column, direction, direction
+    ));
+    query.push_bind(limit + 1);
+
+    query
+}
+
+/// Build the UPDATE for `update`; `update` must not be empty
+fn build_update_query(id: DbId, update: UpdateTodo) -> QueryBuilder<'static, Postgres> {
+    let mut query = QueryBuilder::new("UPDATE todos SET ");
+    let mut fields = query.separated(", ");
+
+    if let Some(title) = update.title {
+        fields.push("title = ").push_bind_unseparated(title);
+    }
+
+    if let Some(description) = update.description {
+        fields.push("description = ").push_bind_unseparated(description);
+    }
+
+    if let Some(status) = update.status {
+        // Keep completed_at consistent with status (used by statistics)
+        let completed = status == crate::shared::types::TodoStatus::Completed;
+        fields.push("status = ").push_bind_unseparated(status.to_string());
+        fields.push(if completed {
+            "completed_at = COALESCE(completed_at, CURRENT_TIMESTAMP)"
+        } else {
+            "completed_at = NULL"
+        });
+    }
+
+    if let Some(priority) = update.priority {
+        fields.push("priority = ").push_bind_unseparated(priority);
+    }
+
+    if let Some(due_date) = update.due_date {
+        fields.push("due_date = ").push_bind_unseparated(due_date);
+    }
+
+    if let Some(tags) = update.tags {
+        fields.push("tags = ").push_bind_unseparated(tags);
+    }
+
+    if let Some(estimate) = update.estimate_minutes {
+        fields.push("estimate_minutes = ").push_bind_unseparated(estimate);
+    }
+
+    query.push(" WHERE id = ").push_bind(id).push(" RETURNING *");
+    query
+}
+
+/// Escape LIKE wildcards so search text matches literally
+fn escape_like(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shared::types::TodoStatus;
+    use chrono::Utc;
+
+    const SORTS: [TodoSort; 6] = [
+        TodoSort::CreatedAtAsc,
+        TodoSort::CreatedAtDesc,
+        TodoSort::PriorityAsc,
+        TodoSort::PriorityDesc,
+        TodoSort::TitleAsc,
+        TodoSort::TitleDesc,
+    ];
+
+    /// Filter with the fields selected by bits of `mask` set
+    fn filter_from_mask(mask: u32) -> TodoFilter {
+        let now = Utc::now();
+        let bit = |n: u32| mask & (1 << n) != 0;
+        TodoFilter {
+            statuses: if bit(0) { vec![TodoStatus::Pending, TodoStatus::InProgress] } else { vec![] },
+            priorities: if bit(1) { vec![1, 2] } else { vec![] },
+            search: bit(2).then(|| "login".to_string()),
+            tags: if bit(3) { vec!["backend".to_string()] } else { vec![] },
+            due_from: bit(4).then_some(now),
+            due_to: bit(5).then_some(now),
+            created_from: bit(6).then_some(now),
+            created_to: bit(7).then_some(now),
+        }
+    }
+
+    fn cursor_for(sort: TodoSort) -> TodoCursor {
+        let key = match sort {
+            TodoSort::CreatedAtAsc | TodoSort::CreatedAtDesc => CursorKey::CreatedAt(Utc::now()),
+            TodoSort::PriorityAsc | TodoSort::PriorityDesc => CursorKey::Priority(2),
+            TodoSort::TitleAsc | TodoSort::TitleDesc => CursorKey::Title("m".to_string()),
+        };
+        TodoCursor { key, id: 42 }
+    }
+
+    /// Placeholders must be exactly $1..$n, each used in order of appearance
+    fn placeholders(sql: &str) -> Vec<usize> {
+        sql.match_indices('$')
+            .map(|(i, _)| {
+                sql[i + 1..]
+                    .chars()
+                    .take_while(|c| c.is_ascii_digit())
+                    .collect::<String>()
+                    .parse()
+                    .unwrap()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_find_query_every_filter_combination() {
+        let fragments = [
+            "status = ANY(",
+            "priority = ANY(",
+            "(title ILIKE",
+            "tags @>",
+            "due_date >=",
+            "due_date <",
+            "created_at >=",
+            "created_at <",
+        ];
+
+        for mask in 0..(1 << fragments.len()) {
+            let filter = filter_from_mask(mask);
+            for sort in SORTS {
+                for with_cursor in [false, true] {
+                    let cursor = with_cursor.then(|| cursor_for(sort));
+                    let sql = build_find_query(7, &filter, sort, cursor, 50).sql().to_string();
+
+                    for (n, fragment) in fragments.iter().enumerate() {
+                        let present = mask & (1 << n) != 0;
+                        assert_eq!(
+                            sql.contains(&format!(" {}", fragment)),
+                            present,
+                            "mask {:#b}, fragment {:?}: {}",
+                            mask,
+                            fragment,
+                            sql
+                        );
+                    }
+
+                    // user_id + one bind per filter (search binds twice) + cursor + limit
+                    let expected = 1
+                        + mask.count_ones() as usize
+                        + usize::from(mask & 0b100 != 0)
+                        + if with_cursor { 2 } else { 0 }
+                        + 1;
+                    let found = placeholders(&sql);
+                    assert_eq!(found, (1..=expected).collect::<Vec<_>>(), "{}", sql);
+
+                    assert!(sql.starts_with("SELECT * FROM todos WHERE user_id = $1"));
+                    assert!(sql.ends_with(&format!("LIMIT ${}", expected)), "{}", sql);
+                    assert_eq!(sql.contains(", id) "), with_cursor, "{}", sql);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_find_query_sort_and_keyset_direction() {
+        let cases = [
+            (TodoSort::CreatedAtAsc, "(created_at, id) > (", "ORDER BY created_at ASC, id ASC"),
+            (TodoSort::CreatedAtDesc, "(created_at, id) < (", "ORDER BY created_at DESC, id DESC"),
+            (TodoSort::PriorityAsc, "(priority, id) > (", "ORDER BY priority ASC, id ASC"),
+            (TodoSort::PriorityDesc, "(priority, id) < (", "ORDER BY priority DESC, id DESC"),
+            (TodoSort::TitleAsc, "(title, id) > (", "ORDER BY title ASC, id ASC"),
+            (TodoSort::TitleDesc, "(title, id) < (", "ORDER BY title DESC, id DESC"),
+        ];
+
+        for (sort, keyset, order) in cases {
+            let sql = build_find_query(1, &TodoFilter::default(), sort, Some(cursor_for(sort)), 10)
+                .sql()
+                .to_string();
+            assert!(sql.contains(keyset), "{}", sql);
+            assert!(sql.contains(order), "{}", sql);
+        }
+    }
+
+    #[test]
+    fn test_update_query_every_field_combination() {
+        let fragments = [
+            "title = $",
+            "description = $",
+            "status = $",
+            "priority = $",
+            "due_date = $",
+            "tags = $",
+            "estimate_minutes = $",
+        ];
+
+        for mask in 1u32..(1 << fragments.len()) {
+            let bit = |n: u32| mask & (1 << n) != 0;
+            let update = UpdateTodo {
+                title: bit(0).then(|| "t".to_string()),
+                description: bit(1).then(|| "d".to_string()),
+                status: bit(2).then_some(TodoStatus::Completed),
+                priority: bit(3).then_some(2),
+                due_date: bit(4).then_some(None),
+                tags: bit(5).then(Vec::new),
+                estimate_minutes: bit(6).then_some(Some(30)),
+            };
+            let sql = build_update_query(9, update).sql().to_string();
+
+            for (n, fragment) in fragments.iter().enumerate() {
+                assert_eq!(sql.contains(fragment), bit(n as u32), "{}", sql);
+            }
+            assert_eq!(sql.contains("completed_at = COALESCE"), bit(2), "{}", sql);
+
+            let expected = mask.count_ones() as usize + 1;
+            assert_eq!(placeholders(&sql), (1..=expected).collect::<Vec<_>>(), "{}", sql);
+            assert!(sql.starts_with("UPDATE todos SET "));
+            assert!(!sql.contains(", ,") && !sql.contains("SET ,"), "{}", sql);
+            assert!(sql.ends_with(&format!("WHERE id = ${} RETURNING *", expected)), "{}", sql);
+        }
+    }
+
+    #[test]
+    fn test_update_query_reopen_clears_completed_at() {
+        let update = UpdateTodo {
+            status: Some(TodoStatus::Pending),
+            ..Default::default()
+        };
+        let sql = build_update_query(1, update).sql().to_string();
+        assert!(sql.contains("completed_at = NULL"), "{}", sql);
+    }
+
+    #[test]
+    fn test_escape_like() {
+        assert_eq!(escape_like("100%_done\\"), "100\\%\\_done\\\\");
+    }
+
+    #[test]
+    fn test_cursor_roundtrip() {
+        for sort in SORTS {
+            let cursor = cursor_for(sort);
+            let parsed: TodoCursor = cursor.to_string().parse().unwrap();
+            assert_eq!(parsed.id, cursor.id);
+            assert!(parsed.matches(sort));
+        }
+
+        let title = TodoCursor {
+            key: CursorKey::Title("a:b:c".to_string()),
+            id: 5,
+        };
+        assert_eq!(title.to_string().parse::<TodoCursor>().unwrap(), title);
+        assert!("x:1:2".parse::<TodoCursor>().is_err());
+        assert!("p:one:2".parse::<TodoCursor>().is_err());
+    }
 }
@@ -0,0 +1,139 @@
+// Weekly review - automatic Friday summary of user activity
+//
+// Users opt in with /review on. A background task wakes up every
+// 15 minutes and sends the review to subscribed users once per Friday
+// (after REVIEW_HOUR in the user's time zone). /review sends the same
+// report on demand.
+
+use crate::bot::keyboards;
+use crate::db::models::User;
+use crate::error::Result;
+use crate::shared::timezone::local_midnight;
+use crate::shared::types::Timestamp;
+use crate::todo::handlers::format_weekly_review;
+use crate::todo::repository::TodoRepository;
+use crate::todo::service::TodoService;
+use chrono::{DateTime, Datelike, TimeZone, Timelike, Utc, Weekday};
+use sqlx::PgPool;
+use teloxide::prelude::*;
+use teloxide::types::ChatId;
+use tokio::time::{sleep, Duration};
+
+/// Local hour on Friday after which reviews are sent
+const REVIEW_HOUR: u32 = 17;
+
+/// Build the weekly review for a user and send it to their private chat
+pub async fn send_review(bot: &Bot, pool: &PgPool, user: &User, now: Timestamp) -> Result<()> {
+    let service = TodoService::new(TodoRepository::new(pool.clone()));
+    let review = service.weekly_review(user.id, now).await?;
+
+    let text = format_weekly_review(&review, now.with_timezone(&user.tz()));
+    let request = bot.send_message(ChatId(user.telegram_id), text);
+
+    if review.stale.is_empty() {
+        request.await?;
+    } else {
+        request
+            .reply_markup(keyboards::review_actions(&review.stale))
+            .await?;
+    }
+
+    Ok(())
+}
+
+/// Enable or disable the automatic weekly review for a user
+pub async fn set_subscription(pool: &PgPool, user_id: i32, enabled: bool) -> Result<()> {
+    sqlx::query("UPDATE users SET weekly_review = $1 WHERE id = $2")
+        .bind(enabled)
+        .bind(user_id)
+        .execute(pool)
+        .await?;
+
+    tracing::debug!("Weekly review for user {} set to {}", user_id, enabled);
+    Ok(())
+}
+
+/// Check if automatic reviews should go out at local time `now`
+pub fn is_review_time<Tz: TimeZone>(now: &DateTime<Tz>) -> bool {
+    now.weekday() == Weekday::Fri && now.hour() >= REVIEW_HOUR
+}
+
+/// Background sender of automatic weekly reviews
+pub struct ReviewScheduler {
+    pool: PgPool,
+    bot: Bot,
+}
+
+impl ReviewScheduler {
+    pub fn new(pool: PgPool, bot: Bot) -> Self {
+        Self { pool, bot }
+    }
+
+    /// Start scheduler (background task)
+    pub async fn run(self) -> Result<()> {
+        tracing::info!("Starting weekly review scheduler...");
+
+        loop {
+            if let Err(e) = self.send_due_reviews(Utc::now()).await {
+                tracing::error!("Weekly review error: {}", e);
+            }
+
+            sleep(Duration::from_secs(15 * 60)).await;
+        }
+    }
+
+    /// Send reviews to subscribed users who have not received one today
+    async fn send_due_reviews(&self, now: Timestamp) -> Result<()> {
+        let users = sqlx::query_as::<_, User>("SELECT * FROM users WHERE weekly_review = TRUE")
+            .fetch_all(&self.pool)
+            .await?;
+
+        for user in users {
+            let tz = user.tz();
+            if !is_review_time(&now.with_timezone(&tz)) {
+                continue;
+            }
+            if user.last_review_at.is_some_and(|at| at >= local_midnight(now, tz)) {
+                continue;
+            }
+
+            if let Err(e) = send_review(&self.bot, &self.pool, &user, now).await {
+                tracing::error!("Failed to send weekly review to user {}: {}", user.id, e);
+                continue;
+            }
+
+            sqlx::query("UPDATE users SET last_review_at = $1 WHERE id = $2")
+                .bind(now)
+                .bind(user.id)
+                .execute(&self.pool)
+                .await?;
+
+            tracing::info!("Weekly review sent to user {}", user.id);
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_is_review_time() {
+        // 2026-10-23 is a Friday
+        let friday_evening = Utc.with_ymd_and_hms(2026, 10, 23, 18, 0, 0).unwrap();
+        let friday_morning = Utc.with_ymd_and_hms(2026, 10, 23, 9, 0, 0).unwrap();
+        let saturday = Utc.with_ymd_and_hms(2026, 10, 24, 18, 0, 0).unwrap();
+
+        assert!(is_review_time(&friday_evening));
+        assert!(!is_review_time(&friday_morning));
+        assert!(!is_review_time(&saturday));
+
+        // 15:00 UTC on Friday is already 18:00 in Moscow
+        let friday_afternoon = Utc.with_ymd_and_hms(2026, 10, 23, 15, 0, 0).unwrap();
+        assert!(!is_review_time(&friday_afternoon));
+        assert!(is_review_time(&friday_afternoon.with_timezone(&chrono_tz::Europe::Moscow)));
+    }
+}
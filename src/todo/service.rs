@@ -5,9 +5,22 @@
 
 use crate::db::models::{NewTodo, UpdateTodo};
 use crate::error::{validation_error, Result};
-use crate::shared::types::{DbId, Priority, TodoStatus};
-use crate::todo::models::{Todo, TodoFilter, TodoSort, TodoView};
+use crate::shared::types::{DbId, Priority, Timestamp, TodoStatus};
+use crate::shared::utils::QuickAdd;
+use crate::todo::models::{
+    DateRange, Todo, TodoFilter, TodoPage, TodoSort, TodoView, WeeklyReview,
+};
 use crate::todo::repository::TodoRepository;
+use chrono::Duration;
+
+/// Maximum number of tags per task
+const MAX_TAGS: usize = 10;
+
+/// Maximum estimate (30 days)
+const MAX_ESTIMATE_MINUTES: i64 = 30 * 24 * 60;
+
+/// How many stale tasks are offered for rescheduling in the weekly review
+const REVIEW_STALE_LIMIT: usize = 5;
 
 /// Service for working with tasks
 #[derive(Clone)]
@@ -28,16 +41,53 @@ impl TodoService {
         description: Option<String>,
         priority: Option<Priority>,
     ) -> Result<Todo> {
+        let new_todo = NewTodo {
+            user_id,
+            title,
+            description,
+            priority: priority.unwrap_or(3), // Default priority
+            due_date: None,
+            tags: Vec::new(),
+            estimate_minutes: None,
+        };
+
+        self.create_validated(new_todo).await
+    }
+
+    /// Create a new task from parsed quick-add text
+    pub async fn create_quick_todo(&self, user_id: DbId, quick: QuickAdd) -> Result<Todo> {
+        let estimate_minutes = match quick.estimate_minutes {
+            Some(m) if m > MAX_ESTIMATE_MINUTES => {
+                return Err(validation_error("Estimate is too large (max 30 days)"));
+            }
+            other => other.map(|m| m as i32),
+        };
+
+        let new_todo = NewTodo {
+            user_id,
+            title: quick.title,
+            description: None,
+            priority: quick.priority.unwrap_or(3),
+            due_date: quick.due,
+            tags: quick.tags,
+            estimate_minutes,
+        };
+
+        self.create_validated(new_todo).await
+    }
+
+    /// Validate and store a new task
+    async fn create_validated(&self, mut new_todo: NewTodo) -> Result<Todo> {
         // Validation
-        if title.trim().is_empty() {
+        if new_todo.title.trim().is_empty() {
             return Err(validation_error("Todo title cannot be empty"));
         }
 
-        if title.len() > 500 {
+        if new_todo.title.len() > 500 {
             return Err(validation_error("Todo title is too long (max 500 chars)"));
         }
 
-        if let Some(desc) = &description {
+        if let Some(desc) = &new_todo.description {
             if desc.len() > 2000 {
                 return Err(validation_error(
                     "Todo description is too long (max 2000 chars)",
@@ -45,18 +95,19 @@ impl TodoService {
             }
         }
 
-        let priority = priority.unwrap_or(3); // Default priority
-        if !(1..=5).contains(&priority) {
+        if !(1..=5).contains(&new_todo.priority) {
             return Err(validation_error("Priority must be between 1 and 5"));
         }
 
-        let new_todo = NewTodo {
-            user_id,
-            title: title.trim().to_string(),
-            description,
-            priority,
-        };
+        if new_todo.tags.len() > MAX_TAGS {
+            return Err(validation_error("Too many tags (max 10)"));
+        }
 
+        if new_todo.tags.iter().any(|t| t.chars().count() > 32) {
+            return Err(validation_error("Tag is too long (max 32 chars)"));
+        }
+
+        new_todo.title = new_todo.title.trim().to_string();
         self.repo.create(new_todo).await
     }
 
@@ -73,14 +124,17 @@ impl TodoService {
         sort: Option<TodoSort>,
     ) -> Result<Vec<TodoView>> {
         let filter = TodoFilter {
-            status,
+            statuses: status.into_iter().collect(),
             ..Default::default()
         };
 
         let sort = sort.unwrap_or_default();
-        let todos = self.repo.find_by_user(user_id, filter, sort).await?;
+        let page = self
+            .repo
+            .find_by_user(user_id, filter, sort, TodoPage::default())
+            .await?;
 
-        Ok(todos.into_iter().map(TodoView::from).collect())
+        Ok(page.todos.into_iter().map(TodoView::from).collect())
     }
 
     /// Update task
@@ -119,11 +173,17 @@ impl TodoService {
             description,
             status,
             priority,
+            ..Default::default()
         };
 
         self.repo.update(id, update).await
     }
 
+    /// Open tasks with a due date still ahead
+    pub async fn find_upcoming(&self, user_id: DbId, now: Timestamp) -> Result<Vec<Todo>> {
+        self.repo.find_open_due_after(user_id, now).await
+    }
+
     /// Delete task
     pub async fn delete_todo(&self, id: DbId) -> Result<()> {
         self.repo.delete(id).await
@@ -144,9 +204,53 @@ impl TodoService {
         self.repo.update(id, update).await
     }
 
+    /// Record time spent on a task
+    pub async fn track_time(&self, id: DbId, minutes: i64) -> Result<()> {
+        if !(1..=24 * 60).contains(&minutes) {
+            return Err(validation_error("Tracked time must be between 1 minute and 24 hours"));
+        }
+
+        self.repo.add_time_entry(id, minutes as i32).await
+    }
+
+    /// Push task due date one week forward (from now if it already passed)
+    pub async fn postpone_todo(&self, id: DbId, now: Timestamp) -> Result<Todo> {
+        let todo = self.repo.find_by_id(id).await?;
+        let base = todo.due_date.filter(|due| *due > now).unwrap_or(now);
+
+        self.repo.set_due_date(id, Some(base + Duration::weeks(1))).await
+    }
+
+    /// Build weekly review for the 7 days before `now`
+    pub async fn weekly_review(&self, user_id: DbId, now: Timestamp) -> Result<WeeklyReview> {
+        let range = DateRange::last_days(now, 7);
+        let stats = self.repo.get_user_stats(user_id, Some(range)).await?;
+        let slipped = self.repo.find_slipped(user_id, range, now).await?;
+        let oldest = self
+            .repo
+            .find_oldest_open(user_id, REVIEW_STALE_LIMIT as i64)
+            .await?;
+
+        // Stale = slipped first, then oldest open items not already listed
+        let mut stale: Vec<Todo> = slipped.clone();
+        for todo in oldest {
+            if !stale.iter().any(|t| t.id == todo.id) {
+                stale.push(todo);
+            }
+        }
+        stale.truncate(REVIEW_STALE_LIMIT);
+
+        Ok(WeeklyReview {
+            range,
+            stats,
+            slipped,
+            stale,
+        })
+    }
+
     /// Get user statistics
     pub async fn get_stats(&self, user_id: DbId) -> Result<String> {
-        let stats = self.repo.get_user_stats(user_id).await?;
+        let stats = self.repo.get_user_stats(user_id, None).await?;
 
         Ok(format!(
             "📊 Your Statistics:\n\n\
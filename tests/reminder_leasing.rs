@@ -0,0 +1,262 @@
+// Reminder leasing - two schedulers deliver from one database
+//
+// Needs a disposable Postgres database in TEST_DATABASE_URL (skipped
+// otherwise). Telegram is replaced by a local mock that counts sendMessage
+// calls, so every reminder must be sent exactly once across both instances,
+// and an instance that lost the lease can no longer record the outcome.
+
+use sqlx::postgres::PgPoolOptions;
+use sqlx::PgPool;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use telegram_multitool_bot::db::migrations::run_migrations;
+use telegram_multitool_bot::reminder::repository::ReminderRepository;
+use telegram_multitool_bot::reminder::scheduler::ReminderScheduler;
+use teloxide::Bot;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use uuid::Uuid;
+
+const REMINDERS: usize = 30;
+
+#[tokio::test]
+async fn test_two_schedulers_send_each_reminder_once() {
+    let Ok(database_url) = std::env::var("TEST_DATABASE_URL") else {
+        eprintln!("TEST_DATABASE_URL is not set, skipping");
+        return;
+    };
+
+    let pool = connect(&database_url).await;
+    run_migrations(&pool).await.unwrap();
+
+    // Unique text, so reminders of other tests are not counted
+    // No dashes: reminders are sent as MarkdownV2, which escapes them
+    let marker = Uuid::new_v4().simple().to_string();
+    let telegram_id = (Uuid::new_v4().as_u128() % 1_000_000_000) as i64 + 1_000_000_000;
+    let (user_id,): (i32,) =
+        sqlx::query_as("INSERT INTO users (telegram_id) VALUES ($1) RETURNING id")
+            .bind(telegram_id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+
+    for _ in 0..REMINDERS {
+        insert_reminder(&pool, user_id, &marker, None).await;
+    }
+    // Lease of a crashed instance has expired: delivered by a survivor
+    insert_reminder(&pool, user_id, &marker, Some("-1 minute")).await;
+    // Another live instance holds this one: nobody else may send it
+    let held = insert_reminder(&pool, user_id, &marker, Some("1 hour")).await;
+
+    let sent = Arc::new(AtomicUsize::new(0));
+    let api_url = mock_telegram(marker.clone(), Arc::clone(&sent)).await;
+
+    let mut schedulers = Vec::new();
+    for worker in ["replica-a", "replica-b"] {
+        let bot = Bot::new("123:test").set_api_url(api_url.parse().unwrap());
+        let scheduler =
+            ReminderScheduler::new(connect(&database_url).await, bot).with_worker_id(worker);
+        schedulers.push(tokio::spawn(scheduler.run()));
+    }
+
+    // Both replicas race for the same due reminders
+    let expected = REMINDERS + 1;
+    let mut delivered = 0;
+    for _ in 0..300 {
+        delivered = count_sent(&pool, user_id).await;
+        if delivered == expected {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    // Give a duplicate send time to show up
+    tokio::time::sleep(Duration::from_millis(500)).await;
+
+    for scheduler in schedulers {
+        scheduler.abort();
+    }
+
+    let (held_sent,): (bool,) = sqlx::query_as("SELECT is_sent FROM reminders WHERE id = $1")
+        .bind(held)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+
+    sqlx::query("DELETE FROM users WHERE id = $1")
+        .bind(user_id)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    assert_eq!(delivered, expected);
+    assert_eq!(sent.load(Ordering::SeqCst), expected);
+    assert!(!held_sent);
+}
+
+#[tokio::test]
+async fn test_only_lease_holder_records_outcome() {
+    let Ok(database_url) = std::env::var("TEST_DATABASE_URL") else {
+        eprintln!("TEST_DATABASE_URL is not set, skipping");
+        return;
+    };
+
+    let pool = connect(&database_url).await;
+    run_migrations(&pool).await.unwrap();
+
+    let telegram_id = (Uuid::new_v4().as_u128() % 1_000_000_000) as i64 + 1_000_000_000;
+    let (user_id,): (i32,) =
+        sqlx::query_as("INSERT INTO users (telegram_id) VALUES ($1) RETURNING id")
+            .bind(telegram_id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    // Leased to "replica-crashed" for another hour
+    let id = insert_reminder(&pool, user_id, "leased", Some("1 hour")).await;
+
+    let repo = ReminderRepository::new(pool.clone());
+    let lease = chrono::Duration::minutes(2);
+    let later = chrono::Utc::now() + chrono::Duration::minutes(5);
+    let stranger = "replica-a";
+
+    let renewed_by_stranger = repo.renew(&[id], stranger, lease).await.unwrap();
+    let sent_by_stranger = repo.mark_as_sent(id, Some(stranger)).await.unwrap();
+    let retried_by_stranger = repo
+        .schedule_retry(id, stranger, later, 1, "error")
+        .await
+        .unwrap();
+    let dead_by_stranger = repo.dead_letter(id, stranger, 5, "error").await.unwrap();
+    let held_by_stranger = repo.hold(id, stranger, later).await.unwrap();
+    let renewed_by_holder = repo.renew(&[id], "replica-crashed", lease).await.unwrap();
+    let sent_by_holder = repo
+        .mark_as_sent(id, Some("replica-crashed"))
+        .await
+        .unwrap();
+    let renewed_after_sent = repo.renew(&[id], "replica-crashed", lease).await.unwrap();
+
+    sqlx::query("DELETE FROM users WHERE id = $1")
+        .bind(user_id)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    assert_eq!(renewed_by_stranger, 0);
+    assert!(!sent_by_stranger);
+    assert!(!retried_by_stranger);
+    assert!(!dead_by_stranger);
+    assert!(!held_by_stranger);
+    assert_eq!(renewed_by_holder, 1);
+    assert!(sent_by_holder);
+    assert_eq!(renewed_after_sent, 0);
+}
+
+async fn connect(database_url: &str) -> PgPool {
+    PgPoolOptions::new()
+        .max_connections(5)
+        .connect(database_url)
+        .await
+        .unwrap()
+}
+
+/// Due reminder, optionally leased by another instance for `lease`
+async fn insert_reminder(pool: &PgPool, user_id: i32, text: &str, lease: Option<&str>) -> i32 {
+    let (id,): (i32,) = sqlx::query_as(
+        r#"
+        INSERT INTO reminders (user_id, chat_id, message, remind_at, locked_by, locked_until)
+        VALUES ($1, (SELECT telegram_id FROM users WHERE id = $1), $2, NOW() - INTERVAL '1 second',
+                CASE WHEN $3::interval IS NOT NULL THEN 'replica-crashed' END,
+                NOW() + $3::interval)
+        RETURNING id
+        "#,
+    )
+    .bind(user_id)
+    .bind(text)
+    .bind(lease)
+    .fetch_one(pool)
+    .await
+    .unwrap();
+    id
+}
+
+async fn count_sent(pool: &PgPool, user_id: i32) -> usize {
+    let (count,): (i64,) =
+        sqlx::query_as("SELECT COUNT(*) FROM reminders WHERE user_id = $1 AND is_sent = TRUE")
+            .bind(user_id)
+            .fetch_one(pool)
+            .await
+            .unwrap();
+    count as usize
+}
+
+/// Local Bot API answering every request; counts messages containing `marker`
+async fn mock_telegram(marker: String, sent: Arc<AtomicUsize>) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+
+    tokio::spawn(async move {
+        loop {
+            let Ok((stream, _)) = listener.accept().await else {
+                return;
+            };
+            let marker = marker.clone();
+            let sent = Arc::clone(&sent);
+            tokio::spawn(async move {
+                if let Some(request) = read_request(stream).await {
+                    if request.body.contains(&marker) {
+                        sent.fetch_add(1, Ordering::SeqCst);
+                    }
+                }
+            });
+        }
+    });
+
+    format!("http://{}", address)
+}
+
+struct Request {
+    body: String,
+}
+
+/// Read one request and answer it with a sent message
+async fn read_request(mut stream: TcpStream) -> Option<Request> {
+    let mut data = Vec::new();
+    let mut buffer = [0; 4096];
+
+    let body_start = loop {
+        let read = stream.read(&mut buffer).await.ok()?;
+        if read == 0 {
+            return None;
+        }
+        data.extend_from_slice(&buffer[..read]);
+        if let Some(end) = data.windows(4).position(|w| w == b"\r\n\r\n") {
+            break end + 4;
+        }
+    };
+
+    let head = String::from_utf8_lossy(&data[..body_start]).to_lowercase();
+    let length = head
+        .lines()
+        .find_map(|line| line.strip_prefix("content-length:"))
+        .and_then(|value| value.trim().parse::<usize>().ok())
+        .unwrap_or(0);
+
+    while data.len() < body_start + length {
+        let read = stream.read(&mut buffer).await.ok()?;
+        if read == 0 {
+            break;
+        }
+        data.extend_from_slice(&buffer[..read]);
+    }
+
+    let body = r#"{"ok":true,"result":{"message_id":1,"date":0,"chat":{"id":1,"type":"private","first_name":"Test"},"text":"ok"}}"#;
+    let response = format!(
+        "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        body.len(),
+        body
+    );
+    stream.write_all(response.as_bytes()).await.ok()?;
+
+    Some(Request {
+        body: String::from_utf8_lossy(&data[body_start..]).into_owned(),
+    })
+}